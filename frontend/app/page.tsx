@@ -218,7 +218,7 @@ function HTMLCardOverlay({
     }
   }
   
-  const imageUrl = stage.image_base64 ? getImageUrl(stage.image_base64) : undefined
+  const imageUrl = stageImageUrl(stage, getImageUrl)
   
   return (
     <motion.div
@@ -257,7 +257,7 @@ function HTMLCardOverlay({
           {imageUrl ? (
             <img 
               src={imageUrl} 
-              alt={stage.stage_name}
+              alt={stage.alt_text || stage.stage_name}
               className="w-full h-full object-cover rounded-lg"
             />
           ) : (
@@ -440,7 +440,9 @@ function StageModal({
     if (base64.startsWith('/9j/')) return `data:image/jpeg;base64,${base64}`
     return `data:image/png;base64,${base64}`
   }
-  const imageUrl = stage?.image_base64 ? getImageUrl(stage.image_base64) : undefined
+  const imageUrl = stageImageUrl(stage, getImageUrl)
+  const previousImageUrl = stageImageUrl(stage?.previous_image, getImageUrl)
+  const [comparePosition, setComparePosition] = useState(50)
 
   // Keyboard handlers (Esc close, arrows navigate, Enter toggle zoom)
   useEffect(() => {
@@ -545,7 +547,7 @@ function StageModal({
                 >
                   <img
                     src={imageUrl}
-                    alt={stage.stage_name}
+                    alt={stage.alt_text || stage.stage_name}
                     className="w-full h-full object-contain transition-transform duration-300 group-hover:scale-105"
                   />
                   <div className="absolute inset-0 bg-black/0 group-hover:bg-black/20 flex items-center justify-center opacity-0 group-hover:opacity-100 transition-opacity text-white text-sm font-medium">
@@ -557,12 +559,36 @@ function StageModal({
                   Image generating...
                 </div>
               )}
+              {imageUrl && previousImageUrl && (
+                <div className="absolute inset-4 rounded-xl overflow-hidden">
+                  {/* Before (previous image) on the left of the handle, after (current) on the right */}
+                  <img src={imageUrl} alt={stage.alt_text || stage.stage_name} className="absolute inset-0 w-full h-full object-contain" />
+                  <img
+                    src={previousImageUrl}
+                    alt={`${stage.stage_name} before regeneration`}
+                    className="absolute inset-0 w-full h-full object-contain"
+                    style={{ clipPath: `inset(0 ${100 - comparePosition}% 0 0)` }}
+                  />
+                  <div className="absolute inset-y-0 w-0.5 bg-cyan-400 pointer-events-none" style={{ left: `${comparePosition}%` }} />
+                  <span className="absolute top-2 left-2 px-2 py-0.5 rounded bg-black/60 text-white/80 text-xs">Before</span>
+                  <span className="absolute top-2 right-2 px-2 py-0.5 rounded bg-black/60 text-white/80 text-xs">After</span>
+                  <input
+                    type="range"
+                    min={0}
+                    max={100}
+                    value={comparePosition}
+                    onChange={e => setComparePosition(Number(e.target.value))}
+                    className="absolute bottom-2 left-4 right-4 w-[calc(100%-2rem)] accent-cyan-400"
+                    aria-label="Compare with the image before regeneration"
+                  />
+                </div>
+              )}
             </div>
           {/* Description */}
           <div className="flex-1 overflow-y-auto custom-scrollbar p-6">
             <h3 className="text-lg font-semibold mb-3 text-white/90">Description</h3>
             <p className="text-white/80 leading-relaxed whitespace-pre-wrap">
-              {stage.description}
+              <DescriptionWithTerms text={stage.description} terms={stage.terms} />
             </p>
           </div>
         </div>
@@ -600,7 +626,7 @@ function StageModal({
         >
           <img
             src={imageUrl}
-            alt={stage.stage_name}
+            alt={stage.alt_text || stage.stage_name}
             className="max-w-[95vw] max-h-[95vh] object-contain shadow-2xl"
           />
           <button
@@ -691,12 +717,72 @@ function ProductForm({ onSubmit }: { onSubmit: (product: string) => void }) {
 }
 
 // Lifecycle Stage Card Component
+interface StageTerm {
+  term: string
+  definition: string
+}
+
 interface LifecycleStage {
   stage_name: string
   prompt: string
   description: string
   image_base64?: string
+  mime_type?: string | null
+  is_placeholder?: boolean
+  image_ref?: string | null
+  image_url?: string | null
+  alt_text?: string | null
   last_updated: string
+  terms?: StageTerm[]
+  previous_image?: PreviousImage | null
+}
+
+// The image the latest regeneration replaced, for the before/after slider.
+interface PreviousImage {
+  image_base64?: string | null
+  mime_type?: string | null
+  image_ref?: string | null
+  image_url?: string | null
+  prompt: string
+  replaced_at: string
+}
+
+// Data URL for a stage image; uses the server-reported mime_type and only guesses for older payloads without it.
+// Images the server stores externally (IMAGE_DIR, S3_BUCKET) come without base64 and are loaded from image_url,
+// a server path or a presigned bucket URL.
+function stageImageUrl(stage: { image_base64?: string | null, mime_type?: string | null, image_ref?: string | null, image_url?: string | null } | null | undefined, guess: (base64: string) => string): string | undefined {
+  if (!stage?.image_base64) {
+    const url = stage?.image_url ?? (stage?.image_ref ? `/api/images/${stage.image_ref}` : undefined)
+    return url?.startsWith('/') ? `http://localhost:8080${url}` : url
+  }
+  return stage.mime_type ? `data:${stage.mime_type};base64,${stage.image_base64}` : guess(stage.image_base64)
+}
+
+// Description text with extracted technical terms underlined; hovering shows the definition
+function DescriptionWithTerms({ text, terms }: { text: string; terms?: StageTerm[] }) {
+  if (!terms || terms.length === 0) return <>{text}</>
+  const definitions = new Map(terms.map(t => [t.term.toLowerCase(), t.definition]))
+  const escaped = terms.map(t => t.term.replace(/[.*+?^${}()|[\]\\]/g, '\\$&'))
+  // Longest first so "closed-loop recycling" wins over "recycling"
+  escaped.sort((a, b) => b.length - a.length)
+  const parts = text.split(new RegExp(`\\b(${escaped.join('|')})\\b`, 'gi'))
+  return (
+    <>
+      {parts.map((part, i) =>
+        i % 2 === 1 ? (
+          <abbr
+            key={i}
+            title={definitions.get(part.toLowerCase())}
+            className="cursor-help no-underline border-b border-dotted border-emerald-300/70"
+          >
+            {part}
+          </abbr>
+        ) : (
+          <span key={i}>{part}</span>
+        )
+      )}
+    </>
+  )
 }
 
 interface LifecycleData {
@@ -723,7 +809,7 @@ function StageCard({ stage, index }: { stage: LifecycleStage; index: number }) {
     }
   }
   
-  const imageUrl = stage.image_base64 ? getImageUrl(stage.image_base64) : undefined
+  const imageUrl = stageImageUrl(stage, getImageUrl)
   
   return (
     <motion.div
@@ -743,7 +829,7 @@ function StageCard({ stage, index }: { stage: LifecycleStage; index: number }) {
         {imageUrl ? (
           <img 
             src={imageUrl} 
-            alt={stage.stage_name}
+            alt={stage.alt_text || stage.stage_name}
             className="w-full h-full object-cover rounded-lg"
           />
         ) : (
@@ -927,6 +1013,8 @@ export default function HomePage() {
   const [isGenerating, setIsGenerating] = useState(false)
   const [currentStage, setCurrentStage] = useState<string>('')
   const [completedStages, setCompletedStages] = useState<Set<string>>(new Set())
+  // Set by the backend (X-Degraded-Mode) when the Gemini key was rejected or ran out of quota
+  const [degradedReason, setDegradedReason] = useState<string | null>(null)
 
   const generateLifecycle = async (productDescription: string) => {
     setIsGenerating(true)
@@ -949,6 +1037,8 @@ export default function HomePage() {
         }),
       })
       
+      setDegradedReason(skeletonResponse.headers.get('X-Degraded-Mode'))
+
       if (!skeletonResponse.ok) {
         throw new Error(`Failed to create lifecycle skeleton: ${skeletonResponse.status}`)
       }
@@ -959,45 +1049,56 @@ export default function HomePage() {
       // Set initial lifecycle with empty stages
       setLifecycle(skeletonData)
       
-      // Step 2: Generate each stage individually
-      for (let i = 0; i < stages.length; i++) {
-        const stageName = stages[i]
-        setCurrentStage(stageName)
-        
-        console.log(`🎯 Generating stage ${i + 1}/${stages.length}: ${stageName}`)
-        
-        const stageResponse = await fetch(`http://localhost:8080/api/lifecycle/${skeletonData.id}/stage/${i}`, {
-          method: 'POST',
-          headers: {
-            'Content-Type': 'application/json',
-          },
-        })
-        
-        if (!stageResponse.ok) {
-          console.error(`Failed to generate stage ${stageName}:`, stageResponse.status)
-          continue // Skip this stage but continue with others
-        }
-        
-        const stageData = await stageResponse.json()
-        console.log(`✅ Generated stage: ${stageName}`)
-        
-        // Update completed stages
-        setCompletedStages(prev => new Set([...Array.from(prev), stageName]))
-        
-        // Update lifecycle with new stage data
-        setLifecycle(prevLifecycle => {
-          if (!prevLifecycle) return prevLifecycle
-          const updatedStages = [...prevLifecycle.stages]
-          updatedStages[i] = stageData
-          return {
-            ...prevLifecycle,
-            stages: updatedStages,
-            updated_at: new Date().toISOString()
+      // Step 2: Generate all stages concurrently; the server streams each one as it is stored
+      setCurrentStage(stages.join(', '))
+      const batchResponse = await fetch(`http://localhost:8080/api/lifecycle/${skeletonData.id}/stages/generate`, {
+        method: 'POST',
+        headers: {
+          'Content-Type': 'application/json',
+        },
+        body: JSON.stringify({}),
+      })
+
+      setDegradedReason(batchResponse.headers.get('X-Degraded-Mode'))
+
+      if (!batchResponse.ok || !batchResponse.body) {
+        throw new Error(`Failed to generate stages: ${batchResponse.status}`)
+      }
+
+      // Server-sent events: `stage` per stored stage, `failed` per stage that could not be generated, then `done`
+      const reader = batchResponse.body.getReader()
+      const decoder = new TextDecoder()
+      let buffer = ''
+      for (;;) {
+        const { value, done } = await reader.read()
+        if (done) break
+        buffer += decoder.decode(value, { stream: true })
+        const events = buffer.split('\n\n')
+        buffer = events.pop() ?? ''
+        for (const raw of events) {
+          const lines = raw.split('\n')
+          const event = lines.find(l => l.startsWith('event:'))?.slice(6).trim()
+          const data = lines.filter(l => l.startsWith('data:')).map(l => l.slice(5).trim()).join('\n')
+          if (!data) continue
+          const payload = JSON.parse(data)
+          if (event === 'stage') {
+            const stageData: LifecycleStage = payload.stage
+            console.log(`✅ Generated stage: ${stageData.stage_name}`)
+            setCompletedStages(prev => new Set([...Array.from(prev), stageData.stage_name]))
+            setLifecycle(prevLifecycle => {
+              if (!prevLifecycle) return prevLifecycle
+              const updatedStages = [...prevLifecycle.stages]
+              updatedStages[payload.stage_index] = stageData
+              return {
+                ...prevLifecycle,
+                stages: updatedStages,
+                updated_at: new Date().toISOString()
+              }
+            })
+          } else if (event === 'failed') {
+            console.error(`Failed to generate stage ${payload.stage_index}:`, payload.error?.message)
           }
-        })
-        
-        // Small delay for smooth UI transitions
-        await new Promise(resolve => setTimeout(resolve, 500))
+        }
       }
       
       console.log('🎉 All stages generated successfully!')
@@ -1019,6 +1120,13 @@ export default function HomePage() {
 
   return (
     <div className="min-h-screen">
+      {degradedReason && (
+        <div className="fixed top-0 inset-x-0 z-40 bg-amber-500/90 text-black text-sm text-center py-2 px-4">
+          {degradedReason === 'quota_exhausted'
+            ? 'Image generation quota is exhausted; showing placeholder images for now.'
+            : 'The image generation API key was rejected; showing placeholder images for now.'}
+        </div>
+      )}
       {!lifecycle ? (
         <HeroSection onGenerateLifecycle={generateLifecycle} />
       ) : (
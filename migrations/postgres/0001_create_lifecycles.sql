@@ -0,0 +1,9 @@
+-- Lifecycles are stored as JSON documents; the indexed columns mirror fields used for listing and retention.
+CREATE TABLE IF NOT EXISTS lifecycles (
+    id UUID PRIMARY KEY,
+    created_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL,
+    data JSONB NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS lifecycles_created_at ON lifecycles (created_at);
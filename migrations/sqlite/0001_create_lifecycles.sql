@@ -0,0 +1,9 @@
+-- Lifecycles are stored as JSON documents; the indexed columns mirror fields used for listing and retention.
+CREATE TABLE IF NOT EXISTS lifecycles (
+    id TEXT PRIMARY KEY NOT NULL,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL,
+    data TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS lifecycles_created_at ON lifecycles (created_at);
@@ -0,0 +1,33 @@
+use axum::http::{header, HeaderMap, StatusCode};
+
+use crate::error::ApiError;
+use sha2::{Digest, Sha256};
+use tracing::warn;
+
+/// `ADMIN_TOKEN`: bearer token required by every `/api/admin/` endpoint and the bulk purge. Unset disables them.
+pub struct AdminAuth {
+    token_digest: Option<[u8; 32]>,
+}
+
+impl AdminAuth {
+    pub fn from_env() -> Self {
+        let token = std::env::var("ADMIN_TOKEN").ok().filter(|t| !t.is_empty());
+        Self { token_digest: token.map(|t| Sha256::digest(t.as_bytes()).into()) }
+    }
+
+    /// 403 when no token is configured, 401 when the request doesn't carry it as `Authorization: Bearer`.
+    pub fn check(&self, headers: &HeaderMap) -> Result<(), ApiError> {
+        let unauthorized = || ApiError::new(StatusCode::UNAUTHORIZED, "unauthorized", "Missing or invalid admin bearer token");
+        let Some(expected) = self.token_digest else {
+            warn!("🔒 Admin endpoint called but ADMIN_TOKEN is not set");
+            return Err(ApiError::new(StatusCode::FORBIDDEN, "admin_disabled", "Admin endpoints are disabled; set ADMIN_TOKEN to enable them"));
+        };
+        let presented = headers.get(header::AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "))
+            .ok_or_else(unauthorized)?;
+        // Compare digests so the comparison time doesn't depend on how much of the token matched.
+        let presented: [u8; 32] = Sha256::digest(presented.trim().as_bytes()).into();
+        if presented == expected { Ok(()) } else { Err(unauthorized()) }
+    }
+}
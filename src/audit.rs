@@ -0,0 +1,43 @@
+use chrono::{DateTime, Utc};
+use parking_lot::RwLock;
+use serde::Serialize;
+use std::collections::VecDeque;
+use uuid::Uuid;
+
+/// Upper bound on retained audit entries; oldest entries are dropped first.
+const MAX_ENTRIES: usize = 10_000;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AuditEntry {
+    pub id: Uuid,
+    pub at: DateTime<Utc>,
+    /// Who triggered it (`retention`, or the admin endpoint that did).
+    pub actor: String,
+    /// Machine-readable action, e.g. `retention.delete`.
+    pub action: String,
+    pub lifecycle_id: Option<Uuid>,
+    pub detail: String,
+}
+
+/// In-memory ring buffer of destructive or administrative actions.
+#[derive(Default)]
+pub struct AuditLog {
+    entries: RwLock<VecDeque<AuditEntry>>,
+}
+
+impl AuditLog {
+    pub fn record(&self, actor: &str, action: &str, lifecycle_id: Option<Uuid>, detail: impl Into<String>) {
+        let mut entries = self.entries.write();
+        if entries.len() >= MAX_ENTRIES {
+            entries.pop_front();
+        }
+        entries.push_back(AuditEntry { id: Uuid::new_v4(), at: Utc::now(), actor: actor.to_string(), action: action.to_string(), lifecycle_id, detail: detail.into() });
+    }
+
+    /// Every retained entry, newest first (ties broken by id, matching the cursor order).
+    pub fn newest_first(&self) -> Vec<AuditEntry> {
+        let mut entries: Vec<AuditEntry> = self.entries.read().iter().cloned().collect();
+        entries.sort_by(|a, b| b.at.cmp(&a.at).then(a.id.cmp(&b.id)));
+        entries
+    }
+}
@@ -0,0 +1,138 @@
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+use crate::models::Lifecycle;
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BillingFormat {
+    #[default]
+    Json,
+    Csv,
+}
+
+/// Usage of one stage with one provider model during the month.
+#[derive(Debug, Serialize)]
+pub struct BillingLine {
+    pub lifecycle_id: Uuid,
+    pub stage_index: usize,
+    pub stage_name: String,
+    pub provider: String,
+    pub model: String,
+    pub calls: u64,
+    pub prompt_tokens: u64,
+    pub output_tokens: u64,
+    pub images: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TenantBilling {
+    /// `None` for lifecycles created without a tenant.
+    pub tenant: Option<String>,
+    pub calls: u64,
+    pub prompt_tokens: u64,
+    pub output_tokens: u64,
+    pub images: u64,
+    pub lines: Vec<BillingLine>,
+}
+
+/// `GET /api/admin/billing`: provider usage per tenant for one calendar month (UTC), broken down by stage and
+/// model, for internal chargeback.
+#[derive(Debug, Serialize)]
+pub struct BillingReport {
+    /// `YYYY-MM`
+    pub month: String,
+    pub generated_at: DateTime<Utc>,
+    pub tenants: Vec<TenantBilling>,
+}
+
+/// Lifecycle, stage index, provider and model.
+type LineKey = (Uuid, usize, String, String);
+
+/// First day of a `YYYY-MM` month.
+pub fn parse_month(month: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(&format!("{}-01", month), "%Y-%m-%d").ok()
+}
+
+impl BillingReport {
+    /// Usage recorded on stored stages with a timestamp in the month starting at `first_day`. Usage of deleted
+    /// lifecycles is gone with them, so export before retention removes anything billable.
+    pub fn for_month(lifecycles: &[Lifecycle], first_day: NaiveDate) -> Self {
+        let start = first_day.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc();
+        let end = first_day.checked_add_months(chrono::Months::new(1)).unwrap_or(first_day).and_hms_opt(0, 0, 0).unwrap_or_default().and_utc();
+
+        let mut tenants: BTreeMap<Option<String>, BTreeMap<LineKey, BillingLine>> = BTreeMap::new();
+        for lifecycle in lifecycles {
+            for (stage_index, stage) in lifecycle.stages.iter().enumerate() {
+                for usage in stage.usage.iter().filter(|u| start <= u.at && u.at < end) {
+                    let line = tenants.entry(lifecycle.tenant.clone()).or_default()
+                        .entry((lifecycle.id, stage_index, usage.provider.clone(), usage.model.clone()))
+                        .or_insert_with(|| BillingLine {
+                            lifecycle_id: lifecycle.id,
+                            stage_index,
+                            stage_name: stage.stage_name.clone(),
+                            provider: usage.provider.clone(),
+                            model: usage.model.clone(),
+                            calls: 0,
+                            prompt_tokens: 0,
+                            output_tokens: 0,
+                            images: 0,
+                        });
+                    line.calls += 1;
+                    line.prompt_tokens += usage.prompt_tokens;
+                    line.output_tokens += usage.output_tokens;
+                    line.images += u64::from(usage.images);
+                }
+            }
+        }
+
+        let tenants = tenants.into_iter().map(|(tenant, lines)| {
+            let lines: Vec<BillingLine> = lines.into_values().collect();
+            TenantBilling {
+                tenant,
+                calls: lines.iter().map(|l| l.calls).sum(),
+                prompt_tokens: lines.iter().map(|l| l.prompt_tokens).sum(),
+                output_tokens: lines.iter().map(|l| l.output_tokens).sum(),
+                images: lines.iter().map(|l| l.images).sum(),
+                lines,
+            }
+        }).collect();
+        Self { month: format!("{:04}-{:02}", first_day.year(), first_day.month()), generated_at: Utc::now(), tenants }
+    }
+
+    /// One row per billing line, tenant first; the empty tenant is lifecycles without one.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("month,tenant,lifecycle_id,stage_index,stage_name,provider,model,calls,prompt_tokens,output_tokens,images\n");
+        for tenant in &self.tenants {
+            for l in &tenant.lines {
+                let row = [
+                    self.month.clone(),
+                    csv_field(tenant.tenant.as_deref().unwrap_or_default()),
+                    l.lifecycle_id.to_string(),
+                    l.stage_index.to_string(),
+                    csv_field(&l.stage_name),
+                    csv_field(&l.provider),
+                    csv_field(&l.model),
+                    l.calls.to_string(),
+                    l.prompt_tokens.to_string(),
+                    l.output_tokens.to_string(),
+                    l.images.to_string(),
+                ];
+                csv.push_str(&row.join(","));
+                csv.push('\n');
+            }
+        }
+        csv
+    }
+}
+
+/// Quote a field containing separators, quotes or line breaks (RFC 4180).
+pub fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
@@ -0,0 +1,167 @@
+//! Stand-in for the Gemini `generateContent` API: answers every request this service makes with canned images
+//! and text, so the full stack runs (locally, in CI, for frontend work) without a key or network access.
+//!
+//! ```text
+//! cargo run --bin plv-fake-gemini
+//! GEMINI_API_KEY=fake GEMINI_API_BASE=http://localhost:8090/v1beta cargo run
+//! ```
+
+use axum::{extract::Path, http::StatusCode, response::{IntoResponse, Response}, routing::post, Json, Router};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use image::{ImageFormat, Rgb, RgbImage};
+use serde_json::{json, Value};
+use std::{io::Cursor, net::SocketAddr, time::Duration};
+use tracing::info;
+use tracing_subscriber::{fmt, EnvFilter};
+
+/// Edge length of the canned images; large enough to pass for a real generation in the UI.
+const IMAGE_SIZE: u32 = 512;
+
+#[tokio::main]
+async fn main() {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+    fmt().with_env_filter(filter).init();
+
+    let latency = Duration::from_millis(std::env::var("FAKE_GEMINI_LATENCY_MS").ok().and_then(|v| v.parse().ok()).unwrap_or(0));
+    let app = Router::new().route("/*path", post(move |path, body| generate_content(path, body, latency)));
+
+    let port: u16 = std::env::var("FAKE_GEMINI_PORT").ok().and_then(|v| v.parse().ok()).unwrap_or(8090);
+    let addr = SocketAddr::from(([0, 0, 0, 0], port));
+    tracing::info!(%addr, "Starting fake Gemini API (GEMINI_API_BASE=http://localhost:{}/v1beta)", port);
+    axum::serve(tokio::net::TcpListener::bind(addr).await.unwrap(), app).await.unwrap();
+}
+
+/// `POST {base}/models/{model}:generateContent`, whatever the base path.
+async fn generate_content(Path(path): Path<String>, Json(request): Json<Value>, latency: Duration) -> Response {
+    let Some(model) = path.strip_suffix(":generateContent").and_then(|p| p.rsplit('/').next()) else {
+        return google_error(StatusCode::NOT_FOUND, "NOT_FOUND", &format!("/{} is not a generateContent call", path));
+    };
+    tokio::time::sleep(latency).await;
+
+    let config = &request["generationConfig"];
+    let prompt: String = request["contents"].as_array().into_iter().flatten()
+        .flat_map(|c| c["parts"].as_array().into_iter().flatten())
+        .filter_map(|p| p["text"].as_str())
+        .collect::<Vec<_>>()
+        .join("\n");
+    let wants_image = config["responseModalities"].as_array().is_some_and(|m| m.iter().any(|v| v == "IMAGE"));
+    let wants_json = config["responseMimeType"] == "application/json";
+    let count = config["candidateCount"].as_u64().unwrap_or(1).clamp(1, 8) as usize;
+
+    let candidates: Vec<Value> = if wants_image {
+        info!("🖼️ {} image: {}", model, preview(&prompt));
+        vec![json!({ "content": { "parts": [
+            { "text": "Here is the requested illustration." },
+            { "inlineData": { "mimeType": "image/png", "data": canned_image(&prompt) } }
+        ] } })]
+    } else {
+        let reply = if wants_json { json_reply(&prompt).to_string() } else { text_reply(&prompt) };
+        info!("💬 {} {} reply x{}: {}", model, if wants_json { "JSON" } else { "text" }, count, preview(&prompt));
+        (0..count).map(|i| {
+            let text = if i == 0 { reply.clone() } else { format!("{}\n\n(Alternative {}.)", reply, i + 1) };
+            json!({ "content": { "parts": [{ "text": text }] } })
+        }).collect()
+    };
+
+    let prompt_tokens = prompt.chars().count().div_ceil(4);
+    let output_tokens: usize = candidates.iter()
+        .flat_map(|c| c["content"]["parts"].as_array().cloned().unwrap_or_default())
+        .map(|p| p["text"].as_str().map_or(258, |t| t.chars().count().div_ceil(4)))
+        .sum();
+    Json(json!({
+        "candidates": candidates,
+        "usageMetadata": {
+            "promptTokenCount": prompt_tokens,
+            "candidatesTokenCount": output_tokens,
+            "totalTokenCount": prompt_tokens + output_tokens
+        },
+        "modelVersion": model
+    })).into_response()
+}
+
+fn google_error(status: StatusCode, reason: &str, message: &str) -> Response {
+    (status, Json(json!({ "error": { "code": status.as_u16(), "message": message, "status": reason } }))).into_response()
+}
+
+fn preview(prompt: &str) -> String {
+    prompt.chars().take(80).collect::<String>().replace('\n', " ")
+}
+
+/// The text between `start` and the next `end` in `text`.
+fn between<'a>(text: &'a str, start: &str, end: &str) -> Option<&'a str> {
+    let rest = &text[text.find(start)? + start.len()..];
+    Some(&rest[..rest.find(end)?])
+}
+
+/// A PNG with a diagonal two-color gradient derived from the prompt, so every stage gets a distinct image and
+/// the same prompt always the same one.
+fn canned_image(prompt: &str) -> String {
+    let hash = prompt.bytes().fold(0xcbf29ce484222325u64, |h, b| (h ^ b as u64).wrapping_mul(0x100000001b3));
+    let [r, g, b, r2, g2, b2, ..] = hash.to_le_bytes();
+    let image = RgbImage::from_fn(IMAGE_SIZE, IMAGE_SIZE, |x, y| {
+        let t = (x + y) as f32 / (2 * IMAGE_SIZE) as f32;
+        let channel = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t) as u8;
+        Rgb([channel(r, r2), channel(g, g2), channel(b, b2)])
+    });
+    let mut png = Cursor::new(Vec::new());
+    image.write_to(&mut png, ImageFormat::Png).expect("encoding an in-memory PNG");
+    BASE64.encode(png.into_inner())
+}
+
+/// The structured replies, recognised by the schema each prompt asks for.
+fn json_reply(prompt: &str) -> Value {
+    let estimate = |value: f32, spread: f32| json!({ "value": value, "low": (value - spread).max(0.0), "high": value + spread, "confidence": "medium" });
+    if prompt.contains("\"relevance\"") {
+        json!({ "relevance": 9, "artifacts": 9, "text_free": 10 })
+    } else if prompt.contains("\"has_text\"") {
+        json!({ "has_text": false, "detected_text": "" })
+    } else if prompt.contains("\"mismatches\"") {
+        json!({ "mismatches": [] })
+    } else if prompt.contains("\"flagged\"") {
+        json!({ "flagged": [], "revised": "" })
+    } else if prompt.contains("\"terms\"") {
+        json!({ "terms": [
+            { "term": "embodied carbon", "definition": "Greenhouse gases emitted while making and transporting a product, before it is used." },
+            { "term": "closed-loop recycling", "definition": "Recycling a material back into the same kind of product it came from." }
+        ] })
+    } else if prompt.contains("\"processes\"") {
+        json!({ "processes": ["assembly", "transport"], "materials": ["recycled content"], "impacts": ["energy use", "embodied carbon", "waste"] })
+    } else if prompt.contains("\"stages\"") {
+        let stages: Vec<&str> = between(prompt, "consider these lifecycle stages: ", ".\n").map(|s| s.split(", ").collect()).unwrap_or_default();
+        let share = 100.0 / stages.len().max(1) as f32;
+        json!({ "stages": stages.iter().enumerate().map(|(i, stage)| json!({
+            "stage": stage,
+            "cost_share_pct": estimate(share, share / 2.0),
+            "components": [
+                { "name": "Materials", "share_pct": estimate(60.0, 15.0) },
+                { "name": "Energy", "share_pct": estimate(40.0, 15.0) }
+            ],
+            "impact_score": estimate((3 + i % 5) as f32, 2.0)
+        })).collect::<Vec<_>>() })
+    } else {
+        json!({})
+    }
+}
+
+/// Free-text replies: stage descriptions, rewrites, summaries and image explanations.
+fn text_reply(prompt: &str) -> String {
+    if let Some(stage) = prompt.starts_with("Write a rich").then(|| between(prompt, "description (approx 150-200 words total) of the ", " stage in")).flatten() {
+        return format!(
+            "The {stage} stage covers the operations that turn inputs into the outputs handed to the next stage, with the \
+            main transformations happening at a small number of sites.\n\n\
+            Its sustainability challenges center on energy efficiency and material use; typical mitigations include \
+            closed-loop recycling of scrap, recycled content and process optimization.\n\n\
+            Key impact dimensions are energy use, emissions, waste and water, with embodied carbon as the largest lever. \
+            Practical improvements include renewable power, lighter packaging and design for disassembly."
+        );
+    }
+    if let Some(text) = prompt.starts_with("Rewrite").then(|| prompt.split_once("Description:\n")).flatten().map(|(_, text)| text) {
+        return text.trim().to_string();
+    }
+    if let Some(product) = prompt.starts_with("Summarize").then(|| prompt.split_once("\n\n")).flatten().map(|(_, product)| product) {
+        return product.split_whitespace().take(40).collect::<Vec<_>>().join(" ");
+    }
+    "The image shows the stage as an infographic: the main process in the center, its inputs on the left and its \
+    outputs on the right, against a neutral background."
+        .to_string()
+}
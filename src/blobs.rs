@@ -0,0 +1,52 @@
+use std::path::PathBuf;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+/// Files attached to stages (photos, certificates, datasheets), kept out of the lifecycle documents so listing
+/// and updating lifecycles doesn't drag megabytes of uploads along. Laid out as `{BLOB_DIR}/{lifecycle}/{asset}`.
+pub struct BlobStore {
+    root: PathBuf,
+    /// `MAX_ASSET_MB` (default 20): upload size limit.
+    pub max_asset_bytes: usize,
+}
+
+impl BlobStore {
+    /// Reads `BLOB_DIR` (default `blobs`) and `MAX_ASSET_MB`.
+    pub fn from_env() -> Self {
+        let root = std::env::var("BLOB_DIR").unwrap_or_else(|_| "blobs".to_string());
+        let max_mb = std::env::var("MAX_ASSET_MB").ok().and_then(|v| v.parse().ok()).unwrap_or(20usize);
+        info!("📎 Storing stage attachments in {}", root);
+        Self { root: root.into(), max_asset_bytes: max_mb.max(1) * 1024 * 1024 }
+    }
+
+    fn path(&self, lifecycle_id: Uuid, asset_id: Uuid) -> PathBuf {
+        self.root.join(lifecycle_id.to_string()).join(asset_id.to_string())
+    }
+
+    pub async fn put(&self, lifecycle_id: Uuid, asset_id: Uuid, bytes: &[u8]) -> std::io::Result<()> {
+        let path = self.path(lifecycle_id, asset_id);
+        if let Some(dir) = path.parent() {
+            tokio::fs::create_dir_all(dir).await?;
+        }
+        tokio::fs::write(path, bytes).await
+    }
+
+    pub async fn get(&self, lifecycle_id: Uuid, asset_id: Uuid) -> Option<Vec<u8>> {
+        tokio::fs::read(self.path(lifecycle_id, asset_id)).await.ok()
+    }
+
+    pub async fn remove(&self, lifecycle_id: Uuid, asset_id: Uuid) {
+        if let Err(e) = tokio::fs::remove_file(self.path(lifecycle_id, asset_id)).await {
+            warn!("⚠️ Could not remove attachment {}/{}: {}", lifecycle_id, asset_id, e);
+        }
+    }
+
+    /// Drop every attachment of a deleted lifecycle.
+    pub async fn remove_lifecycle(&self, lifecycle_id: Uuid) {
+        match tokio::fs::remove_dir_all(self.root.join(lifecycle_id.to_string())).await {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => warn!("⚠️ Could not remove attachments of {}: {}", lifecycle_id, e),
+        }
+    }
+}
@@ -0,0 +1,231 @@
+use chrono::{DateTime, Datelike, Timelike, Utc};
+use flate2::{write::GzEncoder, Compression};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::{collections::BTreeMap, io::Write};
+use uuid::Uuid;
+use zip::{write::SimpleFileOptions, CompressionMethod, ZipWriter};
+
+use crate::{
+    gemini::sniff_mime_type,
+    images::stage_image_bytes,
+    models::{Lifecycle, PromptInputs, Resolution, StageImage, StageStatus, TemplateVersions},
+    share::slug,
+};
+
+/// Where the lifecycle's content came from, for `provenance.json`.
+#[derive(Debug, Serialize)]
+pub struct Provenance {
+    pub lifecycle_id: Uuid,
+    pub revision: u64,
+    pub bundle_created_at: DateTime<Utc>,
+    pub image_provider: String,
+    /// Provider and model descriptions and rewrites come from.
+    pub text_provider: String,
+    pub text_model: String,
+    /// Logical model names and the provider model ids they resolved to when the bundle was built.
+    pub models: BTreeMap<String, String>,
+    pub stages: Vec<StageProvenance>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StageProvenance {
+    pub index: usize,
+    pub stage_name: String,
+    /// Path of the image inside the bundle, `None` if the stage has no image.
+    pub image: Option<String>,
+    pub image_sha256: Option<String>,
+    pub mime_type: Option<String>,
+    pub is_placeholder: bool,
+    pub status: StageStatus,
+    pub resolution: Resolution,
+    pub templates: TemplateVersions,
+    pub description_fallback: bool,
+    pub description_revisions: usize,
+    pub last_updated: DateTime<Utc>,
+}
+
+#[derive(Serialize)]
+struct Prompts<'a> {
+    product_description: &'a str,
+    constraints: &'a [String],
+    /// Shortened inputs actually used in prompts, when the originals exceeded the prompt budget.
+    prompt_inputs: Option<&'a PromptInputs>,
+    stages: Vec<StagePrompt<'a>>,
+}
+
+#[derive(Serialize)]
+struct StagePrompt<'a> {
+    index: usize,
+    stage_name: &'a str,
+    prompt: &'a str,
+}
+
+/// A stage attachment to archive under `assets/`.
+pub struct BundleAsset {
+    pub id: Uuid,
+    pub stage_index: usize,
+    pub filename: String,
+    pub bytes: Vec<u8>,
+}
+
+fn hex_sha256(bytes: &[u8]) -> String {
+    Sha256::digest(bytes).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn extension(mime: &str) -> &'static str {
+    match mime {
+        "image/svg+xml" => "svg",
+        "image/png" => "png",
+        "image/jpeg" => "jpg",
+        _ => "bin",
+    }
+}
+
+/// Path of a stage's image inside the bundle.
+fn image_path(index: usize, stage_name: &str, mime: &str) -> String {
+    format!("images/{:02}-{}.{}", index + 1, slug(stage_name), extension(mime))
+}
+
+fn pretty<T: Serialize>(value: &T) -> Vec<u8> {
+    serde_json::to_vec_pretty(value).unwrap_or_default()
+}
+
+impl Provenance {
+    pub fn new(lifecycle: &Lifecycle, image_provider: &str, (text_provider, text_model): (&str, &str), models: BTreeMap<String, String>) -> Self {
+        let stages = lifecycle.stages.iter().enumerate().map(|(index, stage)| {
+            let image = stage_image_bytes(stage);
+            StageProvenance {
+                index,
+                stage_name: stage.stage_name.clone(),
+                image: image.as_ref().map(|(_, mime)| image_path(index, &stage.stage_name, mime)),
+                image_sha256: image.as_ref().map(|(bytes, _)| hex_sha256(bytes)),
+                mime_type: image.as_ref().map(|(_, mime)| mime.to_string()),
+                is_placeholder: stage.is_placeholder(),
+                status: stage.status.clone(),
+                resolution: stage.resolution,
+                templates: stage.templates.clone(),
+                description_fallback: stage.description_fallback,
+                description_revisions: stage.description_history.len(),
+                last_updated: stage.last_updated,
+            }
+        }).collect();
+        Self {
+            lifecycle_id: lifecycle.id,
+            revision: lifecycle.revision,
+            bundle_created_at: Utc::now(),
+            image_provider: image_provider.to_string(),
+            text_provider: text_provider.to_string(),
+            text_model: text_model.to_string(),
+            models,
+            stages,
+        }
+    }
+}
+
+/// Archival `.tar.gz` of a lifecycle under `lifecycle-{id}/`: `lifecycle.json` (images left out), every stage
+/// image in its native format, `prompts.json`, `provenance.json`, the rendered `lifecycle.pdf`, stage
+/// attachments under `assets/`, and `SHA256SUMS` covering all of them.
+pub fn build_bundle(lifecycle: &Lifecycle, provenance: &Provenance, pdf: &[u8], assets: &[BundleAsset]) -> std::io::Result<Vec<u8>> {
+    let prompts = Prompts {
+        product_description: &lifecycle.product_description,
+        constraints: &lifecycle.constraints,
+        prompt_inputs: lifecycle.prompt_inputs.as_ref(),
+        stages: lifecycle.stages.iter().enumerate().map(|(index, s)| StagePrompt { index, stage_name: &s.stage_name, prompt: &s.prompt }).collect(),
+    };
+    let mut files: Vec<(String, Vec<u8>)> = vec![
+        ("lifecycle.json".to_string(), pretty(&lifecycle.clone().with_images(false))),
+        ("prompts.json".to_string(), pretty(&prompts)),
+        ("provenance.json".to_string(), pretty(provenance)),
+    ];
+    for (index, stage) in lifecycle.stages.iter().enumerate() {
+        if let Some((bytes, mime)) = stage_image_bytes(stage) {
+            files.push((image_path(index, &stage.stage_name, mime), bytes));
+        }
+    }
+    files.push(("lifecycle.pdf".to_string(), pdf.to_vec()));
+    for asset in assets {
+        let stage = lifecycle.stages.get(asset.stage_index).map_or_else(String::new, |s| slug(&s.stage_name));
+        // Prefixed with the asset id since uploads to one stage may share a file name.
+        let filename = asset.filename.replace(['/', '\\'], "_");
+        files.push((format!("assets/{:02}-{}/{}-{}", asset.stage_index + 1, stage, asset.id, filename), asset.bytes.clone()));
+    }
+    let sums: String = files.iter().map(|(path, bytes)| format!("{}  {}\n", hex_sha256(bytes), path)).collect();
+    files.push(("SHA256SUMS".to_string(), sums.into_bytes()));
+
+    let root = format!("lifecycle-{}", lifecycle.id);
+    let mtime = provenance.bundle_created_at.timestamp().max(0) as u64;
+    let mut archive = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+    for (path, bytes) in &files {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(bytes.len() as u64);
+        header.set_mode(0o644);
+        header.set_mtime(mtime);
+        archive.append_data(&mut header, format!("{}/{}", root, path), bytes.as_slice())?;
+    }
+    archive.into_inner()?.finish()
+}
+
+/// Hand-off `.zip` for decks and docs, under `lifecycle-{id}/`: `summary.md` (every stage with its image and
+/// description), `lifecycle.json` (images left out) and every stage image decoded into its native format.
+pub fn build_zip(lifecycle: &Lifecycle) -> std::io::Result<Vec<u8>> {
+    let mut files: Vec<(String, Vec<u8>)> = vec![
+        ("summary.md".to_string(), markdown_report(lifecycle, |index, stage, mime| image_path(index, &stage.stage_name, mime)).into_bytes()),
+        ("lifecycle.json".to_string(), pretty(&lifecycle.clone().with_images(false))),
+    ];
+    for (index, stage) in lifecycle.stages.iter().enumerate() {
+        if let Some((bytes, mime)) = stage_image_bytes(stage) {
+            files.push((image_path(index, &stage.stage_name, mime), bytes));
+        }
+    }
+
+    let now = Utc::now();
+    let modified = zip::DateTime::from_date_and_time(now.year() as u16, now.month() as u8, now.day() as u8, now.hour() as u8, now.minute() as u8, now.second() as u8).unwrap_or_default();
+    let root = format!("lifecycle-{}", lifecycle.id);
+    let mut archive = ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    for (path, bytes) in &files {
+        // PNG and JPEG are compressed already.
+        let method = if path.ends_with(".png") || path.ends_with(".jpg") { CompressionMethod::Stored } else { CompressionMethod::Deflated };
+        let options = SimpleFileOptions::default().compression_method(method).last_modified_time(modified).unix_permissions(0o644);
+        archive.start_file(format!("{}/{}", root, path), options).map_err(std::io::Error::other)?;
+        archive.write_all(bytes)?;
+    }
+    Ok(archive.finish().map_err(std::io::Error::other)?.into_inner())
+}
+
+/// Markdown rendering of a lifecycle (`summary.md` of the ZIP export, `GET /markdown`): product, constraints and
+/// one section per stage. `image_src` gives the link target of a stage image from its index and mime type.
+pub fn markdown_report(lifecycle: &Lifecycle, image_src: impl Fn(usize, &StageImage, &str) -> String) -> String {
+    let mut md = format!("# {}\n\n", lifecycle.product_description.trim());
+    if !lifecycle.constraints.is_empty() {
+        md.push_str(&format!("**Constraints:** {}\n\n", lifecycle.constraints.join(", ")));
+    }
+    let status = serde_json::to_value(lifecycle.review_status).ok().and_then(|v| v.as_str().map(str::to_string)).unwrap_or_default();
+    md.push_str(&format!("Lifecycle `{}`, revision {}, {}; exported {}.\n", lifecycle.id, lifecycle.revision, status.replace('_', " "), Utc::now().format("%Y-%m-%d %H:%M UTC")));
+
+    for (index, stage) in lifecycle.stages.iter().enumerate() {
+        md.push_str(&format!("\n## {}. {}\n\n", index + 1, stage.stage_name));
+        if let Some(b64) = stage.image_base64.as_deref() {
+            let alt = stage.alt_text.as_deref().unwrap_or(&stage.stage_name).replace(['[', ']'], "");
+            md.push_str(&format!("![{}]({})\n\n", alt, image_src(index, stage, sniff_mime_type(b64))));
+            if stage.is_placeholder() {
+                md.push_str("_Placeholder image; the stage has not been generated successfully yet._\n\n");
+            }
+        }
+        md.push_str(stage.description.trim());
+        md.push('\n');
+        if !stage.actors.is_empty() {
+            md.push_str("\n**Supply chain**\n\n");
+            for actor in &stage.actors {
+                md.push_str(&format!("- {}\n", actor.describe()));
+            }
+        }
+        if !stage.locations.is_empty() {
+            md.push_str("\n**Locations**\n\n");
+            for location in &stage.locations {
+                md.push_str(&format!("- {} ({:.3}, {:.3})\n", location.label, location.lat, location.lon));
+            }
+        }
+    }
+    md
+}
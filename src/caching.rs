@@ -0,0 +1,112 @@
+use axum::{http::{header, HeaderMap, HeaderValue, StatusCode}, response::{IntoResponse, Response}};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+/// `caching` section of the deployment config: the `Cache-Control` policy of each cacheable route. Every
+/// response from these routes also carries an `ETag` and `Last-Modified`, and conditional requests get a 304.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct CachingConfig {
+    /// `GET /api/lifecycle/{id}/stage/{stage_index}/image`
+    pub image: CachePolicy,
+    /// `GET /api/lifecycle/{id}/thumbnails.png` / `.json` and per-stage `/thumbnail`
+    pub thumbnails: CachePolicy,
+    /// `GET /api/lifecycle/{id}/pdf`
+    pub export: CachePolicy,
+    /// `GET /api/lifecycle/{id}/stage/{stage_index}/assets/{asset_id}`; uploads never change once stored.
+    pub assets: CachePolicy,
+    /// `GET /api/images/{image_ref}`; content-addressed, so a URL always serves the same bytes.
+    pub image_files: CachePolicy,
+}
+
+impl Default for CachingConfig {
+    fn default() -> Self {
+        Self {
+            image: CachePolicy { max_age_secs: 300, ..CachePolicy::default() },
+            thumbnails: CachePolicy { max_age_secs: 300, ..CachePolicy::default() },
+            export: CachePolicy::default(),
+            assets: CachePolicy { max_age_secs: 86_400, ..CachePolicy::default() },
+            image_files: CachePolicy { max_age_secs: 31_536_000, ..CachePolicy::default() },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct CachePolicy {
+    /// `max-age`; 0 means caches must revalidate (cheap thanks to the validators) before every reuse.
+    pub max_age_secs: u64,
+    /// `s-maxage`: lets a CDN keep the response longer than browsers.
+    pub shared_max_age_secs: Option<u64>,
+    /// Browser-only caching (`private`); CDNs won't store the response.
+    pub private: bool,
+    /// `no-store`: disable caching for the route entirely.
+    pub no_store: bool,
+}
+
+impl CachePolicy {
+    pub fn header_value(&self) -> String {
+        if self.no_store {
+            return "no-store".to_string();
+        }
+        let mut directives = vec![if self.private { "private".to_string() } else { "public".to_string() }, format!("max-age={}", self.max_age_secs)];
+        if let Some(s) = self.shared_max_age_secs.filter(|_| !self.private) {
+            directives.push(format!("s-maxage={}", s));
+        }
+        if self.max_age_secs == 0 {
+            directives.push("must-revalidate".to_string());
+        }
+        directives.join(", ")
+    }
+}
+
+/// `ETag` / `Last-Modified` pair for one representation of a resource.
+pub struct Validators {
+    etag: String,
+    last_modified: DateTime<Utc>,
+}
+
+impl Validators {
+    /// `content` is whatever identifies the representation: the body itself when it is cheap and deterministic,
+    /// or the inputs it is rendered from otherwise.
+    pub fn new(content: &[u8], last_modified: DateTime<Utc>) -> Self {
+        let digest = Sha256::digest(content);
+        let etag = format!("\"{}\"", digest.iter().take(16).map(|b| format!("{:02x}", b)).collect::<String>());
+        Self { etag, last_modified }
+    }
+
+    /// Whether the client's cached copy (per `If-None-Match`, else `If-Modified-Since`) is still current.
+    pub fn is_fresh(&self, request: &HeaderMap) -> bool {
+        if let Some(if_none_match) = request.get(header::IF_NONE_MATCH).and_then(|v| v.to_str().ok()) {
+            return if_none_match.split(',').map(|t| t.trim().trim_start_matches("W/")).any(|t| t == "*" || t == self.etag);
+        }
+        request.get(header::IF_MODIFIED_SINCE)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| DateTime::parse_from_rfc2822(v).ok())
+            .is_some_and(|since| self.last_modified.timestamp() <= since.timestamp())
+    }
+
+    fn insert_headers(&self, policy: &CachePolicy, headers: &mut HeaderMap) {
+        let last_modified = self.last_modified.format("%a, %d %b %Y %H:%M:%S GMT").to_string();
+        for (name, value) in [(header::ETAG, &self.etag), (header::LAST_MODIFIED, &last_modified), (header::CACHE_CONTROL, &policy.header_value())] {
+            if let Ok(value) = HeaderValue::from_str(value) {
+                headers.insert(name, value);
+            }
+        }
+    }
+
+    /// 304 carrying the validators and policy, for when [`Validators::is_fresh`] holds.
+    pub fn not_modified(&self, policy: &CachePolicy) -> Response {
+        let mut response = StatusCode::NOT_MODIFIED.into_response();
+        self.insert_headers(policy, response.headers_mut());
+        response
+    }
+
+    /// Attach the validators and policy to a full response.
+    pub fn apply(&self, policy: &CachePolicy, response: impl IntoResponse) -> Response {
+        let mut response = response.into_response();
+        self.insert_headers(policy, response.headers_mut());
+        response
+    }
+}
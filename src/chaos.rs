@@ -0,0 +1,141 @@
+use async_trait::async_trait;
+use rand::Rng;
+use std::{sync::Arc, time::Duration};
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::{
+    gemini::GeminiError,
+    models::Lifecycle,
+    repository::{LifecycleRepository, StoreError},
+};
+
+/// Fault injection for resilience testing (`CHAOS_MODE=true`; never enable in production). Provider calls
+/// randomly fail with a 429 or hang and time out, and store operations randomly slow down, so the retry, circuit
+/// breaker and placeholder fallbacks can be exercised in integration tests and staging.
+pub struct Chaos {
+    /// `CHAOS_PROVIDER_429_RATE` (default 0.1): share of provider calls answered with a synthetic 429.
+    provider_429_rate: f64,
+    /// `CHAOS_PROVIDER_TIMEOUT_RATE` (default 0.05): share of provider calls that stall, then fail as a timeout.
+    provider_timeout_rate: f64,
+    /// `CHAOS_PROVIDER_TIMEOUT_MS` (default 10000): how long a stalled call hangs.
+    provider_timeout: Duration,
+    /// `CHAOS_STORE_LATENCY_RATE` (default 0.2): share of store operations that are delayed.
+    store_latency_rate: f64,
+    /// `CHAOS_STORE_LATENCY_MS` (default 500): upper bound of the added delay.
+    store_latency: Duration,
+}
+
+/// What an injected provider failure looks like to the caller.
+pub enum ProviderFault {
+    /// Answer as if the provider returned 429 with `Retry-After: 1`.
+    RateLimited,
+    /// The call already stalled for the timeout; fail it as a network error.
+    TimedOut,
+}
+
+impl ProviderFault {
+    pub fn into_error(self) -> GeminiError {
+        match self {
+            ProviderFault::RateLimited => GeminiError::Api { status: 429, body: "injected rate limit (chaos mode)".to_string() },
+            ProviderFault::TimedOut => GeminiError::Http("operation timed out (chaos mode)".to_string()),
+        }
+    }
+}
+
+impl Chaos {
+    /// `None` unless `CHAOS_MODE=true`.
+    pub fn from_env() -> Option<Self> {
+        if !std::env::var("CHAOS_MODE").map(|v| v == "true" || v == "1").unwrap_or(false) {
+            return None;
+        }
+        let rate = |name: &str, default: f64| std::env::var(name).ok().and_then(|v| v.parse::<f64>().ok()).unwrap_or(default).clamp(0.0, 1.0);
+        let millis = |name: &str, default: u64| Duration::from_millis(std::env::var(name).ok().and_then(|v| v.parse().ok()).unwrap_or(default));
+        let chaos = Self {
+            provider_429_rate: rate("CHAOS_PROVIDER_429_RATE", 0.1),
+            provider_timeout_rate: rate("CHAOS_PROVIDER_TIMEOUT_RATE", 0.05),
+            provider_timeout: millis("CHAOS_PROVIDER_TIMEOUT_MS", 10_000),
+            store_latency_rate: rate("CHAOS_STORE_LATENCY_RATE", 0.2),
+            store_latency: millis("CHAOS_STORE_LATENCY_MS", 500),
+        };
+        warn!(
+            "🐒 CHAOS MODE: injecting provider 429s ({:.0}%), timeouts ({:.0}%, {:?}) and store latency ({:.0}%, up to {:?})",
+            chaos.provider_429_rate * 100.0, chaos.provider_timeout_rate * 100.0, chaos.provider_timeout,
+            chaos.store_latency_rate * 100.0, chaos.store_latency,
+        );
+        Some(chaos)
+    }
+
+    /// Roll for a provider failure before a real call goes out. Timeouts stall here before being reported.
+    pub async fn provider_fault(&self, provider: &str, model: &str) -> Option<ProviderFault> {
+        let roll = rand::thread_rng().gen_range(0.0..1.0);
+        if roll < self.provider_429_rate {
+            warn!("🐒 Injecting 429 into {} {} call", provider, model);
+            return Some(ProviderFault::RateLimited);
+        }
+        if roll < self.provider_429_rate + self.provider_timeout_rate {
+            warn!("🐒 Stalling {} {} call for {:?}", provider, model, self.provider_timeout);
+            tokio::time::sleep(self.provider_timeout).await;
+            return Some(ProviderFault::TimedOut);
+        }
+        None
+    }
+
+    async fn store_delay(&self) {
+        let delay = {
+            let mut rng = rand::thread_rng();
+            if !rng.gen_bool(self.store_latency_rate) {
+                return;
+            }
+            self.store_latency.mul_f64(rng.gen_range(0.0..=1.0))
+        };
+        tokio::time::sleep(delay).await;
+    }
+}
+
+/// Delays a random share of store operations before passing them to `inner`.
+pub struct ChaosRepository {
+    inner: Arc<dyn LifecycleRepository>,
+    chaos: Arc<Chaos>,
+}
+
+impl ChaosRepository {
+    pub fn wrap(inner: Arc<dyn LifecycleRepository>, chaos: Option<Arc<Chaos>>) -> Arc<dyn LifecycleRepository> {
+        match chaos {
+            Some(chaos) => Arc::new(Self { inner, chaos }),
+            None => inner,
+        }
+    }
+}
+
+#[async_trait]
+impl LifecycleRepository for ChaosRepository {
+    async fn insert(&self, lifecycle: &Lifecycle) -> Result<(), StoreError> {
+        self.chaos.store_delay().await;
+        self.inner.insert(lifecycle).await
+    }
+
+    async fn get(&self, id: Uuid) -> Result<Option<Lifecycle>, StoreError> {
+        self.chaos.store_delay().await;
+        self.inner.get(id).await
+    }
+
+    async fn update(&self, id: Uuid, f: &mut (dyn for<'l> FnMut(&'l mut Lifecycle) -> bool + Send)) -> Result<bool, StoreError> {
+        self.chaos.store_delay().await;
+        self.inner.update(id, f).await
+    }
+
+    async fn delete(&self, id: Uuid) -> Result<bool, StoreError> {
+        self.chaos.store_delay().await;
+        self.inner.delete(id).await
+    }
+
+    async fn list(&self) -> Result<Vec<Lifecycle>, StoreError> {
+        self.chaos.store_delay().await;
+        self.inner.list().await
+    }
+
+    async fn flush(&self) -> Result<(), StoreError> {
+        self.inner.flush().await
+    }
+}
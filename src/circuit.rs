@@ -0,0 +1,118 @@
+use chrono::{DateTime, Duration, Utc};
+use parking_lot::Mutex;
+use serde::Serialize;
+use tracing::{info, warn};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BreakerState {
+    /// Calls go through.
+    Closed,
+    /// Calls fail fast (stages get placeholders) until the cooldown has passed.
+    Open,
+    /// One probe call is in flight; its outcome closes or re-opens the circuit.
+    HalfOpen,
+}
+
+/// Breaker state as reported by `GET /api/health`.
+#[derive(Debug, Serialize)]
+pub struct BreakerSnapshot {
+    pub state: BreakerState,
+    pub consecutive_failures: u32,
+    pub failure_threshold: u32,
+    pub cooldown_secs: i64,
+    pub opened_at: Option<DateTime<Utc>>,
+    /// When the next probe call is let through (open circuits only).
+    pub next_probe_at: Option<DateTime<Utc>>,
+}
+
+struct Inner {
+    state: BreakerState,
+    consecutive_failures: u32,
+    opened_at: Option<DateTime<Utc>>,
+    probe_started_at: Option<DateTime<Utc>>,
+}
+
+/// Circuit breaker for provider calls: opens after `GEMINI_BREAKER_THRESHOLD` (default 5; 0 disables)
+/// consecutive failed calls and lets a single probe through every `GEMINI_BREAKER_COOLDOWN_SECS` (default 30)
+/// until one succeeds. A failed call is one that still failed after its retries.
+pub struct CircuitBreaker {
+    failure_threshold: u32,
+    cooldown: Duration,
+    inner: Mutex<Inner>,
+}
+
+impl CircuitBreaker {
+    pub fn from_env() -> Self {
+        let read = |name: &str, default: u32| std::env::var(name).ok().and_then(|v| v.parse().ok()).unwrap_or(default);
+        Self {
+            failure_threshold: read("GEMINI_BREAKER_THRESHOLD", 5),
+            cooldown: Duration::seconds(read("GEMINI_BREAKER_COOLDOWN_SECS", 30).max(1) as i64),
+            inner: Mutex::new(Inner { state: BreakerState::Closed, consecutive_failures: 0, opened_at: None, probe_started_at: None }),
+        }
+    }
+
+    /// Whether a call may go out now. Once the cooldown has passed the first caller becomes the probe
+    /// (half-open); everyone else keeps failing fast until it reports back.
+    pub fn allow(&self) -> bool {
+        if self.failure_threshold == 0 {
+            return true;
+        }
+        let mut inner = self.inner.lock();
+        let now = Utc::now();
+        match inner.state {
+            BreakerState::Closed => true,
+            BreakerState::Open if inner.opened_at.is_some_and(|at| now < at + self.cooldown) => false,
+            // A probe whose caller went away never reports back; let another one through after a cooldown.
+            BreakerState::HalfOpen if inner.probe_started_at.is_some_and(|at| now < at + self.cooldown) => false,
+            BreakerState::Open | BreakerState::HalfOpen => {
+                info!("🔌 Circuit half-open, probing the provider");
+                inner.state = BreakerState::HalfOpen;
+                inner.probe_started_at = Some(now);
+                true
+            }
+        }
+    }
+
+    pub fn record_success(&self) {
+        let mut inner = self.inner.lock();
+        if inner.state != BreakerState::Closed {
+            info!("🔌 Circuit closed, provider calls resumed");
+        }
+        inner.state = BreakerState::Closed;
+        inner.consecutive_failures = 0;
+        inner.opened_at = None;
+        inner.probe_started_at = None;
+    }
+
+    pub fn record_failure(&self) {
+        if self.failure_threshold == 0 {
+            return;
+        }
+        let mut inner = self.inner.lock();
+        inner.consecutive_failures += 1;
+        let trips = match inner.state {
+            BreakerState::Closed => inner.consecutive_failures >= self.failure_threshold,
+            BreakerState::HalfOpen => true,
+            BreakerState::Open => false,
+        };
+        if trips {
+            warn!("🔌 Circuit open after {} consecutive failures; failing fast for {}s", inner.consecutive_failures, self.cooldown.num_seconds());
+            inner.state = BreakerState::Open;
+            inner.opened_at = Some(Utc::now());
+            inner.probe_started_at = None;
+        }
+    }
+
+    pub fn snapshot(&self) -> BreakerSnapshot {
+        let inner = self.inner.lock();
+        BreakerSnapshot {
+            state: inner.state,
+            consecutive_failures: inner.consecutive_failures,
+            failure_threshold: self.failure_threshold,
+            cooldown_secs: self.cooldown.num_seconds(),
+            opened_at: inner.opened_at,
+            next_probe_at: (inner.state == BreakerState::Open).then(|| inner.opened_at.map(|at| at + self.cooldown)).flatten(),
+        }
+    }
+}
@@ -0,0 +1,129 @@
+use chrono::{DateTime, Utc};
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::HashMap;
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+use crate::models::{Lifecycle, StageFingerprint};
+
+/// Events buffered per viewer before a slow WebSocket client starts missing some.
+const CHANNEL_CAPACITY: usize = 256;
+
+/// What happened to a lifecycle, as sent on `GET /api/lifecycle/{id}/ws`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum CollabEventKind {
+    StageAdded { stage_index: usize, stage_name: String },
+    StageRemoved { stage_name: String },
+    /// Stage names in their new order.
+    StagesReordered { stages: Vec<String> },
+    /// New image: generated again, a candidate picked or a previous image restored.
+    StageRegenerated { stage_index: usize, stage_name: String },
+    DescriptionEdited { stage_index: usize, stage_name: String },
+    /// Anything else in a stage: name, actors, locations, assets, economics, ...
+    StageUpdated { stage_index: usize, stage_name: String },
+    /// Lifecycle-level fields only: review status, constraints, tags, ...
+    LifecycleUpdated,
+    Deleted,
+    /// Someone opened or closed the lifecycle; `count` is who is connected now.
+    Viewers { count: usize },
+}
+
+/// Published after the change has been stored, so a client reacting to it with `GET /api/lifecycle/{id}` sees
+/// the new data.
+#[derive(Debug, Clone, Serialize)]
+pub struct CollabEvent {
+    pub lifecycle_id: Uuid,
+    /// Revision the change produced; the current one for `viewers`, none for `deleted`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub revision: Option<u64>,
+    pub at: DateTime<Utc>,
+    #[serde(flatten)]
+    pub kind: CollabEventKind,
+}
+
+/// Fan-out of lifecycle mutations and presence to WebSocket viewers (this instance only).
+pub struct CollabHub {
+    sender: broadcast::Sender<CollabEvent>,
+    viewers: Mutex<HashMap<Uuid, usize>>,
+}
+
+impl Default for CollabHub {
+    fn default() -> Self {
+        Self { sender: broadcast::channel(CHANNEL_CAPACITY).0, viewers: Mutex::default() }
+    }
+}
+
+impl CollabHub {
+    /// Whether anyone is listening; working out what a change did is skipped otherwise.
+    pub fn has_subscribers(&self) -> bool {
+        self.sender.receiver_count() > 0
+    }
+
+    pub fn publish(&self, lifecycle_id: Uuid, revision: Option<u64>, kinds: Vec<CollabEventKind>) {
+        let at = Utc::now();
+        for kind in kinds {
+            // No subscribers is the common case and not an error.
+            let _ = self.sender.send(CollabEvent { lifecycle_id, revision, at, kind });
+        }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<CollabEvent> {
+        self.sender.subscribe()
+    }
+
+    /// Count a viewer in (`joined`) or out and tell everyone on the lifecycle. Returns the new count.
+    pub fn presence(&self, lifecycle_id: Uuid, revision: u64, joined: bool) -> usize {
+        let count = {
+            let mut viewers = self.viewers.lock();
+            let count = viewers.entry(lifecycle_id).or_default();
+            *count = if joined { *count + 1 } else { count.saturating_sub(1) };
+            let count = *count;
+            if count == 0 { viewers.remove(&lifecycle_id); }
+            count
+        };
+        self.publish(lifecycle_id, Some(revision), vec![CollabEventKind::Viewers { count }]);
+        count
+    }
+}
+
+/// What a stored change did, from the stages before it and the lifecycle after `stamp_stage_revisions`.
+pub fn describe_change(before: &[StageFingerprint], after: &Lifecycle) -> Vec<CollabEventKind> {
+    let revision = after.revision;
+    let names: Vec<&str> = after.stages.iter().map(|stage| stage.stage_name.as_str()).collect();
+    let mut kinds = Vec::new();
+    if after.stages.len() < before.len() {
+        kinds.extend(before.iter().filter(|b| !names.contains(&b.name())).map(|b| CollabEventKind::StageRemoved { stage_name: b.name().to_string() }));
+    }
+    let previous: Vec<&str> = before.iter().map(StageFingerprint::name).collect();
+    let (mut previous_sorted, mut names_sorted) = (previous.clone(), names.clone());
+    previous_sorted.sort_unstable();
+    names_sorted.sort_unstable();
+    if previous != names && previous_sorted == names_sorted {
+        kinds.push(CollabEventKind::StagesReordered { stages: names.iter().map(|name| name.to_string()).collect() });
+    }
+    for (stage_index, stage) in after.stages.iter().enumerate() {
+        let revisions = &stage.revisions;
+        if revisions.changed != revision { continue; }
+        let stage_name = stage.stage_name.clone();
+        if revisions.added == revision {
+            kinds.push(CollabEventKind::StageAdded { stage_index, stage_name });
+            continue;
+        }
+        let before = kinds.len();
+        if revisions.image == revision {
+            kinds.push(CollabEventKind::StageRegenerated { stage_index, stage_name: stage_name.clone() });
+        }
+        if revisions.description == revision {
+            kinds.push(CollabEventKind::DescriptionEdited { stage_index, stage_name: stage_name.clone() });
+        }
+        if kinds.len() == before {
+            kinds.push(CollabEventKind::StageUpdated { stage_index, stage_name });
+        }
+    }
+    if kinds.is_empty() {
+        kinds.push(CollabEventKind::LifecycleUpdated);
+    }
+    kinds
+}
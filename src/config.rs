@@ -0,0 +1,45 @@
+use serde::Deserialize;
+use tracing::{info, warn};
+
+use crate::{caching::CachingConfig, export::ExportConfig, glossary::Glossary, model_aliases::ModelAliases, palette::Palette, retention::RetentionConfig, rules::CategoryRule, tenants::TenantPolicies};
+
+/// Deployment configuration loaded from the JSON file named by `CONFIG_FILE`. Every section is optional so an
+/// empty (or missing) file yields the stock behaviour.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct AppConfig {
+    pub glossary: Glossary,
+    pub export: ExportConfig,
+    /// Adjustments to the default stage list per detected product category.
+    pub category_rules: Vec<CategoryRule>,
+    pub retention: RetentionConfig,
+    pub caching: CachingConfig,
+    /// Logical model names ("image-default", "text-default") → provider model ids.
+    pub models: ModelAliases,
+    /// Colors of generated graphics, contrast-checked.
+    pub palette: Palette,
+    /// Default and mandatory constraints per tenant.
+    pub tenants: TenantPolicies,
+}
+
+impl AppConfig {
+    pub fn load() -> Self {
+        let Ok(path) = std::env::var("CONFIG_FILE") else { return Self::default() };
+        match std::fs::read_to_string(&path) {
+            Ok(raw) => match serde_json::from_str(&raw) {
+                Ok(config) => {
+                    info!("⚙️ Loaded config from {}", path);
+                    config
+                }
+                Err(e) => {
+                    warn!("⚠️ Invalid config file {}: {}; using defaults", path, e);
+                    Self::default()
+                }
+            },
+            Err(e) => {
+                warn!("⚠️ Could not read config file {}: {}; using defaults", path, e);
+                Self::default()
+            }
+        }
+    }
+}
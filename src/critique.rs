@@ -0,0 +1,94 @@
+use crate::{gemini::{GeminiClient, GeminiError, TEXT_MODEL}, vision::parse_json_reply};
+use serde::Deserialize;
+use serde_json::json;
+use tracing::{info, warn};
+
+/// `DESCRIPTION_CRITIQUE`: what the self-critique pass does with a freshly generated description.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CritiqueMode {
+    #[default]
+    Off,
+    /// Keep the text, attach flagged sentences as stage warnings.
+    Flag,
+    /// Replace the text with the model's revision (flags are still kept as warnings if no revision comes back).
+    Revise,
+}
+
+impl CritiqueMode {
+    pub fn from_env() -> Self {
+        match std::env::var("DESCRIPTION_CRITIQUE").unwrap_or_default().to_lowercase().as_str() {
+            "flag" => CritiqueMode::Flag,
+            "revise" => CritiqueMode::Revise,
+            _ => CritiqueMode::Off,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct CritiqueResponse {
+    #[serde(default)]
+    flagged: Vec<FlaggedSentence>,
+    #[serde(default)]
+    revised: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct FlaggedSentence {
+    sentence: String,
+    issue: String,
+}
+
+/// Prefix of the stage warnings produced by the critique pass.
+pub const UNSUPPORTED_CLAIM_PREFIX: &str = "Unsupported or greenwashing claim:";
+
+/// Outcome of the critique pass: the (possibly revised) description plus warnings for the stage.
+pub struct CritiquedText {
+    pub text: String,
+    pub warnings: Vec<String>,
+}
+
+impl GeminiClient {
+    pub async fn critique_description(&self, stage: &str, product: &str, description: String) -> CritiquedText {
+        if self.critique == CritiqueMode::Off || self.is_demo() {
+            return CritiquedText { text: description, warnings: Vec::new() };
+        }
+        match self.request_critique(stage, product, &description).await {
+            Ok(critique) => {
+                info!("🧐 Critique of stage '{}' flagged {} sentence(s)", stage, critique.flagged.len());
+                if critique.flagged.is_empty() {
+                    return CritiquedText { text: description, warnings: Vec::new() };
+                }
+                if self.critique == CritiqueMode::Revise && !critique.revised.trim().is_empty() {
+                    return CritiquedText { text: critique.revised.trim().to_string(), warnings: Vec::new() };
+                }
+                let warnings = critique.flagged.into_iter()
+                    .map(|f| format!("{} \"{}\" ({})", UNSUPPORTED_CLAIM_PREFIX, f.sentence, f.issue))
+                    .collect();
+                CritiquedText { text: description, warnings }
+            }
+            Err(e) => {
+                warn!("⚠️ Critique of stage '{}' failed, keeping original description: {}", stage, e);
+                CritiquedText { text: description, warnings: Vec::new() }
+            }
+        }
+    }
+
+    async fn request_critique(&self, stage: &str, product: &str, description: &str) -> Result<CritiqueResponse, GeminiError> {
+        let instruction = format!(
+            "You are a sustainability fact-checker. Review this description of the {stage} stage in the lifecycle of {product}.\n\
+            Flag sentences that make unsupported quantitative claims, absolute claims (\"zero impact\", \"100% sustainable\"), \
+            vague greenwashing language (\"eco-friendly\", \"green\", \"natural\") or statements that are likely false.\n\
+            Then rewrite the description fixing those sentences while keeping its structure (3 paragraphs separated by a blank line).\n\
+            Reply with JSON only: {{\"flagged\": [{{\"sentence\": \"...\", \"issue\": \"...\"}}], \"revised\": \"...\"}}\n\n\
+            Description:\n{description}"
+        );
+        let payload = json!({
+            "contents": [{ "parts": [{"text": instruction}] }],
+            "generationConfig": { "temperature": 0.2, "responseMimeType": "application/json" }
+        });
+        let reply = self.generate_content(TEXT_MODEL, &payload).await?
+            .first_text()
+            .ok_or_else(|| GeminiError::Other("No text content found in response".to_string()))?;
+        parse_json_reply(&reply)
+    }
+}
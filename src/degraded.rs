@@ -0,0 +1,104 @@
+use axum::{extract::{Request, State}, http::HeaderValue, middleware::Next, response::Response};
+use chrono::{DateTime, Duration, Utc};
+use parking_lot::Mutex;
+use serde::Serialize;
+use tracing::{info, warn};
+
+use crate::routes::AppState;
+
+/// Response header set on every response while degraded mode is active; the frontend shows a banner for it.
+pub const DEGRADED_HEADER: &str = "x-degraded-mode";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DegradedReason {
+    /// The provider rejected the API key.
+    InvalidKey,
+    /// The key's quota is used up; retrying won't help until it resets.
+    QuotaExhausted,
+}
+
+impl DegradedReason {
+    /// Classify a final (post-retry) provider error; `None` for errors that say nothing about the key itself.
+    pub fn classify(status: u16, body: &str) -> Option<Self> {
+        match status {
+            401 | 403 => Some(DegradedReason::InvalidKey),
+            400 if body.contains("API_KEY_INVALID") || body.contains("API key not valid") => Some(DegradedReason::InvalidKey),
+            429 if body.to_lowercase().contains("quota") => Some(DegradedReason::QuotaExhausted),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DegradedReason::InvalidKey => "invalid_key",
+            DegradedReason::QuotaExhausted => "quota_exhausted",
+        }
+    }
+}
+
+/// Degraded mode as reported by `GET /readyz` and `GET /api/health`.
+#[derive(Debug, Clone, Serialize)]
+pub struct DegradedSnapshot {
+    pub reason: DegradedReason,
+    pub since: DateTime<Utc>,
+    /// After this, provider calls are tried again; the first success leaves degraded mode.
+    pub recheck_at: DateTime<Utc>,
+}
+
+/// Automatic downgrade to demo behaviour when the Gemini key turns out to be invalid or out of quota at runtime
+/// (`GEMINI_AUTO_DEGRADE`, default true). While active, stages get placeholders straight away instead of every
+/// request waiting through a failing provider call. Calls are tried again every `GEMINI_DEGRADED_RECHECK_SECS`
+/// (default 300); a success switches back, another key failure extends the downgrade.
+pub struct DegradedMode {
+    enabled: bool,
+    recheck: Duration,
+    current: Mutex<Option<DegradedSnapshot>>,
+}
+
+impl DegradedMode {
+    pub fn from_env() -> Self {
+        Self {
+            enabled: std::env::var("GEMINI_AUTO_DEGRADE").map(|v| v != "false" && v != "0").unwrap_or(true),
+            recheck: Duration::seconds(std::env::var("GEMINI_DEGRADED_RECHECK_SECS").ok().and_then(|v| v.parse().ok()).unwrap_or(300).max(1)),
+            current: Mutex::new(None),
+        }
+    }
+
+    /// Reason provider calls should be skipped right now; `None` once the recheck time has come.
+    pub fn active(&self) -> Option<DegradedReason> {
+        self.current.lock().as_ref().filter(|s| Utc::now() < s.recheck_at).map(|s| s.reason)
+    }
+
+    pub fn snapshot(&self) -> Option<DegradedSnapshot> {
+        self.current.lock().clone()
+    }
+
+    pub fn enter(&self, reason: DegradedReason) {
+        if !self.enabled {
+            return;
+        }
+        let now = Utc::now();
+        let mut current = self.current.lock();
+        let since = current.as_ref().map_or(now, |s| s.since);
+        if current.is_none() {
+            warn!("🪫 Gemini key failure ({}); serving placeholders in degraded mode until {}", reason.as_str(), now + self.recheck);
+        }
+        *current = Some(DegradedSnapshot { reason, since, recheck_at: now + self.recheck });
+    }
+
+    pub fn record_success(&self) {
+        if self.current.lock().take().is_some() {
+            info!("🔋 Gemini call succeeded, leaving degraded mode");
+        }
+    }
+}
+
+/// Tag every response with `X-Degraded-Mode: <reason>` while degraded mode is active.
+pub async fn banner(State(state): State<AppState>, req: Request, next: Next) -> Response {
+    let mut response = next.run(req).await;
+    if let Some(snapshot) = state.gemini.degraded.snapshot() {
+        response.headers_mut().insert(DEGRADED_HEADER, HeaderValue::from_static(snapshot.reason.as_str()));
+    }
+    response
+}
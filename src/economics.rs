@@ -0,0 +1,108 @@
+use crate::{
+    gemini::{GeminiClient, GeminiError, TEXT_MODEL},
+    models::{Confidence, CostComponent, Estimate, EstimateSource, Lifecycle, StageEconomics},
+    vision::parse_json_reply,
+};
+use serde::Deserialize;
+use serde_json::json;
+use tracing::info;
+
+#[derive(Debug, Deserialize)]
+struct EconomicsReply {
+    stages: Vec<StageEconomicsReply>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StageEconomicsReply {
+    stage: String,
+    cost_share_pct: EstimateReply,
+    #[serde(default)]
+    components: Vec<CostComponentReply>,
+    impact_score: EstimateReply,
+}
+
+#[derive(Debug, Deserialize)]
+struct CostComponentReply {
+    name: String,
+    share_pct: EstimateReply,
+}
+
+/// Models sometimes answer with a bare number despite being asked for a range; accept both.
+#[derive(Debug, Deserialize, Clone, Copy)]
+#[serde(untagged)]
+enum EstimateReply {
+    Ranged { value: f32, low: f32, high: f32, confidence: Confidence },
+    Plain(f32),
+}
+
+impl From<EstimateReply> for Estimate {
+    fn from(reply: EstimateReply) -> Self {
+        match reply {
+            EstimateReply::Ranged { value, low, high, confidence } => Estimate { value, low, high, confidence },
+            EstimateReply::Plain(value) => Estimate::unknown(value),
+        }
+    }
+}
+
+impl GeminiClient {
+    /// Rough cost-of-goods composition and relative environmental impact for every stage, in stage order.
+    /// Demo mode returns an even split marked as a placeholder.
+    pub async fn estimate_economics(&self, lifecycle: &Lifecycle) -> Result<Vec<StageEconomics>, GeminiError> {
+        let stage_names: Vec<&str> = lifecycle.stages.iter().map(|s| s.stage_name.as_str()).collect();
+        if self.is_demo() {
+            let share = 100.0 / stage_names.len().max(1) as f32;
+            return Ok(stage_names.iter().map(|_| StageEconomics {
+                cost_share_pct: Estimate::unknown(share),
+                components: Vec::new(),
+                impact_score: Estimate::unknown(5.0),
+                source: EstimateSource::Placeholder,
+            }).collect());
+        }
+
+        let instruction = format!(
+            "You are a product cost and life-cycle analyst. For the product \"{}\" consider these lifecycle stages: {}.\n\
+            For each stage estimate (1) its rough share of the total cost of goods over the lifecycle in percent (central values of all stages sum to 100), \
+            (2) up to 4 main cost components with their share of that stage's cost in percent, and \
+            (3) a relative environmental impact score from 0 (negligible) to 10 (dominant).\n\
+            Every number must be an estimate object {{\"value\": <best guess>, \"low\": <plausible minimum>, \"high\": <plausible maximum>, \"confidence\": \"low\"|\"medium\"|\"high\"}}; \
+            use wide ranges and low confidence where data is scarce.\n\
+            Reply with JSON only: {{\"stages\": [{{\"stage\": \"<stage name>\", \"cost_share_pct\": <estimate>, \
+            \"components\": [{{\"name\": \"...\", \"share_pct\": <estimate>}}], \"impact_score\": <estimate>}}]}}",
+            lifecycle.product_description,
+            stage_names.join(", ")
+        );
+        let payload = json!({
+            "contents": [{ "parts": [{"text": instruction}] }],
+            "generationConfig": { "temperature": 0.2, "responseMimeType": "application/json" }
+        });
+        let reply = self.generate_content(TEXT_MODEL, &payload).await?
+            .first_text()
+            .ok_or_else(|| GeminiError::Other("No text content found in response".to_string()))?;
+        let parsed: EconomicsReply = parse_json_reply(&reply)?;
+        info!("💰 Economics estimated for {} stage(s)", parsed.stages.len());
+
+        // Match by name, falling back to position, so a renamed stage in the reply still lines up.
+        let total: f32 = parsed.stages.iter().map(|s| Estimate::from(s.cost_share_pct).value.max(0.0)).sum::<f32>().max(f32::EPSILON);
+        let normalise = 100.0 / total;
+        Ok(stage_names.iter().enumerate().map(|(i, name)| {
+            let reply = parsed.stages.iter().find(|s| s.stage.eq_ignore_ascii_case(name)).or_else(|| parsed.stages.get(i));
+            match reply {
+                Some(r) => StageEconomics {
+                    cost_share_pct: Estimate::from(r.cost_share_pct).clamped(0.0, f32::MAX).scaled(normalise).clamped(0.0, 100.0),
+                    components: r.components.iter().map(|c| CostComponent {
+                        name: c.name.clone(),
+                        share_pct: Estimate::from(c.share_pct).clamped(0.0, 100.0),
+                    }).collect(),
+                    impact_score: Estimate::from(r.impact_score).clamped(0.0, 10.0),
+                    source: EstimateSource::Model,
+                },
+                None => StageEconomics {
+                    cost_share_pct: Estimate::unknown(0.0),
+                    components: Vec::new(),
+                    impact_score: Estimate::unknown(0.0),
+                    source: EstimateSource::Placeholder,
+                },
+            }
+        }).collect())
+    }
+}
@@ -0,0 +1,96 @@
+use axum::{body::to_bytes, extract::Request, http::{header, StatusCode}, middleware::Next, response::{IntoResponse, Response}, Json};
+use serde_json::{json, Value};
+
+use crate::{gemini::{GeminiError, PROVIDER}, repository::StoreError};
+
+/// Error body shared by every route: `{"error": {"code", "message", "details"}}`. `code` is a stable
+/// machine-readable string; `message` is for humans and may change.
+#[derive(Debug, Clone)]
+pub struct ApiError {
+    pub status: StatusCode,
+    pub code: &'static str,
+    pub message: String,
+    pub details: Option<Value>,
+}
+
+impl ApiError {
+    pub fn new(status: StatusCode, code: &'static str, message: impl Into<String>) -> Self {
+        Self { status, code, message: message.into(), details: None }
+    }
+
+    pub fn with_details(mut self, details: Value) -> Self {
+        self.details = Some(details);
+        self
+    }
+
+    /// `{"error": {...}}`, also embedded in streamed events that report a failure.
+    pub fn body(&self) -> Value {
+        json!({ "error": { "code": self.code, "message": self.message, "details": self.details } })
+    }
+}
+
+fn code_for(status: StatusCode) -> &'static str {
+    match status {
+        StatusCode::BAD_REQUEST => "bad_request",
+        StatusCode::UNAUTHORIZED => "unauthorized",
+        StatusCode::FORBIDDEN => "forbidden",
+        StatusCode::NOT_FOUND => "not_found",
+        StatusCode::METHOD_NOT_ALLOWED => "method_not_allowed",
+        StatusCode::REQUEST_TIMEOUT => "request_timeout",
+        StatusCode::CONFLICT => "conflict",
+        StatusCode::PAYLOAD_TOO_LARGE => "payload_too_large",
+        StatusCode::UNSUPPORTED_MEDIA_TYPE => "unsupported_media_type",
+        StatusCode::UNPROCESSABLE_ENTITY => "unprocessable",
+        StatusCode::TOO_MANY_REQUESTS => "rate_limited",
+        StatusCode::BAD_GATEWAY => "provider_error",
+        s if s.is_server_error() => "internal",
+        _ => "error",
+    }
+}
+
+impl From<StatusCode> for ApiError {
+    fn from(status: StatusCode) -> Self {
+        Self::new(status, code_for(status), status.canonical_reason().unwrap_or("Error"))
+    }
+}
+
+impl From<StoreError> for ApiError {
+    fn from(e: StoreError) -> Self {
+        tracing::error!("❌ Lifecycle store error: {}", e);
+        // Database details stay in the log.
+        Self::new(StatusCode::INTERNAL_SERVER_ERROR, "storage_error", "The lifecycle store failed")
+    }
+}
+
+impl From<GeminiError> for ApiError {
+    fn from(e: GeminiError) -> Self {
+        let mut details = json!({ "provider": PROVIDER, "reason": e.reason() });
+        if let GeminiError::Api { status, .. } = &e {
+            details["provider_status"] = json!(status);
+        }
+        Self::new(StatusCode::BAD_GATEWAY, "provider_error", e.to_string()).with_details(details)
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.status, Json(self.body())).into_response()
+    }
+}
+
+/// Rewrites error responses produced outside our handlers (extractor rejections, unknown routes, body limits)
+/// into the `ApiError` shape, keeping their text as the message.
+pub async fn json_error_bodies(req: Request, next: Next) -> Response {
+    let response = next.run(req).await;
+    let status = response.status();
+    let is_json = response.headers().get(header::CONTENT_TYPE).and_then(|v| v.to_str().ok()).is_some_and(|v| v.starts_with("application/json"));
+    if !(status.is_client_error() || status.is_server_error()) || is_json {
+        return response;
+    }
+    let text = to_bytes(response.into_body(), 64 * 1024).await.map(|b| String::from_utf8_lossy(&b).trim().to_string()).unwrap_or_default();
+    let mut error = ApiError::from(status);
+    if !text.is_empty() {
+        error.message = text;
+    }
+    error.into_response()
+}
@@ -0,0 +1,69 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+use crate::models::{StageImage, StageStatus};
+
+/// Events buffered per subscriber before a slow SSE client starts missing some.
+const CHANNEL_CAPACITY: usize = 256;
+
+/// Which part of a stage just finished; used as the SSE event name.
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StagePart {
+    Image,
+    Description,
+}
+
+impl StagePart {
+    pub fn event_name(self) -> &'static str {
+        match self {
+            StagePart::Image => "image",
+            StagePart::Description => "description",
+        }
+    }
+}
+
+/// Published after the change has been stored, so a client reacting to it with `GET /api/lifecycle/{id}`
+/// sees the new data.
+#[derive(Debug, Clone, Serialize)]
+pub struct StageEvent {
+    pub lifecycle_id: Uuid,
+    pub stage_index: usize,
+    pub stage_name: String,
+    pub part: StagePart,
+    pub status: StageStatus,
+    pub at: DateTime<Utc>,
+}
+
+/// Fan-out of stage progress to `GET /api/lifecycle/{id}/events` subscribers (this instance only).
+pub struct StageEvents {
+    sender: broadcast::Sender<StageEvent>,
+}
+
+impl Default for StageEvents {
+    fn default() -> Self {
+        Self { sender: broadcast::channel(CHANNEL_CAPACITY).0 }
+    }
+}
+
+impl StageEvents {
+    pub fn publish(&self, lifecycle_id: Uuid, stage_index: usize, stage: &StageImage, parts: &[StagePart]) {
+        for &part in parts {
+            // No subscribers is the common case and not an error.
+            let _ = self.sender.send(StageEvent {
+                lifecycle_id,
+                stage_index,
+                stage_name: stage.stage_name.clone(),
+                part,
+                status: stage.status.clone(),
+                at: Utc::now(),
+            });
+        }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<StageEvent> {
+        self.sender.subscribe()
+    }
+}
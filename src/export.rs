@@ -0,0 +1,132 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::{models::Audience, theme::ExportTheme};
+
+/// Settings shared by every export format (PDF today, plus any text/HTML renderers).
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ExportConfig {
+    pub disclosures: Disclosures,
+    /// Named presets selectable with `?profile=` on export endpoints.
+    pub profiles: BTreeMap<String, ExportProfile>,
+    /// Named looks selectable with `?theme=` (or a profile's `theme`) on PDF and HTML exports.
+    pub themes: BTreeMap<String, ExportTheme>,
+    /// Encoded in the QR code on the PDF cover page, `{id}` replaced by the lifecycle id (e.g. a link into the UI).
+    pub cover_link: Option<String>,
+}
+
+impl ExportConfig {
+    /// The named profile, or the stock layout when no profile was asked for. `None` for unknown names.
+    pub fn profile(&self, name: Option<&str>) -> Option<ExportProfile> {
+        match name {
+            None => Some(ExportProfile::default()),
+            Some(name) => self.profiles.get(name).cloned(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportFormat {
+    #[default]
+    Pdf,
+}
+
+/// Paper size of PDF pages.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PageSize {
+    #[default]
+    A4,
+    /// US Letter, 8.5 x 11 in.
+    Letter,
+}
+
+impl PageSize {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::A4 => "a4",
+            Self::Letter => "letter",
+        }
+    }
+}
+
+/// Orientation of PDF pages; landscape stage pages put the details beside the image instead of below it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Orientation {
+    #[default]
+    Portrait,
+    Landscape,
+}
+
+impl Orientation {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Portrait => "portrait",
+            Self::Landscape => "landscape",
+        }
+    }
+}
+
+/// Format, layout and branding choices bundled under one name (e.g. "investor" or "regulatory") so clients
+/// don't have to stitch export options together.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExportProfile {
+    pub format: ExportFormat,
+    /// Cover page with the product, constraints, generation date and a QR code of the lifecycle.
+    pub cover_page: bool,
+    /// Page after the cover listing every section with its page number, each line linking to it.
+    pub table_of_contents: bool,
+    /// Overview page opening with the best generated stage image.
+    pub hero_image: bool,
+    /// Overview page listing every stage with the lead sentence of its description.
+    pub executive_summary: bool,
+    /// Include the deployment-wide `disclosures`.
+    pub disclosures: bool,
+    /// Extra notices for this profile, printed after the disclosures.
+    pub disclaimers: Vec<String>,
+    /// Closing page with every stage's cost share, impact score and cost components.
+    pub impact_appendix: bool,
+    /// Closing pages with every stage's exact prompt, generation parameters and provider calls, so readers of the
+    /// printed report can trace how each image was produced.
+    pub prompt_appendix: bool,
+    /// Narrative variant printed instead of the stage descriptions, where a stage has one.
+    pub narrative: Option<Audience>,
+    /// Theme (from `themes`) used unless the request picks another.
+    pub theme: Option<String>,
+    /// PDF paper size and orientation unless the request picks others.
+    pub page_size: PageSize,
+    pub orientation: Orientation,
+}
+
+impl Default for ExportProfile {
+    fn default() -> Self {
+        Self { format: ExportFormat::Pdf, cover_page: true, table_of_contents: true, hero_image: false, executive_summary: false, disclosures: true, disclaimers: Vec::new(), impact_appendix: false, prompt_appendix: false, narrative: None, theme: None, page_size: PageSize::A4, orientation: Orientation::Portrait }
+    }
+}
+
+/// Boilerplate appended to every exported report.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Disclosures {
+    pub legal: Vec<String>,
+    pub methodology: Vec<String>,
+    pub citations: Vec<String>,
+}
+
+impl Disclosures {
+    /// Non-empty sections as `(heading, paragraphs)`, in report order.
+    pub fn sections(&self) -> Vec<(&'static str, &[String])> {
+        [
+            ("Methodology", self.methodology.as_slice()),
+            ("Data sources", self.citations.as_slice()),
+            ("Legal notice", self.legal.as_slice()),
+        ]
+        .into_iter()
+        .filter(|(_, items)| !items.is_empty())
+        .collect()
+    }
+}
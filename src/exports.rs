@@ -0,0 +1,91 @@
+use bytes::Bytes;
+use chrono::{DateTime, Duration, Utc};
+use parking_lot::RwLock;
+use rand::{distributions::Alphanumeric, Rng};
+use serde::Serialize;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::jobs::JobState;
+
+/// Upper bound on retained exports; the oldest are forgotten first.
+const MAX_EXPORTS: usize = 100;
+
+/// A PDF rendered in the background (`GET /pdf?async=true`), downloaded through `GET /api/exports/{token}`.
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportJob {
+    pub token: String,
+    pub lifecycle_id: Uuid,
+    pub state: JobState,
+    pub error: Option<String>,
+    pub status_url: String,
+    pub created_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    /// After this the token is unknown; the result has to be downloaded before.
+    pub expires_at: DateTime<Utc>,
+    #[serde(skip)]
+    pub filename: String,
+    #[serde(skip)]
+    pub content: Option<Bytes>,
+}
+
+/// In-memory registry of export jobs (this instance only). The random token is the only credential for the
+/// download, like a signed link.
+pub struct ExportJobs {
+    jobs: RwLock<HashMap<String, ExportJob>>,
+    ttl: Duration,
+}
+
+impl ExportJobs {
+    /// `EXPORT_TTL_SECS` (default 3600): how long a queued export and its result stay available.
+    pub fn from_env() -> Self {
+        let ttl_secs = std::env::var("EXPORT_TTL_SECS").ok().and_then(|v| v.parse().ok()).unwrap_or(3600);
+        Self { jobs: RwLock::default(), ttl: Duration::seconds(ttl_secs) }
+    }
+
+    pub fn create(&self, lifecycle_id: Uuid, filename: String) -> ExportJob {
+        let token: String = rand::thread_rng().sample_iter(&Alphanumeric).take(32).map(char::from).collect();
+        let now = Utc::now();
+        let job = ExportJob {
+            status_url: format!("/api/exports/{}", token),
+            token: token.clone(),
+            lifecycle_id,
+            state: JobState::Queued,
+            error: None,
+            created_at: now,
+            finished_at: None,
+            expires_at: now + self.ttl,
+            filename,
+            content: None,
+        };
+        let mut jobs = self.jobs.write();
+        jobs.retain(|_, job| job.expires_at > now);
+        while jobs.len() >= MAX_EXPORTS {
+            let Some(oldest) = jobs.values().min_by_key(|job| job.created_at).map(|job| job.token.clone()) else { break };
+            jobs.remove(&oldest);
+        }
+        jobs.insert(token, job.clone());
+        job
+    }
+
+    pub fn start(&self, token: &str) {
+        if let Some(job) = self.jobs.write().get_mut(token) {
+            job.state = JobState::Running;
+        }
+    }
+
+    pub fn finish(&self, token: &str, result: Result<Bytes, String>) {
+        if let Some(job) = self.jobs.write().get_mut(token) {
+            job.state = if result.is_ok() { JobState::Succeeded } else { JobState::Failed };
+            (job.content, job.error) = match result {
+                Ok(content) => (Some(content), None),
+                Err(e) => (None, Some(e)),
+            };
+            job.finished_at = Some(Utc::now());
+        }
+    }
+
+    pub fn get(&self, token: &str) -> Option<ExportJob> {
+        self.jobs.read().get(token).filter(|job| job.expires_at > Utc::now()).cloned()
+    }
+}
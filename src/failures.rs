@@ -0,0 +1,136 @@
+use chrono::{DateTime, Duration, DurationRound, Utc};
+use parking_lot::RwLock;
+use serde::Serialize;
+use std::{cell::RefCell, collections::{BTreeMap, VecDeque}, future::Future};
+
+/// Upper bound on retained failure records; oldest entries are dropped first.
+const MAX_RECORDS: usize = 10_000;
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureKind {
+    Image,
+    Description,
+}
+
+impl FailureKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            FailureKind::Image => "image",
+            FailureKind::Description => "description",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FailureRecord {
+    pub stage_name: String,
+    pub provider: String,
+    pub model: String,
+    pub kind: FailureKind,
+    /// Coarse error class (`quota`, `http_4xx`, `http_5xx`, `network`, `no_image`, `parse`, ...).
+    pub reason: String,
+    pub occurred_at: DateTime<Utc>,
+}
+
+impl FailureRecord {
+    /// Whether trying again can help: outages, rate limits and bad responses pass, rejected requests (4xx) don't.
+    pub fn retryable(&self) -> bool {
+        self.reason != "http_4xx"
+    }
+}
+
+tokio::task_local! {
+    static TRACKED: RefCell<Vec<FailureRecord>>;
+}
+
+/// Run `fut`, collecting the failures recorded while it runs (on this task), such as those hidden behind a
+/// placeholder image or fallback description.
+pub async fn track<F: Future>(fut: F) -> (F::Output, Vec<FailureRecord>) {
+    TRACKED.scope(RefCell::new(Vec::new()), async move {
+        let out = fut.await;
+        (out, TRACKED.with(RefCell::take))
+    }).await
+}
+
+/// In-memory ring buffer of generation failures (anything that ended in a placeholder or fallback text).
+#[derive(Default)]
+pub struct FailureLog {
+    records: RwLock<VecDeque<FailureRecord>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FailureCount {
+    pub key: String,
+    pub count: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FailureCell {
+    pub bucket_start: DateTime<Utc>,
+    pub stage_name: String,
+    pub provider: String,
+    pub count: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FailureHeatmap {
+    pub window_hours: i64,
+    pub since: DateTime<Utc>,
+    pub total: usize,
+    pub by_stage: Vec<FailureCount>,
+    pub by_provider: Vec<FailureCount>,
+    pub by_model: Vec<FailureCount>,
+    pub by_reason: Vec<FailureCount>,
+    pub by_kind: Vec<FailureCount>,
+    /// Hourly buckets (UTC, truncated to the hour) per stage/provider; empty cells are omitted.
+    pub cells: Vec<FailureCell>,
+}
+
+impl FailureLog {
+    pub fn record(&self, record: FailureRecord) {
+        let _ = TRACKED.try_with(|tracked| tracked.borrow_mut().push(record.clone()));
+        let mut records = self.records.write();
+        if records.len() >= MAX_RECORDS {
+            records.pop_front();
+        }
+        records.push_back(record);
+    }
+
+    pub fn heatmap(&self, window_hours: i64) -> FailureHeatmap {
+        let since = Utc::now() - Duration::hours(window_hours);
+        let records = self.records.read();
+        let recent: Vec<&FailureRecord> = records.iter().filter(|r| r.occurred_at >= since).collect();
+
+        let count_by = |key: &dyn Fn(&FailureRecord) -> String| -> Vec<FailureCount> {
+            let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+            for r in &recent {
+                *counts.entry(key(r)).or_default() += 1;
+            }
+            let mut out: Vec<FailureCount> = counts.into_iter().map(|(key, count)| FailureCount { key, count }).collect();
+            out.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.key.cmp(&b.key)));
+            out
+        };
+
+        let mut cells: BTreeMap<(DateTime<Utc>, String, String), usize> = BTreeMap::new();
+        for r in &recent {
+            let bucket = r.occurred_at.duration_trunc(Duration::hours(1)).unwrap_or(r.occurred_at);
+            *cells.entry((bucket, r.stage_name.clone(), r.provider.clone())).or_default() += 1;
+        }
+
+        FailureHeatmap {
+            window_hours,
+            since,
+            total: recent.len(),
+            by_stage: count_by(&|r| r.stage_name.clone()),
+            by_provider: count_by(&|r| r.provider.clone()),
+            by_model: count_by(&|r| r.model.clone()),
+            by_reason: count_by(&|r| r.reason.clone()),
+            by_kind: count_by(&|r| r.kind.as_str().to_string()),
+            cells: cells
+                .into_iter()
+                .map(|((bucket_start, stage_name, provider), count)| FailureCell { bucket_start, stage_name, provider, count })
+                .collect(),
+        }
+    }
+}
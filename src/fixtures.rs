@@ -0,0 +1,78 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::PathBuf;
+use tracing::{info, warn};
+
+/// How `GeminiClient` talks to the API: live, live + recording fixtures, or replaying recorded fixtures.
+#[derive(Debug, Clone)]
+pub enum FixtureMode {
+    Off,
+    /// `RECORD_FIXTURES=dir`: call the live API and write every request/response pair to `dir`.
+    Record(PathBuf),
+    /// `REPLAY_FIXTURES=dir`: never touch the network; serve responses recorded in `dir`.
+    Replay(PathBuf),
+}
+
+/// One recorded `generateContent` exchange. Image payloads in the request are truncated; the response is kept
+/// verbatim so replays are byte-identical.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Fixture {
+    pub key: String,
+    pub model: String,
+    pub request: serde_json::Value,
+    pub status: u16,
+    pub response: String,
+    pub recorded_at: DateTime<Utc>,
+}
+
+impl FixtureMode {
+    pub fn from_env() -> Self {
+        if let Ok(dir) = std::env::var("REPLAY_FIXTURES") {
+            info!("📼 Replaying Gemini fixtures from {}", dir);
+            FixtureMode::Replay(dir.into())
+        } else if let Ok(dir) = std::env::var("RECORD_FIXTURES") {
+            info!("📼 Recording Gemini fixtures to {}", dir);
+            FixtureMode::Record(dir.into())
+        } else {
+            FixtureMode::Off
+        }
+    }
+
+    pub fn is_replay(&self) -> bool { matches!(self, FixtureMode::Replay(_)) }
+}
+
+/// Stable key for a request: model plus the canonical JSON body.
+pub fn fixture_key(model: &str, request: &serde_json::Value) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(model.as_bytes());
+    hasher.update(b"\n");
+    hasher.update(request.to_string().as_bytes());
+    let digest = hasher.finalize();
+    digest.iter().take(12).map(|b| format!("{:02x}", b)).collect()
+}
+
+fn fixture_path(dir: &std::path::Path, model: &str, key: &str) -> PathBuf {
+    dir.join(format!("{}-{}.json", model, key))
+}
+
+pub async fn write_fixture(dir: &std::path::Path, fixture: &Fixture) {
+    if let Err(e) = tokio::fs::create_dir_all(dir).await {
+        warn!("⚠️ Could not create fixture dir {}: {}", dir.display(), e);
+        return;
+    }
+    let path = fixture_path(dir, &fixture.model, &fixture.key);
+    match serde_json::to_vec_pretty(fixture) {
+        Ok(bytes) => match tokio::fs::write(&path, bytes).await {
+            Ok(()) => info!("📼 Recorded fixture {}", path.display()),
+            Err(e) => warn!("⚠️ Could not write fixture {}: {}", path.display(), e),
+        },
+        Err(e) => warn!("⚠️ Could not serialize fixture: {}", e),
+    }
+}
+
+pub async fn read_fixture(dir: &std::path::Path, model: &str, key: &str) -> Option<Fixture> {
+    let path = fixture_path(dir, model, key);
+    let bytes = tokio::fs::read(&path).await.ok()?;
+    serde_json::from_slice(&bytes).ok()
+}
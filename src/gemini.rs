@@ -1,16 +1,40 @@
-use crate::models::StageImage;
+use crate::{templates::{DESCRIPTION_PROMPT_VERSION, IMAGE_PROMPT_VERSION}, ollama::{OllamaClient, PROVIDER as OLLAMA}, provider::ImageGenerator, critique::CritiqueMode, failures::{FailureKind, FailureLog, FailureRecord}, glossary::Glossary, palette::Palette, images::downscale_base64, metrics::{QuotaTracker, PROVIDER_REQUEST_DURATION}, retry::RetryPolicy, circuit::CircuitBreaker, degraded::{DegradedMode, DegradedReason}, usage::{self, TokenUsage}, model_aliases::{ModelAliases, IMAGE_DEFAULT, TEXT_DEFAULT}, chaos::{Chaos, ProviderFault}, prompt_budget::PromptBudget, fixtures::{self, Fixture, FixtureMode}, models::{Resolution, StageImage, TemplateVersions, StageLocation, StageStatus, SupplyChainActor}, vision::{CheckedImage, VisionConfig}};
 use chrono::Utc;
 use serde_json::json;
 use thiserror::Error;
 use serde::Deserialize;
 use base64::Engine;
 use reqwest::Client;
-use tracing::{info, error};
+use std::{sync::Arc, time::Instant};
+use tracing::{info, error, warn};
+
+pub const PROVIDER: &str = "gemini";
+/// Logical model names; `ModelAliases` maps them to provider model ids.
+pub const IMAGE_MODEL: &str = IMAGE_DEFAULT;
+pub const TEXT_MODEL: &str = TEXT_DEFAULT;
 
 #[derive(Debug, Error)]
 pub enum GeminiError {
     #[error("HTTP error: {0}")] Http(String),
+    #[error("API error: status={status} body={body}")] Api { status: u16, body: String },
+    #[error("No image data in response")] NoImage,
     #[error("Other: {0}")] Other(String),
+    #[error("Circuit open: provider calls are paused after repeated failures")] CircuitOpen,
+}
+
+impl GeminiError {
+    /// Coarse classification used for failure aggregation.
+    pub fn reason(&self) -> String {
+        match self {
+            GeminiError::Http(_) => "network".into(),
+            GeminiError::Api { status: 429, .. } => "quota".into(),
+            GeminiError::Api { status, .. } if *status >= 500 => "http_5xx".into(),
+            GeminiError::Api { .. } => "http_4xx".into(),
+            GeminiError::NoImage => "no_image".into(),
+            GeminiError::Other(_) => "parse".into(),
+            GeminiError::CircuitOpen => "circuit_open".into(),
+        }
+    }
 }
 
 // Helper function to truncate base64 data in JSON for cleaner logging
@@ -38,37 +62,272 @@ fn truncate_base64_in_json(value: &mut serde_json::Value) {
     }
 }
 
+/// Everything the prompt builders know about the stage being generated.
+#[derive(Debug, Clone, Copy)]
+pub struct StageContext<'a> {
+    pub product: &'a str,
+    pub stage: &'a str,
+    pub constraints: &'a [String],
+    pub actors: &'a [SupplyChainActor],
+    pub locations: &'a [StageLocation],
+    pub resolution: Resolution,
+}
+
+impl StageContext<'_> {
+    fn actors_sentence(&self) -> String {
+        if self.actors.is_empty() {
+            return String::new();
+        }
+        let actors: Vec<String> = self.actors.iter().map(|a| a.describe()).collect();
+        format!(" Supply chain actors involved: {}.", actors.join("; "))
+    }
+
+    fn locations_sentence(&self) -> String {
+        if self.locations.is_empty() {
+            return String::new();
+        }
+        let places: Vec<&str> = self.locations.iter().map(|l| l.label.as_str()).collect();
+        format!(" Takes place in: {}.", places.join(", "))
+    }
+}
+
 pub struct GeminiClient {
     client: Client,
     api_key: String,
     base_url: String,
+    failures: Arc<FailureLog>,
+    fixtures: FixtureMode,
+    pub(crate) vision: VisionConfig,
+    pub(crate) critique: CritiqueMode,
+    glossary: Glossary,
+    /// Colors of placeholder images (`palette` config section).
+    pub(crate) palette: Palette,
+    /// Logical → provider model ids (`models` config section).
+    pub(crate) models: ModelAliases,
+    /// Generated images are downscaled to fit this box (public demo mode).
+    max_image_px: Option<u32>,
+    /// `DESCRIPTION_CANDIDATES` (1-8): how many description candidates to request per stage.
+    description_candidates: u32,
+    /// `EXTRACT_TERMS=true`: extract glossary terms for tooltips from every generated description.
+    pub(crate) extract_terms: bool,
+    /// `EXTRACT_KEYWORDS=true`: extract normalized process/material/impact keywords from every description.
+    pub(crate) extract_keywords: bool,
+    /// Rate-limit headers seen on provider responses, exported via `/metrics`.
+    pub(crate) quota: QuotaTracker,
+    pub(crate) prompt_budget: PromptBudget,
+    retry: RetryPolicy,
+    /// Fails calls fast while Gemini keeps erroring; state shown on `/api/health`.
+    pub(crate) breaker: CircuitBreaker,
+    /// Switches to placeholders when the key is rejected or out of quota; shown on `/readyz`.
+    pub(crate) degraded: DegradedMode,
+    /// `CHAOS_MODE=true`: injected provider failures (shared with the Stability client).
+    pub(crate) chaos: Option<Arc<Chaos>>,
+    /// `TEXT_PROVIDER=ollama`: descriptions and rewrites come from a local Ollama server instead.
+    ollama: Option<OllamaClient>,
+}
+
+/// Status, `Retry-After` header and body of one `generateContent` attempt.
+struct RawResponse {
+    status: u16,
+    retry_after: Option<String>,
+    body: String,
 }
 
-#[derive(Debug, Clone)]
-pub struct GeminiGenerationResult {
-    pub image_data: Option<String>,
-    pub texts: Vec<String>,
+/// Final description for a stage plus, when several candidates were requested, all of them (the chosen text
+/// first) so a user can pick another one later.
+pub struct StageDescription {
+    pub text: String,
+    pub warnings: Vec<String>,
+    pub candidates: Vec<String>,
+    /// `text` is the canned fallback because generation failed.
+    pub fallback: bool,
 }
 
 impl GeminiClient {
-    pub fn new(api_key: String) -> Self { 
+    pub fn new(api_key: String, failures: Arc<FailureLog>) -> Self { 
         let base_url = std::env::var("GEMINI_API_BASE").unwrap_or_else(|_| "https://generativelanguage.googleapis.com/v1beta".to_string());
         Self { 
-            client: Client::new(), 
+            client: crate::outbound::google_client(), 
             api_key, 
-            base_url 
+            base_url,
+            failures,
+            fixtures: FixtureMode::from_env(),
+            vision: VisionConfig::from_env(),
+            critique: CritiqueMode::from_env(),
+            glossary: Glossary::default(),
+            palette: Palette::default(),
+            models: ModelAliases::default(),
+            max_image_px: None,
+            extract_terms: std::env::var("EXTRACT_TERMS").map(|v| v == "true" || v == "1").unwrap_or(false),
+            quota: QuotaTracker::default(),
+            prompt_budget: PromptBudget::from_env(),
+            retry: RetryPolicy::from_env(),
+            breaker: CircuitBreaker::from_env(),
+            degraded: DegradedMode::from_env(),
+            chaos: None,
+            ollama: None,
+            extract_keywords: std::env::var("EXTRACT_KEYWORDS").map(|v| v == "true" || v == "1").unwrap_or(false),
+            description_candidates: std::env::var("DESCRIPTION_CANDIDATES").ok().and_then(|v| v.parse().ok()).unwrap_or(1).clamp(1, 8),
         }
     }
 
-    async fn perform_api_call(&self, prompt: &str) -> Result<String, GeminiError> {
-        let url = format!(
-            "{}/models/gemini-2.5-flash-image-preview:generateContent?key={}",
-            self.base_url, self.api_key
-        );
+    pub fn with_glossary(mut self, glossary: Glossary) -> Self {
+        self.glossary = glossary;
+        self
+    }
+
+    pub fn with_palette(mut self, palette: Palette) -> Self {
+        self.palette = palette;
+        self
+    }
+
+    pub fn with_max_image_px(mut self, max_image_px: Option<u32>) -> Self {
+        self.max_image_px = max_image_px;
+        self
+    }
+
+    pub fn with_model_aliases(mut self, models: ModelAliases) -> Self {
+        self.models = models;
+        self
+    }
+
+    pub fn with_chaos(mut self, chaos: Option<Arc<Chaos>>) -> Self {
+        self.chaos = chaos;
+        self
+    }
+
+    pub fn with_text_provider(mut self, ollama: Option<OllamaClient>) -> Self {
+        self.ollama = ollama;
+        self
+    }
+
+    /// Whether free-form text generation is real (Gemini key or local Ollama) rather than demo filler.
+    pub fn has_text_model(&self) -> bool { self.ollama.is_some() || !self.is_demo() }
+
+    /// Provider and model that descriptions and rewrites come from, for failure attribution.
+    pub(crate) fn text_source(&self) -> (&str, &str) {
+        match &self.ollama {
+            Some(ollama) => (OLLAMA, &ollama.model),
+            None => (PROVIDER, self.models.resolve(TEXT_MODEL)),
+        }
+    }
+
+    /// Demo mode serves placeholders without calling the API; replaying fixtures counts as a live key. A live key
+    /// that the provider rejected or ran out of quota is treated the same until degraded mode rechecks it.
+    pub fn is_demo(&self) -> bool { (self.api_key == "DEMO_KEY" && !self.fixtures.is_replay()) || self.degraded.active().is_some() }
+
+    /// Single choke point for `generateContent` calls. Resolves `model` through the alias table, returns the raw
+    /// response body on success and honours the fixture record/replay modes.
+    async fn send_generate_content(&self, model: &str, payload: &serde_json::Value) -> Result<String, GeminiError> {
+        let (alias, model) = (model, self.models.resolve(model));
+        if alias != model {
+            info!("🏷️ Model {} resolved to {}", alias, model);
+        }
+        let key = fixtures::fixture_key(model, payload);
+        if let FixtureMode::Replay(dir) = &self.fixtures {
+            let fixture = fixtures::read_fixture(dir, model, &key).await
+                .ok_or_else(|| GeminiError::Other(format!("no recorded fixture {}-{} in {}", model, key, dir.display())))?;
+            info!("📼 Replaying fixture {}-{} (status {})", model, key, fixture.status);
+            if !(200..300).contains(&fixture.status) {
+                return Err(GeminiError::Api { status: fixture.status, body: fixture.response });
+            }
+            return Ok(fixture.response);
+        }
 
+        let url = format!("{}/models/{}:generateContent?key={}", self.base_url, model, self.api_key);
         info!("🔗 Making request to: {}", url.replace(&self.api_key, "***"));
+        if !self.breaker.allow() {
+            warn!("🔌 Circuit open, skipping Gemini {} call", model);
+            return Err(GeminiError::CircuitOpen);
+        }
+        let mut retry = 0;
+        let attempt = loop {
+            let attempt = self.post_generate_content(&url, model, payload).await;
+            let (retryable, retry_after, outcome) = match &attempt {
+                Ok(r) => (RetryPolicy::is_retryable_status(r.status), r.retry_after.clone(), format!("status {}", r.status)),
+                Err(e) => (true, None, e.to_string()),
+            };
+            retry += 1;
+            let Some(delay) = retryable.then(|| self.retry.delay(retry, retry_after.as_deref())).flatten() else {
+                // Only outages count against the breaker; a 4xx means the provider is up.
+                if retryable { self.breaker.record_failure() } else { self.breaker.record_success() }
+                break attempt;
+            };
+            warn!("🔁 Gemini {} attempt {} failed ({}), retrying in {:?}", model, retry, outcome, delay);
+            tokio::time::sleep(delay).await;
+        };
+        let RawResponse { status, body: response_text, .. } = attempt?;
+
+        if let FixtureMode::Record(dir) = &self.fixtures {
+            let mut request = payload.clone();
+            truncate_base64_in_json(&mut request);
+            fixtures::write_fixture(dir, &Fixture {
+                key,
+                model: model.to_string(),
+                request,
+                status,
+                response: response_text.clone(),
+                recorded_at: Utc::now(),
+            }).await;
+        }
+
+        if !(200..300).contains(&status) {
+            error!("❌ Gemini {} call failed with status {}: {}", model, status, response_text);
+            if let Some(reason) = DegradedReason::classify(status, &response_text) {
+                self.degraded.enter(reason);
+            }
+            return Err(GeminiError::Api { status, body: response_text });
+        }
+        self.degraded.record_success();
+        usage::record(TokenUsage::from_gemini(PROVIDER, model, &response_text));
+        Ok(response_text)
+    }
+
+    /// One HTTP attempt; the caller decides whether to retry.
+    async fn post_generate_content(&self, url: &str, model: &str, payload: &serde_json::Value) -> Result<RawResponse, GeminiError> {
+        if let Some(chaos) = &self.chaos {
+            match chaos.provider_fault(PROVIDER, model).await {
+                Some(ProviderFault::RateLimited) => return Ok(RawResponse { status: 429, retry_after: Some("1".to_string()), body: "{}".to_string() }),
+                Some(fault) => return Err(fault.into_error()),
+                None => {}
+            }
+        }
+        let started = Instant::now();
+        let response = self.client
+            .post(url)
+            .json(payload)
+            .send()
+            .await
+            .map_err(|e| GeminiError::Http(e.to_string()))?;
+        let status = response.status();
+        info!("📥 Response status: {}", status);
+        self.quota.observe(PROVIDER, model, status.as_u16(), response.headers());
+        let retry_after = response.headers().get(reqwest::header::RETRY_AFTER).and_then(|v| v.to_str().ok()).map(str::to_string);
+        let body = response.text().await.map_err(|e| GeminiError::Http(e.to_string()))?;
+        PROVIDER_REQUEST_DURATION.observe(&[PROVIDER, model], started.elapsed());
+        Ok(RawResponse { status: status.as_u16(), retry_after, body })
+    }
+
+    /// Plain `generateContent` call returning the parsed response; used by the auxiliary (vision/critique) passes.
+    pub(crate) async fn generate_content(&self, model: &str, payload: &serde_json::Value) -> Result<GeminiResponse, GeminiError> {
+        let response_text = self.send_generate_content(model, payload).await?;
+        serde_json::from_str(&response_text)
+            .map_err(|e| GeminiError::Other(format!("Failed to parse response: {}", e)))
+    }
+
+    fn record_failure(&self, stage: &str, kind: FailureKind, (provider, model): (&str, &str), err: &GeminiError) {
+        self.failures.record(FailureRecord {
+            stage_name: stage.to_string(),
+            provider: provider.to_string(),
+            model: model.to_string(),
+            kind,
+            reason: err.reason(),
+            occurred_at: Utc::now(),
+        });
+    }
 
-        let request_body = json!({
+    async fn perform_api_call(&self, prompt: &str, resolution: Resolution) -> Result<String, GeminiError> {
+        let mut request_body = json!({
             "contents": [{
                 "parts": [{"text": prompt}]
             }],
@@ -80,27 +339,13 @@ impl GeminiClient {
                 "candidateCount": 1
             }
         });
+        if let Some(size) = resolution.provider_size() {
+            request_body["generationConfig"]["imageConfig"] = json!({ "imageSize": size });
+        }
 
         info!("📤 Request body: {}", serde_json::to_string_pretty(&request_body).unwrap_or_default());
 
-        let response = self.client
-            .post(&url)
-            .json(&request_body)
-            .send()
-            .await
-            .map_err(|e| GeminiError::Http(e.to_string()))?;
-
-        let status = response.status();
-        info!("📥 Response status: {}", status);
-
-        if !status.is_success() {
-            let error_body = response.text().await.unwrap_or_default();
-            error!("❌ API Error response: {}", error_body);
-            return Err(GeminiError::Http(format!("status={} body={}", status, error_body)));
-        }
-
-        let response_text = response.text().await
-            .map_err(|e| GeminiError::Other(e.to_string()))?;
+        let response_text = self.send_generate_content(IMAGE_MODEL, &request_body).await?;
         
         // Truncate base64 image data for cleaner logging
         let truncated_response = if response_text.len() > 1000 {
@@ -126,27 +371,25 @@ impl GeminiClient {
             } else {
                 image_data.clone()
             };
-            let image_type = if image_data.starts_with("PHN2Zyg") {
-                "SVG"
-            } else if image_data.starts_with("iVBORw0KGgo") {
-                "PNG"
-            } else if image_data.starts_with("/9j/") {
-                "JPEG"
-            } else {
-                "Unknown"
+            let image_type = match sniff_mime_type(image_data) {
+                "image/svg+xml" => "SVG",
+                "image/png" => "PNG",
+                "image/jpeg" => "JPEG",
+                _ => "Unknown",
             };
             info!("🖼️ Extracted {} image from API response: {}", image_type, preview);
         } else {
             info!("⚠️ No image data found in API response");
         }
 
-        image_result.ok_or_else(|| GeminiError::Other("no image data in response".into()))
+        image_result.ok_or(GeminiError::NoImage)
     }
 
-    pub async fn generate_image(&self, prompt: &str) -> Result<String, GeminiError> {
-        if self.api_key == "DEMO_KEY" { 
+    /// `stage` is only used to attribute failures in the failure log.
+    pub async fn generate_image(&self, stage: &str, prompt: &str, resolution: Resolution) -> Result<String, GeminiError> {
+        if self.is_demo() { 
             info!("Using demo mode - no real images generated");
-            let placeholder = self.generate_placeholder_image(prompt);
+            let placeholder = placeholder_image(prompt, &self.palette);
             let preview = if placeholder.len() > 50 {
                 format!("{}...[{} chars total]", &placeholder[..50], placeholder.len())
             } else {
@@ -157,7 +400,9 @@ impl GeminiClient {
         }
         
         info!("Generating image with Gemini API...");
-        let result = self.perform_api_call(prompt).await;
+        let max_px = resolution.max_px_within(self.max_image_px);
+        let result = self.perform_api_call(prompt, resolution).await
+            .map(|img| match max_px { Some(px) => downscale_base64(img, px), None => img });
         match &result {
             Ok(image_data) => {
                 let preview = if image_data.len() > 50 {
@@ -169,9 +414,10 @@ impl GeminiClient {
             }
             Err(e) => {
                 error!("❌ Failed to generate image: {}", e);
+                self.record_failure(stage, FailureKind::Image, (PROVIDER, self.models.resolve(IMAGE_MODEL)), e);
                 info!("🔄 Falling back to placeholder image");
                 // Return a placeholder instead of failing
-                let placeholder = self.generate_placeholder_image(prompt);
+                let placeholder = placeholder_image(prompt, &self.palette);
                 let preview = if placeholder.len() > 50 {
                     format!("{}...[{} chars total]", &placeholder[..50], placeholder.len())
                 } else {
@@ -184,80 +430,73 @@ impl GeminiClient {
         result
     }
 
-    fn generate_placeholder_image(&self, prompt: &str) -> String {
-        // Generate a simple SVG placeholder that represents the stage
-        let stage_colors = [
-            "#3B82F6", // Blue for Raw Materials
-            "#EF4444", // Red for Manufacturing  
-            "#10B981", // Green for Distribution
-            "#F59E0B", // Yellow for Usage
-            "#8B5CF6", // Purple for End-of-Life
-        ];
-        
-        let color = stage_colors[prompt.len() % stage_colors.len()];
-        let title = if prompt.contains("Raw Materials") { "🌱 Raw Materials" }
-                   else if prompt.contains("Manufacturing") { "🏭 Manufacturing" }
-                   else if prompt.contains("Distribution") { "🚚 Distribution" }
-                   else if prompt.contains("Usage") { "👤 Usage" }
-                   else if prompt.contains("End-of-Life") { "♻️ Recycling" }
-                   else { "📦 Lifecycle Stage" };
-
-        let svg = format!(r#"<svg width="400" height="300" xmlns="http://www.w3.org/2000/svg">
-            <defs>
-                <linearGradient id="grad" x1="0%" y1="0%" x2="100%" y2="100%">
-                    <stop offset="0%" style="stop-color:{};stop-opacity:1" />
-                    <stop offset="100%" style="stop-color:{};stop-opacity:0.6" />
-                </linearGradient>
-            </defs>
-            <rect width="400" height="300" fill="url(#grad)" />
-            <text x="200" y="150" font-family="Arial, sans-serif" font-size="24" font-weight="bold" 
-                  text-anchor="middle" fill="white" text-shadow="2px 2px 4px rgba(0,0,0,0.5)">
-                {}
-            </text>
-            <text x="200" y="200" font-family="Arial, sans-serif" font-size="12" 
-                  text-anchor="middle" fill="white" opacity="0.8">
-                Sustainability Lifecycle Stage
-            </text>
-        </svg>"#, color, color, title);
-
-        // Convert SVG to base64
-        base64::engine::general_purpose::STANDARD.encode(svg.as_bytes())
-    }
-
-    pub fn build_stage_prompt(product: &str, stage: &str, constraints: &[String]) -> String {
-        let sustainability = if constraints.is_empty() { 
+    fn sustainability_clause(constraints: &[String]) -> String {
+        if constraints.is_empty() { 
             String::new() 
         } else { 
             format!("Sustainability focus: {}.", constraints.join(", ")) 
-        };
-        format!("High-quality infographic style depiction of the {stage} stage in the lifecycle of: {product}. {sustainability} Show realistic materials, clean labeling, neutral background, vector style clarity, no text over image.")
+        }
     }
 
-    pub async fn generate_stage_description(&self, product: &str, stage: &str, constraints: &[String]) -> String {
+    /// Whether a prompt built by `build_stage_prompt` embeds exactly these constraints, i.e. regenerating the stage
+    /// would not change what it asks for on that account.
+    pub fn prompt_matches_constraints(prompt: &str, constraints: &[String]) -> bool {
+        match Self::sustainability_clause(constraints) {
+            clause if clause.is_empty() => !prompt.contains("Sustainability focus:"),
+            clause => prompt.contains(&clause),
+        }
+    }
+
+    pub fn build_stage_prompt(ctx: StageContext<'_>) -> String {
+        let StageContext { product, stage, constraints, .. } = ctx;
+        let sustainability = Self::sustainability_clause(constraints);
+        let actors = ctx.actors_sentence();
+        let places = ctx.locations_sentence();
+        format!("High-quality infographic style depiction of the {stage} stage in the lifecycle of: {product}. {sustainability}{actors}{places} Show realistic materials, clean labeling, neutral background, vector style clarity, no text over image.")
+    }
+
+    pub async fn generate_stage_description(&self, ctx: StageContext<'_>) -> StageDescription {
+        let StageContext { product, stage, constraints, .. } = ctx;
         let sustainability = if constraints.is_empty() { 
             String::new() 
         } else { 
             format!(" with focus on {}", constraints.join(", ")) 
         };
+        let actors = format!("{}{}", ctx.actors_sentence(), ctx.locations_sentence());
 
         // Request a richer multi‑paragraph narrative (~150–200 words) for better detail in the expanded modal.
         let description_prompt = format!(
-            "Write a rich, informative 3-paragraph description (approx 150-200 words total) of the {stage} stage in the lifecycle of {product}{sustainability}. \
+            "Write a rich, informative 3-paragraph description (approx 150-200 words total) of the {stage} stage in the lifecycle of {product}{sustainability}.{actors} \
             Paragraph 1: Operationally what happens and primary transformations. \
             Paragraph 2: Sustainability challenges, typical mitigation strategies, material/energy efficiency considerations. \
             Paragraph 3: Key environmental impact dimensions (energy use, emissions, waste, water, circularity opportunities) and practical improvement levers. \
-            Use clear plain language, no marketing fluff, no bullet points, no headings, no list markers. Keep paragraphs separated by a single blank line."
+            Use clear plain language, no marketing fluff, no bullet points, no headings, no list markers. Keep paragraphs separated by a single blank line.{}",
+            self.glossary.prompt_instructions()
         );
 
-        info!("🎯 Generating description for stage '{}' (rich mode) with prompt (truncated): {}", stage, &description_prompt[..std::cmp::min(120, description_prompt.len())]);
-
-        match self.generate_text(&description_prompt).await {
-            Ok(description) => {
-                info!("✅ Stage '{}' description generated ({} chars)", stage, description.len());
-                description
+        info!("🎯 Generating description for stage '{}' (rich mode) with prompt (truncated): {}", stage, description_prompt.chars().take(120).collect::<String>());
+
+        match self.generate_text_candidates(&description_prompt, self.description_candidates).await {
+            Ok(mut candidates) => {
+                let description = candidates.remove(0);
+                info!("✅ Stage '{}' description generated ({} chars, {} alternative(s))", stage, description.len(), candidates.len());
+                let mut critiqued = self.critique_description(stage, product, description).await;
+                if !self.glossary.is_empty() {
+                    let (text, glossary_warnings) = self.glossary.enforce(&critiqued.text);
+                    critiqued.text = text;
+                    critiqued.warnings.extend(glossary_warnings);
+                }
+                // Alternatives skip the (costly) critique pass but still get the house terminology.
+                let candidates = if candidates.is_empty() {
+                    Vec::new()
+                } else {
+                    std::iter::once(critiqued.text.clone()).chain(candidates.iter().map(|c| self.glossary.enforce(c).0)).collect()
+                };
+                StageDescription { text: critiqued.text, warnings: critiqued.warnings, candidates, fallback: false }
             }
             Err(e) => {
                 error!("❌ Stage '{}' description generation failed: {}", stage, e);
+                self.record_failure(stage, FailureKind::Description, self.text_source(), &e);
                 // Expanded fallback text (3 short paragraphs) to preserve UX expectations
                 let (p1, p2, p3) = match stage {
                     "Raw Materials" => (
@@ -291,20 +530,36 @@ impl GeminiClient {
                         "Environmental levers include energy optimization, material circularity and emission reductions." 
                     )
                 };
-                format!("{}\n\n{}\n\n{}", p1, p2, p3)
+                StageDescription { text: format!("{}\n\n{}\n\n{}", p1, p2, p3), warnings: Vec::new(), candidates: Vec::new(), fallback: true }
             }
         }
     }
 
-    pub async fn generate_text(&self, prompt: &str) -> Result<String, GeminiError> {
-        if self.api_key == "DEMO_KEY" { 
+    /// Rewrite a stage description following a user instruction ("make it shorter and less jargony").
+    pub async fn rewrite_description(&self, stage: &str, product: &str, text: &str, instruction: &str) -> Result<String, GeminiError> {
+        let prompt = format!(
+            "Rewrite this description of the {stage} stage in the lifecycle of {product} following the instruction below. \
+            Keep the facts, do not add new claims, no bullet points or headings, paragraphs separated by a single blank line. \
+            Reply with the rewritten description only.{}\n\nInstruction: {instruction}\n\nDescription:\n{text}",
+            self.glossary.prompt_instructions()
+        );
+        let mut texts = self.generate_text_candidates(&prompt, 1).await?;
+        Ok(self.glossary.enforce(&texts.remove(0)).0)
+    }
+
+    /// Up to `count` alternative completions for one prompt (a single `candidateCount` request). Never empty on `Ok`.
+    pub async fn generate_text_candidates(&self, prompt: &str, count: u32) -> Result<Vec<String>, GeminiError> {
+        if let Some(ollama) = &self.ollama {
+            return ollama.generate_candidates(prompt, count).await;
+        }
+        if self.is_demo() { 
             info!("Using demo mode - generating fallback text");
-            return Ok("Demo description: This stage represents an important part of the product lifecycle with environmental considerations.".to_string());
+            return Ok(vec!["Demo description: This stage represents an important part of the product lifecycle with environmental considerations.".to_string()]);
         }
         
-        info!("Generating text with Gemini API...");
+        info!("Generating text with Gemini API ({} candidate(s))...", count);
         
-        let payload = json!({
+        let mut payload = json!({
             "contents": [{
                 "parts": [{"text": prompt}]
             }],
@@ -315,49 +570,38 @@ impl GeminiClient {
                 "maxOutputTokens": 450
             }
         });
-
-        let url = format!("{}/v1beta/models/gemini-1.5-flash:generateContent?key={}", self.base_url, self.api_key);
-        
-        let response = self.client
-            .post(&url)
-            .header("Content-Type", "application/json")
-            .json(&payload)
-            .send()
-            .await
-            .map_err(|e| GeminiError::Http(e.to_string()))?;
-
-        let status = response.status();
-        let response_text = response.text().await.map_err(|e| GeminiError::Http(e.to_string()))?;
-        
-        if !status.is_success() {
-            error!("❌ Gemini API text generation failed with status {}: {}", status, response_text);
-            return Err(GeminiError::Http(format!("HTTP {}: {}", status, response_text)));
+        if count > 1 {
+            payload["generationConfig"]["candidateCount"] = json!(count);
         }
 
-        let parsed: GeminiResponse = serde_json::from_str(&response_text)
-            .map_err(|e| GeminiError::Other(format!("Failed to parse response: {}", e)))?;
-
-        if let Some(candidate) = parsed.candidates.first() {
-            for part in &candidate.content.parts {
-                if let Part::Text { text } = part {
-                    return Ok(text.trim().to_string());
-                }
-            }
+        let texts = self.generate_content(TEXT_MODEL, &payload).await?.texts();
+        if texts.is_empty() {
+            return Err(GeminiError::Other("No text content found in response".to_string()));
         }
-        
-        Err(GeminiError::Other("No text content found in response".to_string()))
+        Ok(texts)
+    }
+
+    pub async fn gen_stage_image(&self, ctx: StageContext<'_>) -> StageImage {
+        self.gen_stage_image_with(self, ctx).await
     }
 
-    pub async fn gen_stage_image(&self, product: &str, stage: &str, constraints: &[String]) -> StageImage {
-        let prompt = Self::build_stage_prompt(product, stage, constraints);
-        info!("🎯 Generating stage '{}' with prompt: {}", stage, &prompt[..std::cmp::min(100, prompt.len())]);
+    /// Full stage generation with images from `images` (possibly another provider); descriptions and the
+    /// post-generation passes stay on Gemini.
+    pub async fn gen_stage_image_with(&self, images: &dyn ImageGenerator, ctx: StageContext<'_>) -> StageImage {
+        let stage = ctx.stage;
+        let prompt = Self::build_stage_prompt(ctx);
+        info!("🎯 Generating stage '{}' with prompt: {}", stage, prompt.chars().take(100).collect::<String>());
         
         // Generate image and description concurrently
         let (img_result, description) = tokio::join!(
-            self.generate_image(&prompt),
-            self.generate_stage_description(product, stage, constraints)
+            images.generate_checked_image(stage, &prompt, ctx.resolution),
+            self.generate_stage_description(ctx)
         );
         
+        let CheckedImage { image: img_result, quality_checks, mut warnings } = img_result;
+        let StageDescription { text: description, warnings: description_warnings, candidates: description_candidates, fallback: description_fallback } = description;
+        warnings.extend(description_warnings);
+        let mut status = StageStatus::Complete;
         let img = match img_result {
             Ok(image_data) => {
                 let preview = if image_data.len() > 50 {
@@ -370,28 +614,84 @@ impl GeminiClient {
             }
             Err(e) => {
                 error!("❌ Stage '{}' image generation failed: {}", stage, e);
+                status = StageStatus::Failed { error: e.to_string() };
                 None
             }
         };
+        let (consistency, terms, keywords) = tokio::join!(
+            self.auto_consistency_check(stage, img.as_deref(), &description),
+            self.auto_extract_terms(stage, &description),
+            self.auto_extract_keywords(stage, &description)
+        );
         
-        StageImage { 
+        let mut stage_image = StageImage { 
             stage_name: stage.to_string(), 
             prompt, 
             description,
-            image_base64: img, 
-            last_updated: Utc::now() 
+            image_base64: None,
+            mime_type: None,
+            is_placeholder: false,
+            image_ref: None,
+            image_url: None,
+            alt_text: None,
+            usage: Vec::new(),
+            last_updated: Utc::now(),
+            resolution: ctx.resolution,
+            quality_checks,
+            warnings,
+            actors: ctx.actors.to_vec(),
+            locations: ctx.locations.to_vec(),
+            economics: None,
+            consistency: None,
+            description_candidates,
+            description_history: Vec::new(),
+            terms,
+            status,
+            keywords,
+            assets: Vec::new(),
+            templates: TemplateVersions {
+                image: Some(IMAGE_PROMPT_VERSION.to_string()),
+                description: Some(DESCRIPTION_PROMPT_VERSION.to_string()),
+            },
+            description_fallback,
+            narratives: Default::default(),
+            revisions: Default::default(),
+            image_candidates: Vec::new(),
+            previous_image: None,
+        };
+        stage_image.set_image(img);
+        if let Some(check) = consistency {
+            stage_image.apply_consistency(check);
         }
+        stage_image
     }
 }
 
 // --- Response Parsing Helpers ---
 
 #[derive(Debug, Deserialize)]
-struct GeminiResponse {
+pub(crate) struct GeminiResponse {
     #[serde(default)]
     candidates: Vec<Candidate>,
 }
 
+impl GeminiResponse {
+    /// First text part of every candidate that has one, in candidate order.
+    pub(crate) fn texts(&self) -> Vec<String> {
+        self.candidates.iter().filter_map(|c| c.content.parts.iter().find_map(|p| match p {
+            Part::Text { text } => Some(text.trim().to_string()),
+            _ => None,
+        })).collect()
+    }
+
+    pub(crate) fn first_text(&self) -> Option<String> {
+        self.candidates.first()?.content.parts.iter().find_map(|p| match p {
+            Part::Text { text } => Some(text.trim().to_string()),
+            _ => None,
+        })
+    }
+}
+
 #[derive(Debug, Deserialize)]
 struct Candidate { #[serde(default)] content: Content }
 
@@ -406,7 +706,7 @@ enum Part {
         inline_data: InlineData 
     },
     Text { text: String },
-    Other(serde_json::Value) 
+    Other(#[allow(dead_code)] serde_json::Value) 
 }
 
 #[derive(Debug, Deserialize)]
@@ -428,3 +728,49 @@ fn extract_first_image_b64(resp: &GeminiResponse) -> Option<String> {
     info!("⚠️ No inline image data found in response structure");
     None
 }
+
+/// Colored SVG card standing in for an image in demo mode or after a provider failure, in `palette` colors
+/// adjusted to keep its text readable.
+pub(crate) fn placeholder_image(prompt: &str, palette: &Palette) -> String {
+    let (start, end, text) = palette.placeholder(prompt.len());
+    let title = if prompt.contains("Raw Materials") { "🌱 Raw Materials" }
+               else if prompt.contains("Manufacturing") { "🏭 Manufacturing" }
+               else if prompt.contains("Distribution") { "🚚 Distribution" }
+               else if prompt.contains("Usage") { "👤 Usage" }
+               else if prompt.contains("End-of-Life") { "♻️ Recycling" }
+               else { "📦 Lifecycle Stage" };
+
+    let svg = format!(r#"<svg width="400" height="300" xmlns="http://www.w3.org/2000/svg">
+        <defs>
+            <linearGradient id="grad" x1="0%" y1="0%" x2="100%" y2="100%">
+                <stop offset="0%" style="stop-color:{};stop-opacity:1" />
+                <stop offset="100%" style="stop-color:{};stop-opacity:1" />
+            </linearGradient>
+        </defs>
+        <rect width="400" height="300" fill="url(#grad)" />
+        <text x="200" y="150" font-family="Arial, sans-serif" font-size="24" font-weight="bold" 
+              text-anchor="middle" fill="{}">
+            {}
+        </text>
+        <text x="200" y="200" font-family="Arial, sans-serif" font-size="12" 
+              text-anchor="middle" fill="{}">
+            Sustainability Lifecycle Stage
+        </text>
+    </svg>"#, start, end, text, title, text);
+
+    // Convert SVG to base64
+    base64::engine::general_purpose::STANDARD.encode(svg.as_bytes())
+}
+
+/// Guess the mime type of a base64 payload from its magic prefix.
+pub fn sniff_mime_type(image_b64: &str) -> &'static str {
+    if image_b64.starts_with("PHN2Zy") {
+        "image/svg+xml"
+    } else if image_b64.starts_with("iVBORw0KGgo") {
+        "image/png"
+    } else if image_b64.starts_with("/9j/") {
+        "image/jpeg"
+    } else {
+        "application/octet-stream"
+    }
+}
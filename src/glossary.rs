@@ -0,0 +1,61 @@
+use regex::{Regex, RegexBuilder};
+use serde::Deserialize;
+
+/// Prefix of the stage warnings raised for banned phrases.
+pub const BANNED_PHRASE_PREFIX: &str = "Description contains banned phrase";
+
+/// Deployment terminology rules applied to generated descriptions.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Glossary {
+    pub preferred: Vec<PreferredTerm>,
+    /// Phrases that must never appear; occurrences are flagged as stage warnings.
+    pub banned: Vec<String>,
+}
+
+/// e.g. `{ "term": "post-consumer recycled content", "instead_of": ["recycled plastic"] }`
+#[derive(Debug, Clone, Deserialize)]
+pub struct PreferredTerm {
+    pub term: String,
+    #[serde(default)]
+    pub instead_of: Vec<String>,
+}
+
+fn phrase_regex(phrase: &str) -> Option<Regex> {
+    RegexBuilder::new(&format!(r"\b{}\b", regex::escape(phrase))).case_insensitive(true).build().ok()
+}
+
+impl Glossary {
+    pub fn is_empty(&self) -> bool { self.preferred.is_empty() && self.banned.is_empty() }
+
+    /// Extra prompt instructions describing the terminology rules.
+    pub fn prompt_instructions(&self) -> String {
+        let mut out = String::new();
+        for p in self.preferred.iter().filter(|p| !p.instead_of.is_empty()) {
+            out.push_str(&format!(" Use the term \"{}\" instead of {}.", p.term,
+                p.instead_of.iter().map(|t| format!("\"{}\"", t)).collect::<Vec<_>>().join(" or ")));
+        }
+        if !self.banned.is_empty() {
+            out.push_str(&format!(" Never use these phrases: {}.",
+                self.banned.iter().map(|t| format!("\"{}\"", t)).collect::<Vec<_>>().join(", ")));
+        }
+        out
+    }
+
+    /// Replace discouraged terms with their preferred form and report any banned phrases still present.
+    pub fn enforce(&self, text: &str) -> (String, Vec<String>) {
+        let mut text = text.to_string();
+        for p in &self.preferred {
+            for discouraged in &p.instead_of {
+                if let Some(re) = phrase_regex(discouraged) {
+                    text = re.replace_all(&text, p.term.as_str()).into_owned();
+                }
+            }
+        }
+        let warnings = self.banned.iter()
+            .filter(|phrase| phrase_regex(phrase).is_some_and(|re| re.is_match(&text)))
+            .map(|phrase| format!("{} \"{}\"", BANNED_PHRASE_PREFIX, phrase))
+            .collect();
+        (text, warnings)
+    }
+}
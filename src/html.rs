@@ -0,0 +1,131 @@
+use chrono::Utc;
+use regex::RegexBuilder;
+
+use crate::{gemini::sniff_mime_type, models::{Lifecycle, StageTerm}, theme::{FontFamily, ThemeStyle}};
+
+const STYLE: &str = "\
+body{font-family:-apple-system,'Segoe UI',Roboto,Helvetica,Arial,sans-serif;max-width:860px;margin:2rem auto;padding:0 1rem;color:#1f2937;line-height:1.6}\
+h1{font-size:1.8rem;margin-bottom:.25rem}\
+.meta{color:#6b7280;font-size:.85rem}\
+.draft{display:inline-block;padding:.1rem .5rem;border-radius:4px;background:#fee2e2;color:#b91c1c;font-weight:600;font-size:.8rem}\
+.constraints span{display:inline-block;margin:0 .3rem .3rem 0;padding:.1rem .6rem;border-radius:999px;background:#ecfdf5;color:#047857;font-size:.85rem}\
+section{border-top:1px solid #e5e7eb;padding-top:1rem;margin-top:1.5rem}\
+img{max-width:100%;border-radius:8px;display:block;margin:.5rem 0}\
+.note{color:#92400e;font-size:.85rem;font-style:italic}\
+abbr{text-decoration:none;border-bottom:1px dotted #059669;cursor:help}\
+dt{font-weight:600}\
+dd{margin:0 0 .4rem 1rem;color:#4b5563}\
+h3{font-size:1rem;margin-bottom:.25rem}\
+img.logo{max-height:48px;max-width:240px;border-radius:0;margin:0 0 1rem}\
+footer{border-top:1px solid #e5e7eb;margin-top:2rem;padding-top:.75rem}";
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;").replace('\'', "&#39;")
+}
+
+/// Escaped description with every occurrence of an extracted term wrapped in `<abbr>` carrying its definition,
+/// longest terms first (as in the UI).
+fn description_with_terms(text: &str, terms: &[StageTerm]) -> String {
+    let mut sorted: Vec<&StageTerm> = terms.iter().filter(|t| !t.term.trim().is_empty()).collect();
+    sorted.sort_by_key(|t| std::cmp::Reverse(t.term.len()));
+    let pattern = sorted.iter().map(|t| regex::escape(&t.term)).collect::<Vec<_>>().join("|");
+    let Some(regex) = (!sorted.is_empty()).then(|| RegexBuilder::new(&format!(r"\b(?:{})\b", pattern)).case_insensitive(true).build().ok()).flatten() else {
+        return escape(text);
+    };
+    let mut html = String::new();
+    let mut last = 0;
+    for m in regex.find_iter(text) {
+        let definition = sorted.iter().find(|t| t.term.eq_ignore_ascii_case(m.as_str())).map_or("", |t| t.definition.as_str());
+        html.push_str(&escape(&text[last..m.start()]));
+        html.push_str(&format!("<abbr title=\"{}\">{}</abbr>", escape(definition), escape(m.as_str())));
+        last = m.end();
+    }
+    html.push_str(&escape(&text[last..]));
+    html
+}
+
+/// Overrides of the stock `STYLE` for the export theme, empty for the stock look.
+fn theme_style(style: &ThemeStyle) -> String {
+    let mut css = String::new();
+    if style.font != FontFamily::Sans {
+        css.push_str(&format!("body{{font-family:{}}}", style.font.css_stack()));
+    }
+    if style.spacing != 1.0 {
+        css.push_str(&format!("body{{line-height:{:.2}}}section{{padding-top:{:.2}rem;margin-top:{:.2}rem}}", 1.6 * style.spacing, style.spacing, 1.5 * style.spacing));
+    }
+    if let Some(heading) = style.heading {
+        css.push_str(&format!("h1,h2,h3{{color:{0}}}abbr{{border-bottom-color:{0}}}", heading));
+    }
+    if let Some(muted) = style.muted {
+        css.push_str(&format!(".meta{{color:{}}}", muted));
+    }
+    css
+}
+
+/// Single-file HTML report (`GET /html`): inline styles and `data:` images, so it can be emailed or archived and
+/// still renders without this service. `style` sets the font stack, heading and metadata colors and spacing, and
+/// adds the theme's logo above the title and its footer line at the end.
+pub fn render_html(lifecycle: &Lifecycle, style: &ThemeStyle) -> String {
+    let title = escape(lifecycle.product_description.trim());
+    let status = serde_json::to_value(lifecycle.review_status).ok().and_then(|v| v.as_str().map(str::to_string)).unwrap_or_default();
+    let mut body = String::new();
+    if let Some(logo) = &style.logo {
+        body.push_str(&format!("<img class=\"logo\" src=\"{}\" alt=\"\">\n", escape(&logo.data_uri)));
+    }
+    body.push_str(&format!("<h1>{}</h1>\n", title));
+    if !lifecycle.review_status.is_final() {
+        body.push_str("<p><span class=\"draft\">DRAFT</span></p>\n");
+    }
+    body.push_str(&format!(
+        "<p class=\"meta\">Lifecycle {}, revision {}, {}; exported {}</p>\n",
+        lifecycle.id, lifecycle.revision, escape(&status.replace('_', " ")), Utc::now().format("%Y-%m-%d %H:%M UTC"),
+    ));
+    if !lifecycle.constraints.is_empty() {
+        let chips: String = lifecycle.constraints.iter().map(|c| format!("<span>{}</span>", escape(c))).collect();
+        body.push_str(&format!("<p class=\"constraints\">{}</p>\n", chips));
+    }
+
+    for (index, stage) in lifecycle.stages.iter().enumerate() {
+        body.push_str(&format!("<section>\n<h2>{}. {}</h2>\n", index + 1, escape(&stage.stage_name)));
+        if let Some(b64) = stage.image_base64.as_deref() {
+            let alt = stage.alt_text.as_deref().unwrap_or(&stage.stage_name);
+            body.push_str(&format!("<img src=\"data:{};base64,{}\" alt=\"{}\">\n", sniff_mime_type(b64), b64, escape(alt)));
+            if stage.is_placeholder() {
+                body.push_str("<p class=\"note\">Placeholder image; the stage has not been generated successfully yet.</p>\n");
+            }
+        }
+        for paragraph in stage.description.split("\n\n").map(str::trim).filter(|p| !p.is_empty()) {
+            body.push_str(&format!("<p>{}</p>\n", description_with_terms(paragraph, &stage.terms)));
+        }
+        if !stage.terms.is_empty() {
+            body.push_str("<h3>Terms</h3>\n<dl>\n");
+            for term in &stage.terms {
+                body.push_str(&format!("<dt>{}</dt><dd>{}</dd>\n", escape(&term.term), escape(&term.definition)));
+            }
+            body.push_str("</dl>\n");
+        }
+        if !stage.actors.is_empty() {
+            body.push_str("<h3>Supply chain</h3>\n<ul>\n");
+            for actor in &stage.actors {
+                body.push_str(&format!("<li>{}</li>\n", escape(&actor.describe())));
+            }
+            body.push_str("</ul>\n");
+        }
+        if !stage.locations.is_empty() {
+            body.push_str("<h3>Locations</h3>\n<ul>\n");
+            for location in &stage.locations {
+                body.push_str(&format!("<li>{} ({:.3}, {:.3})</li>\n", escape(&location.label), location.lat, location.lon));
+            }
+            body.push_str("</ul>\n");
+        }
+        body.push_str("</section>\n");
+    }
+    if let Some(footer) = &style.footer {
+        body.push_str(&format!("<footer class=\"meta\">{}</footer>\n", escape(footer)));
+    }
+
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n<title>{}</title>\n<style>{}{}</style>\n</head>\n<body>\n{}</body>\n</html>\n",
+        title, STYLE, theme_style(style), body,
+    )
+}
@@ -0,0 +1,347 @@
+use async_trait::async_trait;
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use sha2::{Digest, Sha256};
+use std::{collections::{HashMap, HashSet}, io, path::PathBuf, sync::Arc, time::{Duration, SystemTime}};
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::{
+    images::sniff_image_bytes,
+    models::Lifecycle,
+    repository::{LifecycleRepository, StoreError},
+    s3::S3Store,
+};
+
+/// Unreferenced files younger than this are left alone by garbage collection: an image is stored before the
+/// lifecycle update referencing it is committed.
+const GC_GRACE: Duration = Duration::from_secs(600);
+
+/// SHA-256 of the image bytes, the name an image is stored under.
+pub fn image_ref(bytes: &[u8]) -> String {
+    Sha256::digest(bytes).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Whether `s` looks like an [`image_ref`], so it is safe to turn into a path or key.
+pub fn is_image_ref(s: &str) -> bool {
+    s.len() == 64 && s.bytes().all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b))
+}
+
+fn decode(b64: &str) -> Option<Vec<u8>> {
+    base64::engine::general_purpose::STANDARD.decode(b64).ok()
+}
+
+enum Backend {
+    /// `{IMAGE_DIR}/{ref[..2]}/{ref}`
+    Disk(PathBuf),
+    /// `{S3_PREFIX}{ref}` in `S3_BUCKET`
+    S3(Box<S3Store>),
+}
+
+/// Content-addressed stage images outside the lifecycle documents, on local disk (`IMAGE_DIR`) or in an
+/// S3-compatible bucket (`S3_BUCKET`, for deployments whose disk is ephemeral). Stored lifecycles then carry only
+/// `image_ref` per stage, which keeps the store and list responses small and shares identical images
+/// (placeholders, duplicated lifecycles) between stages.
+pub struct ImageFiles {
+    backend: Backend,
+    /// `IMAGE_GC_INTERVAL_MINUTES` (default 60): how often orphaned images are collected.
+    gc_interval_minutes: u64,
+}
+
+impl ImageFiles {
+    /// S3 when `S3_BUCKET` is set, else disk when `IMAGE_DIR` is set; `None` keeps images inline.
+    pub fn from_env() -> Option<Self> {
+        let backend = match S3Store::from_env() {
+            Some(s3) => Backend::S3(Box::new(s3)),
+            None => {
+                let root = std::env::var("IMAGE_DIR").ok().filter(|dir| !dir.is_empty())?;
+                info!("🖼️ Storing stage images in {}", root);
+                Backend::Disk(root.into())
+            }
+        };
+        Some(Self {
+            backend,
+            gc_interval_minutes: std::env::var("IMAGE_GC_INTERVAL_MINUTES").ok().and_then(|v| v.parse().ok()).unwrap_or(60),
+        })
+    }
+
+    fn path(root: &std::path::Path, image_ref: &str) -> PathBuf {
+        root.join(&image_ref[..2]).join(image_ref)
+    }
+
+    async fn put(&self, image_ref: &str, bytes: Vec<u8>) -> io::Result<()> {
+        match &self.backend {
+            Backend::Disk(root) => {
+                let path = Self::path(root, image_ref);
+                if tokio::fs::try_exists(&path).await.unwrap_or(false) {
+                    return Ok(());
+                }
+                if let Some(dir) = path.parent() {
+                    tokio::fs::create_dir_all(dir).await?;
+                }
+                // Write-then-rename so readers never see a partial file.
+                let tmp = path.with_extension(format!("{}.tmp", Uuid::new_v4()));
+                tokio::fs::write(&tmp, &bytes).await?;
+                tokio::fs::rename(&tmp, &path).await
+            }
+            Backend::S3(s3) => s3.put(image_ref, bytes.clone(), sniff_image_bytes(&bytes)).await,
+        }
+    }
+
+    async fn get(&self, image_ref: &str) -> Option<Vec<u8>> {
+        let bytes = match &self.backend {
+            Backend::Disk(root) => tokio::fs::read(Self::path(root, image_ref)).await.ok(),
+            Backend::S3(s3) => s3.get(image_ref).await,
+        };
+        if bytes.is_none() {
+            warn!("⚠️ Stored image {} is unreadable", image_ref);
+        }
+        bytes
+    }
+
+    /// Where clients download an image: a presigned bucket URL, or `/api/images/{ref}` for disk storage.
+    pub fn url(&self, image_ref: &str) -> String {
+        match &self.backend {
+            Backend::Disk(_) => format!("/api/images/{}", image_ref),
+            Backend::S3(s3) => s3.download_url(image_ref),
+        }
+    }
+
+    /// Presigned download URL when images live in a bucket; `None` for disk storage.
+    pub fn redirect_url(&self, image_ref: &str) -> Option<String> {
+        match &self.backend {
+            Backend::Disk(_) => None,
+            Backend::S3(s3) => Some(s3.download_url(image_ref)),
+        }
+    }
+
+    /// Raw bytes and modification time of an image on disk, for serving it.
+    pub async fn read(&self, image_ref: &str) -> Option<(Vec<u8>, SystemTime)> {
+        let Backend::Disk(root) = &self.backend else { return None };
+        if !is_image_ref(image_ref) {
+            return None;
+        }
+        let path = Self::path(root, image_ref);
+        let modified = tokio::fs::metadata(&path).await.ok()?.modified().ok()?;
+        Some((tokio::fs::read(&path).await.ok()?, modified))
+    }
+
+    /// Every stored image with when it was written.
+    async fn list(&self) -> io::Result<Vec<(String, Option<DateTime<Utc>>)>> {
+        let mut images = Vec::new();
+        match &self.backend {
+            Backend::Disk(root) => {
+                let Ok(mut shards) = tokio::fs::read_dir(root).await else { return Ok(images) };
+                while let Some(shard) = shards.next_entry().await? {
+                    let Ok(mut files) = tokio::fs::read_dir(shard.path()).await else { continue };
+                    while let Some(file) = files.next_entry().await? {
+                        let modified = file.metadata().await.ok().and_then(|m| m.modified().ok()).map(DateTime::<Utc>::from);
+                        images.push((file.file_name().to_string_lossy().into_owned(), modified));
+                    }
+                }
+            }
+            Backend::S3(s3) => {
+                images.extend(s3.list().await?.into_iter().filter_map(|o| Some((s3.name(&o.key)?.to_string(), o.last_modified))));
+            }
+        }
+        Ok(images)
+    }
+
+    async fn delete(&self, image_ref: &str) -> io::Result<()> {
+        match &self.backend {
+            Backend::Disk(root) => tokio::fs::remove_file(root.join(&image_ref[..2.min(image_ref.len())]).join(image_ref)).await,
+            Backend::S3(s3) => s3.delete(image_ref).await,
+        }
+    }
+
+    /// Store every inline image of `lifecycle` not in `stored` yet. Returns the refs now safely stored.
+    async fn upload(&self, lifecycle: &Lifecycle, stored: &HashSet<String>) -> HashSet<String> {
+        let mut uploaded = HashSet::new();
+        for (b64, _) in lifecycle.images() {
+            let Some(bytes) = b64.and_then(decode) else { continue };
+            let image_ref = image_ref(&bytes);
+            if stored.contains(&image_ref) || uploaded.contains(&image_ref) {
+                continue;
+            }
+            match self.put(&image_ref, bytes).await {
+                Ok(()) => {
+                    uploaded.insert(image_ref);
+                }
+                Err(e) => error!("❌ Could not store image {} of {}; keeping it inline: {}", image_ref, lifecycle.id, e),
+            }
+        }
+        uploaded
+    }
+
+    /// Images of a stored lifecycle, by ref, for filling stages back in.
+    async fn fetch(&self, lifecycle: &Lifecycle) -> HashMap<String, String> {
+        let mut images = HashMap::new();
+        for image_ref in lifecycle.images().filter(|(b64, _)| b64.is_none()).filter_map(|(_, r)| r).filter(|r| is_image_ref(r)) {
+            if !images.contains_key(image_ref) {
+                if let Some(bytes) = self.get(image_ref).await {
+                    images.insert(image_ref.to_string(), base64::engine::general_purpose::STANDARD.encode(bytes));
+                }
+            }
+        }
+        images
+    }
+
+    /// Delete images no stored lifecycle references any more (older than the grace period). Returns how many
+    /// were removed.
+    async fn collect_garbage(&self, repo: &dyn LifecycleRepository) -> Result<usize, StoreError> {
+        let referenced: HashSet<String> = repo.list().await?.into_iter()
+            .flat_map(|l| l.images().filter_map(|(_, r)| r.map(str::to_string)).collect::<Vec<_>>())
+            .collect();
+        let images = self.list().await.map_err(|e| StoreError::Database(format!("listing images: {}", e)))?;
+        let cutoff = Utc::now() - chrono::Duration::from_std(GC_GRACE).unwrap_or_default();
+        let mut removed = 0;
+        for (name, modified) in images {
+            if referenced.contains(&name) || !is_image_ref(&name) || modified.is_none_or(|m| m > cutoff) {
+                continue;
+            }
+            match self.delete(&name).await {
+                Ok(()) => removed += 1,
+                Err(e) => warn!("⚠️ Could not remove orphaned image {}: {}", name, e),
+            }
+        }
+        Ok(removed)
+    }
+}
+
+/// Fill in images (of stages and candidates) that only carry an `image_ref` from `images`. False if one of them
+/// is missing.
+fn inline(lifecycle: &mut Lifecycle, images: &HashMap<String, String>) -> bool {
+    let mut complete = true;
+    for (b64, image_ref) in lifecycle.images_mut().filter(|(b64, _)| b64.is_none()) {
+        if let Some(image_ref) = image_ref {
+            match images.get(image_ref.as_str()) {
+                Some(image) => *b64 = Some(image.clone()),
+                None => complete = false,
+            }
+        }
+    }
+    complete
+}
+
+/// Replace inline images that are safely stored (their ref is in `stored`) by their ref.
+fn externalize(lifecycle: &mut Lifecycle, stored: &HashSet<String>) -> bool {
+    let mut changed = false;
+    for (b64, stored_ref) in lifecycle.images_mut() {
+        let Some(image_ref) = b64.as_deref().and_then(decode).map(|bytes| image_ref(&bytes)) else { continue };
+        if stored.contains(&image_ref) {
+            *b64 = None;
+            *stored_ref = Some(image_ref);
+            changed = true;
+        }
+    }
+    changed
+}
+
+/// Background task removing orphaned images every `IMAGE_GC_INTERVAL_MINUTES`.
+pub fn spawn_gc_task(files: Option<Arc<ImageFiles>>, repo: Arc<dyn LifecycleRepository>) {
+    let Some(files) = files else { return };
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(Duration::from_secs(files.gc_interval_minutes.max(1) * 60));
+        loop {
+            ticker.tick().await;
+            match files.collect_garbage(repo.as_ref()).await {
+                Ok(0) => {}
+                Ok(removed) => info!("🗑️ Removed {} orphaned image(s)", removed),
+                Err(e) => error!("❌ Image garbage collection failed: {}", e),
+            }
+        }
+    });
+}
+
+/// Keeps stage images out of the wrapped store: `get` and `update` load them back so handlers see complete
+/// stages, and `list` returns refs only. New images are committed inline first, then stored and swapped for
+/// their ref, so a failed upload never loses an image; it just stays inline until the next write.
+pub struct ImageFileRepository {
+    inner: Arc<dyn LifecycleRepository>,
+    files: Arc<ImageFiles>,
+}
+
+impl ImageFileRepository {
+    pub fn wrap(inner: Arc<dyn LifecycleRepository>, files: Option<Arc<ImageFiles>>) -> Arc<dyn LifecycleRepository> {
+        match files {
+            Some(files) => Arc::new(Self { inner, files }),
+            None => inner,
+        }
+    }
+
+    /// Move the inline images of a just-written lifecycle out of the store.
+    async fn externalize_stored(&self, id: Uuid, lifecycle: &Lifecycle, already_stored: &HashSet<String>) -> Result<(), StoreError> {
+        let uploaded = self.files.upload(lifecycle, already_stored).await;
+        if uploaded.is_empty() {
+            return Ok(());
+        }
+        self.inner.update(id, &mut |stored| externalize(stored, &uploaded)).await.map(drop)
+    }
+}
+
+/// Attempts at loading a lifecycle's images and applying an update before giving up on images that went missing.
+const UPDATE_ATTEMPTS: usize = 3;
+
+#[async_trait]
+impl LifecycleRepository for ImageFileRepository {
+    async fn insert(&self, lifecycle: &Lifecycle) -> Result<(), StoreError> {
+        let uploaded = self.files.upload(lifecycle, &HashSet::new()).await;
+        let mut lifecycle = lifecycle.clone();
+        externalize(&mut lifecycle, &uploaded);
+        self.inner.insert(&lifecycle).await
+    }
+
+    async fn get(&self, id: Uuid) -> Result<Option<Lifecycle>, StoreError> {
+        let mut lifecycle = self.inner.get(id).await?;
+        if let Some(lifecycle) = &mut lifecycle {
+            let images = self.files.fetch(lifecycle).await;
+            inline(lifecycle, &images);
+        }
+        Ok(lifecycle)
+    }
+
+    async fn update(&self, id: Uuid, f: &mut (dyn for<'l> FnMut(&'l mut Lifecycle) -> bool + Send)) -> Result<bool, StoreError> {
+        for attempt in 1..=UPDATE_ATTEMPTS {
+            // The update callback is synchronous, so images are loaded up front from the current version. If a
+            // concurrent write changed an image meanwhile, nothing is applied and the load is repeated.
+            let Some(current) = self.inner.get(id).await? else { return Ok(false) };
+            let images = self.files.fetch(&current).await;
+            let stored: HashSet<String> = images.keys().cloned().collect();
+            let mut missing = false;
+            let mut written = None;
+            let found = self.inner.update(id, &mut |lifecycle| {
+                if !inline(lifecycle, &images) && attempt < UPDATE_ATTEMPTS {
+                    missing = true;
+                    return false;
+                }
+                let changed = f(lifecycle);
+                if changed {
+                    externalize(lifecycle, &stored);
+                    written = Some(lifecycle.clone());
+                }
+                changed
+            }).await?;
+            if missing {
+                continue;
+            }
+            if let Some(written) = written {
+                self.externalize_stored(id, &written, &stored).await?;
+            }
+            return Ok(found);
+        }
+        Ok(false)
+    }
+
+    async fn delete(&self, id: Uuid) -> Result<bool, StoreError> {
+        // Images may be shared with other stages; garbage collection removes them once unreferenced.
+        self.inner.delete(id).await
+    }
+
+    async fn list(&self) -> Result<Vec<Lifecycle>, StoreError> {
+        self.inner.list().await
+    }
+
+    async fn flush(&self) -> Result<(), StoreError> {
+        self.inner.flush().await
+    }
+}
@@ -0,0 +1,48 @@
+use base64::Engine;
+use image::{codecs::jpeg::JpegEncoder, imageops::FilterType, DynamicImage};
+use std::io::Cursor;
+
+use crate::{gemini::sniff_mime_type, models::StageImage};
+
+/// Decoded bytes and mime type of a stage's stored image.
+pub fn stage_image_bytes(stage: &StageImage) -> Option<(Vec<u8>, &'static str)> {
+    let b64 = stage.image_base64.as_deref()?;
+    let bytes = base64::engine::general_purpose::STANDARD.decode(b64).ok()?;
+    Some((bytes, sniff_mime_type(b64)))
+}
+
+/// Mime type of raw image bytes, by the same signatures as [`sniff_mime_type`].
+pub fn sniff_image_bytes(bytes: &[u8]) -> &'static str {
+    // 12 bytes encode to 16 base64 characters with no padding, enough for every signature.
+    sniff_mime_type(&base64::engine::general_purpose::STANDARD.encode(&bytes[..bytes.len().min(12)]))
+}
+
+/// Raster (PNG/JPEG) image for a stage. SVG placeholders and undecodable data yield `None`.
+pub fn decode_raster(stage: &StageImage) -> Option<DynamicImage> {
+    let (bytes, mime) = stage_image_bytes(stage)?;
+    if mime == "image/svg+xml" { return None; }
+    image::load_from_memory(&bytes).ok()
+}
+
+/// Shrink a base64 raster so neither side exceeds `max_px`, re-encoded as PNG. Smaller images, SVGs and
+/// undecodable data are returned unchanged.
+pub fn downscale_base64(b64: String, max_px: u32) -> String {
+    if sniff_mime_type(&b64) == "image/svg+xml" { return b64; }
+    let Some(img) = base64::engine::general_purpose::STANDARD.decode(&b64).ok().and_then(|bytes| image::load_from_memory(&bytes).ok()) else { return b64 };
+    if img.width() <= max_px && img.height() <= max_px { return b64; }
+    let mut png = Vec::new();
+    if img.resize(max_px, max_px, FilterType::Triangle).write_to(&mut Cursor::new(&mut png), image::ImageFormat::Png).is_err() {
+        return b64;
+    }
+    base64::engine::general_purpose::STANDARD.encode(png)
+}
+
+/// JPEG no wider than `width` (aspect ratio kept, never upscaled) for gallery views. `None` for SVGs and
+/// undecodable data.
+pub fn jpeg_thumbnail(bytes: &[u8], width: u32) -> Option<Vec<u8>> {
+    let img = image::load_from_memory(bytes).ok()?;
+    let img = if img.width() > width { img.resize(width, u32::MAX, FilterType::Triangle) } else { img };
+    let mut jpeg = Vec::new();
+    JpegEncoder::new_with_quality(&mut jpeg, 80).encode_image(&DynamicImage::ImageRgb8(img.to_rgb8())).ok()?;
+    Some(jpeg)
+}
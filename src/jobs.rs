@@ -0,0 +1,80 @@
+use chrono::{DateTime, Utc};
+use parking_lot::RwLock;
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use uuid::Uuid;
+
+/// Upper bound on retained jobs; the oldest are forgotten first.
+const MAX_JOBS: usize = 10_000;
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum JobState {
+    Queued,
+    Running,
+    Succeeded,
+    Failed,
+}
+
+/// A stage regeneration queued in the background, e.g. after a constraint change invalidated the stage.
+#[derive(Debug, Clone, Serialize)]
+pub struct Job {
+    pub id: Uuid,
+    pub lifecycle_id: Uuid,
+    pub stage_index: usize,
+    pub stage_name: String,
+    pub state: JobState,
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+/// In-memory registry of background jobs (this instance only), polled through `GET /api/jobs/{id}`.
+#[derive(Default)]
+pub struct Jobs {
+    jobs: RwLock<(HashMap<Uuid, Job>, VecDeque<Uuid>)>,
+}
+
+impl Jobs {
+    pub fn create(&self, lifecycle_id: Uuid, stage_index: usize, stage_name: &str) -> Uuid {
+        let job = Job {
+            id: Uuid::new_v4(),
+            lifecycle_id,
+            stage_index,
+            stage_name: stage_name.to_string(),
+            state: JobState::Queued,
+            error: None,
+            created_at: Utc::now(),
+            finished_at: None,
+        };
+        let id = job.id;
+        let mut guard = self.jobs.write();
+        let (jobs, order) = &mut *guard;
+        jobs.insert(id, job);
+        order.push_back(id);
+        while order.len() > MAX_JOBS {
+            if let Some(oldest) = order.pop_front() {
+                jobs.remove(&oldest);
+            }
+        }
+        id
+    }
+
+    pub fn start(&self, id: Uuid) {
+        if let Some(job) = self.jobs.write().0.get_mut(&id) {
+            job.state = JobState::Running;
+        }
+    }
+
+    pub fn finish(&self, id: Uuid, error: Option<String>) {
+        if let Some(job) = self.jobs.write().0.get_mut(&id) {
+            job.state = if error.is_some() { JobState::Failed } else { JobState::Succeeded };
+            job.error = error;
+            job.finished_at = Some(Utc::now());
+        }
+    }
+
+    pub fn get(&self, id: Uuid) -> Option<Job> {
+        self.jobs.read().0.get(&id).cloned()
+    }
+}
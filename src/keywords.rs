@@ -0,0 +1,131 @@
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::{BTreeMap, BTreeSet};
+use tracing::{info, warn};
+
+use crate::{gemini::{GeminiClient, GeminiError, TEXT_MODEL}, models::{Lifecycle, StageKeywords}, vision::parse_json_reply};
+
+const MAX_PER_KIND: usize = 10;
+
+/// Canonical form used for storage and matching: lowercase, hyphens/underscores as spaces, single spaces, no
+/// surrounding punctuation. "Injection-Molding." and "injection  molding" both become "injection molding".
+pub fn normalize_keyword(raw: &str) -> String {
+    raw.to_lowercase()
+        .replace(['-', '_'], " ")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .trim_matches(|c: char| !c.is_alphanumeric())
+        .to_string()
+}
+
+fn normalize_all(raw: Vec<String>) -> Vec<String> {
+    let mut seen = BTreeSet::new();
+    raw.iter()
+        .map(|k| normalize_keyword(k))
+        .filter(|k| !k.is_empty() && seen.insert(k.clone()))
+        .take(MAX_PER_KIND)
+        .collect()
+}
+
+impl GeminiClient {
+    /// Processes, materials and impacts mentioned in a stage description, normalized for aggregation.
+    pub async fn extract_keywords(&self, stage: &str, description: &str) -> Result<StageKeywords, GeminiError> {
+        if self.is_demo() {
+            return Ok(StageKeywords::default());
+        }
+        let instruction = format!(
+            "Extract keywords from this description of the {stage} stage of a product lifecycle. \
+            Use short generic noun phrases (1-3 words) as they would be indexed, e.g. \"injection molding\", \"polypropylene\", \"water use\". \
+            At most {MAX_PER_KIND} per list.\n\
+            Reply with JSON only: {{\"processes\": [...], \"materials\": [...], \"impacts\": [...]}}\n\n\
+            Description:\n{description}"
+        );
+        let payload = json!({
+            "contents": [{ "parts": [{"text": instruction}] }],
+            "generationConfig": { "temperature": 0.0, "responseMimeType": "application/json" }
+        });
+        let reply = self.generate_content(TEXT_MODEL, &payload).await?
+            .first_text()
+            .ok_or_else(|| GeminiError::Other("No text content found in response".to_string()))?;
+        let raw: StageKeywords = parse_json_reply(&reply)?;
+        Ok(StageKeywords {
+            processes: normalize_all(raw.processes),
+            materials: normalize_all(raw.materials),
+            impacts: normalize_all(raw.impacts),
+        })
+    }
+
+    /// Keyword extraction run during generation when `EXTRACT_KEYWORDS` is enabled; failures yield no keywords.
+    pub async fn auto_extract_keywords(&self, stage: &str, description: &str) -> StageKeywords {
+        if !self.extract_keywords {
+            return StageKeywords::default();
+        }
+        match self.extract_keywords(stage, description).await {
+            Ok(keywords) => {
+                info!("🏷️ Stage '{}': {} process, {} material, {} impact keyword(s)", stage, keywords.processes.len(), keywords.materials.len(), keywords.impacts.len());
+                keywords
+            }
+            Err(e) => {
+                warn!("⚠️ Keyword extraction failed for stage '{}': {}", stage, e);
+                StageKeywords::default()
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeywordKind {
+    Process,
+    Material,
+    Impact,
+}
+
+#[derive(Debug, Serialize)]
+pub struct KeywordCount {
+    pub keyword: String,
+    pub kind: KeywordKind,
+    /// Lifecycles with at least one stage mentioning it.
+    pub lifecycles: usize,
+    pub stages: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct KeywordStats {
+    pub total_lifecycles: usize,
+    pub keywords: Vec<KeywordCount>,
+}
+
+impl StageKeywords {
+    pub fn by_kind(&self) -> impl Iterator<Item = (KeywordKind, &String)> {
+        self.processes.iter().map(|k| (KeywordKind::Process, k))
+            .chain(self.materials.iter().map(|k| (KeywordKind::Material, k)))
+            .chain(self.impacts.iter().map(|k| (KeywordKind::Impact, k)))
+    }
+}
+
+/// Does any stage of the lifecycle carry `keyword` (already normalized)?
+pub fn mentions(lifecycle: &Lifecycle, keyword: &str) -> bool {
+    lifecycle.stages.iter().any(|s| s.keywords.by_kind().any(|(_, k)| k == keyword))
+}
+
+/// Keyword frequencies across lifecycles, most widespread first; optionally restricted to one kind.
+pub fn keyword_stats(lifecycles: &[Lifecycle], kind: Option<KeywordKind>, limit: usize) -> KeywordStats {
+    let mut counts: BTreeMap<(KeywordKind, String), (BTreeSet<uuid::Uuid>, usize)> = BTreeMap::new();
+    for lifecycle in lifecycles {
+        for stage in &lifecycle.stages {
+            for (k, keyword) in stage.keywords.by_kind().filter(|(k, _)| kind.is_none_or(|want| *k == want)) {
+                let entry = counts.entry((k, keyword.clone())).or_default();
+                entry.0.insert(lifecycle.id);
+                entry.1 += 1;
+            }
+        }
+    }
+    let mut keywords: Vec<KeywordCount> = counts.into_iter()
+        .map(|((kind, keyword), (ids, stages))| KeywordCount { keyword, kind, lifecycles: ids.len(), stages })
+        .collect();
+    keywords.sort_by(|a, b| b.lifecycles.cmp(&a.lifecycles).then(b.stages.cmp(&a.stages)).then(a.keyword.cmp(&b.keyword)));
+    keywords.truncate(limit);
+    KeywordStats { total_lifecycles: lifecycles.len(), keywords }
+}
@@ -0,0 +1,41 @@
+use axum::{http::{header, StatusCode}, BoxError};
+use std::time::Duration;
+use tracing::{info, warn};
+
+use crate::error::ApiError;
+
+/// Process-wide guards against a single client exhausting the server, from the environment.
+pub struct ServerLimits {
+    /// `MAX_BODY_MB` (default 2): body limit of JSON and other buffered requests. Imports and attachments keep
+    /// their own (`MAX_IMPORT_MB`, `MAX_ASSET_MB`).
+    pub max_body_bytes: usize,
+    /// `REQUEST_TIMEOUT_SECS` (default 300, `0` disables): how long a request may take until its response starts;
+    /// streamed bodies and WebSockets are not cut off. Long enough for a full generation at `print`.
+    pub timeout: Option<Duration>,
+    /// `MAX_CONCURRENT_REQUESTS` (default 256, `0` disables): requests handled at once; more are turned away
+    /// with 503 rather than queued.
+    pub max_concurrent: Option<usize>,
+}
+
+impl ServerLimits {
+    pub fn from_env() -> Self {
+        let read = |name: &str, default: u64| std::env::var(name).ok().and_then(|v| v.parse().ok()).unwrap_or(default);
+        let limits = Self {
+            max_body_bytes: read("MAX_BODY_MB", 2).max(1) as usize * 1024 * 1024,
+            timeout: Some(read("REQUEST_TIMEOUT_SECS", 300)).filter(|s| *s > 0).map(Duration::from_secs),
+            max_concurrent: Some(read("MAX_CONCURRENT_REQUESTS", 256) as usize).filter(|n| *n > 0),
+        };
+        info!("🛡️ Server limits: {} MB bodies, {} timeout, {} concurrent requests",
+            limits.max_body_bytes / (1024 * 1024),
+            limits.timeout.map_or("no".to_string(), |t| format!("{}s", t.as_secs())),
+            limits.max_concurrent.map_or("unlimited".to_string(), |n| n.to_string()));
+        limits
+    }
+}
+
+/// The concurrency limit shed a request (the only error the limit layers produce).
+pub async fn overloaded(error: BoxError) -> ([(header::HeaderName, &'static str); 1], ApiError) {
+    warn!("🛡️ Turned a request away: {}", error);
+    let error = ApiError::new(StatusCode::SERVICE_UNAVAILABLE, "overloaded", "The server is handling as many requests as it allows; retry shortly");
+    ([(header::RETRY_AFTER, "1")], error)
+}
@@ -2,15 +2,77 @@ mod routes;
 mod models;
 mod gemini;
 mod pdf;
+mod failures;
+mod vision;
+mod scheduler;
+mod fixtures;
+mod critique;
+mod config;
+mod glossary;
+mod export;
+mod images;
+mod sprite;
+mod rules;
+mod maps;
+mod economics;
+mod audit;
+mod retention;
+mod public_demo;
+mod repository;
+mod terms;
+mod keywords;
+mod metrics;
+mod caching;
+mod events;
+mod pagination;
+mod admin;
+mod blobs;
+mod image_files;
+mod s3;
+mod jobs;
+mod exports;
+mod tenants;
+mod share;
+mod usage;
+mod billing;
+mod error;
+mod provider;
+mod retry;
+mod circuit;
+mod collab;
+mod degraded;
+mod chaos;
+mod single_flight;
+mod seed;
+mod model_aliases;
+mod bundle;
+mod ollama;
+mod prompt_budget;
+mod signing;
+mod stability;
+mod sync;
+mod templates;
+mod precondition;
+mod quality;
+mod outbound;
+mod html;
+mod palette;
+mod theme;
+mod workspaces;
+mod rate_limit;
+mod validation;
+mod limits;
+mod shutdown;
 
-use axum::{Router, routing::{post, get}};
-use routes::{generate_lifecycle, get_lifecycle, regenerate_stage, export_pdf, create_lifecycle_skeleton, generate_stage_image, AppState};
+use axum::{Router, error_handling::HandleErrorLayer, extract::DefaultBodyLimit, http::HeaderName, routing::{post, get, put, delete}};
+use routes::{generate_lifecycle, generate_lifecycle_stream, list_lifecycles, search_lifecycles, keyword_analytics, get_lifecycle, delete_lifecycle, regenerate_stage, export_pdf, create_lifecycle_skeleton, generate_stage_image, generate_stages, failure_heatmap, scheduler_stats, set_review_status, thumbnail_sprite, thumbnail_sprite_index, list_stage_actors, add_stage_actor, remove_stage_actor, set_stage_locations, estimate_economics, explain_stage_image, check_stage_consistency, regenerate_to_match, select_description_candidate, select_image_candidate, edit_stage_description, patch_stage, patch_lifecycle, get_job, clone_lifecycle, share_lifecycle, create_share_link, revoke_share_link, insert_stage, delete_stage, reorder_stages, extract_stage_terms, generate_narratives, retention_report, template_version_stats, audit_log, billing_export, metrics, health, readyz, export_profiles, model_aliases, export_bundle, export_zip, export_markdown, export_html, export_pdf_with_theme, export_html_with_theme, export_csv, import_lifecycle, quality_report, stage_image, image_file, stage_thumbnail, lifecycle_events, lifecycle_sync, lifecycle_ws, purge_lifecycles, get_export, export_store, import_store, list_stage_assets, upload_stage_asset, download_stage_asset, remove_stage_asset, download_links, AppState};
 use std::net::SocketAddr;
 use tracing_subscriber::{fmt, EnvFilter};
 use std::sync::Arc;
-use tower_http::cors::{CorsLayer, Any};
+use tower::{limit::GlobalConcurrencyLimitLayer, ServiceBuilder};
+use tower_http::{cors::{CorsLayer, Any}, timeout::TimeoutLayer};
 
-use crate::gemini::GeminiClient;
+use crate::{audit::AuditLog, public_demo::PublicDemo, config::AppConfig, retention::spawn_retention_task, gemini::GeminiClient, failures::FailureLog, maps::MapRenderer, scheduler::GenerationScheduler};
 
 #[tokio::main]
 async fn main() {
@@ -23,28 +85,150 @@ async fn main() {
 
     let api_key = std::env::var("GEMINI_API_KEY").unwrap_or_else(|_| "DEMO_KEY".into());
     tracing::info!("Using API key: {}...", &api_key[..std::cmp::min(10, api_key.len())]);
+    let config = AppConfig::load();
+    let failures = Arc::new(FailureLog::default());
+    let audit = Arc::new(AuditLog::default());
+    let public_demo = PublicDemo::from_env().map(Arc::new);
+    let chaos = chaos::Chaos::from_env().map(Arc::new);
+    let gemini = Arc::new(GeminiClient::new(api_key, failures.clone()).with_chaos(chaos.clone()).with_glossary(config.glossary.clone()).with_palette(config.palette.clone()).with_model_aliases(config.models.clone()).with_max_image_px(public_demo.as_ref().map(|d| d.max_image_px)).with_text_provider(ollama::OllamaClient::from_env()));
+    let image_files = image_files::ImageFiles::from_env().map(Arc::new);
     let state = AppState { 
-        store: Arc::default(),
-        gemini: Arc::new(GeminiClient::new(api_key)),
+        repo: image_files::ImageFileRepository::wrap(chaos::ChaosRepository::wrap(repository::from_env().await, chaos), image_files.clone()),
+        images: match stability::StabilityClient::from_env(failures.clone(), gemini.clone()) {
+            Some(stability) => Arc::new(stability.with_max_image_px(public_demo.as_ref().map(|d| d.max_image_px))),
+            None => gemini.clone(),
+        },
+        gemini,
+        failures,
+        scheduler: Arc::new(GenerationScheduler::from_env()),
+        config: Arc::new(config),
+        maps: Arc::new(MapRenderer::from_env()),
+        audit,
+        public_demo,
+        events: Arc::default(),
+        admin: Arc::new(admin::AdminAuth::from_env()),
+        blobs: Arc::new(blobs::BlobStore::from_env()),
+        signer: Arc::new(signing::UrlSigner::from_env()),
+        patches: Arc::default(),
+        collab: Arc::default(),
+        workspaces: Arc::new(workspaces::WorkspaceKeys::from_env()),
+        rate_limits: Arc::new(rate_limit::RateLimits::from_env()),
+        stage_generations: Arc::default(),
+        image_files,
+        jobs: Arc::default(),
+        exports: Arc::new(exports::ExportJobs::from_env()),
+        shutdown: Arc::new(shutdown::Shutdown::from_env()),
     };
+    seed::seed_from_env(state.repo.as_ref(), &state.config.palette).await;
+    public_demo::spawn_expiry_task(state.clone());
+    image_files::spawn_gc_task(state.image_files.clone(), state.repo.clone());
+    spawn_retention_task(state.config.retention.clone(), state.repo.clone(), state.audit.clone(), state.blobs.clone());
+
+    let limits = limits::ServerLimits::from_env();
+    // Exports carry their images inline, far beyond the default body limit.
+    let max_import_bytes = std::env::var("MAX_IMPORT_MB").ok().and_then(|v| v.parse().ok()).unwrap_or(50usize) * 1024 * 1024;
+
+    // Same handlers as their /api counterparts, but authorized by the signature in the query string.
+    let signed_downloads = Router::new()
+        .route("/dl/lifecycle/:id/pdf", get(export_pdf))
+        .route("/dl/lifecycle/:id/stage/:stage_index/image", get(stage_image))
+        .route("/dl/lifecycle/:id/stage/:stage_index/assets/:asset_id", get(download_stage_asset))
+        .route_layer(axum::middleware::from_fn_with_state(state.clone(), signing::require_signature));
 
     let app = Router::new()
-        .route("/api/lifecycle", post(generate_lifecycle))
+        .route("/api/lifecycle", post(generate_lifecycle).get(list_lifecycles))
+        .route("/api/lifecycles", delete(purge_lifecycles))
         .route("/api/lifecycle/create", post(create_lifecycle_skeleton))
-        .route("/api/lifecycle/:id", get(get_lifecycle))
-        .route("/api/lifecycle/:id/stage/:stage_index", post(generate_stage_image))
+        .route("/api/lifecycle/stream", post(generate_lifecycle_stream))
+        .route("/api/lifecycle/import", post(import_lifecycle).layer(DefaultBodyLimit::max(max_import_bytes)))
+        .route("/api/lifecycle/search", get(search_lifecycles))
+        .route("/api/lifecycle/:id", get(get_lifecycle).patch(patch_lifecycle).delete(delete_lifecycle))
+        .route("/share/:slug", get(share_lifecycle))
+        .route("/api/lifecycle/:id/share", post(create_share_link))
+        .route("/api/lifecycle/:id/share/:share_id", delete(revoke_share_link))
+        .route("/api/lifecycle/:id/events", get(lifecycle_events))
+        .route("/api/lifecycle/:id/sync", get(lifecycle_sync))
+        .route("/api/lifecycle/:id/ws", get(lifecycle_ws))
+        .route("/api/lifecycle/:id/stage/:stage_index", post(generate_stage_image).patch(patch_stage).delete(delete_stage))
         .route("/api/lifecycle/:id/stage", post(regenerate_stage))
-        .route("/api/lifecycle/:id/pdf", get(export_pdf))
+        .route("/api/lifecycle/:id/stages", post(insert_stage))
+        .route("/api/lifecycle/:id/stages/generate", post(generate_stages))
+        .route("/api/lifecycle/:id/clone", post(clone_lifecycle))
+        .route("/api/lifecycle/:id/narratives", post(generate_narratives))
+        .route("/api/lifecycle/:id/stages/order", put(reorder_stages))
+        .route("/api/lifecycle/:id/stage/:stage_index/image", get(stage_image))
+        .route("/api/lifecycle/:id/stage/:stage_index/thumbnail", get(stage_thumbnail))
+        .route("/api/images/:image_ref", get(image_file))
+        .route("/api/lifecycle/:id/stage/:stage_index/actors", get(list_stage_actors).post(add_stage_actor))
+        .route("/api/lifecycle/:id/stage/:stage_index/actors/:actor_id", delete(remove_stage_actor))
+        .route("/api/lifecycle/:id/stage/:stage_index/assets", get(list_stage_assets).post(upload_stage_asset).layer(DefaultBodyLimit::max(state.blobs.max_asset_bytes)))
+        .route("/api/lifecycle/:id/stage/:stage_index/assets/:asset_id", get(download_stage_asset).delete(remove_stage_asset))
+        .route("/api/lifecycle/:id/stage/:stage_index/locations", put(set_stage_locations))
+        .route("/api/lifecycle/:id/stage/:stage_index/explain", post(explain_stage_image))
+        .route("/api/lifecycle/:id/stage/:stage_index/consistency", post(check_stage_consistency))
+        .route("/api/lifecycle/:id/stage/:stage_index/regenerate-to-match", post(regenerate_to_match))
+        .route("/api/lifecycle/:id/stage/:stage_index/description", put(edit_stage_description))
+        .route("/api/lifecycle/:id/stage/:stage_index/description/select", post(select_description_candidate))
+        .route("/api/lifecycle/:id/stage/:stage_index/image/select", post(select_image_candidate))
+        .route("/api/lifecycle/:id/stage/:stage_index/terms", post(extract_stage_terms))
+        .route("/api/lifecycle/:id/pdf", get(export_pdf).post(export_pdf_with_theme))
+        .route("/api/lifecycle/:id/bundle.tar.gz", get(export_bundle))
+        .route("/api/lifecycle/:id/export.zip", get(export_zip))
+        .route("/api/lifecycle/:id/markdown", get(export_markdown))
+        .route("/api/lifecycle/:id/html", get(export_html).post(export_html_with_theme))
+        .route("/api/lifecycle/:id/csv", get(export_csv))
+        .route("/api/lifecycle/:id/quality", get(quality_report))
+        .route("/api/lifecycle/:id/links", get(download_links))
+        .route("/api/export/profiles", get(export_profiles))
+        .route("/api/lifecycle/:id/economics", post(estimate_economics))
+        .route("/api/lifecycle/:id/review", put(set_review_status))
+        .route("/api/lifecycle/:id/thumbnails.png", get(thumbnail_sprite))
+        .route("/api/lifecycle/:id/thumbnails.json", get(thumbnail_sprite_index))
+        .route("/api/analytics/keywords", get(keyword_analytics))
+        .route("/api/admin/failures", get(failure_heatmap))
+        .route("/api/admin/scheduler", get(scheduler_stats))
+        .route("/api/admin/retention", get(retention_report))
+        .route("/api/admin/templates", get(template_version_stats))
+        .route("/api/admin/models", get(model_aliases))
+        .route("/api/admin/audit", get(audit_log))
+        .route("/api/admin/billing", get(billing_export))
+        .route("/api/admin/export", get(export_store))
+        .route("/api/admin/import", post(import_store))
+        .route("/api/jobs/:job_id", get(get_job))
+        .route("/api/exports/:token", get(get_export))
+        .merge(signed_downloads)
+        .layer(
+            ServiceBuilder::new()
+                .layer(HandleErrorLayer::new(limits::overloaded))
+                .load_shed()
+                .option_layer(limits.max_concurrent.map(GlobalConcurrencyLimitLayer::new))
+                .option_layer(limits.timeout.map(TimeoutLayer::new))
+                .layer(DefaultBodyLimit::max(limits.max_body_bytes))
+        )
+        // Probes stay answerable when the server is at its limits.
+        .route("/metrics", get(metrics))
+        .route("/api/health", get(health))
+        .route("/readyz", get(readyz))
+        .layer(axum::middleware::from_fn(precondition::require_version))
+        .layer(axum::middleware::from_fn_with_state(state.clone(), workspaces::require_key))
+        .layer(axum::middleware::from_fn(error::json_error_bodies))
+        .layer(axum::middleware::from_fn_with_state(state.clone(), degraded::banner))
+        .layer(axum::middleware::from_fn_with_state(state.clone(), public_demo::rate_limit))
+        .layer(axum::middleware::from_fn_with_state(state.clone(), rate_limit::limit))
+        .layer(axum::middleware::from_fn_with_state(state.clone(), shutdown::refuse_generations))
         .layer(
             CorsLayer::new()
                 .allow_origin(Any)
                 .allow_methods(Any)
                 .allow_headers(Any)
+                .expose_headers([HeaderName::from_static(degraded::DEGRADED_HEADER), axum::http::header::ETAG, axum::http::header::RETRY_AFTER])
         )
-        .with_state(state);
+        .with_state(state.clone());
 
     let port: u16 = std::env::var("PORT").ok().and_then(|v| v.parse().ok()).unwrap_or(8080);
     let addr = SocketAddr::from(([0,0,0,0], port));
     tracing::info!(%addr, "Starting server");
-    axum::serve(tokio::net::TcpListener::bind(addr).await.unwrap(), app).await.unwrap();
+    let server = axum::serve(tokio::net::TcpListener::bind(addr).await.unwrap(), app.into_make_service_with_connect_info::<SocketAddr>())
+        .with_graceful_shutdown(state.shutdown.clone().signalled());
+    state.shutdown.drain(server, state.repo.clone()).await;
 }
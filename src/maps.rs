@@ -0,0 +1,140 @@
+use image::{Rgba, RgbaImage};
+use parking_lot::Mutex;
+use reqwest::Client;
+use std::collections::HashMap;
+use tracing::{info, warn};
+
+use crate::models::StageLocation;
+
+const TILE_SIZE: u32 = 256;
+const MAX_ZOOM: u32 = 12;
+/// Keep the most recent tiles around; OSM's tile usage policy asks clients to cache.
+const MAX_CACHED_TILES: usize = 512;
+const MARKER: Rgba<u8> = Rgba([220, 38, 38, 255]);
+const MARKER_OUTLINE: Rgba<u8> = Rgba([255, 255, 255, 255]);
+const BLANK: Rgba<u8> = Rgba([229, 231, 235, 255]);
+
+pub const ATTRIBUTION: &str = "Map data (c) OpenStreetMap contributors";
+
+/// Renders small static maps by stitching slippy-map tiles (`MAP_TILE_URL`, OpenStreetMap by default).
+pub struct MapRenderer {
+    client: Client,
+    /// `{z}/{x}/{y}` template; `None` disables maps (`MAP_TILE_URL=off`).
+    tile_url: Option<String>,
+    cache: Mutex<HashMap<(u32, u32, u32), RgbaImage>>,
+}
+
+impl MapRenderer {
+    pub fn from_env() -> Self {
+        let tile_url = match std::env::var("MAP_TILE_URL") {
+            Ok(v) if v == "off" || v.is_empty() => None,
+            Ok(v) => Some(v),
+            Err(_) => Some("https://tile.openstreetmap.org/{z}/{x}/{y}.png".to_string()),
+        };
+        Self { client: crate::outbound::client(), tile_url, cache: Mutex::default() }
+    }
+
+    /// Render `width` x `height` pixels framing every location, with a marker per location.
+    pub async fn render(&self, locations: &[StageLocation], width: u32, height: u32) -> Option<RgbaImage> {
+        let tile_url = self.tile_url.as_ref()?;
+        if locations.is_empty() { return None; }
+
+        let zoom = fit_zoom(locations, width, height);
+        let points: Vec<(f64, f64)> = locations.iter().map(|l| project(l.lat, l.lon, zoom)).collect();
+        let (min_x, max_x) = min_max(points.iter().map(|p| p.0));
+        let (min_y, max_y) = min_max(points.iter().map(|p| p.1));
+        let origin_x = ((min_x + max_x) / 2.0 - width as f64 / 2.0).floor();
+        let origin_y = ((min_y + max_y) / 2.0 - height as f64 / 2.0).floor();
+
+        let tiles_per_axis = 1u32 << zoom;
+        let mut canvas = RgbaImage::from_pixel(width, height, BLANK);
+        let first_tx = (origin_x / TILE_SIZE as f64).floor() as i64;
+        let first_ty = (origin_y / TILE_SIZE as f64).floor() as i64;
+        let last_tx = ((origin_x + width as f64 - 1.0) / TILE_SIZE as f64).floor() as i64;
+        let last_ty = ((origin_y + height as f64 - 1.0) / TILE_SIZE as f64).floor() as i64;
+
+        for ty in first_ty..=last_ty {
+            if ty < 0 || ty >= tiles_per_axis as i64 { continue; }
+            for tx in first_tx..=last_tx {
+                let wrapped_x = tx.rem_euclid(tiles_per_axis as i64) as u32;
+                let Some(tile) = self.tile(tile_url, zoom, wrapped_x, ty as u32).await else { continue };
+                let dx = tx * TILE_SIZE as i64 - origin_x as i64;
+                let dy = ty * TILE_SIZE as i64 - origin_y as i64;
+                blit(&mut canvas, &tile, dx, dy);
+            }
+        }
+
+        for (x, y) in points {
+            draw_marker(&mut canvas, (x - origin_x) as i64, (y - origin_y) as i64);
+        }
+        Some(canvas)
+    }
+
+    async fn tile(&self, tile_url: &str, z: u32, x: u32, y: u32) -> Option<RgbaImage> {
+        if let Some(tile) = self.cache.lock().get(&(z, x, y)) {
+            return Some(tile.clone());
+        }
+        let url = tile_url.replace("{z}", &z.to_string()).replace("{x}", &x.to_string()).replace("{y}", &y.to_string());
+        let bytes = match self.client.get(&url).send().await.and_then(|r| r.error_for_status()) {
+            Ok(resp) => resp.bytes().await.ok()?,
+            Err(e) => {
+                warn!("⚠️ Map tile {} failed: {}", url, e);
+                return None;
+            }
+        };
+        let tile = image::load_from_memory(&bytes).ok()?.to_rgba8();
+        info!("🗺️ Fetched map tile {}/{}/{}", z, x, y);
+        let mut cache = self.cache.lock();
+        if cache.len() >= MAX_CACHED_TILES {
+            cache.clear();
+        }
+        cache.insert((z, x, y), tile.clone());
+        Some(tile)
+    }
+}
+
+/// Web-Mercator world pixel coordinates at `zoom`.
+fn project(lat: f64, lon: f64, zoom: u32) -> (f64, f64) {
+    let scale = TILE_SIZE as f64 * (1u64 << zoom) as f64;
+    let lat = lat.clamp(-85.0511, 85.0511).to_radians();
+    let x = (lon + 180.0) / 360.0 * scale;
+    let y = (1.0 - (lat.tan() + 1.0 / lat.cos()).ln() / std::f64::consts::PI) / 2.0 * scale;
+    (x, y)
+}
+
+/// Highest zoom (capped at `MAX_ZOOM`) at which every location fits with a margin.
+fn fit_zoom(locations: &[StageLocation], width: u32, height: u32) -> u32 {
+    let margin = 24.0;
+    (1..=MAX_ZOOM).rev().find(|&z| {
+        let points: Vec<(f64, f64)> = locations.iter().map(|l| project(l.lat, l.lon, z)).collect();
+        let (min_x, max_x) = min_max(points.iter().map(|p| p.0));
+        let (min_y, max_y) = min_max(points.iter().map(|p| p.1));
+        max_x - min_x <= width as f64 - 2.0 * margin && max_y - min_y <= height as f64 - 2.0 * margin
+    }).unwrap_or(1)
+}
+
+fn min_max(values: impl Iterator<Item = f64>) -> (f64, f64) {
+    values.fold((f64::MAX, f64::MIN), |(lo, hi), v| (lo.min(v), hi.max(v)))
+}
+
+fn blit(canvas: &mut RgbaImage, tile: &RgbaImage, dx: i64, dy: i64) {
+    for (x, y, px) in tile.enumerate_pixels() {
+        let (cx, cy) = (dx + x as i64, dy + y as i64);
+        if cx >= 0 && cy >= 0 && (cx as u32) < canvas.width() && (cy as u32) < canvas.height() {
+            canvas.put_pixel(cx as u32, cy as u32, *px);
+        }
+    }
+}
+
+fn draw_marker(canvas: &mut RgbaImage, cx: i64, cy: i64) {
+    for dy in -7i64..=7 {
+        for dx in -7i64..=7 {
+            let d2 = dx * dx + dy * dy;
+            let color = if d2 <= 25 { MARKER } else if d2 <= 49 { MARKER_OUTLINE } else { continue };
+            let (x, y) = (cx + dx, cy + dy);
+            if x >= 0 && y >= 0 && (x as u32) < canvas.width() && (y as u32) < canvas.height() {
+                canvas.put_pixel(x as u32, y as u32, color);
+            }
+        }
+    }
+}
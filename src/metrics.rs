@@ -0,0 +1,177 @@
+use chrono::{DateTime, Utc};
+use parking_lot::RwLock;
+use reqwest::header::HeaderMap;
+use std::{collections::BTreeMap, fmt::Write, time::Duration};
+
+/// Which rate-limit header family a reading came from (`x-ratelimit-<field>-<resource>`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum QuotaField {
+    Limit,
+    Remaining,
+    Reset,
+}
+
+/// When a provider/model last answered 429, and the `Retry-After` hint in seconds if one was sent.
+struct Throttled {
+    at: DateTime<Utc>,
+    retry_after: Option<f64>,
+}
+
+/// Latest provider rate-limit readings, keyed by provider, model and resource (`requests`, `tokens`, ...).
+#[derive(Default)]
+pub struct QuotaTracker {
+    readings: RwLock<BTreeMap<(String, String, String, QuotaField), f64>>,
+    throttled: RwLock<BTreeMap<(String, String), Throttled>>,
+}
+
+/// `"30"`, `"1.5s"`, `"250ms"`, `"6m0s"`, `"1h2m3s"` → seconds.
+pub(crate) fn parse_seconds(raw: &str) -> Option<f64> {
+    let raw = raw.trim();
+    if let Ok(v) = raw.parse::<f64>() {
+        return Some(v);
+    }
+    let (mut total, mut number) = (0.0, String::new());
+    let mut chars = raw.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c.is_ascii_digit() || c == '.' {
+            number.push(c);
+            continue;
+        }
+        let value: f64 = number.parse().ok()?;
+        number.clear();
+        total += match c {
+            'h' => value * 3600.0,
+            'm' if chars.peek() == Some(&'s') => { chars.next(); value / 1000.0 }
+            'm' => value * 60.0,
+            's' => value,
+            _ => return None,
+        };
+    }
+    number.is_empty().then_some(total)
+}
+
+impl QuotaTracker {
+    /// Record whatever rate-limit headers the provider sent with a response.
+    pub fn observe(&self, provider: &str, model: &str, status: u16, headers: &HeaderMap) {
+        let mut found = Vec::new();
+        for (name, value) in headers {
+            let Some(rest) = name.as_str().strip_prefix("x-ratelimit-") else { continue };
+            let Ok(value) = value.to_str() else { continue };
+            let (field, resource) = if let Some(r) = rest.strip_prefix("limit-") {
+                (QuotaField::Limit, r)
+            } else if let Some(r) = rest.strip_prefix("remaining-") {
+                (QuotaField::Remaining, r)
+            } else if let Some(r) = rest.strip_prefix("reset-") {
+                (QuotaField::Reset, r)
+            } else {
+                continue;
+            };
+            let parsed = match field {
+                QuotaField::Reset => parse_seconds(value),
+                _ => value.trim().parse().ok(),
+            };
+            if let Some(v) = parsed {
+                found.push(((provider.to_string(), model.to_string(), resource.to_string(), field), v));
+            }
+        }
+        if !found.is_empty() {
+            self.readings.write().extend(found);
+        }
+        if status == 429 {
+            let retry_after = headers.get(reqwest::header::RETRY_AFTER).and_then(|v| v.to_str().ok()).and_then(parse_seconds);
+            self.throttled.write().insert((provider.to_string(), model.to_string()), Throttled { at: Utc::now(), retry_after });
+        }
+    }
+
+    /// Prometheus text exposition of the quota gauges.
+    pub fn render(&self, out: &mut String) {
+        let readings = self.readings.read();
+        for (field, name, help) in [
+            (QuotaField::Remaining, "provider_quota_remaining", "Remaining provider quota from rate-limit response headers."),
+            (QuotaField::Limit, "provider_quota_limit", "Provider quota limit from rate-limit response headers."),
+            (QuotaField::Reset, "provider_quota_reset_seconds", "Seconds until the provider quota window resets, as last reported."),
+        ] {
+            let _ = writeln!(out, "# HELP {} {}\n# TYPE {} gauge", name, help, name);
+            for ((provider, model, resource, _), value) in readings.iter().filter(|(k, _)| k.3 == field) {
+                let _ = writeln!(out, "{}{{provider=\"{}\",model=\"{}\",resource=\"{}\"}} {}", name, provider, model, resource, value);
+            }
+        }
+        let throttled = self.throttled.read();
+        let _ = writeln!(out, "# HELP provider_last_throttled_timestamp_seconds Unix time of the last 429 from the provider.\n# TYPE provider_last_throttled_timestamp_seconds gauge");
+        for ((provider, model), t) in throttled.iter() {
+            let _ = writeln!(out, "provider_last_throttled_timestamp_seconds{{provider=\"{}\",model=\"{}\"}} {}", provider, model, t.at.timestamp());
+        }
+        let _ = writeln!(out, "# HELP provider_retry_after_seconds Retry-After hint sent with the last 429.\n# TYPE provider_retry_after_seconds gauge");
+        for ((provider, model), t) in throttled.iter() {
+            if let Some(seconds) = t.retry_after {
+                let _ = writeln!(out, "provider_retry_after_seconds{{provider=\"{}\",model=\"{}\"}} {}", provider, model, seconds);
+            }
+        }
+    }
+}
+
+/// Upper bounds (seconds) of the latency histogram buckets: sub-millisecond lock waits up to provider calls.
+const LATENCY_BUCKETS: [f64; 12] = [0.0001, 0.0005, 0.001, 0.005, 0.01, 0.05, 0.1, 0.25, 0.5, 1.0, 5.0, 30.0];
+
+#[derive(Default)]
+struct Series {
+    buckets: [u64; LATENCY_BUCKETS.len()],
+    sum: f64,
+    count: u64,
+}
+
+/// Prometheus histogram of durations, one series per label set.
+pub struct LatencyHistogram {
+    name: &'static str,
+    help: &'static str,
+    labels: &'static [&'static str],
+    series: RwLock<BTreeMap<Vec<String>, Series>>,
+}
+
+impl LatencyHistogram {
+    pub const fn new(name: &'static str, help: &'static str, labels: &'static [&'static str]) -> Self {
+        Self { name, help, labels, series: RwLock::new(BTreeMap::new()) }
+    }
+
+    /// `values` in the order of the histogram's labels.
+    pub fn observe(&self, values: &[&str], duration: Duration) {
+        let seconds = duration.as_secs_f64();
+        let mut series = self.series.write();
+        let series = series.entry(values.iter().map(|v| v.to_string()).collect()).or_default();
+        for (bucket, bound) in series.buckets.iter_mut().zip(LATENCY_BUCKETS) {
+            if seconds <= bound {
+                *bucket += 1;
+            }
+        }
+        series.sum += seconds;
+        series.count += 1;
+    }
+
+    pub fn render(&self, out: &mut String) {
+        let _ = writeln!(out, "# HELP {} {}\n# TYPE {} histogram", self.name, self.help, self.name);
+        for (values, series) in self.series.read().iter() {
+            let labels: Vec<String> = self.labels.iter().zip(values).map(|(label, value)| format!("{}=\"{}\"", label, value)).collect();
+            let labels = labels.join(",");
+            for (count, bound) in series.buckets.iter().zip(LATENCY_BUCKETS) {
+                let _ = writeln!(out, "{}_bucket{{{},le=\"{}\"}} {}", self.name, labels, bound, count);
+            }
+            let _ = writeln!(out, "{}_bucket{{{},le=\"+Inf\"}} {}", self.name, labels, series.count);
+            let _ = writeln!(out, "{}_sum{{{}}} {}\n{}_count{{{}}} {}", self.name, labels, series.sum, self.name, labels, series.count);
+        }
+    }
+}
+
+/// Time spent waiting for the lifecycle store's lock (in-memory `RwLock`, SQLite write lock, Postgres row lock),
+/// by backend and store operation.
+pub static STORE_LOCK_WAIT: LatencyHistogram = LatencyHistogram::new(
+    "store_lock_wait_seconds",
+    "Time spent waiting to acquire the lifecycle store lock.",
+    &["backend", "operation"],
+);
+
+/// Duration of single provider HTTP attempts, for telling provider latency apart from store contention.
+pub static PROVIDER_REQUEST_DURATION: LatencyHistogram = LatencyHistogram::new(
+    "provider_request_duration_seconds",
+    "Duration of one provider HTTP request, including reading the response.",
+    &["provider", "model"],
+);
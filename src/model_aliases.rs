@@ -0,0 +1,35 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Logical model name for stage images.
+pub const IMAGE_DEFAULT: &str = "image-default";
+/// Logical model name for descriptions, rewrites and the vision/analysis passes.
+pub const TEXT_DEFAULT: &str = "text-default";
+
+/// Provider model ids the logical names map to unless the config says otherwise.
+const BUILT_IN: [(&str, &str); 2] = [
+    (IMAGE_DEFAULT, "gemini-2.5-flash-image-preview"),
+    (TEXT_DEFAULT, "gemini-1.5-flash"),
+];
+
+/// `models` config section: logical model name → provider model id, e.g.
+/// `{ "image-default": "gemini-2.5-flash-image" }`, so a model rename or preview sunset is a config change.
+/// Entries override the built-in mapping; names without an entry are used as model ids unchanged.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ModelAliases(BTreeMap<String, String>);
+
+impl ModelAliases {
+    pub fn resolve<'a>(&'a self, name: &'a str) -> &'a str {
+        self.0.get(name).map(String::as_str)
+            .or_else(|| BUILT_IN.iter().find(|(alias, _)| *alias == name).map(|(_, model)| *model))
+            .unwrap_or(name)
+    }
+
+    /// Every logical name with the model id it currently resolves to.
+    pub fn table(&self) -> BTreeMap<String, String> {
+        let mut table: BTreeMap<String, String> = BUILT_IN.iter().map(|(alias, model)| (alias.to_string(), model.to_string())).collect();
+        table.extend(self.0.clone());
+        table
+    }
+}
@@ -1,7 +1,12 @@
+use base64::Engine;
 use serde::{Serialize, Deserialize};
 use chrono::{DateTime, Utc};
+use std::collections::{hash_map::DefaultHasher, BTreeMap};
+use std::hash::{Hash, Hasher};
 use uuid::Uuid;
 
+use crate::{billing::csv_field, circuit::BreakerSnapshot, degraded::DegradedSnapshot, export::{Orientation, PageSize}, theme::ExportTheme, failures::FailureRecord, usage::TokenUsage};
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct GenerateRequest {
     pub product_description: String,
@@ -9,6 +14,49 @@ pub struct GenerateRequest {
     pub constraints: Option<Vec<String>>, // e.g., low-carbon, recyclable
     #[serde(default)]
     pub stages: Option<Vec<String>>, // allow custom stage naming
+    #[serde(default)]
+    pub tenant: Option<String>, // selects the retention and constraint policies
+    #[serde(default)]
+    pub tags: Vec<String>, // free-form labels, e.g. "load-test"; usable by the bulk purge
+    #[serde(default)]
+    pub resolution: Resolution, // default tier for every stage of this lifecycle
+}
+
+/// Output resolution tier. Drafts can be iterated on at `preview` and only the final storyboard paid for at `print`.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Resolution {
+    Preview,
+    #[default]
+    Standard,
+    Print,
+}
+
+impl Resolution {
+    /// Size requested from the provider; `None` leaves it at the provider default.
+    pub fn provider_size(self) -> Option<&'static str> {
+        match self {
+            Resolution::Print => Some("2K"),
+            Resolution::Preview | Resolution::Standard => None,
+        }
+    }
+
+    /// Longest side generated images are downscaled to before they are stored.
+    pub fn max_px(self) -> Option<u32> {
+        match self {
+            Resolution::Preview => Some(512),
+            Resolution::Standard => Some(1024),
+            Resolution::Print => None,
+        }
+    }
+
+    /// `max_px` further limited by a deployment-wide cap (public demo mode).
+    pub fn max_px_within(self, cap: Option<u32>) -> Option<u32> {
+        match (self.max_px(), cap) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -17,7 +65,794 @@ pub struct StageImage {
     pub prompt: String,
     pub description: String,
     pub image_base64: Option<String>,
+    /// Format of `image_base64` (`image/svg+xml`, `image/png`, `image/jpeg`), `None` without an image. Kept in
+    /// step by `set_image`.
+    #[serde(default)]
+    pub mime_type: Option<String>,
+    /// `image_base64` is the SVG fallback (demo mode or failed generation) rather than a generated image.
+    #[serde(default)]
+    pub is_placeholder: bool,
+    /// SHA-256 of the image bytes. With `IMAGE_DIR` or `S3_BUCKET` set the image is stored under this name,
+    /// responses leave `image_base64` out, and clients fetch `image_url` instead.
+    #[serde(default)]
+    pub image_ref: Option<String>,
+    /// Responses only, with external image storage: `/api/images/{image_ref}`, or a presigned bucket URL valid
+    /// for `S3_PRESIGN_SECS`.
+    #[serde(skip_deserializing, skip_serializing_if = "Option::is_none")]
+    pub image_url: Option<String>,
+    /// Accessible description of the image, written by an editor; cleared when the image is replaced.
+    #[serde(default)]
+    pub alt_text: Option<String>,
+    /// Provider calls made for this stage (generation, checks, rewrites), for billing exports.
+    #[serde(default)]
+    pub usage: Vec<TokenUsage>,
     pub last_updated: DateTime<Utc>,
+    /// Tier the current image was generated at.
+    #[serde(default)]
+    pub resolution: Resolution,
+    #[serde(default)]
+    pub quality_checks: Vec<QualityCheck>,
+    /// Reviewer-facing issues detected by post-generation checks.
+    #[serde(default)]
+    pub warnings: Vec<String>,
+    #[serde(default)]
+    pub actors: Vec<SupplyChainActor>,
+    #[serde(default)]
+    pub locations: Vec<StageLocation>,
+    #[serde(default)]
+    pub economics: Option<StageEconomics>,
+    /// Latest image-vs-description comparison, if one has been run.
+    #[serde(default)]
+    pub consistency: Option<ConsistencyCheck>,
+    /// Alternative descriptions when `DESCRIPTION_CANDIDATES` > 1 (the originally chosen one first).
+    #[serde(default)]
+    pub description_candidates: Vec<String>,
+    /// Previous descriptions, oldest first; appended whenever the description is replaced after generation.
+    #[serde(default)]
+    pub description_history: Vec<DescriptionRevision>,
+    /// Technical terms in the description with short definitions, for hover tooltips.
+    #[serde(default)]
+    pub terms: Vec<StageTerm>,
+    #[serde(default)]
+    pub status: StageStatus,
+    /// Normalized keywords for search and analytics.
+    #[serde(default)]
+    pub keywords: StageKeywords,
+    /// Uploaded real-world documentation; the files themselves live in the blob store.
+    #[serde(default)]
+    pub assets: Vec<StageAsset>,
+    /// Prompt template versions that produced the current image and description.
+    #[serde(default)]
+    pub templates: TemplateVersions,
+    /// The description is canned fallback text because generation failed.
+    #[serde(default)]
+    pub description_fallback: bool,
+    /// The description rewritten for other readers, written from the description as it was at the time.
+    #[serde(default)]
+    pub narratives: BTreeMap<Audience, String>,
+    /// Lifecycle revisions at which the stage and its parts last changed, for delta exports.
+    #[serde(default)]
+    pub revisions: StageRevisions,
+    /// Alternatives from the latest multi-candidate regeneration, until one is selected.
+    #[serde(default)]
+    pub image_candidates: Vec<ImageCandidate>,
+    /// The image the latest regeneration replaced, for a before/after comparison.
+    #[serde(default)]
+    pub previous_image: Option<PreviousImage>,
+}
+
+/// A stage image as it was before `POST /stage` (or an image candidate selection) replaced it. Stored like stage
+/// images: inline, or by `image_ref` in external storage.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PreviousImage {
+    pub image_base64: Option<String>,
+    pub mime_type: Option<String>,
+    #[serde(default)]
+    pub image_ref: Option<String>,
+    #[serde(skip_deserializing, skip_serializing_if = "Option::is_none")]
+    pub image_url: Option<String>,
+    pub prompt: String,
+    pub resolution: Resolution,
+    pub replaced_at: DateTime<Utc>,
+}
+
+/// One image of a regeneration with `candidate_count` > 1, kept apart from the stage image until selected
+/// (`POST /stage/{index}/image/select`). Stored like stage images: inline, or by `image_ref` in external storage.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ImageCandidate {
+    pub image_base64: Option<String>,
+    pub mime_type: Option<String>,
+    #[serde(default)]
+    pub image_ref: Option<String>,
+    #[serde(skip_deserializing, skip_serializing_if = "Option::is_none")]
+    pub image_url: Option<String>,
+    /// Prompt the candidate was generated from; becomes the stage prompt when selected.
+    pub prompt: String,
+    pub resolution: Resolution,
+    #[serde(default)]
+    pub quality_checks: Vec<QualityCheck>,
+    #[serde(default)]
+    pub warnings: Vec<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl ImageCandidate {
+    pub fn new(image: String, prompt: String, resolution: Resolution, quality_checks: Vec<QualityCheck>, warnings: Vec<String>) -> Self {
+        let (mime_type, image_ref) = image_metadata(&image);
+        Self {
+            image_base64: Some(image),
+            mime_type: Some(mime_type),
+            image_ref,
+            image_url: None,
+            prompt,
+            resolution,
+            quality_checks,
+            warnings,
+            created_at: Utc::now(),
+        }
+    }
+}
+
+/// Lifecycle `revision`s stamped on a stage by each stored change that touches it (0: unchanged since creation).
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default)]
+pub struct StageRevisions {
+    /// Revision that inserted the stage.
+    pub added: u64,
+    /// Last revision that changed anything in the stage.
+    pub changed: u64,
+    pub image: u64,
+    pub description: u64,
+}
+
+/// Hashes of a stage before a change, to tell afterwards which stages (and which parts of them) it touched.
+pub struct StageFingerprint {
+    name: String,
+    content: u64,
+    image: u64,
+    description: u64,
+}
+
+impl StageFingerprint {
+    /// Images count by their `image_ref` (the SHA-256 of their bytes), so a write under the store lock doesn't
+    /// serialize and hash every image. Their data is moved out meanwhile and put back; only images stored before
+    /// refs existed are hashed whole.
+    pub fn of(stage: &mut StageImage) -> Self {
+        fn hash(value: impl Hash) -> u64 {
+            let mut hasher = DefaultHasher::new();
+            value.hash(&mut hasher);
+            hasher.finish()
+        }
+        let set_aside: Vec<Option<String>> = stage.images_mut()
+            .map(|(image, image_ref)| if image_ref.is_some() { image.take() } else { None })
+            .collect();
+        let fingerprint = Self {
+            name: stage.stage_name.clone(),
+            content: hash(serde_json::to_vec(&*stage).unwrap_or_default()),
+            image: hash((&stage.image_ref, &stage.image_base64)),
+            description: hash(&stage.description),
+        };
+        for ((image, _), set_aside) in stage.images_mut().zip(set_aside) {
+            if set_aside.is_some() {
+                *image = set_aside;
+            }
+        }
+        fingerprint
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// MIME type and content ref of a base64 image.
+fn image_metadata(image: &str) -> (String, Option<String>) {
+    let image_ref = base64::engine::general_purpose::STANDARD.decode(image).ok().map(|bytes| crate::image_files::image_ref(&bytes));
+    (crate::gemini::sniff_mime_type(image).to_string(), image_ref)
+}
+
+/// Reader a narrative variant of the stage descriptions is written for.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum Audience {
+    Engineering,
+    Consumer,
+    Investor,
+}
+
+impl Audience {
+    pub const ALL: [Audience; 3] = [Audience::Engineering, Audience::Consumer, Audience::Investor];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Audience::Engineering => "engineering",
+            Audience::Consumer => "consumer",
+            Audience::Investor => "investor",
+        }
+    }
+
+    /// Rewrite instruction for the text model.
+    pub fn instruction(self) -> &'static str {
+        match self {
+            Audience::Engineering => "Write for engineers: name the processes, materials and energy or emission drivers precisely, in a neutral technical tone.",
+            Audience::Consumer => "Write for consumers: plain everyday language, no jargon, focused on what the stage means for the product they use and how to use or dispose of it well.",
+            Audience::Investor => "Write for investors: emphasise cost drivers, supply-chain and regulatory risks and improvement opportunities, concise and businesslike.",
+        }
+    }
+}
+
+/// `None` for stages generated before templates were versioned (or not generated yet).
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct TemplateVersions {
+    pub image: Option<String>,
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct StageKeywords {
+    pub processes: Vec<String>,
+    pub materials: Vec<String>,
+    pub impacts: Vec<String>,
+}
+
+/// Where a stage is in generation, so pollers don't have to infer it from `image_base64`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum StageStatus {
+    /// Skeleton stage, generation not started.
+    #[default]
+    Pending,
+    Generating,
+    Complete,
+    /// No image could be produced; `error` is the provider error.
+    Failed { error: String },
+}
+
+impl StageImage {
+    /// The stage's images as `(image_base64, image_ref)`: its own, pending candidates and the replaced one.
+    pub fn images_mut(&mut self) -> impl Iterator<Item = (&mut Option<String>, &mut Option<String>)> {
+        let StageImage { image_base64, image_ref, image_candidates, previous_image, .. } = self;
+        std::iter::once((image_base64, image_ref))
+            .chain(image_candidates.iter_mut().map(|c| (&mut c.image_base64, &mut c.image_ref)))
+            .chain(previous_image.iter_mut().map(|p| (&mut p.image_base64, &mut p.image_ref)))
+    }
+
+    /// Store a consistency result, replacing warnings from any previous check.
+    pub fn apply_consistency(&mut self, check: ConsistencyCheck) {
+        self.warnings.retain(|w| !w.starts_with(crate::vision::MISMATCH_WARNING_PREFIX));
+        self.warnings.extend(check.mismatches.iter().map(|m| m.warning()));
+        self.consistency = Some(check);
+    }
+
+    /// Replace the description, keeping the old text in `description_history`.
+    pub fn revise_description(&mut self, text: String, source: RevisionSource, instruction: Option<String>) {
+        let previous = std::mem::replace(&mut self.description, text);
+        self.description_history.push(DescriptionRevision { text: previous, source, instruction, replaced_at: Utc::now() });
+        self.description_fallback = false;
+        self.last_updated = Utc::now();
+    }
+
+    /// Replace the image, updating `mime_type`, `is_placeholder` and `image_ref` to match.
+    pub fn set_image(&mut self, image: Option<String>) {
+        let (mime_type, image_ref) = image.as_deref().map(image_metadata).unzip();
+        self.mime_type = mime_type;
+        self.is_placeholder = self.mime_type.as_deref() == Some("image/svg+xml");
+        self.image_ref = image_ref.flatten();
+        self.image_base64 = image;
+        self.alt_text = None;
+    }
+
+    /// Keep the current image as `previous_image` ahead of a regeneration replacing it; stages without an image
+    /// keep the one before.
+    pub fn keep_previous_image(&mut self) {
+        if !self.has_image() {
+            return;
+        }
+        self.previous_image = Some(PreviousImage {
+            image_base64: self.image_base64.clone(),
+            mime_type: self.mime_type.clone(),
+            image_ref: self.image_ref.clone(),
+            image_url: None,
+            prompt: self.prompt.clone(),
+            resolution: self.resolution,
+            replaced_at: Utc::now(),
+        });
+    }
+
+    /// Commit an image candidate as the stage image, with the prompt and checks it was generated with.
+    pub fn select_image_candidate(&mut self, candidate: ImageCandidate) {
+        self.keep_previous_image();
+        self.is_placeholder = candidate.mime_type.as_deref() == Some("image/svg+xml");
+        self.mime_type = candidate.mime_type;
+        self.image_ref = candidate.image_ref;
+        self.image_base64 = candidate.image_base64;
+        self.alt_text = None;
+        self.prompt = candidate.prompt;
+        self.resolution = candidate.resolution;
+        self.quality_checks = candidate.quality_checks;
+        self.warnings = candidate.warnings;
+        self.status = StageStatus::Complete;
+        self.image_candidates.clear();
+        self.last_updated = Utc::now();
+    }
+
+    /// For imports: re-derive image metadata from the inline data, the only part of an image that travels between
+    /// deployments (`image_ref`s name files in the exporting deployment's storage). Images without inline data
+    /// are dropped; data that isn't base64 is an error.
+    pub fn reattach_imported_images(&mut self) -> Result<(), String> {
+        let is_base64 = |image: &Option<String>| image.as_deref().is_none_or(|b64| base64::engine::general_purpose::STANDARD.decode(b64).is_ok());
+        if !is_base64(&self.image_base64)
+            || !self.image_candidates.iter().all(|c| is_base64(&c.image_base64))
+            || !self.previous_image.as_ref().is_none_or(|p| is_base64(&p.image_base64)) {
+            return Err("image data must be base64".to_string());
+        }
+        let (image, alt_text) = (self.image_base64.take(), self.alt_text.take());
+        self.set_image(image);
+        self.alt_text = alt_text.filter(|_| self.has_image());
+        self.image_candidates.retain(|c| c.image_base64.is_some());
+        for candidate in &mut self.image_candidates {
+            let (mime_type, image_ref) = image_metadata(candidate.image_base64.as_deref().unwrap_or_default());
+            (candidate.mime_type, candidate.image_ref) = (Some(mime_type), image_ref);
+        }
+        self.previous_image = self.previous_image.take().filter(|p| p.image_base64.is_some()).map(|mut previous| {
+            let (mime_type, image_ref) = image_metadata(previous.image_base64.as_deref().unwrap_or_default());
+            (previous.mime_type, previous.image_ref) = (Some(mime_type), image_ref);
+            previous
+        });
+        Ok(())
+    }
+
+    /// Whether the stage has an image, inline or on disk.
+    pub fn has_image(&self) -> bool {
+        self.image_base64.is_some() || self.image_ref.is_some()
+    }
+
+    /// For responses: drops `image_base64` unless `include` (`mime_type` and `is_placeholder` stay).
+    pub fn with_image(mut self, include: bool) -> Self {
+        if !include {
+            self.image_base64 = None;
+            for candidate in &mut self.image_candidates {
+                candidate.image_base64 = None;
+            }
+            if let Some(previous) = &mut self.previous_image {
+                previous.image_base64 = None;
+            }
+        }
+        self
+    }
+
+    /// The stored image is one of our SVG placeholders rather than a real generation. Sniffs the data itself, so
+    /// it also holds for stages stored before the `is_placeholder` field existed; images kept on disk and not
+    /// loaded (store listings) go by the flag.
+    pub fn is_placeholder(&self) -> bool {
+        match self.image_base64.as_deref() {
+            Some(img) => crate::gemini::sniff_mime_type(img) == "image/svg+xml",
+            None => self.image_ref.is_some() && self.is_placeholder,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct StageTerm {
+    pub term: String,
+    pub definition: String,
+}
+
+/// How the description that replaced this revision was produced.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RevisionSource {
+    Manual,
+    AiRewrite,
+    CandidateSelection,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DescriptionRevision {
+    /// The text as it was before being replaced.
+    pub text: String,
+    pub source: RevisionSource,
+    /// Rewrite instruction, for `ai_rewrite`.
+    pub instruction: Option<String>,
+    pub replaced_at: DateTime<Utc>,
+}
+
+/// Manual edit and/or AI rewrite of a stage description. The rewrite applies to `description` when given,
+/// otherwise to the current text.
+#[derive(Debug, Deserialize)]
+pub struct DescriptionEditRequest {
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub rewrite_instruction: Option<String>,
+}
+
+/// Manual correction of a stage without regenerating it; fields left out stay as they are.
+#[derive(Debug, Deserialize)]
+pub struct StagePatchRequest {
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub stage_name: Option<String>,
+    /// Stored for the next regeneration; the current image is kept.
+    #[serde(default)]
+    pub prompt: Option<String>,
+    #[serde(default)]
+    pub alt_text: Option<String>,
+}
+
+/// `PATCH /api/lifecycle/{id}`: lifecycle metadata to replace; fields left out stay as they are.
+#[derive(Debug, Deserialize)]
+pub struct LifecyclePatchRequest {
+    #[serde(default)]
+    pub constraints: Option<Vec<String>>,
+    #[serde(default)]
+    pub tags: Option<Vec<String>>,
+    /// Queue background regeneration of the stages a constraint change invalidated.
+    #[serde(default)]
+    pub regenerate_affected: bool,
+}
+
+/// A generated stage whose prompt embeds different constraints than the lifecycle now has.
+#[derive(Debug, Serialize)]
+pub struct InvalidatedStage {
+    pub stage_index: usize,
+    pub stage_name: String,
+    /// Regeneration job (`GET /api/jobs/{job_id}`), when `regenerate_affected` was set.
+    pub job_id: Option<Uuid>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LifecyclePatchResult {
+    pub lifecycle: Lifecycle,
+    pub invalidated: Vec<InvalidatedStage>,
+}
+
+/// `POST /api/lifecycle`: the stored lifecycle and how each stage's generation went. Answered with 207 when any
+/// stage failed, so placeholders don't pass for generated content.
+#[derive(Debug, Serialize)]
+pub struct GenerateResult {
+    #[serde(flatten)]
+    pub lifecycle: Lifecycle,
+    pub stage_results: Vec<StageResult>,
+    /// One call regenerating every failed stage worth retrying; `None` when there is none.
+    pub retry: Option<RetryHint>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StageResult {
+    pub stage_index: usize,
+    pub stage_name: String,
+    pub succeeded: bool,
+    /// Provider failures behind the stage's placeholder image or fallback description.
+    pub failures: Vec<FailureRecord>,
+    /// No image could be produced at all (`status` is `failed`).
+    pub error: Option<String>,
+    /// The stage failed, and only in ways that trying again can fix (not a rejected request).
+    pub retryable: bool,
+    /// Seconds to wait before retrying (rate limits, an open circuit breaker), `0` for right away.
+    pub retry_after_secs: Option<i64>,
+}
+
+/// The request that retries the failed stages.
+#[derive(Debug, Serialize)]
+pub struct RetryHint {
+    pub method: &'static str,
+    pub path: String,
+    pub body: serde_json::Value,
+    pub retry_after_secs: i64,
+}
+
+/// `POST /api/lifecycle/{id}/clone`: what to change in the copy. Stages, images included, are copied as they are.
+#[derive(Debug, Default, Deserialize)]
+pub struct CloneRequest {
+    #[serde(default)]
+    pub product_description: Option<String>,
+    #[serde(default)]
+    pub constraints: Option<Vec<String>>,
+}
+
+/// `POST /api/lifecycle/{id}/share`: an optional note on who the link is for.
+#[derive(Debug, Default, Deserialize)]
+pub struct ShareRequest {
+    #[serde(default)]
+    pub label: Option<String>,
+}
+
+/// A public read-only link to a lifecycle. Only a digest of the token is stored; the token itself is returned once,
+/// when the link is created.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShareLink {
+    pub id: Uuid,
+    pub token_sha256: String,
+    #[serde(default)]
+    pub label: Option<String>,
+    pub created_at: DateTime<Utc>,
+    /// Key owner who created it, with workspace keys.
+    #[serde(default)]
+    pub created_by: Option<String>,
+    #[serde(default)]
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+/// Answer to `POST /api/lifecycle/{id}/share`.
+#[derive(Debug, Serialize)]
+pub struct SharedLink {
+    pub id: Uuid,
+    pub token: String,
+    pub url: String,
+    pub label: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// `POST /api/lifecycle/{id}/narratives`: audiences to (re)write every generated stage description for.
+#[derive(Debug, Deserialize)]
+pub struct NarrativeRequest {
+    #[serde(default = "all_audiences")]
+    pub audiences: Vec<Audience>,
+}
+
+fn all_audiences() -> Vec<Audience> { Audience::ALL.to_vec() }
+
+/// `POST /api/lifecycle/{id}/stages/generate`: stages to generate, every stage when left out.
+#[derive(Debug, Deserialize)]
+pub struct BatchGenerateRequest {
+    #[serde(default)]
+    pub stage_indexes: Option<Vec<usize>>,
+    /// Defaults to the lifecycle's tier.
+    #[serde(default)]
+    pub resolution: Option<Resolution>,
+}
+
+/// A new pending stage, inserted at `index` (default: after the last stage).
+#[derive(Debug, Deserialize)]
+pub struct NewStageRequest {
+    pub stage_name: String,
+    #[serde(default)]
+    pub index: Option<usize>,
+}
+
+/// New stage order as a permutation of the current indexes: `order[i]` is the current index of the stage to put
+/// at position `i`.
+#[derive(Debug, Deserialize)]
+pub struct StageOrderRequest {
+    pub order: Vec<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SelectCandidateRequest {
+    pub index: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ConsistencyCheck {
+    pub consistent: bool,
+    pub mismatches: Vec<Mismatch>,
+    pub checked_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Mismatch {
+    pub description_says: String,
+    pub image_shows: String,
+}
+
+impl Mismatch {
+    pub fn warning(&self) -> String {
+        format!("{} description says \"{}\" but image shows \"{}\"", crate::vision::MISMATCH_WARNING_PREFIX, self.description_says, self.image_shows)
+    }
+
+    /// Prompt fragment steering a regeneration towards the description.
+    pub fn correction(&self) -> String {
+        format!("Show {} (not {}).", self.description_says, self.image_shows)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum EstimateSource {
+    Model,
+    /// Demo mode or a stage the model skipped; not a real estimate.
+    Placeholder,
+}
+
+/// Rough per-stage cost composition next to its relative environmental impact.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct StageEconomics {
+    /// Share of lifecycle cost of goods, normalised so all stages' central values sum to 100.
+    pub cost_share_pct: Estimate,
+    pub components: Vec<CostComponent>,
+    /// Relative environmental impact, 0 (negligible) – 10 (dominant).
+    pub impact_score: Estimate,
+    pub source: EstimateSource,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CostComponent {
+    pub name: String,
+    pub share_pct: Estimate,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Confidence {
+    Low,
+    Medium,
+    High,
+}
+
+impl Confidence {
+    pub fn label(self) -> &'static str {
+        match self {
+            Confidence::Low => "low",
+            Confidence::Medium => "medium",
+            Confidence::High => "high",
+        }
+    }
+}
+
+/// An AI-derived number: central value plus a low/high range and how confident the model is in it.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+pub struct Estimate {
+    pub value: f32,
+    pub low: f32,
+    pub high: f32,
+    pub confidence: Confidence,
+}
+
+impl Estimate {
+    /// A placeholder number with no real range behind it.
+    pub fn unknown(value: f32) -> Self {
+        Self { value, low: value, high: value, confidence: Confidence::Low }
+    }
+
+    /// Normalise ordering (`low <= value <= high`) and clamp into `[min, max]`.
+    pub fn clamped(self, min: f32, max: f32) -> Self {
+        let value = self.value.clamp(min, max);
+        Self {
+            value,
+            low: self.low.min(self.high).clamp(min, max).min(value),
+            high: self.high.max(self.low).clamp(min, max).max(value),
+            confidence: self.confidence,
+        }
+    }
+
+    pub fn scaled(self, factor: f32) -> Self {
+        Self { value: self.value * factor, low: self.low * factor, high: self.high * factor, confidence: self.confidence }
+    }
+
+    /// e.g. `20 (15-25, medium confidence)`
+    pub fn describe(&self, precision: usize) -> String {
+        format!("{:.p$} ({:.p$}-{:.p$}, {} confidence)", self.value, self.low, self.high, self.confidence.label(), p = precision)
+    }
+}
+
+/// Where (part of) a stage physically happens; rendered as a map marker in exports.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct StageLocation {
+    pub label: String,
+    pub lat: f64,
+    pub lon: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ActorRole {
+    Supplier,
+    Factory,
+    LogisticsPartner,
+    Retailer,
+    Recycler,
+    Other,
+}
+
+impl ActorRole {
+    pub fn label(self) -> &'static str {
+        match self {
+            ActorRole::Supplier => "supplier",
+            ActorRole::Factory => "factory",
+            ActorRole::LogisticsPartner => "logistics partner",
+            ActorRole::Retailer => "retailer",
+            ActorRole::Recycler => "recycler",
+            ActorRole::Other => "partner",
+        }
+    }
+}
+
+/// A real-world organisation taking part in a stage (e.g. the recycler handling end-of-life).
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SupplyChainActor {
+    pub id: Uuid,
+    pub role: ActorRole,
+    pub name: String,
+    /// ISO country name or code, free text.
+    #[serde(default)]
+    pub country: Option<String>,
+}
+
+impl SupplyChainActor {
+    /// e.g. `recycler GreenLoop (Germany)`
+    pub fn describe(&self) -> String {
+        match &self.country {
+            Some(country) => format!("{} {} ({})", self.role.label(), self.name, country),
+            None => format!("{} {}", self.role.label(), self.name),
+        }
+    }
+}
+
+/// A non-AI file attached to a stage, e.g. a supplier's ISO 14001 certificate.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct StageAsset {
+    pub id: Uuid,
+    pub kind: AssetKind,
+    pub filename: String,
+    pub content_type: String,
+    #[serde(default)]
+    pub labels: Vec<String>,
+    pub size_bytes: usize,
+    pub uploaded_at: DateTime<Utc>,
+}
+
+impl StageAsset {
+    /// e.g. `[certification] iso14001.pdf, 120 KB (ISO 14001, 2024)`
+    pub fn describe(&self) -> String {
+        let size = format!("{} KB", self.size_bytes.div_ceil(1024));
+        if self.labels.is_empty() {
+            format!("[{}] {}, {}", self.kind.label(), self.filename, size)
+        } else {
+            format!("[{}] {}, {} ({})", self.kind.label(), self.filename, size, self.labels.join(", "))
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AssetKind {
+    Photo,
+    Certification,
+    Datasheet,
+    Other,
+}
+
+impl AssetKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            AssetKind::Photo => "photo",
+            AssetKind::Certification => "certification",
+            AssetKind::Datasheet => "datasheet",
+            AssetKind::Other => "document",
+        }
+    }
+}
+
+/// Query of an attachment upload; the request body is the file itself.
+#[derive(Debug, Deserialize)]
+pub struct AssetUploadQuery {
+    pub kind: AssetKind,
+    pub filename: String,
+    /// Comma-separated.
+    #[serde(default)]
+    pub labels: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NewActorRequest {
+    pub role: ActorRole,
+    pub name: String,
+    #[serde(default)]
+    pub country: Option<String>,
+}
+
+/// Vision-model rating of one generated image attempt (scores 0–10, higher is better).
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct QualityCheck {
+    pub attempt: u32,
+    pub relevance: f32,
+    pub artifacts: f32,
+    pub text_free: f32,
+    pub overall: f32,
+    pub accepted: bool,
+    pub checked_at: DateTime<Utc>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -28,6 +863,341 @@ pub struct Lifecycle {
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub constraints: Vec<String>,
+    #[serde(default)]
+    pub review_status: ReviewStatus,
+    /// Product categories detected by the stage rules when the default stage list was used.
+    #[serde(default)]
+    pub categories: Vec<String>,
+    #[serde(default)]
+    pub tenant: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Set on every successful export; retention rules can spare exported lifecycles.
+    #[serde(default)]
+    pub last_exported_at: Option<DateTime<Utc>>,
+    /// Incremented on every stored change; realtime patches are keyed by it.
+    #[serde(default)]
+    pub revision: u64,
+    /// Default tier for stage generations that don't ask for one.
+    #[serde(default)]
+    pub resolution: Resolution,
+    /// Shortened product description and constraints used in prompts when the originals exceeded the budget.
+    #[serde(default)]
+    pub prompt_inputs: Option<PromptInputs>,
+    /// Lifecycle-level issues, e.g. inputs shortened to fit the prompt budget.
+    #[serde(default)]
+    pub warnings: Vec<String>,
+    /// Lifecycle this one was cloned from; its stages started out as copies, images included.
+    #[serde(default)]
+    pub cloned_from: Option<Uuid>,
+    /// Legible name for `GET /share/{slug}`, fixed at creation. See `share_slug` for lifecycles stored before it.
+    #[serde(default)]
+    pub slug: Option<String>,
+    /// Workspace (team) of the API key that created it; with `WORKSPACE_KEYS` set, only that workspace sees it.
+    #[serde(default)]
+    pub workspace_id: Option<String>,
+    /// Owner of that key.
+    #[serde(default)]
+    pub owner: Option<String>,
+    /// Public read-only links (`GET /share/{token}`), revoked ones included.
+    #[serde(default)]
+    pub share_links: Vec<ShareLink>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PromptInputs {
+    pub product: String,
+    pub constraints: Vec<String>,
+}
+
+impl Lifecycle {
+    /// The stored slug, or the one the lifecycle would have been given at creation.
+    pub fn share_slug(&self) -> String {
+        self.slug.clone().unwrap_or_else(|| crate::share::share_slug(&self.product_description, self.id))
+    }
+
+    /// Product description as it goes into prompts.
+    pub fn prompt_product(&self) -> &str {
+        self.prompt_inputs.as_ref().map_or(&self.product_description, |p| &p.product)
+    }
+
+    pub fn prompt_constraints(&self) -> &[String] {
+        self.prompt_inputs.as_ref().map_or(&self.constraints, |p| &p.constraints)
+    }
+
+    /// Stamp the current `revision` on every stage that differs from all of `before` (the stages prior to the
+    /// change). A changed stage is compared with its previous version, found by name or, when renamed, by
+    /// position, to tell whether its image or description changed; one without a previous version was added.
+    pub fn stamp_stage_revisions(&mut self, before: &[StageFingerprint]) {
+        let revision = self.revision;
+        let names: Vec<String> = self.stages.iter().map(|s| s.stage_name.clone()).collect();
+        for (i, stage) in self.stages.iter_mut().enumerate() {
+            let now = StageFingerprint::of(stage);
+            if before.iter().any(|b| b.content == now.content) {
+                continue;
+            }
+            let previous = before.iter().find(|b| b.name == now.name)
+                .or_else(|| before.get(i).filter(|b| before.len() == names.len() && !names.contains(&b.name)));
+            let revisions = &mut stage.revisions;
+            revisions.changed = revision;
+            match previous {
+                Some(previous) => {
+                    if previous.image != now.image { revisions.image = revision; }
+                    if previous.description != now.description { revisions.description = revision; }
+                }
+                None => *revisions = StageRevisions { added: revision, changed: revision, image: revision, description: revision },
+            }
+        }
+    }
+
+    /// Every stored image as `(image_base64, image_ref)`: stage images and pending image candidates.
+    pub fn images(&self) -> impl Iterator<Item = (Option<&str>, Option<&str>)> {
+        self.stages.iter().flat_map(|stage| {
+            std::iter::once((stage.image_base64.as_deref(), stage.image_ref.as_deref()))
+                .chain(stage.image_candidates.iter().map(|c| (c.image_base64.as_deref(), c.image_ref.as_deref())))
+                .chain(stage.previous_image.iter().map(|p| (p.image_base64.as_deref(), p.image_ref.as_deref())))
+        })
+    }
+
+    pub fn images_mut(&mut self) -> impl Iterator<Item = (&mut Option<String>, &mut Option<String>)> {
+        self.stages.iter_mut().flat_map(StageImage::images_mut)
+    }
+
+    /// What a public share link shows: the storyboard without its workspace, tenant, share links or usage.
+    pub fn shared_view(mut self) -> Self {
+        (self.workspace_id, self.owner, self.tenant) = (None, None, None);
+        self.tags.clear();
+        self.share_links.clear();
+        for stage in &mut self.stages {
+            stage.usage.clear();
+        }
+        self
+    }
+
+    /// For exports: stage descriptions replaced by their `audience` variant where one was written.
+    pub fn with_narrative(mut self, audience: Option<Audience>) -> Self {
+        let Some(audience) = audience else { return self };
+        for stage in &mut self.stages {
+            if let Some(text) = stage.narratives.get(&audience) {
+                stage.description = text.clone();
+            }
+        }
+        self
+    }
+
+    /// One row per stage, in stage order, for spreadsheets and BI tools.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("stage_index,stage_name,prompt,description,last_updated,has_image,is_placeholder\n");
+        for (index, stage) in self.stages.iter().enumerate() {
+            let row = [
+                index.to_string(),
+                csv_field(&stage.stage_name),
+                csv_field(&stage.prompt),
+                csv_field(&stage.description),
+                stage.last_updated.to_rfc3339(),
+                stage.has_image().to_string(),
+                stage.is_placeholder().to_string(),
+            ];
+            csv.push_str(&row.join(","));
+            csv.push('\n');
+        }
+        csv
+    }
+
+    /// For responses: drops every stage's `image_base64` unless `include`.
+    pub fn with_images(mut self, include: bool) -> Self {
+        self.stages = self.stages.into_iter().map(|stage| stage.with_image(include)).collect();
+        self
+    }
+
+    /// Latest change to the lifecycle or any of its stages.
+    pub fn last_activity(&self) -> DateTime<Utc> {
+        self.stages.iter().map(|s| s.last_updated).fold(self.updated_at, |a, b| a.max(b))
+    }
+
+    pub fn has_completeness(&self, completeness: Completeness) -> bool {
+        match completeness {
+            Completeness::Complete => self.stages.iter().all(|s| s.status == StageStatus::Complete && s.has_image() && !s.is_placeholder()),
+            Completeness::HasPlaceholders => self.stages.iter().any(StageImage::is_placeholder),
+            Completeness::HasFailures => self.stages.iter().any(|s| matches!(s.status, StageStatus::Failed { .. })),
+        }
+    }
+}
+
+/// Filters of `DELETE /api/lifecycles`; a lifecycle must match every filter given.
+#[derive(Debug, Deserialize)]
+pub struct PurgeQuery {
+    pub tag: Option<String>,
+    pub created_before: Option<DateTime<Utc>>,
+    /// Count what would be deleted without deleting anything.
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PurgeResult {
+    pub affected: usize,
+    pub dry_run: bool,
+}
+
+/// `POST /api/admin/import`: what to do with lifecycles whose id is already stored.
+#[derive(Debug, Deserialize)]
+pub struct RestoreQuery {
+    /// Replace them with the backup's version; by default they are kept and the line is skipped.
+    #[serde(default)]
+    pub overwrite: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RestoreLineError {
+    /// 1-based line of the NDJSON body.
+    pub line: usize,
+    pub error: String,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct RestoreResult {
+    pub imported: usize,
+    pub replaced: usize,
+    pub skipped: usize,
+    pub failed: Vec<RestoreLineError>,
+}
+
+/// `GET /api/lifecycle/{id}/sync?revision=`: the revision the client already holds, if any.
+#[derive(Debug, Deserialize)]
+pub struct SyncQuery {
+    pub revision: Option<u64>,
+}
+
+/// `?profile=` on export endpoints: a preset from the `export.profiles` config section.
+#[derive(Debug, Deserialize)]
+pub struct ExportQuery {
+    pub profile: Option<String>,
+    /// Narrative variant to export instead of the stage descriptions; overrides the profile's.
+    pub narrative: Option<Audience>,
+    /// PDF and HTML only: named theme from `export.themes`; overrides the profile's.
+    pub theme: Option<String>,
+    /// PDF only: a delta report with just the stages changed after this revision.
+    pub since_revision: Option<u64>,
+    /// PDF only: paper size and orientation of the pages; override the profile's.
+    pub size: Option<PageSize>,
+    pub orientation: Option<Orientation>,
+    /// Markdown only: how stage images are referenced.
+    #[serde(default)]
+    pub images: MarkdownImages,
+    /// PDF only: render in the background and answer 202 with a download token (`GET /api/exports/{token}`).
+    #[serde(default, rename = "async")]
+    pub run_async: bool,
+}
+
+/// Body of `POST /api/lifecycle/{id}/pdf` and `/html`: a one-off theme with the fields of an `export.themes` entry,
+/// e.g. to brand a report for one client.
+#[derive(Debug, Deserialize)]
+pub struct ThemedExportRequest {
+    pub theme: ExportTheme,
+}
+
+/// `?images=` on the Markdown export.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum MarkdownImages {
+    /// Absolute links to `GET /api/lifecycle/{id}/stage/{index}/image` on this server.
+    #[default]
+    Link,
+    /// Inline `data:` URIs, for a self-contained document.
+    Embed,
+}
+
+/// Short-lived, credential-free link to a binary download (`/dl/...?expires=&sig=`).
+#[derive(Debug, Clone, Serialize)]
+pub struct SignedUrl {
+    pub url: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Signed links for everything downloadable in a lifecycle, all sharing one expiry.
+#[derive(Debug, Serialize)]
+pub struct DownloadLinks {
+    pub expires_at: DateTime<Utc>,
+    pub pdf: String,
+    pub stages: Vec<StageDownloadLinks>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StageDownloadLinks {
+    pub index: usize,
+    /// `None` while the stage has no image yet.
+    pub image: Option<String>,
+    pub assets: Vec<AssetDownloadLink>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AssetDownloadLink {
+    pub id: Uuid,
+    pub filename: String,
+    pub url: String,
+}
+
+/// Generation-completeness filter for the lifecycle listing.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Completeness {
+    /// Every stage generated with a real image.
+    Complete,
+    /// At least one stage shows an SVG placeholder (generation fell back).
+    HasPlaceholders,
+    /// At least one stage's generation failed outright.
+    HasFailures,
+}
+
+/// Row of the lifecycle listing; stages are summarised to counts.
+#[derive(Debug, Serialize, Clone)]
+pub struct LifecycleSummary {
+    pub id: Uuid,
+    pub slug: String,
+    pub product_description: String,
+    pub created_at: DateTime<Utc>,
+    pub last_activity: DateTime<Utc>,
+    pub stage_count: usize,
+    pub placeholder_stages: usize,
+    pub failed_stages: usize,
+    pub owner: Option<String>,
+}
+
+impl From<&Lifecycle> for LifecycleSummary {
+    fn from(l: &Lifecycle) -> Self {
+        Self {
+            id: l.id,
+            slug: l.share_slug(),
+            product_description: l.product_description.clone(),
+            created_at: l.created_at,
+            last_activity: l.last_activity(),
+            stage_count: l.stages.len(),
+            placeholder_stages: l.stages.iter().filter(|s| s.is_placeholder()).count(),
+            failed_stages: l.stages.iter().filter(|s| matches!(s.status, StageStatus::Failed { .. })).count(),
+            owner: l.owner.clone(),
+        }
+    }
+}
+
+/// Editorial state of a lifecycle. Anything short of `Approved` is exported with a DRAFT watermark.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ReviewStatus {
+    #[default]
+    Draft,
+    InReview,
+    Approved,
+    Published,
+}
+
+impl ReviewStatus {
+    pub fn is_final(self) -> bool { matches!(self, ReviewStatus::Approved | ReviewStatus::Published) }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ReviewStatusRequest {
+    pub status: ReviewStatus,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -36,4 +1206,43 @@ pub struct RegenerateRequest {
     pub edit_instruction: String,
     #[serde(default)]
     pub alternative_sustainability_focus: Option<String>,
+    /// Defaults to the tier of the stage's current image.
+    #[serde(default)]
+    pub resolution: Option<Resolution>,
+    /// More than 1 (at most 4) keeps the stage image and stores that many alternatives as `image_candidates`
+    /// instead, to pick from with `POST /stage/{index}/image/select`.
+    #[serde(default)]
+    pub candidate_count: Option<usize>,
+}
+
+/// `POST /api/lifecycle/{id}/stage/{stage_index}?resolution=`: overrides the lifecycle's default tier.
+#[derive(Debug, Deserialize)]
+pub struct StageGenerateQuery {
+    pub resolution: Option<Resolution>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ImageExplanation {
+    pub stage_index: usize,
+    pub stage_name: String,
+    pub explanation: String,
+    pub explained_at: DateTime<Utc>,
+}
+
+/// `GET /api/health`: always 200 while the server runs; `degraded` while the Gemini circuit is not closed or
+/// degraded mode is active (stages are getting placeholders).
+#[derive(Debug, Serialize)]
+pub struct HealthReport {
+    pub status: &'static str,
+    pub gemini: BreakerSnapshot,
+    pub degraded_mode: Option<DegradedSnapshot>,
+}
+
+/// `GET /readyz`: 200 whether degraded or not, since a degraded server still serves placeholders and stored
+/// lifecycles; `status` is `degraded` while the key is rejected or out of quota. 503 with `draining` once a
+/// shutdown began.
+#[derive(Debug, Serialize)]
+pub struct ReadinessReport {
+    pub status: &'static str,
+    pub degraded_mode: Option<DegradedSnapshot>,
 }
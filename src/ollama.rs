@@ -0,0 +1,78 @@
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::json;
+use std::time::Instant;
+use tracing::{error, info};
+
+use crate::{gemini::GeminiError, metrics::PROVIDER_REQUEST_DURATION};
+
+pub const PROVIDER: &str = "ollama";
+
+/// Text generation on a locally hosted Ollama server (`/api/generate`), so descriptions and rewrites work
+/// without a Gemini key. Images are unaffected (placeholders in demo mode).
+pub struct OllamaClient {
+    client: Client,
+    /// `OLLAMA_BASE_URL` (default `http://localhost:11434`).
+    base_url: String,
+    /// `OLLAMA_MODEL` (default `llama3.1`).
+    pub model: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct GenerateResponse {
+    #[serde(default)]
+    response: String,
+}
+
+impl OllamaClient {
+    /// `None` unless `TEXT_PROVIDER=ollama`.
+    pub fn from_env() -> Option<Self> {
+        if std::env::var("TEXT_PROVIDER").map(|v| v.to_lowercase()).as_deref() != Ok(PROVIDER) {
+            return None;
+        }
+        let base_url = std::env::var("OLLAMA_BASE_URL").unwrap_or_else(|_| "http://localhost:11434".to_string());
+        let model = std::env::var("OLLAMA_MODEL").unwrap_or_else(|_| "llama3.1".to_string());
+        info!("🦙 Generating text with Ollama ({} at {})", model, base_url);
+        Some(Self { client: crate::outbound::client(), base_url: base_url.trim_end_matches('/').to_string(), model })
+    }
+
+    async fn generate(&self, prompt: &str) -> Result<String, GeminiError> {
+        let payload = json!({
+            "model": self.model,
+            "prompt": prompt,
+            "stream": false,
+            "options": { "temperature": 0.7, "top_k": 40, "top_p": 0.95, "num_predict": 450 }
+        });
+        let started = Instant::now();
+        let response = self.client
+            .post(format!("{}/api/generate", self.base_url))
+            .json(&payload)
+            .send()
+            .await
+            .map_err(|e| GeminiError::Http(e.to_string()))?;
+        let status = response.status();
+        let body = response.text().await.map_err(|e| GeminiError::Http(e.to_string()))?;
+        PROVIDER_REQUEST_DURATION.observe(&[PROVIDER, &self.model], started.elapsed());
+        if !status.is_success() {
+            error!("❌ Ollama {} call failed with status {}: {}", self.model, status, body);
+            return Err(GeminiError::Api { status: status.as_u16(), body });
+        }
+        let parsed: GenerateResponse = serde_json::from_str(&body)
+            .map_err(|e| GeminiError::Other(format!("Failed to parse response: {}", e)))?;
+        let text = parsed.response.trim();
+        if text.is_empty() {
+            return Err(GeminiError::Other("No text content found in response".to_string()));
+        }
+        Ok(text.to_string())
+    }
+
+    /// `count` independent completions (Ollama has no candidate count), requested concurrently. Never empty on `Ok`.
+    pub async fn generate_candidates(&self, prompt: &str, count: u32) -> Result<Vec<String>, GeminiError> {
+        info!("Generating text with Ollama ({} candidate(s))...", count);
+        let results = futures::future::join_all((0..count.max(1)).map(|_| self.generate(prompt))).await;
+        // The first completion decides success; failed alternatives are just left out.
+        let mut results = results.into_iter();
+        let first = results.next().unwrap_or_else(|| Err(GeminiError::Other("No text content found in response".to_string())))?;
+        Ok(std::iter::once(first).chain(results.filter_map(Result::ok)).collect())
+    }
+}
@@ -0,0 +1,31 @@
+use reqwest::{header::{HeaderMap, HeaderValue}, Client};
+
+/// `name/version` of this service, the first product token of every outbound request.
+const PRODUCT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"));
+
+/// Product tokens identifying this service, plus `deployment/{DEPLOYMENT_ID}` when set (characters outside
+/// `A-Za-z0-9._-` become `-`), so provider usage dashboards can tell deployments apart.
+pub fn client_tokens() -> String {
+    match std::env::var("DEPLOYMENT_ID").ok().map(|id| id.trim().to_string()).filter(|id| !id.is_empty()) {
+        Some(id) => {
+            let id: String = id.chars().map(|c| if c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-') { c } else { '-' }).collect();
+            format!("{} deployment/{}", PRODUCT, id)
+        }
+        None => PRODUCT.to_string(),
+    }
+}
+
+/// HTTP client for outbound calls (providers, storage, map tiles), sending [`client_tokens`] as `User-Agent`.
+pub fn client() -> Client {
+    Client::builder().user_agent(client_tokens()).build().unwrap_or_default()
+}
+
+/// [`client`] for Google APIs, which additionally attribute traffic by the `x-goog-api-client` header.
+pub fn google_client() -> Client {
+    let tokens = client_tokens();
+    let mut headers = HeaderMap::new();
+    if let Ok(value) = HeaderValue::from_str(&tokens) {
+        headers.insert("x-goog-api-client", value);
+    }
+    Client::builder().user_agent(tokens).default_headers(headers).build().unwrap_or_default()
+}
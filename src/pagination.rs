@@ -0,0 +1,50 @@
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use uuid::Uuid;
+
+/// Position in a newest-first listing ordered by a timestamp (`created_at`, or last activity when sorting by
+/// it) descending, then `id` ascending. Unlike an offset it stays valid when items are inserted or deleted
+/// between requests.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cursor {
+    pub at: DateTime<Utc>,
+    pub id: Uuid,
+}
+
+impl Cursor {
+    /// Opaque token handed to clients as `next_cursor`.
+    pub fn encode(&self) -> String {
+        let raw = format!("{}|{}", self.at.timestamp_nanos_opt().unwrap_or_default(), self.id);
+        base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(raw)
+    }
+
+    pub fn decode(token: &str) -> Option<Self> {
+        let raw = base64::engine::general_purpose::URL_SAFE_NO_PAD.decode(token).ok()?;
+        let (nanos, id) = std::str::from_utf8(&raw).ok()?.split_once('|')?;
+        Some(Self { at: DateTime::from_timestamp_nanos(nanos.parse().ok()?), id: id.parse().ok()? })
+    }
+
+    /// Whether `other` comes after this cursor in listing order.
+    fn precedes(&self, other: &Cursor) -> bool {
+        other.at < self.at || (other.at == self.at && other.id > self.id)
+    }
+}
+
+/// One page of a cursor-paginated listing; `next_cursor` is `None` on the last page.
+#[derive(Debug, Serialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub next_cursor: Option<String>,
+}
+
+/// Take the `limit` items following `after` from `items`, which must already be in listing order.
+pub fn paginate<T>(items: impl IntoIterator<Item = T>, cursor_of: impl Fn(&T) -> Cursor, after: Option<Cursor>, limit: usize) -> Page<T> {
+    let mut remaining = items.into_iter().filter(|item| after.is_none_or(|c| c.precedes(&cursor_of(item))));
+    let items: Vec<T> = remaining.by_ref().take(limit).collect();
+    let next_cursor = match (items.last(), remaining.next()) {
+        (Some(last), Some(_)) => Some(cursor_of(last).encode()),
+        _ => None,
+    };
+    Page { items, next_cursor }
+}
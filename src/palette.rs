@@ -0,0 +1,132 @@
+use serde::{Deserialize, Serialize, Serializer};
+use std::fmt;
+
+/// WCAG 2.1 minimum for normal-size text (AA).
+const AA_TEXT_CONTRAST: f64 = 4.5;
+
+/// An sRGB color, written `#RRGGBB` (or `#RGB`) in the config file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct HexColor(pub [u8; 3]);
+
+impl TryFrom<String> for HexColor {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let hex = value.trim().trim_start_matches('#');
+        let hex = match hex.len() {
+            3 => hex.chars().flat_map(|c| [c, c]).collect(),
+            6 => hex.to_string(),
+            _ => return Err(format!("invalid color {:?}, expected #RRGGBB", value)),
+        };
+        let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| format!("invalid color {:?}, expected #RRGGBB", value));
+        Ok(Self([channel(0)?, channel(2)?, channel(4)?]))
+    }
+}
+
+impl Serialize for HexColor {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl fmt::Display for HexColor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [r, g, b] = self.0;
+        write!(f, "#{:02X}{:02X}{:02X}", r, g, b)
+    }
+}
+
+impl HexColor {
+    pub const BLACK: HexColor = HexColor([0, 0, 0]);
+    pub const WHITE: HexColor = HexColor([255, 255, 255]);
+
+    fn hex(s: &str) -> Self {
+        Self::try_from(s.to_string()).expect("built-in palette colors are valid")
+    }
+
+    /// WCAG relative luminance, 0 (black) to 1 (white).
+    pub fn luminance(self) -> f64 {
+        let linear = |c: u8| {
+            let c = c as f64 / 255.0;
+            if c <= 0.03928 { c / 12.92 } else { ((c + 0.055) / 1.055).powf(2.4) }
+        };
+        let [r, g, b] = self.0;
+        0.2126 * linear(r) + 0.7152 * linear(g) + 0.0722 * linear(b)
+    }
+
+    /// WCAG contrast ratio between two colors, 1 to 21.
+    pub fn contrast(self, other: HexColor) -> f64 {
+        let (a, b) = (self.luminance(), other.luminance());
+        (a.max(b) + 0.05) / (a.min(b) + 0.05)
+    }
+
+    /// `t` of the way from `self` to `other`.
+    pub fn mix(self, other: HexColor, t: f64) -> Self {
+        let channel = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * t).round().clamp(0.0, 255.0) as u8;
+        Self([channel(self.0[0], other.0[0]), channel(self.0[1], other.0[1]), channel(self.0[2], other.0[2])])
+    }
+
+    /// This color as a background (or mark) for `against`, darkened or lightened away from it just enough to
+    /// reach `min` contrast. Hue is kept as far as possible; at worst it ends at black or white.
+    pub fn with_contrast(self, against: HexColor, min: f64) -> Self {
+        let target = if against.luminance() > 0.18 { Self::BLACK } else { Self::WHITE };
+        (0..=20).map(|step| self.mix(target, step as f64 / 20.0)).find(|c| c.contrast(against) >= min).unwrap_or(target)
+    }
+
+    /// For PDF drawing: each channel 0-1.
+    pub fn unit_rgb(self) -> (f32, f32, f32) {
+        let [r, g, b] = self.0;
+        (r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0)
+    }
+}
+
+/// `palette` config section: colors of generated graphics (SVG stage placeholders, the PDF cost/impact chart).
+/// Text/background pairs below `min_contrast` are adjusted automatically, so a brand palette can be dropped in
+/// without checking every combination by hand.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct Palette {
+    /// Placeholder card backgrounds, cycled through by stage.
+    pub placeholder_backgrounds: Vec<HexColor>,
+    pub placeholder_text: HexColor,
+    /// Stage markers, error bars and labels in the PDF cost/impact chart (drawn on white).
+    pub chart_accent: HexColor,
+    /// WCAG contrast ratio every text color must reach against its background: 4.5 (AA, default) or 7 (AAA).
+    pub min_contrast: f64,
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Self {
+            placeholder_backgrounds: ["#3B82F6", "#EF4444", "#10B981", "#F59E0B", "#8B5CF6"].map(HexColor::hex).to_vec(),
+            placeholder_text: HexColor::WHITE,
+            chart_accent: HexColor::hex("#0F7857"),
+            min_contrast: AA_TEXT_CONTRAST,
+        }
+    }
+}
+
+impl Palette {
+    pub(crate) fn min_contrast(&self) -> f64 {
+        self.min_contrast.clamp(1.0, 21.0)
+    }
+
+    /// Gradient start and end and the text color of the placeholder for `seed`, both gradient stops readable
+    /// behind the text.
+    pub fn placeholder(&self, seed: usize) -> (HexColor, HexColor, HexColor) {
+        let base = match self.placeholder_backgrounds.as_slice() {
+            [] => Self::default().placeholder_backgrounds[seed % 5],
+            colors => colors[seed % colors.len()],
+        };
+        let (text, min) = (self.placeholder_text, self.min_contrast());
+        let start = base.with_contrast(text, min);
+        let end = base.mix(HexColor::BLACK, 0.3).with_contrast(text, min);
+        (start, end, text)
+    }
+
+    /// The chart accent, adjusted to stay readable on the white page.
+    pub fn chart_accent(&self) -> HexColor {
+        self.chart_accent.with_contrast(HexColor::WHITE, self.min_contrast())
+    }
+}
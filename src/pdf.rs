@@ -1,29 +1,204 @@
-use crate::models::Lifecycle;
-use printpdf::*;
-use std::io::BufWriter;
+use crate::{bundle::Provenance, export::{ExportConfig, ExportProfile, Orientation, PageSize}, images::decode_raster, maps, models::{Lifecycle, StageImage}, palette::HexColor, theme::{FontFamily, ThemeStyle}};
+use ::image::{DynamicImage, RgbaImage};
+use printpdf::{*, lopdf::{self, Object}};
+use qrcode::QrCode;
+use std::{cell::{Cell, RefCell}, collections::HashMap, io::{BufWriter, Cursor}, sync::OnceLock};
 
-/// Minimal PDF (text-only) to avoid image embedding complexity for MVP.
-pub fn generate_pdf(lifecycle: &Lifecycle) -> Vec<u8> {
-    let (doc, _page, layer) = PdfDocument::new(
+/// Bundled TrueType font with broad Unicode coverage (Latin, Greek, Cyrillic, Arabic, Hebrew, ...; not CJK), embedded
+/// in reports with text the built-in PDF fonts can't encode. License: `assets/fonts/LICENSE-DejaVu.txt`.
+const UNICODE_FONT: &[u8] = include_bytes!("../assets/fonts/DejaVuSans.ttf");
+
+/// Storyboard PDF: a cover page and a table of contents linking to every section (both per `profile`), a summary
+/// page, then one page per stage with its image, optional location map
+/// (`maps` is keyed by stage index), supply-chain details and the list of attached documents. `profile` adds
+/// the optional overview and appendix pages and picks which notices close the report; `provenance` feeds the
+/// prompt appendix. With `since_revision` it is a delta report instead: the summary lists what changed after that
+/// revision and only the changed stages follow, each marked with what changed (no overview or impact appendix).
+/// `style` sets the typeface, heading and secondary text colors and line spacing; reports with non-Latin text use
+/// the Unicode font instead. Pages have the profile's size and orientation.
+pub fn generate_pdf(lifecycle: &Lifecycle, export: &ExportConfig, style: &ThemeStyle, profile: &ExportProfile, maps: &HashMap<usize, RgbaImage>, provenance: &Provenance, since_revision: Option<u64>) -> Vec<u8> {
+    let layout = Layout::new(profile.page_size, profile.orientation);
+    let (doc, first_page, first_layer) = PdfDocument::new(
         format!("Lifecycle: {}", truncate(&lifecycle.product_description, 48)),
-        Mm(210.0),
-        Mm(297.0),
+        Mm(layout.width),
+        Mm(layout.height),
         "Layer 1",
     );
-    let font = doc.add_builtin_font(BuiltinFont::Helvetica).unwrap();
-    let summary = doc.get_page(_page).get_layer(layer);
-    summary.use_text("Product Lifecycle Storyboard", 20.0, Mm(15.0), Mm(275.0), &font);
-    summary.use_text(truncate(&lifecycle.product_description, 140), 11.0, Mm(15.0), Mm(260.0), &font);
+    let embedded = needs_unicode(lifecycle, export, profile)
+        .then(|| doc.add_external_font(Cursor::new(unicode_font())).map_err(|e| tracing::warn!("⚠️ Unusable PDF font, non-Latin text will be garbled: {}", e)).ok())
+        .flatten();
+    let font = embedded.unwrap_or_else(|| doc.add_builtin_font(builtin_font(style.font)).unwrap());
+    let draft = !lifecycle.review_status.is_final();
+    let title = match since_revision {
+        Some(since) => format!("Lifecycle Changes since Revision {}", since),
+        None => "Product Lifecycle Storyboard".to_string(),
+    };
+    let exported_at = chrono::Utc::now().format("%Y-%m-%d %H:%M UTC").to_string();
+    // Every page goes through here (the document starts with one), numbered for the table of contents and kept
+    // for the headers and footers drawn once the page count is known
+    let (first, pages) = (Cell::new(Some((first_page, first_layer))), RefCell::new(Vec::new()));
+    let new_page = |name: &str| {
+        let (page, layer) = first.take().unwrap_or_else(|| doc.add_page(Mm(layout.width), Mm(layout.height), name));
+        let layer_ref = doc.get_page(page).get_layer(layer);
+        if draft { draft_watermark(&layer_ref, &font, layout); }
+        let mut pages = pages.borrow_mut();
+        pages.push(layer_ref.clone());
+        (page, layer_ref, pages.len())
+    };
+    // Table of contents entries: label, page and page number, in page order
+    let mut contents: Vec<(String, PdfPageIndex, usize)> = Vec::new();
+
+    if profile.cover_page {
+        let (_, cover, _) = new_page("Cover");
+        cover_page(&cover, &font, lifecycle, (&title, &exported_at), export, style, layout);
+    }
+    let toc = profile.table_of_contents.then(|| new_page("Contents"));
+    let (page, summary, number) = new_page("Summary");
+    let heading = match (profile.cover_page, since_revision) {
+        (false, _) => title,
+        (true, Some(since)) => format!("Changes since revision {}", since),
+        (true, None) => "Summary".to_string(),
+    };
+    contents.push((heading.clone(), page, number));
+    colored_text(&summary, heading, 20.0, (Layout::MARGIN, layout.content_top()), &font, style.heading);
+    summary.use_text(truncate(&lifecycle.product_description, layout.line_chars(140)), 11.0, Mm(Layout::MARGIN), Mm(layout.below_top(37.0)), &font);
     if !lifecycle.constraints.is_empty() {
-        summary.use_text(format!("Constraints: {}", lifecycle.constraints.join(", ")), 10.0, Mm(15.0), Mm(248.0), &font);
+        summary.use_text(format!("Constraints: {}", lifecycle.constraints.join(", ")), 10.0, Mm(Layout::MARGIN), Mm(layout.below_top(49.0)), &font);
+    }
+    // What readers pass as `?since_revision=` next time to get only what changed after this report
+    colored_text(&summary, format!("Revision {}", lifecycle.revision), 9.0, (Layout::MARGIN, layout.below_top(57.0)), &font, style.muted);
+    if let Some(since) = since_revision {
+        change_summary(&summary, &font, lifecycle, since, style, layout);
+    } else if lifecycle.stages.iter().any(|s| s.economics.is_some()) {
+        // Room below for the legend, one line per stage
+        let top = layout.below_top(77.0);
+        let height = (top - 60.0).min(100.0);
+        cost_impact_chart(&summary, &font, lifecycle, (Layout::MARGIN, top - height, layout.content_width().min(180.0), height), style);
     }
-    summary.use_text("(Images not embedded in PDF preview MVP)", 8.0, Mm(15.0), Mm(236.0), &font);
 
-    for stage in &lifecycle.stages {
-        let (page, layer) = doc.add_page(Mm(210.0), Mm(297.0), &stage.stage_name);
-        let layer_ref = doc.get_page(page).get_layer(layer);
-        layer_ref.use_text(&stage.stage_name, 16.0, Mm(15.0), Mm(275.0), &font);
-        layer_ref.use_text(truncate(&stage.prompt, 180), 9.0, Mm(15.0), Mm(260.0), &font);
+    if since_revision.is_none() && (profile.hero_image || profile.executive_summary) {
+        let (page, layer_ref, number) = new_page("Overview");
+        contents.push(("Overview".to_string(), page, number));
+        overview_page(&layer_ref, &font, lifecycle, profile, style, layout);
+    }
+
+    for (i, stage) in lifecycle.stages.iter().enumerate() {
+        let marker = since_revision.map(|since| change_marker(stage, since));
+        if marker == Some(None) { continue; }
+        let (page, layer_ref, number) = new_page(&stage.stage_name);
+        contents.push((format!("{}. {}", i + 1, stage.stage_name), page, number));
+        colored_text(&layer_ref, stage.stage_name.as_str(), 16.0, (Layout::MARGIN, layout.content_top()), &font, style.heading);
+        if let Some(Some(marker)) = marker {
+            colored_text(&layer_ref, format!("[{}]", marker), 9.0, (Layout::MARGIN, layout.below_top(27.5)), &font, style.muted);
+        }
+        colored_text(&layer_ref, truncate(&stage.prompt, layout.line_chars(180)), 9.0, (Layout::MARGIN, layout.below_top(32.0)), &font, style.muted);
+
+        // The details go below the image and map on portrait pages and in a column beside the image on landscape ones
+        let top = layout.below_top(42.0);
+        let image_width = if layout.landscape() { 120.0 } else { 100.0 };
+        let x = if layout.landscape() { Layout::MARGIN + image_width + 10.0 } else { Layout::MARGIN };
+        let mut y = top;
+        if let Some(img) = decode_raster(stage) {
+            let height = embed_image(&layer_ref, &img.thumbnail(900, 900), Layout::MARGIN, top, image_width);
+            if !layout.landscape() { y = y.min(top - height); }
+        }
+        if let Some(map) = maps.get(&i) {
+            let map_x = if layout.landscape() { x } else { Layout::MARGIN + image_width + 5.0 };
+            let height = embed_image(&layer_ref, &DynamicImage::ImageRgba8(map.clone()), map_x, top, 75.0);
+            colored_text(&layer_ref, maps::ATTRIBUTION, 6.0, (map_x, top - height - 4.0), &font, style.muted);
+            y = y.min(top - height - 6.0);
+        }
+        y -= 10.0 * style.spacing;
+        // List lines are cut at the column's edge
+        let fit = Layout::chars_in(layout.right() - x - 3.0, 9.0, style.font);
+
+        if !stage.actors.is_empty() {
+            colored_text(&layer_ref, "Supply chain", 11.0, (x, y), &font, style.heading);
+            y -= 6.0 * style.spacing;
+            for actor in &stage.actors {
+                layer_ref.use_text(truncate(&format!("- {}", actor.describe()), fit), 9.0, Mm(x + 3.0), Mm(y), &font);
+                y -= 5.0 * style.spacing;
+            }
+            y -= 4.0 * style.spacing;
+        }
+        if let Some(economics) = &stage.economics {
+            layer_ref.use_text(format!("Cost share (% of lifecycle cost of goods): {}", economics.cost_share_pct.describe(0)), 10.0, Mm(x), Mm(y), &font);
+            y -= 5.0 * style.spacing;
+            layer_ref.use_text(format!("Impact score (0-10): {}", economics.impact_score.describe(1)), 10.0, Mm(x), Mm(y), &font);
+            y -= 6.0 * style.spacing;
+            for component in &economics.components {
+                layer_ref.use_text(truncate(&format!("- {} (% of stage cost): {}", component.name, component.share_pct.describe(0)), fit), 9.0, Mm(x + 3.0), Mm(y), &font);
+                y -= 5.0 * style.spacing;
+            }
+            y -= 4.0 * style.spacing;
+        }
+        if !stage.assets.is_empty() {
+            colored_text(&layer_ref, "Attached documentation", 11.0, (x, y), &font, style.heading);
+            y -= 6.0 * style.spacing;
+            for asset in &stage.assets {
+                layer_ref.use_text(truncate(&format!("- {}", asset.describe()), fit), 9.0, Mm(x + 3.0), Mm(y), &font);
+                y -= 5.0 * style.spacing;
+            }
+            y -= 4.0 * style.spacing;
+        }
+        if !stage.locations.is_empty() {
+            colored_text(&layer_ref, "Locations", 11.0, (x, y), &font, style.heading);
+            y -= 6.0 * style.spacing;
+            for location in &stage.locations {
+                layer_ref.use_text(truncate(&format!("- {} ({:.3}, {:.3})", location.label, location.lat, location.lon), fit), 9.0, Mm(x + 3.0), Mm(y), &font);
+                y -= 5.0 * style.spacing;
+            }
+        }
+    }
+
+    if profile.impact_appendix && since_revision.is_none() {
+        let (page, layer_ref, number) = new_page("Impact appendix");
+        contents.push(("Impact appendix".to_string(), page, number));
+        impact_appendix(&layer_ref, &font, lifecycle, style, layout);
+    }
+
+    if profile.prompt_appendix {
+        let (page, number) = prompt_appendix(&new_page, &font, lifecycle, provenance, since_revision, style, layout);
+        contents.push(("Prompt appendix".to_string(), page, number));
+    }
+
+    let mut sections = if profile.disclosures { export.disclosures.sections() } else { Vec::new() };
+    if !profile.disclaimers.is_empty() {
+        sections.push(("Disclaimers", profile.disclaimers.as_slice()));
+    }
+    if !sections.is_empty() {
+        let (page, layer_ref, number) = new_page("Disclosures");
+        contents.push(("Disclosures".to_string(), page, number));
+        colored_text(&layer_ref, "Disclosures", 16.0, (Layout::MARGIN, layout.content_top()), &font, style.heading);
+        let mut y = layout.below_top(35.0);
+        'sections: for (heading, paragraphs) in sections {
+            colored_text(&layer_ref, heading, 11.0, (Layout::MARGIN, y), &font, style.heading);
+            y -= 6.0 * style.spacing;
+            for paragraph in paragraphs {
+                for line in wrap(paragraph, layout.line_chars(110)) {
+                    if y < Layout::CONTENT_BOTTOM { break 'sections; }
+                    layer_ref.use_text(line, 9.0, Mm(Layout::MARGIN), Mm(y), &font);
+                    y -= 4.5 * style.spacing;
+                }
+                y -= 2.0 * style.spacing;
+            }
+            y -= 4.0 * style.spacing;
+        }
+    }
+
+    for (label, page, _) in &contents {
+        doc.add_bookmark(label.as_str(), *page);
+    }
+    let links = match &toc {
+        Some((_, layer_ref, _)) => table_of_contents(layer_ref, &font, &contents, style, layout),
+        None => Vec::new(),
+    };
+    let pages = pages.into_inner();
+    let header = truncate(&lifecycle.product_description, layout.line_chars(90));
+    for (n, layer_ref) in pages.iter().enumerate() {
+        // The cover's title band takes the header's place.
+        let header = (n > 0 || !profile.cover_page).then_some(header.as_str());
+        header_footer(layer_ref, &font, header, (n + 1, pages.len()), &exported_at, style, layout);
     }
 
     let mut buf: Vec<u8> = Vec::new();
@@ -31,7 +206,555 @@ pub fn generate_pdf(lifecycle: &Lifecycle) -> Vec<u8> {
         let mut writer = BufWriter::new(&mut buf);
         doc.save(&mut writer).ok();
     }
-    buf
+    match toc {
+        Some((_, _, number)) if !links.is_empty() => link_pages(buf, number, &links),
+        _ => buf,
+    }
+}
+
+/// Page geometry in mm (PDF coordinates: origin bottom left), picked with `?size=` and `?orientation=`. Page
+/// content stays between `CONTENT_BOTTOM` and `content_top`, clear of the running header and footer; positions
+/// below the title are measured from the top edge so they hold on every size.
+#[derive(Debug, Clone, Copy)]
+pub struct Layout {
+    width: f32,
+    height: f32,
+}
+
+impl Layout {
+    const MARGIN: f32 = 15.0;
+    /// Lowest baseline before the content breaks off or continues on a new page.
+    const CONTENT_BOTTOM: f32 = 15.0;
+    /// Baseline of the running footer.
+    const FOOTER_Y: f32 = 8.0;
+    /// Content width the line lengths in characters were tuned for (A4 portrait).
+    const A4_CONTENT_WIDTH: f32 = 180.0;
+
+    pub fn new(size: PageSize, orientation: Orientation) -> Self {
+        let (short, long) = match size {
+            PageSize::A4 => (210.0, 297.0),
+            PageSize::Letter => (215.9, 279.4),
+        };
+        match orientation {
+            Orientation::Portrait => Self { width: short, height: long },
+            Orientation::Landscape => Self { width: long, height: short },
+        }
+    }
+
+    fn landscape(&self) -> bool {
+        self.width > self.height
+    }
+
+    /// Baseline `offset` mm below the top edge.
+    fn below_top(&self, offset: f32) -> f32 {
+        self.height - offset
+    }
+
+    /// Baseline of the page title; continued pages start here.
+    fn content_top(&self) -> f32 {
+        self.below_top(22.0)
+    }
+
+    /// Baseline of the running header.
+    fn header_y(&self) -> f32 {
+        self.below_top(10.0)
+    }
+
+    /// Right edge of the content.
+    fn right(&self) -> f32 {
+        self.width - Self::MARGIN
+    }
+
+    fn content_width(&self) -> f32 {
+        self.width - 2.0 * Self::MARGIN
+    }
+
+    /// Characters per full-width line, for a count that fits a line on A4 portrait.
+    fn line_chars(&self, a4_chars: usize) -> usize {
+        (a4_chars as f32 * self.content_width() / Self::A4_CONTENT_WIDTH) as usize
+    }
+
+    /// Approximate width of `text` at `size` pt, from the average glyph width of the built-in fonts.
+    fn text_width(text: &str, size: f32, family: FontFamily) -> f32 {
+        text.chars().count() as f32 * Self::glyph_width(size, family)
+    }
+
+    /// How many characters at `size` pt fit in `width` mm.
+    fn chars_in(width: f32, size: f32, family: FontFamily) -> usize {
+        (width / Self::glyph_width(size, family)) as usize
+    }
+
+    fn glyph_width(size: f32, family: FontFamily) -> f32 {
+        let em = match family {
+            FontFamily::Sans => 0.52,
+            FontFamily::Serif => 0.48,
+            FontFamily::Mono => 0.6,
+        };
+        size * em * 25.4 / 72.0
+    }
+
+    /// x at which `text` ends at the right margin.
+    fn right_aligned(&self, text: &str, size: f32, family: FontFamily) -> f32 {
+        self.right() - Self::text_width(text, size, family)
+    }
+}
+
+/// Running header (product and the theme's logo, with a rule below) and footer (export time, the theme's footer
+/// line, page X of Y, with a rule above).
+fn header_footer(layer: &PdfLayerReference, font: &IndirectFontRef, header: Option<&str>, (number, total): (usize, usize), exported_at: &str, style: &ThemeStyle, layout: Layout) {
+    let rule = |y: f32| Line {
+        points: vec![(Point::new(Mm(Layout::MARGIN), Mm(y)), false), (Point::new(Mm(layout.right()), Mm(y)), false)],
+        is_closed: false,
+    };
+    layer.set_outline_thickness(0.3);
+    layer.set_outline_color(Color::Greyscale(Greyscale::new(0.75, None)));
+    if let Some(header) = header {
+        colored_text(layer, header, 8.0, (Layout::MARGIN, layout.header_y()), font, style.muted);
+        layer.add_line(rule(layout.header_y() - 2.0));
+        if let Some(logo) = &style.logo {
+            let width = (6.0 * logo.image.width() as f32 / logo.image.height().max(1) as f32).min(40.0);
+            embed_image(layer, &logo.image.thumbnail(240, 240), layout.right() - width, layout.header_y() + 5.0, width);
+        }
+    }
+    layer.add_line(rule(Layout::FOOTER_Y + 4.0));
+    colored_text(layer, format!("Exported {}", exported_at), 8.0, (Layout::MARGIN, Layout::FOOTER_Y), font, style.muted);
+    if let Some(footer) = &style.footer {
+        // Centered between the export time and the page number
+        let footer = truncate(footer, Layout::chars_in(layout.content_width() - 110.0, 8.0, style.font));
+        let x = (layout.width - Layout::text_width(&footer, 8.0, style.font)) / 2.0;
+        colored_text(layer, footer, 8.0, (x, Layout::FOOTER_Y), font, style.muted);
+    }
+    let page = format!("Page {} of {}", number, total);
+    colored_text(layer, page.as_str(), 8.0, (layout.right_aligned(&page, 8.0, style.font), Layout::FOOTER_Y), font, style.muted);
+}
+
+/// Title band in the heading color, the product, its constraints, when and from what the report was generated,
+/// the theme's logo and a QR code of `export.cover_link` (the bare lifecycle id without one).
+fn cover_page(layer: &PdfLayerReference, font: &IndirectFontRef, lifecycle: &Lifecycle, (title, exported_at): (&str, &str), export: &ExportConfig, style: &ThemeStyle, layout: Layout) {
+    let band = style.heading.unwrap_or(style.chart_accent);
+    let (r, g, b) = band.unit_rgb();
+    layer.set_fill_color(Color::Rgb(Rgb::new(r, g, b, None)));
+    layer.add_rect(Rect::new(Mm(0.0), Mm(layout.below_top(52.0)), Mm(layout.width), Mm(layout.height)).with_mode(path::PaintMode::Fill));
+    colored_text(layer, title, 26.0, (Layout::MARGIN, layout.below_top(32.0)), font, Some(HexColor::WHITE));
+
+    let mut y = layout.below_top(72.0);
+    // Landscape covers have less height for the product and constraints
+    let lines = if layout.landscape() { (5, 6) } else { (8, 12) };
+    for line in wrap(&lifecycle.product_description, layout.line_chars(60)).into_iter().take(lines.0) {
+        layer.use_text(line, 14.0, Mm(Layout::MARGIN), Mm(y), font);
+        y -= 7.0 * style.spacing;
+    }
+    if !lifecycle.constraints.is_empty() {
+        y -= 6.0 * style.spacing;
+        colored_text(layer, "Constraints", 11.0, (Layout::MARGIN, y), font, style.heading);
+        y -= 6.0 * style.spacing;
+        for constraint in lifecycle.constraints.iter().take(lines.1) {
+            layer.use_text(format!("- {}", truncate(constraint, layout.line_chars(90))), 10.0, Mm(Layout::MARGIN + 3.0), Mm(y), font);
+            y -= 5.0 * style.spacing;
+        }
+    }
+
+    let status = serde_json::to_value(lifecycle.review_status).ok().and_then(|v| v.as_str().map(|s| s.replace('_', " "))).unwrap_or_default();
+    let details = [
+        format!("Generated {}", exported_at),
+        format!("Lifecycle {}", lifecycle.id),
+        format!("Revision {}, {}", lifecycle.revision, status),
+    ];
+    for (n, line) in details.into_iter().enumerate() {
+        colored_text(layer, line, 9.0, (15.0, 40.0 - n as f32 * 5.0), font, style.muted);
+    }
+
+    if let Some(logo) = &style.logo {
+        // Bottom-aligned with the QR code, to its left
+        let (max_width, max_height) = (50.0, 25.0);
+        let width = (max_height * logo.image.width() as f32 / logo.image.height().max(1) as f32).min(max_width);
+        let height = width * logo.image.height() as f32 / logo.image.width().max(1) as f32;
+        embed_image(layer, &logo.image, layout.right() - 43.0 - width, 20.0 + height, width);
+    }
+
+    let link = export.cover_link.as_deref().map_or_else(|| lifecycle.id.to_string(), |link| link.replace("{id}", &lifecycle.id.to_string()));
+    if let Ok(code) = QrCode::new(link.as_bytes()) {
+        let (x, top, size) = (layout.right() - 35.0, 55.0, 35.0);
+        let module = size / code.width() as f32;
+        layer.set_fill_color(Color::Greyscale(Greyscale::new(0.0, None)));
+        for (n, color) in code.to_colors().into_iter().enumerate() {
+            if color != qrcode::Color::Dark { continue; }
+            let (col, row) = ((n % code.width()) as f32, (n / code.width()) as f32);
+            let (left, bottom) = (x + col * module, top - (row + 1.0) * module);
+            layer.add_rect(Rect::new(Mm(left), Mm(bottom), Mm(left + module), Mm(bottom + module)).with_mode(path::PaintMode::Fill));
+        }
+        colored_text(layer, if export.cover_link.is_some() { "Scan to open" } else { "Lifecycle id" }, 7.0, (x, top - size - 4.0), font, style.muted);
+    }
+    layer.set_fill_color(Color::Greyscale(Greyscale::new(0.0, None)));
+}
+
+/// One line per entry with its page number. Returns each line's clickable area (in pt) and target page number, linked
+/// by `link_pages` once the document is saved.
+fn table_of_contents(layer: &PdfLayerReference, font: &IndirectFontRef, contents: &[(String, PdfPageIndex, usize)], style: &ThemeStyle, layout: Layout) -> Vec<([f32; 4], usize)> {
+    colored_text(layer, "Contents", 16.0, (Layout::MARGIN, layout.content_top()), font, style.heading);
+    let mut links = Vec::new();
+    let mut y = layout.below_top(35.0);
+    for (label, _, number) in contents {
+        if y < Layout::CONTENT_BOTTOM { break; }
+        layer.use_text(truncate(label, layout.line_chars(90)), 11.0, Mm(Layout::MARGIN), Mm(y), font);
+        layer.use_text(number.to_string(), 11.0, Mm(layout.right() - 10.0), Mm(y), font);
+        let area = [Mm(Layout::MARGIN).into_pt().0, Mm(y - 1.5).into_pt().0, Mm(layout.right()).into_pt().0, Mm(y + 4.5).into_pt().0];
+        links.push((area, *number));
+        y -= 7.0 * style.spacing;
+    }
+    links
+}
+
+/// Add go-to-page links over the table of contents lines of a saved document (printpdf only writes URI links).
+/// The document is returned unchanged when it can't be re-read.
+fn link_pages(pdf: Vec<u8>, toc: usize, links: &[([f32; 4], usize)]) -> Vec<u8> {
+    let Ok(mut doc) = lopdf::Document::load_mem(&pdf) else { return pdf };
+    let pages = doc.get_pages();
+    let Some(&toc_id) = pages.get(&(toc as u32)) else { return pdf };
+    let mut annotations = Vec::new();
+    for ([x1, y1, x2, y2], target) in links {
+        let Some(&target_id) = pages.get(&(*target as u32)) else { continue };
+        let annotation = lopdf::Dictionary::from_iter(vec![
+            ("Type", Object::Name(b"Annot".to_vec())),
+            ("Subtype", Object::Name(b"Link".to_vec())),
+            ("Rect", Object::Array(vec![Object::Real(*x1), Object::Real(*y1), Object::Real(*x2), Object::Real(*y2)])),
+            ("Border", Object::Array(vec![0.into(), 0.into(), 0.into()])),
+            ("Dest", Object::Array(vec![Object::Reference(target_id), Object::Name(b"XYZ".to_vec()), Object::Null, Object::Null, Object::Null])),
+        ]);
+        annotations.push(Object::Reference(doc.add_object(annotation)));
+    }
+    let Ok(page) = doc.get_dictionary_mut(toc_id) else { return pdf };
+    page.set("Annots", annotations);
+    let mut linked = Vec::new();
+    match doc.save_to(&mut linked) {
+        Ok(()) => linked,
+        Err(_) => pdf,
+    }
+}
+
+/// Hero image (the first generated raster, preferring stages without warnings) and/or one line per stage with the
+/// lead sentence of its description.
+fn overview_page(layer: &PdfLayerReference, font: &IndirectFontRef, lifecycle: &Lifecycle, profile: &ExportProfile, style: &ThemeStyle, layout: Layout) {
+    colored_text(layer, "Overview", 16.0, (Layout::MARGIN, layout.content_top()), font, style.heading);
+    let mut y = layout.below_top(32.0);
+    if profile.hero_image {
+        let mut candidates: Vec<_> = lifecycle.stages.iter().collect();
+        candidates.sort_by_key(|stage| !stage.warnings.is_empty());
+        if let Some((stage, img)) = candidates.into_iter().find_map(|stage| decode_raster(stage).map(|img| (stage, img))) {
+            let max_height = if layout.landscape() { 80.0 } else { 120.0 };
+            let width = layout.content_width().min(max_height * img.width() as f32 / img.height().max(1) as f32);
+            let height = embed_image(layer, &img.thumbnail(1400, 1400), Layout::MARGIN, y, width);
+            colored_text(layer, stage.stage_name.as_str(), 8.0, (Layout::MARGIN, y - height - 4.0), font, style.muted);
+            y -= height + 12.0;
+        }
+    }
+    if profile.executive_summary {
+        colored_text(layer, "Executive summary", 12.0, (Layout::MARGIN, y), font, style.heading);
+        y -= 7.0 * style.spacing;
+        'stages: for (i, stage) in lifecycle.stages.iter().enumerate() {
+            let lead = lead_sentence(&stage.description);
+            for (n, line) in wrap(&format!("{}. {}: {}", i + 1, stage.stage_name, lead), layout.line_chars(110)).into_iter().enumerate() {
+                if y < Layout::CONTENT_BOTTOM { break 'stages; }
+                layer.use_text(line, 9.0, Mm(if n == 0 { Layout::MARGIN } else { Layout::MARGIN + 4.0 }), Mm(y), font);
+                y -= 4.5 * style.spacing;
+            }
+            y -= 1.5 * style.spacing;
+        }
+    }
 }
 
-fn truncate(s: &str, max: usize) -> String { if s.len() <= max { s.to_string() } else { format!("{}…", &s[..max]) } }
+/// Every stage's economics estimate, or a note when it has none.
+fn impact_appendix(layer: &PdfLayerReference, font: &IndirectFontRef, lifecycle: &Lifecycle, style: &ThemeStyle, layout: Layout) {
+    colored_text(layer, "Impact appendix", 16.0, (Layout::MARGIN, layout.content_top()), font, style.heading);
+    let mut y = layout.below_top(35.0);
+    for (i, stage) in lifecycle.stages.iter().enumerate() {
+        if y < 25.0 { break; }
+        colored_text(layer, format!("{}. {}", i + 1, stage.stage_name), 11.0, (15.0, y), font, style.heading);
+        y -= 5.5 * style.spacing;
+        let Some(economics) = &stage.economics else {
+            layer.use_text("Not estimated", 9.0, Mm(18.0), Mm(y), font);
+            y -= 7.0 * style.spacing;
+            continue;
+        };
+        layer.use_text(format!("Cost share (% of lifecycle cost of goods): {}", economics.cost_share_pct.describe(0)), 9.0, Mm(18.0), Mm(y), font);
+        y -= 4.5 * style.spacing;
+        layer.use_text(format!("Impact score (0-10): {}", economics.impact_score.describe(1)), 9.0, Mm(18.0), Mm(y), font);
+        y -= 4.5 * style.spacing;
+        for component in &economics.components {
+            if y < Layout::CONTENT_BOTTOM { break; }
+            layer.use_text(format!("- {} (% of stage cost): {}", component.name, component.share_pct.describe(0)), 8.0, Mm(21.0), Mm(y), font);
+            y -= 4.0 * style.spacing;
+        }
+        y -= 3.0 * style.spacing;
+    }
+}
+
+/// Every stage's (or, in a delta report, every changed stage's) full prompt, generation parameters and provider
+/// calls, continued over as many pages as needed. Returns the first page and its number.
+fn prompt_appendix(new_page: &impl Fn(&str) -> (PdfPageIndex, PdfLayerReference, usize), font: &IndirectFontRef, lifecycle: &Lifecycle, provenance: &Provenance, since_revision: Option<u64>, style: &ThemeStyle, layout: Layout) -> (PdfPageIndex, usize) {
+    let (first_page, mut layer, number) = new_page("Prompt appendix");
+    colored_text(&layer, "Prompt appendix", 16.0, (Layout::MARGIN, layout.content_top()), font, style.heading);
+    let mut y = layout.below_top(32.0);
+    let line = |layer: &mut PdfLayerReference, y: &mut f32, text: String, size: f32, indent: f32, step: f32| {
+        if *y < Layout::CONTENT_BOTTOM {
+            *layer = new_page("Prompt appendix").1;
+            *y = layout.content_top();
+        }
+        layer.use_text(text, size, Mm(Layout::MARGIN + indent), Mm(*y), font);
+        *y -= step * style.spacing;
+    };
+
+    let mut header = vec![
+        format!("Lifecycle {} (revision {}), created {}", lifecycle.id, lifecycle.revision, lifecycle.created_at.format("%Y-%m-%d %H:%M UTC")),
+        format!("Image provider: {}; text: {} {}", provenance.image_provider, provenance.text_provider, provenance.text_model),
+    ];
+    if !provenance.models.is_empty() {
+        header.push(format!("Models: {}", provenance.models.iter().map(|(name, id)| format!("{} = {}", name, id)).collect::<Vec<_>>().join(", ")));
+    }
+    for text in header.iter().flat_map(|h| wrap(h, layout.line_chars(110))) {
+        line(&mut layer, &mut y, text, 9.0, 0.0, 4.5);
+    }
+    y -= 4.0 * style.spacing;
+
+    for (stage, trace) in lifecycle.stages.iter().zip(&provenance.stages) {
+        if since_revision.is_some_and(|since| stage.revisions.changed <= since) { continue; }
+        line(&mut layer, &mut y, format!("{}. {}", trace.index + 1, stage.stage_name), 11.0, 0.0, 5.5);
+        line(&mut layer, &mut y, "Prompt".to_string(), 9.0, 3.0, 4.5);
+        for text in wrap(&stage.prompt, layout.line_chars(115)) {
+            line(&mut layer, &mut y, text, 8.0, 6.0, 4.0);
+        }
+        let image = match (&trace.mime_type, &trace.image_sha256) {
+            (Some(mime), Some(sha)) => format!("{}{}, sha256 {}", mime, if trace.is_placeholder { " placeholder" } else { "" }, &sha[..16]),
+            _ => "none".to_string(),
+        };
+        let mut details = vec![
+            format!("Image: {}; resolution {}", image, format!("{:?}", trace.resolution).to_lowercase()),
+            format!(
+                "Templates: image {}, description {}{}",
+                trace.templates.image.as_deref().unwrap_or("-"),
+                trace.templates.description.as_deref().unwrap_or("-"),
+                if trace.description_fallback { "; description is the fallback text" } else { "" },
+            ),
+            format!("Last updated {}; {} description revision(s)", trace.last_updated.format("%Y-%m-%d %H:%M:%S UTC"), trace.description_revisions),
+        ];
+        if stage.usage.is_empty() {
+            details.push("No provider calls recorded".to_string());
+        }
+        for text in details {
+            line(&mut layer, &mut y, text, 8.0, 3.0, 4.0);
+        }
+        for call in &stage.usage {
+            let text = format!(
+                "- {} {}/{}: {} prompt + {} output tokens, {} image(s)",
+                call.at.format("%Y-%m-%d %H:%M:%S UTC"), call.provider, call.model, call.prompt_tokens, call.output_tokens, call.images,
+            );
+            line(&mut layer, &mut y, text, 8.0, 6.0, 4.0);
+        }
+        y -= 4.0 * style.spacing;
+    }    (first_page, number)
+}
+
+/// One line per stage changed after `since` with what changed, on the summary page of a delta report.
+fn change_summary(layer: &PdfLayerReference, font: &IndirectFontRef, lifecycle: &Lifecycle, since: u64, style: &ThemeStyle, layout: Layout) {
+    let changes: Vec<_> = lifecycle.stages.iter().enumerate()
+        .filter_map(|(i, stage)| change_marker(stage, since).map(|marker| format!("{}. {}: {}", i + 1, stage.stage_name, marker)))
+        .collect();
+    if changes.is_empty() {
+        layer.use_text("No stage changed since this revision.", 10.0, Mm(Layout::MARGIN), Mm(layout.below_top(69.0)), font);
+        return;
+    }
+    colored_text(layer, format!("Changed stages ({} of {})", changes.len(), lifecycle.stages.len()), 12.0, (Layout::MARGIN, layout.below_top(69.0)), font, style.heading);
+    let mut y = layout.below_top(76.0);
+    for change in changes {
+        if y < Layout::CONTENT_BOTTOM { break; }
+        layer.use_text(truncate(&change, layout.line_chars(110)), 9.0, Mm(Layout::MARGIN + 3.0), Mm(y), font);
+        y -= 5.0 * style.spacing;
+    }
+}
+
+/// What changed in `stage` after revision `since`, `None` if nothing did.
+fn change_marker(stage: &StageImage, since: u64) -> Option<String> {
+    let revisions = &stage.revisions;
+    if revisions.changed <= since {
+        return None;
+    }
+    if revisions.added > since {
+        return Some(format!("new in revision {}", revisions.added));
+    }
+    let mut parts = Vec::new();
+    if revisions.image > since { parts.push("image"); }
+    if revisions.description > since { parts.push("description"); }
+    if parts.is_empty() { parts.push("details"); }
+    Some(format!("changed: {} (last in revision {})", parts.join(", "), revisions.changed))
+}
+
+/// First sentence of the first paragraph (descriptions run to several paragraphs).
+fn lead_sentence(text: &str) -> String {
+    let paragraph = text.trim().lines().next().unwrap_or_default();
+    match paragraph.find(". ") {
+        Some(end) => paragraph[..=end].to_string(),
+        None => paragraph.to_string(),
+    }
+}
+
+/// Cost-vs-impact quadrant chart: x = cost share (%), y = impact score (0–10), one numbered dot per stage with
+/// error bars spanning each estimate's low/high range.
+/// The chart's lower-left corner is at (`x`, `y`) mm; data marks and labels are in the theme's chart accent.
+fn cost_impact_chart(layer: &PdfLayerReference, font: &IndirectFontRef, lifecycle: &Lifecycle, (x, y, w, h): (f32, f32, f32, f32), style: &ThemeStyle) {
+    let max_cost = lifecycle.stages.iter()
+        .filter_map(|s| s.economics.as_ref().map(|e| e.cost_share_pct.high))
+        .fold(0.0f32, f32::max)
+        .max(10.0);
+    let line = |points: &[(f32, f32)]| Line {
+        points: points.iter().map(|&(px, py)| (Point::new(Mm(px), Mm(py)), false)).collect(),
+        is_closed: false,
+    };
+
+    colored_text(layer, "Cost vs. environmental impact (bars show estimate ranges)", 12.0, (x, y + h + 6.0), font, style.heading);
+    layer.set_outline_thickness(0.8);
+    layer.set_outline_color(Color::Greyscale(Greyscale::new(0.2, None)));
+    layer.add_line(line(&[(x, y + h), (x, y), (x + w, y)]));
+    layer.set_outline_thickness(0.3);
+    layer.set_outline_color(Color::Greyscale(Greyscale::new(0.6, None)));
+    layer.add_line(line(&[(x + w / 2.0, y), (x + w / 2.0, y + h)]));
+    layer.add_line(line(&[(x, y + h / 2.0), (x + w, y + h / 2.0)]));
+
+    layer.use_text(format!("Cost share (0-{:.0}%)", max_cost), 8.0, Mm(x + w - 35.0), Mm(y - 5.0), font);
+    layer.use_text("Impact (0-10)", 8.0, Mm(x + 1.0), Mm(y + h + 1.0), font);
+    layer.use_text("Low cost / high impact", 7.0, Mm(x + 2.0), Mm(y + h - 5.0), font);
+    layer.use_text("High cost / high impact", 7.0, Mm(x + w - 32.0), Mm(y + h - 5.0), font);
+    layer.use_text("Low cost / low impact", 7.0, Mm(x + 2.0), Mm(y + 2.0), font);
+    layer.use_text("High cost / low impact", 7.0, Mm(x + w - 32.0), Mm(y + 2.0), font);
+
+    let (r, g, b) = style.chart_accent.unit_rgb();
+    layer.set_fill_color(Color::Rgb(Rgb::new(r, g, b, None)));
+    layer.set_outline_color(Color::Rgb(Rgb::new(r, g, b, None)));
+    let mut legend_y = y - 12.0;
+    for (i, stage) in lifecycle.stages.iter().enumerate() {
+        let Some(economics) = &stage.economics else { continue };
+        let (cost, impact) = (&economics.cost_share_pct, &economics.impact_score);
+        let to_x = |v: f32| x + v / max_cost * w;
+        let to_y = |v: f32| y + v / 10.0 * h;
+        let (px, py) = (to_x(cost.value), to_y(impact.value));
+        layer.add_line(line(&[(to_x(cost.low), py), (to_x(cost.high), py)]));
+        layer.add_line(line(&[(px, to_y(impact.low)), (px, to_y(impact.high))]));
+        layer.add_rect(Rect::new(Mm(px - 1.5), Mm(py - 1.5), Mm(px + 1.5), Mm(py + 1.5)).with_mode(path::PaintMode::Fill));
+        layer.use_text(format!("{}", i + 1), 8.0, Mm(px + 2.0), Mm(py + 1.0), font);
+        layer.use_text(format!("{}. {} - cost % {}, impact {}", i + 1, stage.stage_name, cost.describe(0), impact.describe(1)), 8.0, Mm(x), Mm(legend_y), font);
+        legend_y -= 4.0;
+    }
+    layer.set_fill_color(Color::Greyscale(Greyscale::new(0.0, None)));
+}
+
+/// Place `img` with its top-left corner at (`x`, `top`) mm, scaled to `width` mm. Returns the rendered height in mm.
+fn embed_image(layer: &PdfLayerReference, img: &DynamicImage, x: f32, top: f32, width: f32) -> f32 {
+    let rgb = img.to_rgb8();
+    let (w, h) = rgb.dimensions();
+    let height = width * h as f32 / w as f32;
+    // Store as JPEG (DCTDecode) rather than raw pixels to keep the PDF small.
+    let mut jpeg = Vec::new();
+    let encoded = ::image::codecs::jpeg::JpegEncoder::new_with_quality(&mut jpeg, 85).encode_image(&rgb).is_ok();
+    let (image_data, image_filter) = if encoded { (jpeg, Some(ImageFilter::DCT)) } else { (rgb.into_raw(), None) };
+    let xobject = ImageXObject {
+        width: Px(w as usize),
+        height: Px(h as usize),
+        color_space: ColorSpace::Rgb,
+        bits_per_component: ColorBits::Bit8,
+        interpolate: true,
+        image_data,
+        image_filter,
+        smask: None,
+        clipping_bbox: None,
+    };
+    Image::from(xobject).add_to_layer(layer.clone(), ImageTransform {
+        translate_x: Some(Mm(x)),
+        translate_y: Some(Mm(top - height)),
+        dpi: Some(w as f32 * 25.4 / width),
+        ..Default::default()
+    });
+    height
+}
+
+/// `text` at (`x`, `y`) mm, in `color` when the theme sets one; the fill is back to black afterwards.
+fn colored_text(layer: &PdfLayerReference, text: impl Into<String>, size: f32, (x, y): (f32, f32), font: &IndirectFontRef, color: Option<HexColor>) {
+    let Some(color) = color else {
+        layer.use_text(text, size, Mm(x), Mm(y), font);
+        return;
+    };
+    let (r, g, b) = color.unit_rgb();
+    layer.set_fill_color(Color::Rgb(Rgb::new(r, g, b, None)));
+    layer.use_text(text, size, Mm(x), Mm(y), font);
+    layer.set_fill_color(Color::Greyscale(Greyscale::new(0.0, None)));
+}
+
+/// `PDF_FONT_PATH`: a TrueType font to embed instead of the bundled one, e.g. one covering CJK scripts. Read once.
+fn unicode_font() -> &'static [u8] {
+    static CUSTOM: OnceLock<Option<Vec<u8>>> = OnceLock::new();
+    let custom = CUSTOM.get_or_init(|| {
+        let path = std::env::var("PDF_FONT_PATH").ok()?;
+        std::fs::read(&path).map_err(|e| tracing::warn!("⚠️ Cannot read PDF_FONT_PATH {}: {}; using the bundled font", path, e)).ok()
+    });
+    custom.as_deref().unwrap_or(UNICODE_FONT)
+}
+
+/// Whether `text` is within WinAnsiEncoding, the only encoding of the built-in fonts.
+fn is_winansi(text: &str) -> bool {
+    text.chars().all(|c| matches!(c as u32, 0..=0x7F | 0xA0..=0xFF) || "€‚ƒ„…†‡ˆ‰Š‹ŒŽ‘’“”•–—˜™š›œžŸ".contains(c))
+}
+
+/// Whether any text the report prints is outside what the built-in fonts can show.
+fn needs_unicode(lifecycle: &Lifecycle, export: &ExportConfig, profile: &ExportProfile) -> bool {
+    let notices = export.disclosures.sections().into_iter().flat_map(|(_, paragraphs)| paragraphs).chain(&profile.disclaimers);
+    let mut stage_texts = lifecycle.stages.iter().flat_map(|stage| {
+        [stage.stage_name.clone(), stage.prompt.clone(), stage.description.clone()].into_iter()
+            .chain(stage.actors.iter().map(|actor| actor.describe()))
+            .chain(stage.assets.iter().map(|asset| asset.describe()))
+            .chain(stage.locations.iter().map(|location| location.label.clone()))
+            .chain(stage.economics.iter().flat_map(|economics| economics.components.iter().map(|component| component.name.clone())))
+    });
+    !std::iter::once(&lifecycle.product_description).chain(&lifecycle.constraints).chain(notices).all(|text| is_winansi(text))
+        || !stage_texts.all(|text| is_winansi(&text))
+}
+
+fn builtin_font(family: FontFamily) -> BuiltinFont {
+    match family {
+        FontFamily::Sans => BuiltinFont::Helvetica,
+        FontFamily::Serif => BuiltinFont::TimesRoman,
+        FontFamily::Mono => BuiltinFont::Courier,
+    }
+}
+
+/// Large diagonal light-grey "DRAFT" across the middle of the page, drawn before the page content so text stays legible.
+fn draft_watermark(layer: &PdfLayerReference, font: &IndirectFontRef, layout: Layout) {
+    layer.save_graphics_state();
+    layer.set_fill_color(Color::Greyscale(Greyscale::new(0.88, None)));
+    layer.begin_text_section();
+    layer.set_font(font, 120.0);
+    layer.set_text_matrix(TextMatrix::TranslateRotate(Mm((layout.width - 120.0) / 2.0).into_pt(), Mm((layout.height - 157.0) / 2.0).into_pt(), 45.0));
+    layer.write_text("DRAFT", font);
+    layer.end_text_section();
+    layer.restore_graphics_state();
+}
+
+/// At most `max` characters of `s`, with an ellipsis when cut.
+fn truncate(s: &str, max: usize) -> String {
+    match s.char_indices().nth(max) {
+        Some((end, _)) => format!("{}…", &s[..end]),
+        None => s.to_string(),
+    }
+}
+
+/// Greedy word wrap to at most `width` characters per line.
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut current = String::new();
+    for word in text.split_whitespace() {
+        if !current.is_empty() && current.chars().count() + 1 + word.chars().count() > width {
+            lines.push(std::mem::take(&mut current));
+        }
+        if !current.is_empty() { current.push(' '); }
+        current.push_str(word);
+    }
+    if !current.is_empty() { lines.push(current); }
+    lines
+}
@@ -0,0 +1,95 @@
+use axum::{extract::Request, http::{header, HeaderValue, Method, StatusCode}, middleware::Next, response::{IntoResponse, Response}};
+use std::{cell::Cell, future::Future};
+
+use crate::error::ApiError;
+
+/// Per-request revision bookkeeping.
+struct Revisions {
+    /// From `If-Match` / `?version=`, until the first write.
+    expected: Cell<Option<u64>>,
+    /// Revision of the request's last lifecycle write.
+    written: Cell<Option<u64>>,
+}
+
+tokio::task_local! {
+    static REVISIONS: Revisions;
+}
+
+/// Optimistic concurrency for mutating requests: with `If-Match: "N"` (the `ETag` of `GET /api/lifecycle/{id}`)
+/// or `?version=N`, the request only applies if the lifecycle is still at revision `N`, and gets a 409
+/// `version_mismatch` otherwise. Requests without either keep last-writer-wins. Responses to requests that
+/// changed a lifecycle carry its new revision as `ETag`, ready for the next edit.
+pub async fn require_version(request: Request, next: Next) -> Response {
+    if matches!(*request.method(), Method::GET | Method::HEAD | Method::OPTIONS) {
+        return next.run(request).await;
+    }
+    let expected = match expected_revision(&request) {
+        Ok(expected) => expected,
+        Err(e) => return e.into_response(),
+    };
+    let revisions = Revisions { expected: Cell::new(expected), written: Cell::new(None) };
+    REVISIONS.scope(revisions, async move {
+        let mut response = next.run(request).await;
+        if let Some(revision) = REVISIONS.with(|r| r.written.get()) {
+            if let Ok(value) = HeaderValue::from_str(&format!("\"{}\"", revision)) {
+                response.headers_mut().insert(header::ETAG, value);
+            }
+        }
+        response
+    }).await
+}
+
+fn expected_revision(request: &Request) -> Result<Option<u64>, ApiError> {
+    let invalid = |what: &str| ApiError::new(StatusCode::BAD_REQUEST, "invalid_precondition", format!("{} must be a lifecycle revision number", what));
+    let from_header = match request.headers().get(header::IF_MATCH) {
+        // `*` matches any current version.
+        Some(value) if value.as_bytes() == b"*" => None,
+        Some(value) => {
+            let tag = value.to_str().unwrap_or_default().trim();
+            let tag = tag.strip_prefix("W/").unwrap_or(tag).trim_matches('"');
+            Some(tag.parse::<u64>().map_err(|_| invalid("If-Match"))?)
+        }
+        None => None,
+    };
+    let from_query = match request.uri().query().into_iter().flat_map(|q| q.split('&')).find_map(|pair| pair.strip_prefix("version=")) {
+        Some(value) => Some(value.parse::<u64>().map_err(|_| invalid("version"))?),
+        None => None,
+    };
+    match (from_header, from_query) {
+        (Some(a), Some(b)) if a != b => Err(ApiError::new(StatusCode::BAD_REQUEST, "invalid_precondition", "If-Match and version disagree")),
+        (a, b) => Ok(a.or(b)),
+    }
+}
+
+/// The revision the current request expects, without consuming it (for failing fast before expensive work).
+pub fn expected() -> Option<u64> {
+    REVISIONS.try_with(|r| r.expected.get()).ok().flatten()
+}
+
+/// The revision the current request expects, consumed by its first write: follow-up writes of the same request
+/// (e.g. a generation storing its result after marking the stage) see the revision it produced itself.
+pub fn take_expected() -> Option<u64> {
+    REVISIONS.try_with(|r| r.expected.take()).ok().flatten()
+}
+
+/// `future`, detached from the request (e.g. a shared generation task), with its own bookkeeping starting at
+/// `expected`: its first write still checks it. Also returns the revision of its last write, for the request to
+/// `record_written`.
+pub async fn detached<F: Future>(expected: Option<u64>, future: F) -> (F::Output, Option<u64>) {
+    let revisions = Revisions { expected: Cell::new(expected), written: Cell::new(None) };
+    REVISIONS.scope(revisions, async move {
+        let output = future.await;
+        (output, REVISIONS.with(|r| r.written.get()))
+    }).await
+}
+
+/// Note a stored write for the response's `ETag`. Outside a request (background jobs) it is dropped.
+pub fn record_written(revision: u64) {
+    let _ = REVISIONS.try_with(|r| r.written.set(Some(revision)));
+}
+
+/// 409 for a lifecycle that moved past the revision the client edited.
+pub fn version_mismatch(expected: u64, current: u64) -> ApiError {
+    ApiError::new(StatusCode::CONFLICT, "version_mismatch", format!("The lifecycle changed since revision {} (now {}); reload and retry", expected, current))
+        .with_details(serde_json::json!({ "expected": expected, "current": current }))
+}
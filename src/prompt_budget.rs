@@ -0,0 +1,107 @@
+use tracing::{info, warn};
+
+use crate::{gemini::GeminiClient, models::PromptInputs};
+
+/// Rough token count (~4 characters per token for English text); good enough to keep well clear of limits.
+pub fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(4)
+}
+
+/// `PROMPT_INPUT_TOKEN_BUDGET` (default 600): how many tokens the user-supplied product description and
+/// constraints may take up in a stage prompt. Constraints get at most a third of it.
+#[derive(Debug, Clone, Copy)]
+pub struct PromptBudget {
+    pub max_input_tokens: usize,
+}
+
+impl PromptBudget {
+    pub fn from_env() -> Self {
+        let max = std::env::var("PROMPT_INPUT_TOKEN_BUDGET").ok().and_then(|v| v.parse().ok()).unwrap_or(600usize);
+        Self { max_input_tokens: max.max(50) }
+    }
+}
+
+/// What goes into the prompts when the inputs had to be shortened, plus lifecycle warnings saying so.
+pub struct FittedInputs {
+    /// `None` when everything fit and the original inputs are used as-is.
+    pub inputs: Option<PromptInputs>,
+    pub warnings: Vec<String>,
+}
+
+/// Cut `text` to roughly `max_tokens`, preferring a sentence end, then a word boundary.
+pub fn truncate_to_tokens(text: &str, max_tokens: usize) -> String {
+    let max_chars = max_tokens * 4;
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+    let cut: String = text.chars().take(max_chars).collect();
+    let end = cut.rfind(['.', '!', '?']).map(|i| i + 1)
+        .filter(|&i| i >= cut.len() / 2)
+        .or_else(|| cut.rfind(char::is_whitespace))
+        .unwrap_or(cut.len());
+    let kept = cut[..end].trim_end();
+    if kept.ends_with(['.', '!', '?']) { kept.to_string() } else { format!("{}…", kept) }
+}
+
+impl GeminiClient {
+    /// Keep the product description and constraints within the prompt budget: constraints that don't fit are
+    /// dropped (in order), and an overlong description is summarized by the text model, or truncated when there
+    /// is none (demo mode without Ollama) or summarization fails.
+    pub async fn fit_prompt_inputs(&self, product: &str, constraints: &[String]) -> FittedInputs {
+        let budget = self.prompt_budget;
+        let mut warnings = Vec::new();
+
+        let constraint_budget = budget.max_input_tokens / 3;
+        let mut used = 0;
+        let kept: Vec<String> = constraints.iter()
+            .take_while(|c| {
+                used += estimate_tokens(c);
+                used <= constraint_budget
+            })
+            .cloned()
+            .collect();
+        if kept.len() < constraints.len() {
+            let dropped = &constraints[kept.len()..];
+            warn!("✂️ Dropping {} constraint(s) over the prompt budget", dropped.len());
+            warnings.push(format!("Prompt budget exceeded: {} constraint(s) left out of the prompts: {}", dropped.len(), dropped.join("; ")));
+        }
+
+        let product_budget = budget.max_input_tokens - kept.iter().map(|c| estimate_tokens(c)).sum::<usize>();
+        let product_tokens = estimate_tokens(product);
+        let mut condensed = None;
+        if product_tokens > product_budget {
+            let (text, how) = match self.summarize_product(product, product_budget).await {
+                Some(summary) => (truncate_to_tokens(&summary, product_budget), "summarized"),
+                None => (truncate_to_tokens(product, product_budget), "truncated"),
+            };
+            info!("✂️ Product description ~{} tokens {} to ~{} for prompts", product_tokens, how, estimate_tokens(&text));
+            warnings.push(format!("Prompt budget exceeded: the product description (~{} tokens) was {} to ~{} tokens for generation", product_tokens, how, estimate_tokens(&text)));
+            condensed = Some(text);
+        }
+
+        let inputs = (!warnings.is_empty()).then(|| PromptInputs {
+            product: condensed.unwrap_or_else(|| product.to_string()),
+            constraints: kept,
+        });
+        FittedInputs { inputs, warnings }
+    }
+
+    async fn summarize_product(&self, product: &str, max_tokens: usize) -> Option<String> {
+        if !self.has_text_model() {
+            return None;
+        }
+        let words = max_tokens * 3 / 4;
+        let prompt = format!(
+            "Summarize this product description in at most {words} words for an image and text generator. Keep the product type, \
+            materials, components, manufacturing and packaging details and anything sustainability-related; drop marketing language. \
+            Reply with the summary only.\n\n{product}"
+        );
+        match self.generate_text_candidates(&prompt, 1).await {
+            Ok(texts) => texts.into_iter().next().filter(|t| !t.is_empty()),
+            Err(e) => {
+                warn!("⚠️ Summarizing an overlong product description failed, truncating instead: {}", e);
+                None
+            }
+        }
+    }
+}
@@ -0,0 +1,49 @@
+use async_trait::async_trait;
+
+use crate::{gemini::{GeminiClient, GeminiError, StageContext, PROVIDER}, models::{Resolution, StageImage}, vision::CheckedImage};
+
+/// A backend that can illustrate and describe lifecycle stages. Handlers generate stages only through this
+/// trait so the provider can be swapped (or mocked) without touching the routes.
+#[async_trait]
+pub trait ImageGenerator: Send + Sync {
+    /// Short provider name used in logs, metrics and error details.
+    fn provider(&self) -> &'static str;
+
+    /// Generate a single image for `prompt` and return it base64-encoded.
+    async fn generate_image(&self, stage: &str, prompt: &str, resolution: Resolution) -> Result<String, GeminiError>;
+
+    /// Generate a single piece of text for `prompt`. Not called by any handler yet; providers and test doubles
+    /// implement it so text features can move behind the trait.
+    #[allow(dead_code)]
+    async fn generate_text(&self, prompt: &str) -> Result<String, GeminiError>;
+
+    /// Run the image pipeline for a stage: generation plus whichever post-checks the provider supports.
+    async fn generate_checked_image(&self, stage: &str, prompt: &str, resolution: Resolution) -> CheckedImage;
+
+    /// Produce a complete stage (image, description and checks). Never fails; failures are recorded on the stage.
+    async fn gen_stage_image(&self, ctx: StageContext<'_>) -> StageImage;
+}
+
+#[async_trait]
+impl ImageGenerator for GeminiClient {
+    fn provider(&self) -> &'static str { PROVIDER }
+
+    async fn generate_image(&self, stage: &str, prompt: &str, resolution: Resolution) -> Result<String, GeminiError> {
+        GeminiClient::generate_image(self, stage, prompt, resolution).await
+    }
+
+    async fn generate_text(&self, prompt: &str) -> Result<String, GeminiError> {
+        self.generate_text_candidates(prompt, 1).await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| GeminiError::Other("No text content found in response".to_string()))
+    }
+
+    async fn generate_checked_image(&self, stage: &str, prompt: &str, resolution: Resolution) -> CheckedImage {
+        GeminiClient::generate_checked_image(self, stage, prompt, resolution).await
+    }
+
+    async fn gen_stage_image(&self, ctx: StageContext<'_>) -> StageImage {
+        GeminiClient::gen_stage_image(self, ctx).await
+    }
+}
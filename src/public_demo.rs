@@ -0,0 +1,85 @@
+use axum::{extract::{ConnectInfo, Request, State}, http::{Method, StatusCode}, middleware::Next, response::Response};
+use chrono::{Duration, Utc};
+use std::net::SocketAddr;
+use tracing::{info, warn};
+
+use crate::{error::ApiError, rate_limit::{too_many_requests, Buckets}, routes::AppState};
+
+/// `DEMO_PUBLIC=true`: limits for hosting a public playground without runaway costs.
+pub struct PublicDemo {
+    /// `DEMO_RATE_LIMIT_PER_MIN` (default 10): mutating requests per client IP per minute.
+    pub requests_per_minute: u32,
+    /// `DEMO_MAX_STAGES` (default 5): stage lists are truncated to this length.
+    pub max_stages: usize,
+    /// `DEMO_MAX_IMAGE_PX` (default 512): generated images are downscaled to fit this box.
+    pub max_image_px: u32,
+    /// `DEMO_TTL_MINUTES` (default 60): lifecycles are deleted this long after creation.
+    pub ttl_minutes: i64,
+    /// Per client IP, refilled over a minute.
+    buckets: Buckets,
+}
+
+impl PublicDemo {
+    pub fn from_env() -> Option<Self> {
+        if !std::env::var("DEMO_PUBLIC").map(|v| v == "true" || v == "1").unwrap_or(false) {
+            return None;
+        }
+        let read = |name: &str, default: u64| std::env::var(name).ok().and_then(|v| v.parse().ok()).unwrap_or(default);
+        let requests_per_minute = read("DEMO_RATE_LIMIT_PER_MIN", 10).max(1) as u32;
+        let demo = Self {
+            requests_per_minute,
+            max_stages: read("DEMO_MAX_STAGES", 5).max(1) as usize,
+            max_image_px: read("DEMO_MAX_IMAGE_PX", 512).max(64) as u32,
+            ttl_minutes: read("DEMO_TTL_MINUTES", 60).max(1) as i64,
+            buckets: Buckets::new(requests_per_minute, std::time::Duration::from_secs(60)),
+        };
+        info!("🌐 Public demo mode: {} req/min per IP, {} stages max, {}px images, {} min TTL",
+            demo.requests_per_minute, demo.max_stages, demo.max_image_px, demo.ttl_minutes);
+        Some(demo)
+    }
+}
+
+/// Rate-limit everything except reads, with `Retry-After`. Only active in public demo mode.
+pub async fn rate_limit(State(state): State<AppState>, ConnectInfo(addr): ConnectInfo<SocketAddr>, req: Request, next: Next) -> Response {
+    if let Some(demo) = &state.public_demo {
+        if req.method() != Method::GET && req.method() != Method::OPTIONS {
+            if let Err(retry_after) = demo.buckets.take(&addr.ip().to_string()) {
+                warn!("🚦 Rate limit exceeded for {}", addr.ip());
+                let error = ApiError::new(StatusCode::TOO_MANY_REQUESTS, "rate_limited", format!("Public demo limit of {} changes per minute reached; retry in {}s", demo.requests_per_minute, retry_after))
+                    .with_details(serde_json::json!({ "scope": "public_demo", "limit": demo.requests_per_minute, "retry_after_secs": retry_after }));
+                return too_many_requests(error, retry_after);
+            }
+        }
+    }
+    next.run(req).await
+}
+
+/// Background task deleting lifecycles older than the demo TTL.
+pub fn spawn_expiry_task(state: AppState) {
+    let Some(demo) = state.public_demo.clone() else { return };
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(std::time::Duration::from_secs(60));
+        loop {
+            ticker.tick().await;
+            let cutoff = Utc::now() - Duration::minutes(demo.ttl_minutes);
+            let all = match state.repo.list().await {
+                Ok(all) => all,
+                Err(e) => {
+                    warn!("⚠️ Demo expiry could not list lifecycles: {}", e);
+                    continue;
+                }
+            };
+            let mut expired = 0;
+            for l in all.iter().filter(|l| l.created_at < cutoff) {
+                if let Ok(true) = state.repo.delete(l.id).await {
+                    state.blobs.remove_lifecycle(l.id).await;
+                    state.audit.record("public_demo", "demo.expire", Some(l.id), format!("older than {} minutes", demo.ttl_minutes));
+                    expired += 1;
+                }
+            }
+            if expired > 0 {
+                info!("⌛ Expired {} demo lifecycle(s)", expired);
+            }
+        }
+    });
+}
@@ -0,0 +1,97 @@
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::{critique::UNSUPPORTED_CLAIM_PREFIX, glossary::BANNED_PHRASE_PREFIX, models::{Lifecycle, StageImage, StageStatus}};
+
+/// One item of the publishing checklist, evaluated for every stage.
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QualityCheckKind {
+    /// The stage has an image at all.
+    Images,
+    /// The image is a generation, not the SVG placeholder.
+    GeneratedImages,
+    /// The description is generated text without open reviewer flags (fallback text, unsupported claims,
+    /// banned phrases, image mismatches).
+    ReviewedDescriptions,
+    AltText,
+    /// An impact estimate exists (`POST /economics`).
+    Impacts,
+}
+
+impl QualityCheckKind {
+    const ALL: [QualityCheckKind; 5] = [Self::Images, Self::GeneratedImages, Self::ReviewedDescriptions, Self::AltText, Self::Impacts];
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Images => "Every stage has an image",
+            Self::GeneratedImages => "No placeholder images",
+            Self::ReviewedDescriptions => "Descriptions generated and free of review flags",
+            Self::AltText => "Every stage has alt text",
+            Self::Impacts => "Every stage has an impact estimate",
+        }
+    }
+
+    fn passes(self, stage: &StageImage) -> bool {
+        match self {
+            Self::Images => stage.has_image(),
+            Self::GeneratedImages => stage.has_image() && !stage.is_placeholder(),
+            Self::ReviewedDescriptions => {
+                stage.status == StageStatus::Complete
+                    && !stage.description_fallback
+                    && !stage.warnings.iter().any(|w| w.starts_with(UNSUPPORTED_CLAIM_PREFIX) || w.starts_with(BANNED_PHRASE_PREFIX))
+                    && stage.consistency.as_ref().is_none_or(|c| c.consistent)
+            }
+            Self::AltText => stage.alt_text.as_deref().is_some_and(|alt| !alt.trim().is_empty()),
+            Self::Impacts => stage.economics.is_some(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct FailingStage {
+    pub stage_index: usize,
+    pub stage_name: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ChecklistItem {
+    pub check: QualityCheckKind,
+    pub label: &'static str,
+    pub passed: bool,
+    pub failing_stages: Vec<FailingStage>,
+}
+
+/// `GET /api/lifecycle/{id}/quality`: what stands between the lifecycle and publishing.
+#[derive(Debug, Serialize)]
+pub struct QualityReport {
+    pub lifecycle_id: Uuid,
+    pub revision: u64,
+    /// Share of passed stage checks (every check on every stage), 0-100.
+    pub completeness_pct: u8,
+    /// Every checklist item passed; clients offer the `published` review status only then.
+    pub ready_to_publish: bool,
+    pub checklist: Vec<ChecklistItem>,
+}
+
+impl QualityReport {
+    pub fn for_lifecycle(lifecycle: &Lifecycle) -> Self {
+        let checklist: Vec<ChecklistItem> = QualityCheckKind::ALL.into_iter().map(|check| {
+            let failing_stages: Vec<FailingStage> = lifecycle.stages.iter().enumerate()
+                .filter(|(_, stage)| !check.passes(stage))
+                .map(|(stage_index, stage)| FailingStage { stage_index, stage_name: stage.stage_name.clone() })
+                .collect();
+            ChecklistItem { check, label: check.label(), passed: failing_stages.is_empty(), failing_stages }
+        }).collect();
+        let total = checklist.len() * lifecycle.stages.len();
+        let failed: usize = checklist.iter().map(|item| item.failing_stages.len()).sum();
+        let completeness_pct = ((total - failed) * 100).checked_div(total).unwrap_or(0) as u8;
+        Self {
+            lifecycle_id: lifecycle.id,
+            revision: lifecycle.revision,
+            completeness_pct,
+            ready_to_publish: total > 0 && failed == 0,
+            checklist,
+        }
+    }
+}
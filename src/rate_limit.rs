@@ -0,0 +1,162 @@
+use axum::{body::{to_bytes, Body}, extract::{ConnectInfo, Request, State}, http::{header, HeaderValue, Method, StatusCode}, middleware::Next, response::{IntoResponse, Response}};
+use parking_lot::Mutex;
+use serde_json::Value;
+use std::{collections::HashMap, net::SocketAddr, time::{Duration, Instant}};
+use tracing::{info, warn};
+
+use crate::{error::ApiError, routes::AppState, workspaces};
+
+/// Token bucket per client: holds up to `capacity` tokens, refilled evenly over `period`, one taken per request.
+pub struct Buckets {
+    capacity: f64,
+    period: Duration,
+    /// Tokens left and when they were counted.
+    clients: Mutex<HashMap<String, (f64, Instant)>>,
+}
+
+impl Buckets {
+    pub fn new(capacity: u32, period: Duration) -> Self {
+        Self { capacity: capacity as f64, period, clients: Mutex::default() }
+    }
+
+    fn per_sec(&self) -> f64 {
+        self.capacity / self.period.as_secs_f64()
+    }
+
+    /// Take a token for `client`, or the whole seconds until one is back.
+    pub fn take(&self, client: &str) -> Result<(), u64> {
+        let now = Instant::now();
+        let mut clients = self.clients.lock();
+        // Buckets that have filled up again are the same as new ones; drop them so the map doesn't grow with every client.
+        clients.retain(|_, (tokens, at)| *tokens + now.duration_since(*at).as_secs_f64() * self.per_sec() < self.capacity);
+        let (tokens, at) = clients.entry(client.to_string()).or_insert((self.capacity, now));
+        *tokens = (*tokens + now.duration_since(*at).as_secs_f64() * self.per_sec()).min(self.capacity);
+        *at = now;
+        if *tokens >= 1.0 {
+            *tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(((1.0 - *tokens) / self.per_sec()).ceil().max(1.0) as u64)
+        }
+    }
+
+    /// Give back a token taken for a request that was turned away after all.
+    fn refund(&self, client: &str) {
+        if let Some((tokens, _)) = self.clients.lock().get_mut(client) {
+            *tokens = (*tokens + 1.0).min(self.capacity);
+        }
+    }
+}
+
+/// Per-client limits protecting the provider quota from a single runaway frontend. Clients are told apart by their
+/// workspace API key where they send a valid one, else by IP.
+pub struct RateLimits {
+    /// `RATE_LIMIT_PER_MIN`: requests of any kind per minute (probes excepted). Unset or 0: unlimited.
+    requests: Option<Buckets>,
+    /// `GENERATION_LIMIT_PER_HOUR`: requests that call the AI provider, per hour. Unset or 0: unlimited.
+    generations: Option<Buckets>,
+}
+
+impl RateLimits {
+    pub fn from_env() -> Self {
+        let read = |name: &str| std::env::var(name).ok().and_then(|v| v.parse::<u32>().ok()).filter(|v| *v > 0);
+        let (per_min, per_hour) = (read("RATE_LIMIT_PER_MIN"), read("GENERATION_LIMIT_PER_HOUR"));
+        if per_min.is_some() || per_hour.is_some() {
+            let describe = |limit: Option<u32>| limit.map_or("unlimited".to_string(), |l| l.to_string());
+            info!("🚦 Rate limits per client: {} requests/min, {} generations/hour", describe(per_min), describe(per_hour));
+        }
+        Self {
+            requests: per_min.map(|l| Buckets::new(l, Duration::from_secs(60))),
+            generations: per_hour.map(|l| Buckets::new(l, Duration::from_secs(3600))),
+        }
+    }
+}
+
+/// Description edits and lifecycle PATCHes are read up to this size to tell whether they call the provider; both
+/// are small JSON bodies.
+const INSPECTED_BODY_LIMIT: usize = 1024 * 1024;
+
+enum ProviderCalls {
+    Never,
+    Always,
+    /// Only when the JSON body sets this field (a non-blank string or `true`).
+    WhenBodySets(&'static str),
+}
+
+fn provider_calls(method: &Method, path: &str) -> ProviderCalls {
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    match (method, segments.as_slice()) {
+        (&Method::POST, ["api", "lifecycle"] | ["api", "lifecycle", "stream"]) => ProviderCalls::Always,
+        (&Method::POST, ["api", "lifecycle", _, "stage"] | ["api", "lifecycle", _, "stage", _]
+            | ["api", "lifecycle", _, "stages", "generate"] | ["api", "lifecycle", _, "narratives" | "economics"]
+            | ["api", "lifecycle", _, "stage", _, "regenerate-to-match" | "explain" | "consistency" | "terms"]) => ProviderCalls::Always,
+        (&Method::PUT, ["api", "lifecycle", _, "stage", _, "description"]) => ProviderCalls::WhenBodySets("rewrite_instruction"),
+        (&Method::PATCH, ["api", "lifecycle", _]) => ProviderCalls::WhenBodySets("regenerate_affected"),
+        _ => ProviderCalls::Never,
+    }
+}
+
+/// Whether `request` calls the AI provider: full, streamed and single-stage generations, regenerations, analysis
+/// passes, description rewrites and lifecycle PATCHes queueing regeneration. Bodies needed to tell are buffered
+/// and put back, so the request is handed back either way.
+pub async fn is_generation(request: Request) -> Result<(Request, bool), ApiError> {
+    let field = match provider_calls(request.method(), request.uri().path()) {
+        ProviderCalls::Never => return Ok((request, false)),
+        ProviderCalls::Always => return Ok((request, true)),
+        ProviderCalls::WhenBodySets(field) => field,
+    };
+    let (parts, body) = request.into_parts();
+    let bytes = to_bytes(body, INSPECTED_BODY_LIMIT).await.map_err(|_| ApiError::from(StatusCode::PAYLOAD_TOO_LARGE))?;
+    // Malformed bodies don't count; the handler rejects them without calling the provider.
+    let sets = match serde_json::from_slice::<Value>(&bytes).ok().as_ref().and_then(|body| body.get(field)) {
+        Some(Value::String(s)) => !s.trim().is_empty(),
+        Some(Value::Bool(b)) => *b,
+        _ => false,
+    };
+    Ok((Request::from_parts(parts, Body::from(bytes)), sets))
+}
+
+/// 429 for `error` with `Retry-After: retry_after`.
+pub fn too_many_requests(error: ApiError, retry_after: u64) -> Response {
+    let mut response = error.into_response();
+    response.headers_mut().insert(header::RETRY_AFTER, HeaderValue::from(retry_after));
+    response
+}
+
+/// 429 with `Retry-After` once a client has used up its requests per minute or generations per hour. A request
+/// turned away by one limit doesn't count against the other.
+pub async fn limit(State(state): State<AppState>, ConnectInfo(addr): ConnectInfo<SocketAddr>, request: Request, next: Next) -> Response {
+    let limits = &state.rate_limits;
+    if (limits.requests.is_none() && limits.generations.is_none()) || request.method() == Method::OPTIONS || matches!(request.uri().path(), "/api/health" | "/readyz" | "/metrics") {
+        return next.run(request).await;
+    }
+    let client = match workspaces::presented_key(&request).and_then(|key| state.workspaces.find(key)) {
+        Some(workspace) => format!("key {}:{}", workspace.id, workspace.owner),
+        None => format!("ip {}", addr.ip()),
+    };
+    let (request, generation) = if limits.generations.is_some() {
+        match is_generation(request).await {
+            Ok(classified) => classified,
+            Err(error) => return error.into_response(),
+        }
+    } else {
+        (request, false)
+    };
+    let generations = limits.generations.as_ref().filter(|_| generation);
+    let mut taken: Vec<&Buckets> = Vec::new();
+    for (buckets, scope, unit) in [(limits.requests.as_ref(), "requests", "requests per minute"), (generations, "generations", "generations per hour")] {
+        let Some(buckets) = buckets else { continue };
+        if let Err(retry_after) = buckets.take(&client) {
+            for taken in &taken {
+                taken.refund(&client);
+            }
+            warn!("🚦 Rate limit ({}) exceeded by {} on {} {}", scope, client, request.method(), request.uri().path());
+            let limit = buckets.capacity as u32;
+            let error = ApiError::new(StatusCode::TOO_MANY_REQUESTS, "rate_limited", format!("Limit of {} {} reached; retry in {}s", limit, unit, retry_after))
+                .with_details(serde_json::json!({ "scope": scope, "limit": limit, "retry_after_secs": retry_after }));
+            return too_many_requests(error, retry_after);
+        }
+        taken.push(buckets);
+    }
+    next.run(request).await
+}
@@ -0,0 +1,311 @@
+use async_trait::async_trait;
+use parking_lot::RwLock;
+use sqlx::{postgres::PgPoolOptions, sqlite::{SqliteConnectOptions, SqlitePoolOptions}, types::Json, PgPool, Row, SqlitePool};
+use std::{collections::HashMap, str::FromStr, sync::Arc, time::{Duration, Instant}};
+use thiserror::Error;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::{metrics::STORE_LOCK_WAIT, models::Lifecycle};
+
+#[derive(Debug, Error)]
+pub enum StoreError {
+    #[error("database error: {0}")]
+    Database(String),
+    #[error("stored lifecycle {id} is unreadable: {reason}")]
+    Corrupt { id: String, reason: String },
+}
+
+impl From<sqlx::Error> for StoreError {
+    fn from(e: sqlx::Error) -> Self { StoreError::Database(e.to_string()) }
+}
+
+/// Where lifecycles live. Handlers only go through this trait so the backend can be swapped via `DATABASE_URL`.
+#[async_trait]
+pub trait LifecycleRepository: Send + Sync {
+    async fn insert(&self, lifecycle: &Lifecycle) -> Result<(), StoreError>;
+
+    async fn get(&self, id: Uuid) -> Result<Option<Lifecycle>, StoreError>;
+
+    /// Atomically load, modify and store a lifecycle. `f` returns whether it changed anything; nothing is
+    /// written (and no partial change is kept) when it returns `false`. Returns `false` if `id` doesn't exist.
+    async fn update(&self, id: Uuid, f: &mut (dyn for<'l> FnMut(&'l mut Lifecycle) -> bool + Send)) -> Result<bool, StoreError>;
+
+    /// Returns whether anything was deleted.
+    async fn delete(&self, id: Uuid) -> Result<bool, StoreError>;
+
+    /// Every stored lifecycle, newest first.
+    async fn list(&self) -> Result<Vec<Lifecycle>, StoreError>;
+
+    /// Make every completed write durable and release the backend; called once on shutdown, after the last write.
+    async fn flush(&self) -> Result<(), StoreError> {
+        Ok(())
+    }
+}
+
+/// Records how long store operations wait for their lock in `store_lock_wait_seconds`, and logs waits longer than
+/// `STORE_LOCK_WARN_MS` (default 100), so slow responses can be attributed to contention rather than the provider.
+struct LockTimer {
+    backend: &'static str,
+    warn_after: Duration,
+}
+
+impl LockTimer {
+    fn from_env(backend: &'static str) -> Self {
+        let warn_ms = std::env::var("STORE_LOCK_WARN_MS").ok().and_then(|v| v.parse().ok()).unwrap_or(100);
+        Self { backend, warn_after: Duration::from_millis(warn_ms) }
+    }
+
+    fn record(&self, operation: &str, started: Instant) {
+        let waited = started.elapsed();
+        STORE_LOCK_WAIT.observe(&[self.backend, operation], waited);
+        if waited > self.warn_after {
+            warn!("🐢 {} store {} waited {:?} for its lock", self.backend, operation, waited);
+        }
+    }
+
+    fn acquire<G>(&self, operation: &str, lock: impl FnOnce() -> G) -> G {
+        let started = Instant::now();
+        let guard = lock();
+        self.record(operation, started);
+        guard
+    }
+
+    async fn acquire_async<G>(&self, operation: &str, lock: impl std::future::Future<Output = G>) -> G {
+        let started = Instant::now();
+        let guard = lock.await;
+        self.record(operation, started);
+        guard
+    }
+}
+
+/// Picks the backend from `DATABASE_URL`: `sqlite:` URLs use SQLite, `postgres:`/`postgresql:` URLs use Postgres,
+/// anything else (or unset) keeps lifecycles in memory.
+pub async fn from_env() -> Arc<dyn LifecycleRepository> {
+    match std::env::var("DATABASE_URL") {
+        Ok(url) if url.starts_with("sqlite:") => match SqliteRepository::connect(&url).await {
+            Ok(repo) => {
+                info!("💾 Storing lifecycles in SQLite ({})", url);
+                Arc::new(repo)
+            }
+            Err(e) => panic!("could not open SQLite database {}: {}", url, e),
+        },
+        Ok(url) if url.starts_with("postgres:") || url.starts_with("postgresql:") => match PostgresRepository::connect(&url).await {
+            Ok(repo) => {
+                info!("💾 Storing lifecycles in Postgres");
+                Arc::new(repo)
+            }
+            Err(e) => panic!("could not connect to Postgres: {}", e),
+        },
+        Ok(url) => {
+            warn!("⚠️ Unsupported DATABASE_URL scheme in {}; keeping lifecycles in memory", url);
+            Arc::new(MemoryRepository::new())
+        }
+        Err(_) => Arc::new(MemoryRepository::new()),
+    }
+}
+
+/// Process-local store; everything is lost on restart. Used for demos and when no database is configured.
+pub struct MemoryRepository {
+    lifecycles: RwLock<HashMap<Uuid, Lifecycle>>,
+    lock_timer: LockTimer,
+}
+
+impl MemoryRepository {
+    pub fn new() -> Self {
+        Self { lifecycles: RwLock::default(), lock_timer: LockTimer::from_env("memory") }
+    }
+}
+
+#[async_trait]
+impl LifecycleRepository for MemoryRepository {
+    async fn insert(&self, lifecycle: &Lifecycle) -> Result<(), StoreError> {
+        self.lock_timer.acquire("insert", || self.lifecycles.write()).insert(lifecycle.id, lifecycle.clone());
+        Ok(())
+    }
+
+    async fn get(&self, id: Uuid) -> Result<Option<Lifecycle>, StoreError> {
+        Ok(self.lock_timer.acquire("get", || self.lifecycles.read()).get(&id).cloned())
+    }
+
+    async fn update(&self, id: Uuid, f: &mut (dyn for<'l> FnMut(&'l mut Lifecycle) -> bool + Send)) -> Result<bool, StoreError> {
+        let mut guard = self.lock_timer.acquire("update", || self.lifecycles.write());
+        let Some(stored) = guard.get_mut(&id) else { return Ok(false) };
+        let mut draft = stored.clone();
+        if f(&mut draft) {
+            *stored = draft;
+        }
+        Ok(true)
+    }
+
+    async fn delete(&self, id: Uuid) -> Result<bool, StoreError> {
+        Ok(self.lock_timer.acquire("delete", || self.lifecycles.write()).remove(&id).is_some())
+    }
+
+    async fn list(&self) -> Result<Vec<Lifecycle>, StoreError> {
+        let mut all: Vec<Lifecycle> = self.lock_timer.acquire("list", || self.lifecycles.read()).values().cloned().collect();
+        all.sort_by(|a, b| b.created_at.cmp(&a.created_at).then(a.id.cmp(&b.id)));
+        Ok(all)
+    }
+
+    async fn flush(&self) -> Result<(), StoreError> {
+        let count = self.lifecycles.read().len();
+        if count > 0 {
+            warn!("🧠 {} lifecycle(s) were only held in memory and are gone after exit; set DATABASE_URL to keep them", count);
+        }
+        Ok(())
+    }
+}
+
+/// Lifecycles as JSON documents in a single SQLite table. Writes are serialised in-process so read-modify-write
+/// updates from concurrent stage generations never overwrite each other.
+pub struct SqliteRepository {
+    pool: SqlitePool,
+    write_lock: tokio::sync::Mutex<()>,
+    lock_timer: LockTimer,
+}
+
+impl SqliteRepository {
+    pub async fn connect(url: &str) -> Result<Self, StoreError> {
+        let options = SqliteConnectOptions::from_str(url)?.create_if_missing(true);
+        let pool = SqlitePoolOptions::new().max_connections(8).connect_with(options).await?;
+        sqlx::migrate!("./migrations/sqlite").run(&pool).await.map_err(|e| StoreError::Database(e.to_string()))?;
+        Ok(Self { pool, write_lock: tokio::sync::Mutex::new(()), lock_timer: LockTimer::from_env("sqlite") })
+    }
+
+    async fn write(&self, lifecycle: &Lifecycle) -> Result<(), StoreError> {
+        let data = serde_json::to_string(lifecycle).map_err(|e| StoreError::Corrupt { id: lifecycle.id.to_string(), reason: e.to_string() })?;
+        sqlx::query(
+            "INSERT INTO lifecycles (id, created_at, updated_at, data) VALUES (?, ?, ?, ?) \
+             ON CONFLICT(id) DO UPDATE SET updated_at = excluded.updated_at, data = excluded.data"
+        )
+            .bind(lifecycle.id.to_string())
+            .bind(lifecycle.created_at.to_rfc3339())
+            .bind(lifecycle.updated_at.to_rfc3339())
+            .bind(data)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+}
+
+fn decode(id: &str, data: &str) -> Result<Lifecycle, StoreError> {
+    serde_json::from_str(data).map_err(|e| StoreError::Corrupt { id: id.to_string(), reason: e.to_string() })
+}
+
+#[async_trait]
+impl LifecycleRepository for SqliteRepository {
+    async fn insert(&self, lifecycle: &Lifecycle) -> Result<(), StoreError> {
+        let _guard = self.lock_timer.acquire_async("insert", self.write_lock.lock()).await;
+        self.write(lifecycle).await
+    }
+
+    async fn get(&self, id: Uuid) -> Result<Option<Lifecycle>, StoreError> {
+        let id = id.to_string();
+        let row = sqlx::query("SELECT data FROM lifecycles WHERE id = ?").bind(&id).fetch_optional(&self.pool).await?;
+        row.map(|r| decode(&id, r.get("data"))).transpose()
+    }
+
+    async fn update(&self, id: Uuid, f: &mut (dyn for<'l> FnMut(&'l mut Lifecycle) -> bool + Send)) -> Result<bool, StoreError> {
+        let _guard = self.lock_timer.acquire_async("update", self.write_lock.lock()).await;
+        let Some(mut lifecycle) = self.get(id).await? else { return Ok(false) };
+        if f(&mut lifecycle) {
+            self.write(&lifecycle).await?;
+        }
+        Ok(true)
+    }
+
+    async fn delete(&self, id: Uuid) -> Result<bool, StoreError> {
+        let _guard = self.lock_timer.acquire_async("delete", self.write_lock.lock()).await;
+        let result = sqlx::query("DELETE FROM lifecycles WHERE id = ?").bind(id.to_string()).execute(&self.pool).await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn list(&self) -> Result<Vec<Lifecycle>, StoreError> {
+        let rows = sqlx::query("SELECT id, data FROM lifecycles ORDER BY created_at DESC, id ASC").fetch_all(&self.pool).await?;
+        rows.iter().map(|r| decode(r.get("id"), r.get("data"))).collect()
+    }
+
+    async fn flush(&self) -> Result<(), StoreError> {
+        // Under the write lock so no write is cut off; the checkpoint folds a WAL journal back into the database file.
+        let _guard = self.lock_timer.acquire_async("flush", self.write_lock.lock()).await;
+        sqlx::query("PRAGMA wal_checkpoint(TRUNCATE)").execute(&self.pool).await?;
+        self.pool.close().await;
+        Ok(())
+    }
+}
+
+/// Lifecycles as JSONB documents in Postgres, shared by every instance behind the load balancer. Updates take a
+/// row lock (`SELECT ... FOR UPDATE`) so concurrent writers on different nodes are serialised per lifecycle.
+pub struct PostgresRepository {
+    pool: PgPool,
+    lock_timer: LockTimer,
+}
+
+impl PostgresRepository {
+    /// Pool size comes from `DATABASE_MAX_CONNECTIONS` (default 10).
+    pub async fn connect(url: &str) -> Result<Self, StoreError> {
+        let max_connections = std::env::var("DATABASE_MAX_CONNECTIONS").ok().and_then(|v| v.parse().ok()).unwrap_or(10);
+        let pool = PgPoolOptions::new()
+            .max_connections(max_connections)
+            .acquire_timeout(std::time::Duration::from_secs(10))
+            .connect(url)
+            .await?;
+        sqlx::migrate!("./migrations/postgres").run(&pool).await.map_err(|e| StoreError::Database(e.to_string()))?;
+        Ok(Self { pool, lock_timer: LockTimer::from_env("postgres") })
+    }
+}
+
+#[async_trait]
+impl LifecycleRepository for PostgresRepository {
+    async fn insert(&self, lifecycle: &Lifecycle) -> Result<(), StoreError> {
+        sqlx::query("INSERT INTO lifecycles (id, created_at, updated_at, data) VALUES ($1, $2, $3, $4)")
+            .bind(lifecycle.id)
+            .bind(lifecycle.created_at)
+            .bind(lifecycle.updated_at)
+            .bind(Json(lifecycle))
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn get(&self, id: Uuid) -> Result<Option<Lifecycle>, StoreError> {
+        let row: Option<(Json<Lifecycle>,)> = sqlx::query_as("SELECT data FROM lifecycles WHERE id = $1").bind(id).fetch_optional(&self.pool).await?;
+        Ok(row.map(|(Json(l),)| l))
+    }
+
+    async fn update(&self, id: Uuid, f: &mut (dyn for<'l> FnMut(&'l mut Lifecycle) -> bool + Send)) -> Result<bool, StoreError> {
+        // The row lock is only granted once the select returns; a pool connection has to be free first, too.
+        let started = Instant::now();
+        let mut tx = self.pool.begin().await?;
+        let row: Option<(Json<Lifecycle>,)> = sqlx::query_as("SELECT data FROM lifecycles WHERE id = $1 FOR UPDATE").bind(id).fetch_optional(&mut *tx).await?;
+        self.lock_timer.record("update", started);
+        let Some((Json(mut lifecycle),)) = row else { return Ok(false) };
+        if f(&mut lifecycle) {
+            sqlx::query("UPDATE lifecycles SET updated_at = $2, data = $3 WHERE id = $1")
+                .bind(id)
+                .bind(lifecycle.updated_at)
+                .bind(Json(&lifecycle))
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    async fn delete(&self, id: Uuid) -> Result<bool, StoreError> {
+        let result = sqlx::query("DELETE FROM lifecycles WHERE id = $1").bind(id).execute(&self.pool).await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn list(&self) -> Result<Vec<Lifecycle>, StoreError> {
+        let rows: Vec<(Json<Lifecycle>,)> = sqlx::query_as("SELECT data FROM lifecycles ORDER BY created_at DESC, id ASC").fetch_all(&self.pool).await?;
+        Ok(rows.into_iter().map(|(Json(l),)| l).collect())
+    }
+
+    async fn flush(&self) -> Result<(), StoreError> {
+        // Commits are durable already; closing waits for statements still running and ends the sessions cleanly.
+        self.pool.close().await;
+        Ok(())
+    }
+}
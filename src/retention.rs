@@ -0,0 +1,135 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, sync::Arc};
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::{audit::AuditLog, blobs::BlobStore, models::{Lifecycle, ReviewStatus}, repository::{LifecycleRepository, StoreError}};
+
+/// `retention` section of the deployment config. Lifecycles that match no rule are kept forever.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct RetentionConfig {
+    /// Run the background sweep at all.
+    pub enabled: bool,
+    /// Only report what would be deleted; nothing is removed.
+    pub dry_run: bool,
+    pub interval_minutes: u64,
+    /// Rules for lifecycles without a tenant, or whose tenant has no entry in `tenants`.
+    pub default: Vec<RetentionRule>,
+    /// Per-tenant rules; replace `default` entirely for that tenant.
+    pub tenants: HashMap<String, Vec<RetentionRule>>,
+}
+
+impl Default for RetentionConfig {
+    fn default() -> Self {
+        Self { enabled: false, dry_run: true, interval_minutes: 60, default: Vec::new(), tenants: HashMap::new() }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RetentionRule {
+    pub status: ReviewStatus,
+    /// Age since the last update after which a matching lifecycle is deleted.
+    pub max_age_days: i64,
+    /// Only delete lifecycles that were never exported.
+    #[serde(default)]
+    pub only_unexported: bool,
+}
+
+impl RetentionRule {
+    fn matches(&self, lifecycle: &Lifecycle, now: DateTime<Utc>) -> bool {
+        lifecycle.review_status == self.status
+            && now - lifecycle.updated_at > Duration::days(self.max_age_days)
+            && (!self.only_unexported || lifecycle.last_exported_at.is_none())
+    }
+
+    fn describe(&self) -> String {
+        let exported = if self.only_unexported { "unexported " } else { "" };
+        format!("{}{:?} older than {} days", exported, self.status, self.max_age_days).to_lowercase()
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct RetentionCandidate {
+    pub id: Uuid,
+    pub tenant: Option<String>,
+    pub product_description: String,
+    pub review_status: ReviewStatus,
+    pub updated_at: DateTime<Utc>,
+    pub rule: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RetentionReport {
+    pub dry_run: bool,
+    pub evaluated_at: DateTime<Utc>,
+    pub evaluated: usize,
+    /// Lifecycles deleted (or, in a dry run, that would be).
+    pub candidates: Vec<RetentionCandidate>,
+}
+
+impl RetentionConfig {
+    fn rules_for(&self, tenant: Option<&str>) -> &[RetentionRule] {
+        tenant.and_then(|t| self.tenants.get(t)).unwrap_or(&self.default)
+    }
+
+    /// Apply the policy to the store. With `dry_run` the store is left untouched; otherwise every deletion is
+    /// written to the audit log.
+    pub async fn sweep(&self, repo: &dyn LifecycleRepository, audit: &AuditLog, blobs: &BlobStore, dry_run: bool) -> Result<RetentionReport, StoreError> {
+        let now = Utc::now();
+        let all = repo.list().await?;
+        let evaluated = all.len();
+        let candidates: Vec<RetentionCandidate> = all.iter().filter_map(|l| {
+            let rule = self.rules_for(l.tenant.as_deref()).iter().find(|r| r.matches(l, now))?;
+            Some(RetentionCandidate {
+                id: l.id,
+                tenant: l.tenant.clone(),
+                product_description: l.product_description.clone(),
+                review_status: l.review_status,
+                updated_at: l.updated_at,
+                rule: rule.describe(),
+            })
+        }).collect();
+
+        if !dry_run {
+            for c in &candidates {
+                if repo.delete(c.id).await? {
+                    blobs.remove_lifecycle(c.id).await;
+                    audit.record("retention", "retention.delete", Some(c.id), format!("{} ({})", c.rule, c.tenant.as_deref().unwrap_or("no tenant")));
+                }
+            }
+        }
+        Ok(RetentionReport { dry_run, evaluated_at: now, evaluated, candidates })
+    }
+}
+
+/// Background task enforcing the retention policy every `interval_minutes`.
+pub fn spawn_retention_task(config: RetentionConfig, repo: Arc<dyn LifecycleRepository>, audit: Arc<AuditLog>, blobs: Arc<BlobStore>) {
+    if !config.enabled {
+        return;
+    }
+    info!("🧹 Retention sweep every {} min (dry run: {})", config.interval_minutes, config.dry_run);
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(std::time::Duration::from_secs(config.interval_minutes.max(1) * 60));
+        loop {
+            ticker.tick().await;
+            let report = match config.sweep(repo.as_ref(), &audit, &blobs, config.dry_run).await {
+                Ok(report) => report,
+                Err(e) => {
+                    error!("❌ Retention sweep failed: {}", e);
+                    continue;
+                }
+            };
+            if report.candidates.is_empty() {
+                continue;
+            }
+            let ids: Vec<String> = report.candidates.iter().map(|c| c.id.to_string()).collect();
+            if report.dry_run {
+                warn!("🧹 Retention dry run: {} lifecycle(s) would be deleted: {}", ids.len(), ids.join(", "));
+            } else {
+                info!("🧹 Retention deleted {} lifecycle(s): {}", ids.len(), ids.join(", "));
+            }
+        }
+    });
+}
@@ -0,0 +1,47 @@
+use rand::Rng;
+use std::time::Duration;
+
+use crate::metrics::parse_seconds;
+
+/// Retry settings for provider calls: jittered exponential backoff on network errors, 429 and 5xx responses.
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    /// `GEMINI_MAX_RETRIES` (default 2): retries after the first attempt; 0 disables retrying.
+    pub max_retries: u32,
+    /// `GEMINI_RETRY_BASE_MS` (default 500): backoff before the first retry, doubled for each further one.
+    pub base_delay: Duration,
+    /// `GEMINI_RETRY_MAX_MS` (default 8000): cap on any single wait. A `Retry-After` longer than this ends the
+    /// retries instead, so a request never hangs on a long provider cooldown.
+    pub max_delay: Duration,
+}
+
+impl RetryPolicy {
+    pub fn from_env() -> Self {
+        let read = |name: &str, default: u64| std::env::var(name).ok().and_then(|v| v.parse().ok()).unwrap_or(default);
+        Self {
+            max_retries: read("GEMINI_MAX_RETRIES", 2) as u32,
+            base_delay: Duration::from_millis(read("GEMINI_RETRY_BASE_MS", 500)),
+            max_delay: Duration::from_millis(read("GEMINI_RETRY_MAX_MS", 8000)),
+        }
+    }
+
+    pub fn is_retryable_status(status: u16) -> bool {
+        status == 429 || status >= 500
+    }
+
+    /// Wait before retry number `retry` (1-based), or `None` when retries are exhausted or the provider asked
+    /// for a longer pause than `max_delay`. `retry_after` (seconds) takes precedence over the backoff.
+    pub fn delay(&self, retry: u32, retry_after: Option<&str>) -> Option<Duration> {
+        if retry > self.max_retries {
+            return None;
+        }
+        if let Some(seconds) = retry_after.and_then(parse_seconds) {
+            // Out of `Duration`'s range (`inf`, `1e400`) is longer than any `max_delay`.
+            let wait = Duration::try_from_secs_f64(seconds.max(0.0)).ok();
+            return wait.filter(|wait| *wait <= self.max_delay);
+        }
+        // Full jitter: uniform in [0, min(max, base * 2^(retry-1))], so concurrent stages don't retry in lockstep.
+        let ceiling = self.base_delay.saturating_mul(1u32 << (retry - 1).min(16)).min(self.max_delay);
+        Some(ceiling.mul_f64(rand::thread_rng().gen_range(0.0..=1.0)))
+    }
+}
@@ -1,33 +1,244 @@
-use axum::{Json, extract::{Path, State}, http::StatusCode, response::{IntoResponse, Response}};
-use std::{collections::HashMap, sync::Arc};
-use parking_lot::RwLock;
+use axum::{Json, extract::{Path, Query, State, ws::{Message, WebSocket, WebSocketUpgrade}}, http::{HeaderMap, HeaderName, StatusCode}, response::{IntoResponse, Redirect, Response, sse::{Event, KeepAlive, Sse}}};
+use futures::{Stream, StreamExt};
+use tokio::sync::{broadcast::error::RecvError, mpsc::UnboundedSender};
+use serde::Deserialize;
+use std::{collections::{BTreeMap, HashMap, HashSet}, convert::Infallible, sync::Arc};
 use uuid::Uuid;
-use chrono::Utc;
+use chrono::{Datelike, Utc};
 
-use crate::{models::{GenerateRequest, Lifecycle, RegenerateRequest, StageImage}, gemini::GeminiClient, pdf::generate_pdf};
+use crate::{config::AppConfig, error::ApiError, models::{Audience, Resolution, BatchGenerateRequest, ExportQuery, ThemedExportRequest, MarkdownImages, GenerateResult, StageResult, RetryHint, HealthReport, ReadinessReport, TemplateVersions, ConsistencyCheck, SyncQuery, StageGenerateQuery, AssetUploadQuery, AssetDownloadLink, DownloadLinks, StageDownloadLinks, Completeness, GenerateRequest, PurgeQuery, PurgeResult, RestoreQuery, RestoreResult, RestoreLineError, StageAsset, ImageExplanation, Lifecycle, LifecycleSummary, NewActorRequest, RegenerateRequest, ReviewStatus, ReviewStatusRequest, SelectCandidateRequest, DescriptionEditRequest, RevisionSource, StagePatchRequest, LifecyclePatchRequest, LifecyclePatchResult, InvalidatedStage, NewStageRequest, StageOrderRequest, CloneRequest, ShareRequest, ShareLink, SharedLink, NarrativeRequest, StageFingerprint, ImageCandidate, StageImage, StageKeywords, StageRevisions, StageStatus, StageTerm, StageLocation, SupplyChainActor}, gemini::{sniff_mime_type, GeminiClient, GeminiError, StageContext}, pdf::generate_pdf, html::render_html, bundle::{build_bundle, build_zip, markdown_report, BundleAsset, Provenance}, export::{ExportProfile, ExportConfig}, theme::{ExportTheme, ThemeStyle}, failures::{self, FailureHeatmap, FailureLog, FailureRecord}, scheduler::{GenerationScheduler, Lane, SchedulerStats}, sprite::{build_sprite, SpriteIndex}, rules::apply_category_rules, critique::UNSUPPORTED_CLAIM_PREFIX, glossary::BANNED_PHRASE_PREFIX, maps::MapRenderer, audit::{AuditEntry, AuditLog}, retention::RetentionReport, public_demo::PublicDemo, repository::LifecycleRepository, keywords::{keyword_stats, mentions, normalize_keyword, KeywordKind, KeywordStats}, caching::Validators, images::{jpeg_thumbnail, sniff_image_bytes, stage_image_bytes}, image_files::{is_image_ref, ImageFiles}, jobs::{Job, JobState, Jobs}, exports::ExportJobs, share::{is_share_token, share_slug, share_token, short_id, token_digest}, usage, billing::{parse_month, BillingFormat, BillingReport}, events::{StageEvents, StagePart}, pagination::{paginate, Cursor, Page}, admin::AdminAuth, blobs::BlobStore, provider::ImageGenerator, signing::UrlSigner, templates::{template_stats, TemplateStats}, circuit::BreakerState, single_flight::SingleFlight, precondition::{self, version_mismatch}, quality::QualityReport, metrics::{PROVIDER_REQUEST_DURATION, STORE_LOCK_WAIT}, sync::{ClientMessage, LifecycleChange, LifecyclePatch, LifecyclePatches, SyncMessage}, collab::{describe_change, CollabEventKind, CollabHub}, workspaces::{self, WorkspaceKeys}, rate_limit::RateLimits, validation::Valid, shutdown::Shutdown};
+use image::RgbaImage;
 
 #[derive(Clone)]
 pub struct AppState {
-    pub store: Arc<RwLock<HashMap<Uuid, Lifecycle>>>,
+    pub repo: Arc<dyn LifecycleRepository>,
+    /// Stage generation goes through the provider trait so the backend can be swapped.
+    pub images: Arc<dyn ImageGenerator>,
+    /// Gemini-only analysis passes (vision checks, rewrites, term extraction, quota metrics).
     pub gemini: Arc<GeminiClient>,
+    pub failures: Arc<FailureLog>,
+    pub scheduler: Arc<GenerationScheduler>,
+    pub config: Arc<AppConfig>,
+    pub maps: Arc<MapRenderer>,
+    pub audit: Arc<AuditLog>,
+    pub public_demo: Option<Arc<PublicDemo>>,
+    pub events: Arc<StageEvents>,
+    pub admin: Arc<AdminAuth>,
+    pub blobs: Arc<BlobStore>,
+    pub signer: Arc<UrlSigner>,
+    pub patches: Arc<LifecyclePatches>,
+    /// Mutation and presence events for `GET /api/lifecycle/{id}/ws` viewers.
+    pub collab: Arc<CollabHub>,
+    pub workspaces: Arc<WorkspaceKeys>,
+    pub rate_limits: Arc<RateLimits>,
+    /// In-flight single-stage generations, keyed by lifecycle, stage index and resolution tier.
+    pub stage_generations: Arc<StageGenerations>,
+    /// `IMAGE_DIR` / `S3_BUCKET`: stage images stored by content hash rather than inline.
+    pub image_files: Option<Arc<ImageFiles>>,
+    pub jobs: Arc<Jobs>,
+    pub exports: Arc<ExportJobs>,
+    pub shutdown: Arc<Shutdown>,
+}
+
+/// A shared stage generation's outcome and the revision it stored.
+pub type StageGenerations = SingleFlight<(Uuid, usize, Resolution), (Result<Json<StageImage>, ApiError>, Option<u64>)>;
+
+/// Also fails with 409 when the request expects another revision (`If-Match` / `?version=`), so mutating
+/// handlers give up before any expensive work, and with 404 for another workspace's lifecycle.
+async fn load_lifecycle(state: &AppState, id: Uuid) -> Result<Lifecycle, ApiError> {
+    let lifecycle = state.repo.get(id).await?.filter(workspaces::can_access).ok_or_else(|| lifecycle_not_found(id))?;
+    match precondition::expected() {
+        Some(expected) if expected != lifecycle.revision => Err(version_mismatch(expected, lifecycle.revision)),
+        _ => Ok(lifecycle),
+    }
+}
+
+fn lifecycle_not_found(id: Uuid) -> ApiError {
+    ApiError::new(StatusCode::NOT_FOUND, "lifecycle_not_found", format!("No lifecycle with id {}", id))
+}
+
+/// Atomically apply `f` to a stored lifecycle: 404 if it doesn't exist or belongs to another workspace, 409 if the
+/// request expects another revision, and nothing is written when `f` fails. `f` already sees the new revision.
+async fn modify_lifecycle<T: Send>(state: &AppState, id: Uuid, f: impl FnOnce(&mut Lifecycle) -> Result<T, ApiError> + Send) -> Result<T, ApiError> {
+    let mut f = Some(f);
+    let mut outcome = None;
+    let mut patch = None;
+    let mut written = None;
+    let mut collab_events = None;
+    let expected = precondition::take_expected();
+    let found = state.repo.update(id, &mut |lifecycle| {
+        if !workspaces::can_access(lifecycle) {
+            return false;
+        }
+        if let Some(expected) = expected.filter(|expected| *expected != lifecycle.revision) {
+            outcome = Some(Err(version_mismatch(expected, lifecycle.revision)));
+            return false;
+        }
+        let Some(f) = f.take() else { return false };
+        let before = if state.patches.has_subscribers() { serde_json::to_value(&*lifecycle).ok() } else { None };
+        let stages_before: Vec<StageFingerprint> = lifecycle.stages.iter_mut().map(StageFingerprint::of).collect();
+        // Bumped up front so what `f` returns carries it; discarded along with everything else if `f` fails.
+        lifecycle.revision += 1;
+        let result = f(lifecycle);
+        let changed = result.is_ok();
+        if changed {
+            written = Some(lifecycle.revision);
+            lifecycle.stamp_stage_revisions(&stages_before);
+            patch = before.and_then(|before| LifecyclePatch::between(&before, lifecycle));
+            if state.collab.has_subscribers() {
+                collab_events = Some(describe_change(&stages_before, lifecycle));
+            }
+        }
+        outcome = Some(result);
+        changed
+    }).await?;
+    if !found {
+        return Err(lifecycle_not_found(id));
+    }
+    if let Some(revision) = written {
+        precondition::record_written(revision);
+    }
+    if let Some(patch) = patch {
+        state.patches.publish(LifecycleChange::Patched(patch));
+    }
+    if let (Some(revision), Some(events)) = (written, collab_events) {
+        state.collab.publish(id, Some(revision), events);
+    }
+    outcome.unwrap_or_else(|| Err(lifecycle_not_found(id)))
+}
+
+/// Note a download in `last_exported_at` (retention's `only_unexported` reads it). Not an edit: the revision stays,
+/// so readers don't make editors' `If-Match` stale, and no sync patch or collab event goes out. A failed write
+/// doesn't fail the download.
+async fn note_export(state: &AppState, id: Uuid) {
+    let noted = state.repo.update(id, &mut |lifecycle| {
+        if !workspaces::can_access(lifecycle) {
+            return false;
+        }
+        lifecycle.last_exported_at = Some(Utc::now());
+        true
+    }).await;
+    if let Err(e) = noted {
+        tracing::warn!("⚠️ Could not record the export of {}: {}", id, e);
+    }
+}
+
+fn stage_not_found(stage_index: usize) -> ApiError {
+    ApiError::new(StatusCode::NOT_FOUND, "stage_not_found", format!("No stage at index {}", stage_index))
+}
+
+fn image_status(image: &Result<String, GeminiError>) -> StageStatus {
+    match image {
+        Ok(_) => StageStatus::Complete,
+        Err(e) => StageStatus::Failed { error: e.to_string() },
+    }
 }
 
 pub fn default_stages() -> Vec<&'static str> {
     vec!["Raw Materials","Manufacturing","Distribution","Usage","End-of-Life / Recycling"]
 }
 
-pub async fn generate_lifecycle(State(state): State<AppState>, Json(body): Json<GenerateRequest>) -> Json<Lifecycle> {
+/// Stage list for a new lifecycle: the caller's custom stages verbatim, or the defaults adjusted by the
+/// configured category rules, capped in public demo mode. Also returns the detected categories.
+fn resolve_stages(state: &AppState, body: &GenerateRequest) -> (Vec<String>, Vec<String>) {
+    let (mut stages, categories) = match &body.stages {
+        Some(custom) => (custom.clone(), Vec::new()),
+        None => {
+            let mut stages: Vec<String> = default_stages().into_iter().map(|s| s.to_string()).collect();
+            let categories = apply_category_rules(&state.config.category_rules, &body.product_description, &mut stages);
+            if !categories.is_empty() {
+                tracing::info!("🏷️ Product matched categories {:?}, stages: {:?}", categories, stages);
+            }
+            (stages, categories)
+        }
+    };
+    if let Some(demo) = &state.public_demo {
+        stages.truncate(demo.max_stages);
+    }
+    (stages, categories)
+}
+
+pub async fn generate_lifecycle(Query(images): Query<ImagesQuery>, State(state): State<AppState>, Valid(body): Valid<GenerateRequest>) -> Result<(StatusCode, Json<GenerateResult>), ApiError> {
+    let (lifecycle, stage_results, retry) = generate_and_store(&state, body, None).await?;
+    let failed = stage_results.iter().filter(|r| !r.succeeded).count();
+    let status = if failed == 0 { StatusCode::OK } else {
+        tracing::warn!("⚠️ {} of {} stages of {} failed; answering 207", failed, stage_results.len(), lifecycle.id);
+        StatusCode::MULTI_STATUS
+    };
+    Ok((status, Json(GenerateResult { lifecycle: images.lifecycle(&state, lifecycle), stage_results, retry })))
+}
+
+// Full generation as newline-delimited JSON, so clients can show each stage as soon as it is done: `started`
+// (`{id, stages}`, the stage names in order), `stage` (`{stage_index, stage, result}`) per stage in completion order,
+// then `done` (`{id, stage_results, retry}`, as in the `POST /api/lifecycle` response) or `failed` (`{error}`) when
+// the lifecycle could not be stored
+pub async fn generate_lifecycle_stream(Query(images): Query<ImagesQuery>, State(state): State<AppState>, Valid(body): Valid<GenerateRequest>) -> Response {
+    // Detached like batch generation: the lifecycle is still generated and stored if the client goes away.
+    let (sender, receiver) = tokio::sync::mpsc::unbounded_channel();
+    tokio::spawn(state.shutdown.clone().track(workspaces::carry(async move {
+        let last = match generate_and_store(&state, body, Some((&images, &sender))).await {
+            Ok((lifecycle, stage_results, retry)) => serde_json::json!({ "event": "done", "id": lifecycle.id, "stage_results": stage_results, "retry": retry }),
+            Err(e) => {
+                let mut line = e.body();
+                line["event"] = "failed".into();
+                line
+            }
+        };
+        let _ = sender.send(last);
+    })));
+    let lines = futures::stream::unfold(receiver, |mut receiver| async move {
+        receiver.recv().await.map(|line| (Ok::<_, Infallible>(format!("{}\n", line)), receiver))
+    });
+    (
+        [
+            (axum::http::header::CONTENT_TYPE, "application/x-ndjson"),
+            // Reverse proxies (nginx) would otherwise hold the lines back until the response is complete.
+            (HeaderName::from_static("x-accel-buffering"), "no"),
+        ],
+        axum::body::Body::from_stream(lines),
+    ).into_response()
+}
+
+/// Generate every stage of a new lifecycle concurrently, store it and work out each stage's result and the retry hint.
+/// With `progress`, the `started` and per-stage lines of the streaming variant are sent as generation goes.
+async fn generate_and_store(state: &AppState, body: GenerateRequest, progress: Option<(&ImagesQuery, &UnboundedSender<serde_json::Value>)>) -> Result<(Lifecycle, Vec<StageResult>, Option<RetryHint>), ApiError> {
     let id = Uuid::new_v4();
-    let constraints = body.constraints.clone().unwrap_or_default();
-    let stages_list: Vec<String> = body.stages.clone().map(|v| v).unwrap_or_else(|| default_stages().into_iter().map(|s| s.to_string()).collect());
+    let constraints = state.config.tenants.constraints(body.tenant.as_deref(), body.constraints.clone());
+    let (stages_list, categories) = resolve_stages(state, &body);
 
-    tracing::info!("🚀 Generating lifecycle for product: {}", body.product_description);
-    
-    let mut stages = Vec::new();
-    for s in &stages_list {
-        let img = state.gemini.gen_stage_image(&body.product_description, s, &constraints).await;
-        stages.push(img);
+    tracing::info!("🚀 Generating lifecycle for product: {} (provider: {})", body.product_description, state.images.provider());
+    if let Some((_, sender)) = progress {
+        let _ = sender.send(serde_json::json!({ "event": "started", "id": id, "stages": stages_list }));
     }
+    let fitted = state.gemini.fit_prompt_inputs(&body.product_description, &constraints).await;
+    
+    // Stages are independent, so generate them concurrently and restore their order afterwards
+    let (prompt_product, prompt_constraints) = match &fitted.inputs {
+        Some(inputs) => (&inputs.product, &inputs.constraints),
+        None => (&body.product_description, &constraints),
+    };
+    let (state_ref, product, constraints_ref) = (state, prompt_product, prompt_constraints);
+    let mut generated: Vec<(usize, StageImage, StageResult)> = {
+        let mut pending = futures::stream::iter(stages_list.iter().cloned().enumerate())
+            .map(|(i, s)| async move {
+                let ctx = StageContext { product, stage: &s, constraints: constraints_ref, actors: &[], locations: &[], resolution: body.resolution };
+                let ((mut stage, usage), failed) = failures::track(usage::track(state_ref.scheduler.run(Lane::Batch, state_ref.images.gen_stage_image(ctx)))).await;
+                stage.usage = usage;
+                (i, stage, failed)
+            })
+            .buffer_unordered(state.scheduler.stage_fanout);
+        let mut generated = Vec::new();
+        while let Some((i, stage, failed)) = pending.next().await {
+            let result = stage_result(state, i, &stage, failed);
+            if let Some((images, sender)) = progress {
+                let _ = sender.send(serde_json::json!({ "event": "stage", "stage_index": i, "stage": images.stage(state, stage.clone()), "result": result }));
+            }
+            generated.push((i, stage, result));
+        }
+        generated
+    };
+    generated.sort_by_key(|(i, _, _)| *i);
+    let (stages, stage_results): (Vec<StageImage>, Vec<StageResult>) = generated.into_iter().map(|(_, stage, result)| (stage, result)).unzip();
 
     // Log summary of generated lifecycle with truncated image data
     let stages_summary: Vec<_> = stages.iter().map(|stage| {
@@ -41,127 +252,2269 @@ pub async fn generate_lifecycle(State(state): State<AppState>, Json(body): Json<
     
     tracing::info!("✅ Lifecycle generated with {} stages: {}", stages.len(), stages_summary.join(", "));
 
-    let lifecycle = Lifecycle { id, product_description: body.product_description, stages, created_at: Utc::now(), updated_at: Utc::now(), constraints };
+    let slug = share_slug(&body.product_description, id);
+    let (workspace_id, owner) = workspaces::ownership();
+    let lifecycle = Lifecycle { id, product_description: body.product_description, stages, created_at: Utc::now(), updated_at: Utc::now(), constraints, review_status: ReviewStatus::Draft, categories, tenant: body.tenant, tags: body.tags, last_exported_at: None, revision: 0, resolution: body.resolution, prompt_inputs: fitted.inputs, warnings: fitted.warnings, cloned_from: None, slug: Some(slug), workspace_id, owner, share_links: Vec::new() };
     
-    state.store.write().insert(id, lifecycle.clone());
-    Json(lifecycle)
+    state.repo.insert(&lifecycle).await?;
+    let retry_indexes: Vec<usize> = stage_results.iter().filter(|r| !r.succeeded && r.retryable).map(|r| r.stage_index).collect();
+    let retry = (!retry_indexes.is_empty()).then(|| RetryHint {
+        method: "POST",
+        path: format!("/api/lifecycle/{}/stages/generate", id),
+        body: serde_json::json!({ "stage_indexes": retry_indexes }),
+        retry_after_secs: stage_results.iter().filter_map(|r| r.retry_after_secs).max().unwrap_or(0),
+    });
+    Ok((lifecycle, stage_results, retry))
+}
+
+/// Gemini rate limits are per minute.
+const QUOTA_RETRY_AFTER_SECS: i64 = 60;
+
+/// Outcome of one stage of a full generation from the failures recorded while it was generated.
+fn stage_result(state: &AppState, stage_index: usize, stage: &StageImage, failures: Vec<FailureRecord>) -> StageResult {
+    let error = match &stage.status {
+        StageStatus::Failed { error } => Some(error.clone()),
+        _ => None,
+    };
+    let succeeded = failures.is_empty() && error.is_none();
+    let retryable = !succeeded && failures.iter().all(FailureRecord::retryable);
+    let retry_after_secs = retryable.then(|| failures.iter().map(|f| match f.reason.as_str() {
+        "quota" => QUOTA_RETRY_AFTER_SECS,
+        "circuit_open" => state.gemini.breaker.snapshot().next_probe_at.map_or(0, |at| (at - Utc::now()).num_seconds().max(0)),
+        _ => 0,
+    }).max().unwrap_or(0));
+    StageResult {
+        stage_index,
+        stage_name: stage.stage_name.clone(),
+        succeeded,
+        failures,
+        error,
+        retryable,
+        retry_after_secs,
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ImagesQuery {
+    /// `false` leaves `image_base64` out of the response; fetch `/stage/{index}/image` instead. Has no effect
+    /// when images are stored externally, since they are never inlined then.
+    #[serde(default = "default_include_images")]
+    pub include_images: bool,
+}
+
+fn default_include_images() -> bool { true }
+
+impl ImagesQuery {
+    /// Images in external storage (`IMAGE_DIR`, `S3_BUCKET`) are never inlined; stages link to them instead.
+    fn stage(&self, state: &AppState, stage: StageImage) -> StageImage {
+        let mut stage = stage.with_image(self.include_images && state.image_files.is_none());
+        if let Some(files) = &state.image_files {
+            stage.image_url = stage.image_ref.as_deref().map(|image_ref| files.url(image_ref));
+            for candidate in &mut stage.image_candidates {
+                candidate.image_url = candidate.image_ref.as_deref().map(|image_ref| files.url(image_ref));
+            }
+            if let Some(previous) = &mut stage.previous_image {
+                previous.image_url = previous.image_ref.as_deref().map(|image_ref| files.url(image_ref));
+            }
+        }
+        stage
+    }
+
+    fn lifecycle(&self, state: &AppState, mut lifecycle: Lifecycle) -> Lifecycle {
+        lifecycle.stages = lifecycle.stages.into_iter().map(|stage| self.stage(state, stage)).collect();
+        lifecycle
+    }
+}
+
+/// The `ETag` is the revision, for `If-Match` on later edits.
+pub async fn get_lifecycle(Path(id): Path<Uuid>, Query(images): Query<ImagesQuery>, State(state): State<AppState>) -> Result<([(HeaderName, String); 1], Json<Lifecycle>), ApiError> {
+    let lifecycle = load_lifecycle(&state, id).await?;
+    Ok(([(axum::http::header::ETAG, format!("\"{}\"", lifecycle.revision))], Json(images.lifecycle(&state, lifecycle))))
+}
+
+// Live stage progress as server-sent events: an `image` / `description` event whenever that part of a stage
+// is stored, and `lagged` (with the number of missed events) if this client fell behind and should refetch
+pub async fn lifecycle_events(Path(id): Path<Uuid>, State(state): State<AppState>) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, ApiError> {
+    // Subscribe first so nothing published between the existence check and the stream start is lost.
+    let receiver = state.events.subscribe();
+    load_lifecycle(&state, id).await?;
+    // Ends on shutdown, which would otherwise wait for the viewer to go away.
+    let stream = futures::stream::unfold((receiver, state.shutdown.clone()), move |(mut receiver, shutdown)| async move {
+        loop {
+            let received = tokio::select! {
+                received = receiver.recv() => received,
+                _ = shutdown.draining() => return None,
+            };
+            let event = match received {
+                Ok(event) if event.lifecycle_id == id => {
+                    Event::default().event(event.part.event_name()).json_data(&event).unwrap_or_default()
+                }
+                Ok(_) => continue,
+                Err(RecvError::Lagged(missed)) => Event::default().event("lagged").data(missed.to_string()),
+                Err(RecvError::Closed) => return None,
+            };
+            return Some((Ok(event), (receiver, shutdown)));
+        }
+    });
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
+
+// Realtime sync over WebSocket: a `snapshot` (or `in_sync` when `?revision=` is already current), then one RFC 6902
+// `patch` per stored change. A client that sees a revision gap sends `{"type":"resync"}` for a fresh snapshot
+pub async fn lifecycle_sync(Path(id): Path<Uuid>, Query(q): Query<SyncQuery>, ws: WebSocketUpgrade, State(state): State<AppState>) -> Result<Response, ApiError> {
+    // 404 before upgrading rather than a socket that closes immediately.
+    load_lifecycle(&state, id).await?;
+    Ok(ws.on_upgrade(move |socket| sync_lifecycle(socket, state, id, q.revision)))
+}
+
+async fn sync_lifecycle(mut socket: WebSocket, state: AppState, id: Uuid, client_revision: Option<u64>) {
+    // Subscribe before the snapshot is read; patches it already contains are skipped below.
+    let mut changes = state.patches.subscribe();
+    let Some(mut revision) = send_snapshot(&mut socket, &state, id, client_revision).await else { return };
+    loop {
+        tokio::select! {
+            incoming = socket.recv() => match incoming {
+                Some(Ok(Message::Text(text))) => match serde_json::from_str::<ClientMessage>(&text) {
+                    Ok(ClientMessage::Resync) => {
+                        let Some(current) = send_snapshot(&mut socket, &state, id, None).await else { return };
+                        revision = current;
+                    }
+                    Err(e) => tracing::debug!("Ignoring sync message for {}: {}", id, e),
+                },
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return,
+                Some(Ok(_)) => {}
+            },
+            change = changes.recv() => match change {
+                Ok(change) if change.lifecycle_id() != id => {}
+                Ok(LifecycleChange::Patched(patch)) if patch.revision <= revision => {}
+                Ok(LifecycleChange::Patched(patch)) if patch.from_revision == revision => {
+                    let message = SyncMessage::Patch { from_revision: patch.from_revision, revision: patch.revision, ops: &patch.ops };
+                    if !send_sync_message(&mut socket, &message).await { return }
+                    revision = patch.revision;
+                }
+                // A patch went missing (e.g. the change happened before this subscriber was counted).
+                Ok(LifecycleChange::Patched(_)) | Err(RecvError::Lagged(_)) => {
+                    let Some(current) = send_snapshot(&mut socket, &state, id, None).await else { return };
+                    revision = current;
+                }
+                Ok(LifecycleChange::Deleted(_)) => {
+                    send_sync_message(&mut socket, &SyncMessage::Deleted).await;
+                    return;
+                }
+                Err(RecvError::Closed) => return,
+            },
+        }
+    }
+}
+
+/// Sends the current document (or `in_sync` if the client already holds `client_revision`) and returns the
+/// revision the client now has. `None` ends the session: socket gone, lifecycle deleted or unreadable.
+async fn send_snapshot(socket: &mut WebSocket, state: &AppState, id: Uuid, client_revision: Option<u64>) -> Option<u64> {
+    let lifecycle = match state.repo.get(id).await {
+        Ok(Some(lifecycle)) => lifecycle,
+        Ok(None) => {
+            send_sync_message(socket, &SyncMessage::Deleted).await;
+            return None;
+        }
+        Err(e) => {
+            tracing::error!("❌ Sync snapshot of {} failed: {}", id, e);
+            return None;
+        }
+    };
+    let revision = lifecycle.revision;
+    let message = if client_revision == Some(revision) {
+        SyncMessage::InSync { revision }
+    } else {
+        SyncMessage::Snapshot { revision, lifecycle: &lifecycle }
+    };
+    send_sync_message(socket, &message).await.then_some(revision)
+}
+
+async fn send_sync_message(socket: &mut WebSocket, message: &SyncMessage<'_>) -> bool {
+    let Ok(text) = serde_json::to_string(message) else { return false };
+    socket.send(Message::Text(text)).await.is_ok()
+}
+
+// Collaborative session over WebSocket: `connected` (`{revision, viewers}`), then an event per stored change
+// (`stage_added`, `stage_removed`, `stages_reordered`, `stage_regenerated`, `description_edited`, `stage_updated`,
+// `lifecycle_updated`, `deleted`), `viewers` whenever someone joins or leaves, and `lagged` if this client fell behind
+pub async fn lifecycle_ws(Path(id): Path<Uuid>, ws: WebSocketUpgrade, State(state): State<AppState>) -> Result<Response, ApiError> {
+    // 404 before upgrading rather than a socket that closes immediately.
+    let revision = load_lifecycle(&state, id).await?.revision;
+    Ok(ws.on_upgrade(move |socket| collab_session(socket, state, id, revision)))
+}
+
+async fn collab_session(mut socket: WebSocket, state: AppState, id: Uuid, revision: u64) {
+    let mut events = state.collab.subscribe();
+    let viewers = state.collab.presence(id, revision, true);
+    let connected = serde_json::json!({ "type": "connected", "lifecycle_id": id, "revision": revision, "viewers": viewers });
+    let mut open = socket.send(Message::Text(connected.to_string())).await.is_ok();
+    let mut revision = revision;
+    while open {
+        tokio::select! {
+            incoming = socket.recv() => match incoming {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => open = false,
+                Some(Ok(_)) => {}
+            },
+            event = events.recv() => match event {
+                Ok(event) if event.lifecycle_id != id => {}
+                Ok(event) => {
+                    revision = event.revision.unwrap_or(revision);
+                    let Ok(text) = serde_json::to_string(&event) else { continue };
+                    open = socket.send(Message::Text(text)).await.is_ok() && !matches!(event.kind, CollabEventKind::Deleted);
+                }
+                Err(RecvError::Lagged(missed)) => {
+                    let lagged = serde_json::json!({ "type": "lagged", "lifecycle_id": id, "missed": missed });
+                    open = socket.send(Message::Text(lagged.to_string())).await.is_ok();
+                }
+                Err(RecvError::Closed) => open = false,
+            },
+        }
+    }
+    state.collab.presence(id, revision, false);
+    let _ = socket.close().await;
+}
+
+// Remove a lifecycle and everything attached to it
+pub async fn delete_lifecycle(Path(id): Path<Uuid>, State(state): State<AppState>) -> Result<StatusCode, ApiError> {
+    if precondition::expected().is_some() || workspaces::current().is_some() {
+        // Checked on load; the store has no conditional delete, so a write landing in between is not caught.
+        load_lifecycle(&state, id).await?;
+    }
+    if !state.repo.delete(id).await? {
+        return Err(lifecycle_not_found(id));
+    }
+    state.blobs.remove_lifecycle(id).await;
+    state.patches.publish(LifecycleChange::Deleted(id));
+    state.collab.publish(id, None, vec![CollabEventKind::Deleted]);
+    state.audit.record("api", "lifecycle.delete", Some(id), "deleted via DELETE /api/lifecycle/:id");
+    tracing::info!("🗑️ Deleted lifecycle {}", id);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// Bulk-delete lifecycles by tag and/or creation date (admin token required); every deletion is audited
+pub async fn purge_lifecycles(Query(q): Query<PurgeQuery>, headers: HeaderMap, State(state): State<AppState>) -> Result<Json<PurgeResult>, ApiError> {
+    state.admin.check(&headers)?;
+    if q.tag.is_none() && q.created_before.is_none() {
+        // Refuse to wipe the whole store through a filterless request.
+        return Err(ApiError::new(StatusCode::UNPROCESSABLE_ENTITY, "invalid_input", "Give at least one filter: tag or created_before"));
+    }
+    let all = state.repo.list().await?;
+    let matching: Vec<&Lifecycle> = all.iter()
+        .filter(|l| q.tag.as_ref().is_none_or(|tag| l.tags.contains(tag)))
+        .filter(|l| q.created_before.is_none_or(|before| l.created_at < before))
+        .collect();
+    if q.dry_run {
+        return Ok(Json(PurgeResult { affected: matching.len(), dry_run: true }));
+    }
+    let mut affected = 0;
+    for lifecycle in matching {
+        // Already gone (e.g. a concurrent purge) doesn't count.
+        if state.repo.delete(lifecycle.id).await? {
+            affected += 1;
+            state.blobs.remove_lifecycle(lifecycle.id).await;
+            state.patches.publish(LifecycleChange::Deleted(lifecycle.id));
+            state.collab.publish(lifecycle.id, None, vec![CollabEventKind::Deleted]);
+            state.audit.record("admin", "lifecycle.purge", Some(lifecycle.id), format!("tag={:?} created_before={:?}", q.tag, q.created_before));
+        }
+    }
+    tracing::info!("🗑️ Purged {} lifecycles (tag={:?}, created_before={:?})", affected, q.tag, q.created_before);
+    Ok(Json(PurgeResult { affected, dry_run: false }))
+}
+
+// Every stored lifecycle as NDJSON, one per line, for backups (admin token required); `?include_images=false` leaves out image data
+pub async fn export_store(Query(images): Query<ImagesQuery>, headers: HeaderMap, State(state): State<AppState>) -> Result<Response, ApiError> {
+    state.admin.check(&headers)?;
+    let listed = state.repo.list().await?;
+    state.audit.record("admin", "store.export", None, format!("{} lifecycle(s), include_images={}", listed.len(), images.include_images));
+    tracing::info!("📦 Exporting {} lifecycle(s) as NDJSON (images: {})", listed.len(), images.include_images);
+    let include = images.include_images;
+    // `list` leaves externally stored images as refs; a backup carries the image data so it restores anywhere.
+    let load_images = include && state.image_files.is_some();
+    let lines = futures::stream::iter(listed).filter_map(move |listed| {
+        let state = state.clone();
+        async move {
+            let mut lifecycle = match load_images {
+                true => match state.repo.get(listed.id).await {
+                    Ok(Some(lifecycle)) => lifecycle,
+                    // Deleted since the listing.
+                    Ok(None) => return None,
+                    Err(e) => return Some(Err(std::io::Error::other(e))),
+                },
+                false => listed,
+            };
+            lifecycle.stages = lifecycle.stages.into_iter().map(|stage| stage.with_image(include)).collect();
+            Some(serde_json::to_string(&lifecycle).map(|json| json + "\n").map_err(std::io::Error::other))
+        }
+    });
+    Ok((
+        [
+            (axum::http::header::CONTENT_TYPE, "application/x-ndjson".to_string()),
+            (axum::http::header::CONTENT_DISPOSITION, format!("attachment; filename=\"lifecycles_{}.ndjson\"", Utc::now().format("%Y%m%d-%H%M%S"))),
+        ],
+        axum::body::Body::from_stream(lines),
+    ).into_response())
+}
+
+// Restore lifecycles from an `/api/admin/export` backup, keeping their ids (admin token required); bad lines are reported, not fatal
+pub async fn import_store(Query(q): Query<RestoreQuery>, headers: HeaderMap, State(state): State<AppState>, body: axum::body::Body) -> Result<Json<RestoreResult>, ApiError> {
+    state.admin.check(&headers)?;
+    let mut result = RestoreResult::default();
+    let mut chunks = body.into_data_stream();
+    let mut pending = Vec::new();
+    let mut line = 0;
+    loop {
+        let chunk = chunks.next().await.transpose()
+            .map_err(|e| ApiError::new(StatusCode::BAD_REQUEST, "invalid_input", format!("Reading the backup failed: {}", e)))?;
+        let finished = chunk.is_none();
+        pending.extend_from_slice(&chunk.unwrap_or_default());
+        let mut complete: Vec<Vec<u8>> = Vec::new();
+        while let Some(newline) = pending.iter().position(|b| *b == b'\n') {
+            complete.push(pending.drain(..=newline).collect());
+        }
+        if finished && !pending.is_empty() {
+            complete.push(std::mem::take(&mut pending));
+        }
+        for text in complete {
+            line += 1;
+            if let Err(error) = restore_backup_line(&state, &text, q.overwrite, &mut result).await {
+                result.failed.push(RestoreLineError { line, error });
+            }
+        }
+        if finished {
+            break;
+        }
+    }
+    tracing::info!("📥 Restored backup: {} imported, {} replaced, {} skipped, {} failed", result.imported, result.replaced, result.skipped, result.failed.len());
+    Ok(Json(result))
+}
+
+async fn restore_backup_line(state: &AppState, text: &[u8], overwrite: bool, result: &mut RestoreResult) -> Result<(), String> {
+    if text.trim_ascii().is_empty() {
+        return Ok(());
+    }
+    let mut lifecycle: Lifecycle = serde_json::from_slice(text).map_err(|e| format!("invalid lifecycle: {}", e))?;
+    let id = lifecycle.id;
+    if lifecycle.slug.is_none() {
+        lifecycle.slug = Some(lifecycle.share_slug());
+    }
+    let replaced = overwrite && state.repo.update(id, &mut |stored| {
+        // Past both revisions, so a client still editing either version gets a version mismatch.
+        *stored = Lifecycle { revision: stored.revision.max(lifecycle.revision) + 1, ..lifecycle.clone() };
+        true
+    }).await.map_err(|e| e.to_string())?;
+    if replaced {
+        result.replaced += 1;
+        state.audit.record("admin", "lifecycle.restore", Some(id), "replaced from backup");
+        return Ok(());
+    }
+    if !overwrite && state.repo.get(id).await.map_err(|e| e.to_string())?.is_some() {
+        result.skipped += 1;
+        return Ok(());
+    }
+    state.repo.insert(&lifecycle).await.map_err(|e| e.to_string())?;
+    result.imported += 1;
+    state.audit.record("admin", "lifecycle.restore", Some(id), "inserted from backup");
+    Ok(())
+}
+
+/// `cursor` query parameter: absent for the first page, 400 if it isn't one we issued.
+fn parse_cursor(cursor: Option<&str>) -> Result<Option<Cursor>, ApiError> {
+    cursor.map(|c| Cursor::decode(c).ok_or_else(|| ApiError::new(StatusCode::BAD_REQUEST, "invalid_cursor", "Unknown or malformed pagination cursor"))).transpose()
+}
+
+fn summary_cursor(summary: &LifecycleSummary) -> Cursor {
+    Cursor { at: summary.created_at, id: summary.id }
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ListSort {
+    #[default]
+    Created,
+    /// Most recently touched (lifecycle or any stage) first.
+    Activity,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ListQuery {
+    pub cursor: Option<String>,
+    #[serde(default = "default_list_limit")]
+    pub limit: usize,
+    pub completeness: Option<Completeness>,
+    #[serde(default)]
+    pub sort: ListSort,
+}
+
+fn default_list_limit() -> usize { 20 }
+
+// List stored lifecycles one page at a time, newest (or most recently active) first, optionally only those
+// in a given generation state
+pub async fn list_lifecycles(Query(q): Query<ListQuery>, State(state): State<AppState>) -> Result<Json<Page<LifecycleSummary>>, ApiError> {
+    let after = parse_cursor(q.cursor.as_deref())?;
+    let all = state.repo.list().await?;
+    let mut summaries: Vec<LifecycleSummary> = all.iter()
+        .filter(|l| workspaces::can_access(l))
+        .filter(|l| q.completeness.is_none_or(|c| l.has_completeness(c)))
+        .map(LifecycleSummary::from)
+        .collect();
+    let limit = q.limit.clamp(1, 100);
+    Ok(Json(match q.sort {
+        ListSort::Created => paginate(summaries, summary_cursor, after, limit),
+        ListSort::Activity => {
+            summaries.sort_by(|a, b| b.last_activity.cmp(&a.last_activity).then(a.id.cmp(&b.id)));
+            paginate(summaries, |s| Cursor { at: s.last_activity, id: s.id }, after, limit)
+        }
+    }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SearchQuery {
+    pub keyword: String,
+    pub cursor: Option<String>,
+    #[serde(default = "default_list_limit")]
+    pub limit: usize,
+}
+
+// Lifecycles with a stage tagged with the given keyword, newest first
+pub async fn search_lifecycles(Query(q): Query<SearchQuery>, State(state): State<AppState>) -> Result<Json<Page<LifecycleSummary>>, ApiError> {
+    let keyword = normalize_keyword(&q.keyword);
+    if keyword.is_empty() {
+        return Err(ApiError::new(StatusCode::UNPROCESSABLE_ENTITY, "invalid_input", "keyword must not be empty"));
+    }
+    let after = parse_cursor(q.cursor.as_deref())?;
+    let all = state.repo.list().await?;
+    let matches = all.iter().filter(|l| workspaces::can_access(l) && mentions(l, &keyword)).map(LifecycleSummary::from);
+    Ok(Json(paginate(matches, summary_cursor, after, q.limit.clamp(1, 100))))
 }
 
-pub async fn get_lifecycle(Path(id): Path<Uuid>, State(state): State<AppState>) -> Response {
-    if let Some(l) = state.store.read().get(&id).cloned() { Json(l).into_response() } else { StatusCode::NOT_FOUND.into_response() }
+#[derive(Debug, Deserialize)]
+pub struct KeywordStatsQuery {
+    #[serde(default)]
+    pub kind: Option<KeywordKind>,
+    #[serde(default = "default_keyword_limit")]
+    pub limit: usize,
+}
+
+fn default_keyword_limit() -> usize { 50 }
+
+// How many lifecycles mention each keyword across the whole store (the caller's workspace with workspace keys)
+pub async fn keyword_analytics(Query(q): Query<KeywordStatsQuery>, State(state): State<AppState>) -> Result<Json<KeywordStats>, ApiError> {
+    let mut all = state.repo.list().await?;
+    all.retain(workspaces::can_access);
+    Ok(Json(keyword_stats(&all, q.kind, q.limit.clamp(1, 500))))
 }
 
 #[axum::debug_handler]
 pub async fn regenerate_stage(
     Path(id): Path<Uuid>, 
+    Query(images): Query<ImagesQuery>,
     State(state): State<AppState>, 
     Json(body): Json<RegenerateRequest>
-) -> Result<Json<Lifecycle>, StatusCode> {
+) -> Result<Json<Lifecycle>, ApiError> {
+    let candidate_count = body.candidate_count.unwrap_or(1);
+    if !(1..=MAX_IMAGE_CANDIDATES).contains(&candidate_count) {
+        return Err(ApiError::new(StatusCode::UNPROCESSABLE_ENTITY, "invalid_input", format!("candidate_count must be between 1 and {}", MAX_IMAGE_CANDIDATES)));
+    }
     // First, get the current prompt
-    let current_prompt = {
-        let guard = state.store.read();
-        let lifecycle = guard.get(&id).ok_or(StatusCode::NOT_FOUND)?;
-        if body.stage_index >= lifecycle.stages.len() { 
-            return Err(StatusCode::NOT_FOUND); 
-        }
-        lifecycle.stages[body.stage_index].prompt.clone()
+    let (stage_name, current_prompt, current_resolution) = {
+        let lifecycle = load_lifecycle(&state, id).await?;
+        let stage = lifecycle.stages.get(body.stage_index).ok_or_else(|| stage_not_found(body.stage_index))?;
+        (stage.stage_name.clone(), stage.prompt.clone(), stage.resolution)
     };
     
     // Generate new image outside the lock
     let new_prompt = format!("{} Modify to: {}", current_prompt, body.edit_instruction);
-    let new_img = state.gemini.generate_image(&new_prompt).await.ok();
+    let resolution = body.resolution.unwrap_or(current_resolution);
+    if candidate_count > 1 {
+        let lifecycle = store_image_candidates(&state, id, body.stage_index, &stage_name, new_prompt, resolution, candidate_count).await?;
+        return Ok(Json(images.lifecycle(&state, lifecycle)));
+    }
+    let (checked, usage) = usage::track(state.scheduler.run(Lane::Interactive, state.images.generate_checked_image(&stage_name, &new_prompt, resolution))).await;
+    let status = image_status(&checked.image);
+    let (image, failure) = match checked.image {
+        Ok(image) => (Some(image), None),
+        Err(e) => (None, Some(e)),
+    };
     
     // Update the lifecycle with the new data
-    let mut guard = state.store.write();
-    if let Some(lifecycle) = guard.get_mut(&id) {
-        let stage = &mut lifecycle.stages[body.stage_index];
+    let lifecycle = modify_lifecycle(&state, id, |lifecycle| {
+        let stage = lifecycle.stages.get_mut(body.stage_index).ok_or_else(|| stage_not_found(body.stage_index))?;
+        stage.keep_previous_image();
         stage.prompt = new_prompt;
-        stage.image_base64 = new_img;
+        stage.status = status;
+        stage.usage.extend(usage);
+        stage.set_image(image);
+        stage.resolution = resolution;
+        stage.quality_checks = checked.quality_checks;
+        stage.warnings = checked.warnings;
+        stage.last_updated = Utc::now();
+        lifecycle.updated_at = Utc::now();
+        Ok(lifecycle.clone())
+    }).await?;
+    state.events.publish(id, body.stage_index, &lifecycle.stages[body.stage_index], &[StagePart::Image]);
+    // The failure is recorded on the stage; the caller still learns why nothing was generated.
+    if let Some(e) = failure {
+        let mut error = ApiError::from(e);
+        if let Some(details) = error.details.as_mut() {
+            details["stage_index"] = body.stage_index.into();
+        }
+        return Err(error);
+    }
+    Ok(Json(images.lifecycle(&state, lifecycle)))
+}
+
+/// Most images one regeneration may ask for with `candidate_count`.
+const MAX_IMAGE_CANDIDATES: usize = 4;
+
+/// Generate `count` images for a stage concurrently and store the successful ones as its `image_candidates`,
+/// replacing earlier ones; the stage image stays as it is until one is selected. Fails only if every call failed.
+async fn store_image_candidates(state: &AppState, id: Uuid, stage_index: usize, stage_name: &str, prompt: String, resolution: Resolution, count: usize) -> Result<Lifecycle, ApiError> {
+    let (generated, usage) = usage::track(
+        futures::stream::iter(0..count)
+            .map(|_| state.scheduler.run(Lane::Interactive, state.images.generate_checked_image(stage_name, &prompt, resolution)))
+            .buffer_unordered(state.scheduler.stage_fanout)
+            .collect::<Vec<_>>()
+    ).await;
+    let mut candidates = Vec::new();
+    let mut first_error = None;
+    for checked in generated {
+        match checked.image {
+            Ok(image) => candidates.push(ImageCandidate::new(image, prompt.clone(), resolution, checked.quality_checks, checked.warnings)),
+            Err(e) => {
+                tracing::error!("❌ Image candidate for stage {} of {} failed: {}", stage_index, id, e);
+                first_error.get_or_insert(e);
+            }
+        }
+    }
+    if let Some(e) = first_error.filter(|_| candidates.is_empty()) {
+        let mut error = ApiError::from(e);
+        if let Some(details) = error.details.as_mut() {
+            details["stage_index"] = stage_index.into();
+        }
+        return Err(error);
+    }
+
+    modify_lifecycle(state, id, |lifecycle| {
+        let stage = lifecycle.stages.get_mut(stage_index).ok_or_else(|| stage_not_found(stage_index))?;
+        if stage.stage_name != stage_name {
+            return Err(ApiError::new(StatusCode::CONFLICT, "stages_changed", "Stages changed while generating candidates; retry"));
+        }
+        tracing::info!("🎲 Stored {} image candidate(s) for stage {} of {}", candidates.len(), stage_index, id);
+        stage.usage.extend(usage);
+        stage.image_candidates = candidates;
         stage.last_updated = Utc::now();
         lifecycle.updated_at = Utc::now();
-        return Ok(Json(lifecycle.clone()));
+        Ok(lifecycle.clone())
+    }).await
+}
+
+// Commit one of the stage's image candidates as its image
+pub async fn select_image_candidate(
+    Path((id, stage_index)): Path<(Uuid, usize)>,
+    Query(images): Query<ImagesQuery>,
+    State(state): State<AppState>,
+    Json(body): Json<SelectCandidateRequest>
+) -> Result<Json<StageImage>, ApiError> {
+    let stage = modify_lifecycle(&state, id, |lifecycle| {
+        let stage = lifecycle.stages.get_mut(stage_index).ok_or_else(|| stage_not_found(stage_index))?;
+        if body.index >= stage.image_candidates.len() {
+            return Err(ApiError::new(StatusCode::UNPROCESSABLE_ENTITY, "invalid_input", format!("No image candidate at index {}", body.index)));
+        }
+        let candidate = stage.image_candidates.swap_remove(body.index);
+        stage.select_image_candidate(candidate);
+        lifecycle.updated_at = Utc::now();
+        tracing::info!("🖼️ Stage {} of {} now uses image candidate {}", stage_index, id, body.index);
+        Ok(stage.clone())
+    }).await?;
+    state.events.publish(id, stage_index, &stage, &[StagePart::Image]);
+    Ok(Json(images.stage(&state, stage)))
+}
+
+/// Stage without image or description yet, generated later through `POST /stage/{index}`.
+fn pending_stage(stage_name: &str, prompt_product: &str, resolution: Resolution) -> StageImage {
+    StageImage {
+        stage_name: stage_name.to_string(),
+        prompt: format!("High-quality infographic style depiction of the {} stage in the lifecycle of: {}. Show realistic materials, clean labeling, neutral background, vector style clarity, no text over image.", stage_name, prompt_product),
+        description: "Generating description...".to_string(), // Placeholder until generated
+        image_base64: None, // No image generated yet
+        mime_type: None,
+        is_placeholder: false,
+        image_ref: None,
+        image_url: None,
+        alt_text: None,
+        usage: Vec::new(),
+        last_updated: Utc::now(),
+        resolution,
+        quality_checks: Vec::new(),
+        warnings: Vec::new(),
+        actors: Vec::new(),
+        locations: Vec::new(),
+        economics: None,
+        consistency: None,
+        description_candidates: Vec::new(),
+        description_history: Vec::new(),
+        terms: Vec::new(),
+        status: StageStatus::Pending,
+        keywords: StageKeywords::default(),
+        assets: Vec::new(),
+        templates: TemplateVersions::default(),
+        description_fallback: false,
+        narratives: Default::default(),
+        revisions: Default::default(),
+        image_candidates: Vec::new(),
+        previous_image: None,
     }
-    Err(StatusCode::NOT_FOUND)
+}
+
+// Copy a lifecycle into a new one, optionally with another product description or constraints, reusing its images
+pub async fn clone_lifecycle(
+    Path(id): Path<Uuid>,
+    Query(images): Query<ImagesQuery>,
+    State(state): State<AppState>,
+    body: bytes::Bytes
+) -> Result<Json<Lifecycle>, ApiError> {
+    // The body is optional, but one that is sent must parse: `Option<Json<_>>` would quietly clone unchanged
+    let body: CloneRequest = if body.iter().all(u8::is_ascii_whitespace) {
+        CloneRequest::default()
+    } else {
+        serde_json::from_slice(&body).map_err(|e| ApiError::new(StatusCode::UNPROCESSABLE_ENTITY, "invalid_input", format!("invalid clone request: {}", e)))?
+    };
+    let source = load_lifecycle(&state, id).await?;
+    let product_description = body.product_description.map(|p| p.trim().to_string()).filter(|p| !p.is_empty()).unwrap_or_else(|| source.product_description.clone());
+    let constraints = body.constraints
+        .map(|c| c.into_iter().map(|c| c.trim().to_string()).filter(|c| !c.is_empty()).collect())
+        .unwrap_or_else(|| source.constraints.clone());
+    let constraints = state.config.tenants.constraints(source.tenant.as_deref(), Some(constraints));
+    let (prompt_inputs, warnings) = if product_description == source.product_description && constraints == source.constraints {
+        (source.prompt_inputs.clone(), source.warnings.clone())
+    } else {
+        let fitted = state.gemini.fit_prompt_inputs(&product_description, &constraints).await;
+        (fitted.inputs, fitted.warnings)
+    };
+
+    let now = Utc::now();
+    let clone_id = Uuid::new_v4();
+    let (workspace_id, owner) = workspaces::ownership();
+    let mut clone = Lifecycle {
+        id: clone_id,
+        slug: Some(share_slug(&product_description, clone_id)),
+        product_description,
+        constraints,
+        created_at: now,
+        updated_at: now,
+        review_status: ReviewStatus::Draft,
+        last_exported_at: None,
+        revision: 0,
+        prompt_inputs,
+        warnings,
+        cloned_from: Some(id),
+        workspace_id,
+        owner,
+        share_links: Vec::new(),
+        ..source
+    };
+    for stage in &mut clone.stages {
+        // Past provider calls were billed to the original, and its revisions mean nothing here.
+        stage.usage.clear();
+        stage.revisions = StageRevisions::default();
+        let mut assets = Vec::new();
+        for asset in std::mem::take(&mut stage.assets) {
+            let copied = match state.blobs.get(id, asset.id).await {
+                Some(bytes) => state.blobs.put(clone.id, asset.id, &bytes).await.is_ok(),
+                None => false,
+            };
+            if copied {
+                assets.push(asset);
+            } else {
+                tracing::warn!("⚠️ Attachment {} of {} could not be copied; left out of clone {}", asset.id, id, clone.id);
+            }
+        }
+        stage.assets = assets;
+    }
+
+    state.repo.insert(&clone).await?;
+    state.audit.record("api", "lifecycle.clone", Some(clone.id), format!("cloned from {}", id));
+    tracing::info!("🧬 Cloned lifecycle {} into {}", id, clone.id);
+    Ok(Json(images.lifecycle(&state, clone)))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ShareViewQuery {
+    pub format: Option<ShareFormat>,
+}
+
+/// `?format=` of a share token view; browsers (`Accept: text/html`) get HTML without it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ShareFormat {
+    Json,
+    Html,
+}
+
+// Lifecycle by its legible share slug; slugs with outdated or mistyped product words redirect to the current one.
+// A share token from `POST /api/lifecycle/{id}/share` instead opens a read-only view, no API key needed
+pub async fn share_lifecycle(Path(slug): Path<String>, Query(images): Query<ImagesQuery>, Query(view): Query<ShareViewQuery>, headers: HeaderMap, State(state): State<AppState>) -> Result<Response, ApiError> {
+    if is_share_token(&slug) {
+        let html = view.format.map_or_else(|| accepts_html(&headers), |format| format == ShareFormat::Html);
+        return shared_lifecycle(&state, &slug, &images, html).await;
+    }
+    let not_found = || ApiError::new(StatusCode::NOT_FOUND, "lifecycle_not_found", format!("No lifecycle shared as {}", slug));
+    let short_id = short_id(&slug).ok_or_else(not_found)?;
+    let candidates: Vec<Lifecycle> = state.repo.list().await?.into_iter().filter(|l| workspaces::can_access(l) && l.id.simple().to_string().starts_with(short_id)).collect();
+    let lifecycle = match candidates.iter().position(|l| l.share_slug() == slug) {
+        Some(exact) => candidates.into_iter().nth(exact),
+        None if candidates.len() == 1 => {
+            let location = match images.include_images {
+                true => format!("/share/{}", candidates[0].share_slug()),
+                false => format!("/share/{}?include_images=false", candidates[0].share_slug()),
+            };
+            return Ok(Redirect::permanent(&location).into_response());
+        }
+        None => None,
+    };
+    let lifecycle = lifecycle.ok_or_else(not_found)?;
+    // Listings carry only image refs; load the full lifecycle.
+    let lifecycle = load_lifecycle(&state, lifecycle.id).await?;
+    Ok(Json(images.lifecycle(&state, lifecycle)).into_response())
+}
+
+fn accepts_html(headers: &HeaderMap) -> bool {
+    headers.get(axum::http::header::ACCEPT).and_then(|v| v.to_str().ok()).is_some_and(|accept| accept.contains("text/html"))
+}
+
+/// Read-only view behind a share token: 404 for unknown tokens, 410 once revoked.
+async fn shared_lifecycle(state: &AppState, token: &str, images: &ImagesQuery, html: bool) -> Result<Response, ApiError> {
+    let not_found = || ApiError::new(StatusCode::NOT_FOUND, "share_not_found", "No lifecycle is shared with this link");
+    let digest = token_digest(token);
+    let (id, link) = state.repo.list().await?.into_iter()
+        .find_map(|l| l.share_links.iter().find(|link| link.token_sha256 == digest).map(|link| (l.id, link.clone())))
+        .ok_or_else(not_found)?;
+    if link.revoked_at.is_some() {
+        return Err(ApiError::new(StatusCode::GONE, "share_revoked", "This share link has been revoked"));
+    }
+    // Listings carry only image refs; load the full lifecycle. Tokens stand in for the workspace key.
+    let lifecycle = state.repo.get(id).await?.ok_or_else(not_found)?.shared_view();
+    let robots = (HeaderName::from_static("x-robots-tag"), "noindex");
+    if !html {
+        return Ok(([robots], Json(images.lifecycle(state, lifecycle))).into_response());
+    }
+    let rendered = render_html(&lifecycle, &ExportTheme::default().style(&state.config.palette));
+    Ok(([(axum::http::header::CONTENT_TYPE, "text/html; charset=utf-8"), robots], rendered).into_response())
+}
+
+// Mint a public read-only link (`GET /share/{token}`) for stakeholders without API access; the token is only shown
+// in this response
+pub async fn create_share_link(Path(id): Path<Uuid>, headers: HeaderMap, State(state): State<AppState>, body: bytes::Bytes) -> Result<(StatusCode, Json<SharedLink>), ApiError> {
+    // The body is optional, but one that is sent must parse.
+    let body: ShareRequest = if body.iter().all(u8::is_ascii_whitespace) {
+        ShareRequest::default()
+    } else {
+        serde_json::from_slice(&body).map_err(|e| ApiError::new(StatusCode::UNPROCESSABLE_ENTITY, "invalid_input", format!("invalid share request: {}", e)))?
+    };
+    let label = patched_text("label", body.label, MAX_SHARE_LABEL_CHARS)?;
+    let token = share_token();
+    let link = ShareLink {
+        id: Uuid::new_v4(),
+        token_sha256: token_digest(&token),
+        label,
+        created_at: Utc::now(),
+        created_by: workspaces::current().map(|w| w.owner),
+        revoked_at: None,
+    };
+    let stored = link.clone();
+    modify_lifecycle(&state, id, move |lifecycle| {
+        lifecycle.share_links.push(stored);
+        Ok(())
+    }).await?;
+    state.audit.record("api", "lifecycle.share", Some(id), format!("share link {} created", link.id));
+    tracing::info!("🔗 Shared lifecycle {} as link {}", id, link.id);
+    let url = format!("{}/share/{}", request_origin(&headers), token);
+    Ok((StatusCode::CREATED, Json(SharedLink { id: link.id, token, url, label: link.label, created_at: link.created_at })))
+}
+
+// Revoke a share link; its token answers 410 from then on
+pub async fn revoke_share_link(Path((id, share_id)): Path<(Uuid, Uuid)>, State(state): State<AppState>) -> Result<StatusCode, ApiError> {
+    modify_lifecycle(&state, id, |lifecycle| {
+        let link = lifecycle.share_links.iter_mut().find(|link| link.id == share_id)
+            .ok_or_else(|| ApiError::new(StatusCode::NOT_FOUND, "share_not_found", format!("No share link {} on this lifecycle", share_id)))?;
+        link.revoked_at.get_or_insert_with(Utc::now);
+        Ok(())
+    }).await?;
+    state.audit.record("api", "lifecycle.share_revoke", Some(id), format!("share link {} revoked", share_id));
+    tracing::info!("🔗 Revoked share link {} of {}", share_id, id);
+    Ok(StatusCode::NO_CONTENT)
 }
 
 // Create a new lifecycle with empty stages (no image generation yet)
-pub async fn create_lifecycle_skeleton(State(state): State<AppState>, Json(body): Json<GenerateRequest>) -> Json<Lifecycle> {
+pub async fn create_lifecycle_skeleton(State(state): State<AppState>, Valid(body): Valid<GenerateRequest>) -> Result<Json<Lifecycle>, ApiError> {
     let id = Uuid::new_v4();
-    let constraints = body.constraints.clone().unwrap_or_default();
-    let stages_list: Vec<String> = body.stages.clone().map(|v| v).unwrap_or_else(|| default_stages().into_iter().map(|s| s.to_string()).collect());
+    let constraints = state.config.tenants.constraints(body.tenant.as_deref(), body.constraints.clone());
+    let (stages_list, categories) = resolve_stages(&state, &body);
 
     tracing::info!("🎯 Creating lifecycle skeleton for product: {}", body.product_description);
+    let fitted = state.gemini.fit_prompt_inputs(&body.product_description, &constraints).await;
+    let prompt_product = fitted.inputs.as_ref().map_or(&body.product_description, |p| &p.product);
     
-    let mut stages = Vec::new();
-    for s in &stages_list {
-        let stage = StageImage {
-            stage_name: s.clone(),
-            prompt: format!("High-quality infographic style depiction of the {} stage in the lifecycle of: {}. Show realistic materials, clean labeling, neutral background, vector style clarity, no text over image.", s, body.product_description),
-            description: "Generating description...".to_string(), // Placeholder until generated
-            image_base64: None, // No image generated yet
-            last_updated: Utc::now(),
-        };
-        stages.push(stage);
-    }
+    let stages = stages_list.iter().map(|name| pending_stage(name, prompt_product, body.resolution)).collect();
 
+    let slug = share_slug(&body.product_description, id);
+    let (workspace_id, owner) = workspaces::ownership();
     let lifecycle = Lifecycle { 
         id, 
         product_description: body.product_description, 
         stages, 
         created_at: Utc::now(), 
         updated_at: Utc::now(), 
-        constraints 
+        constraints,
+        review_status: ReviewStatus::Draft,
+        categories,
+        tenant: body.tenant,
+        tags: body.tags,
+        last_exported_at: None,
+        revision: 0,
+        resolution: body.resolution,
+        prompt_inputs: fitted.inputs,
+        warnings: fitted.warnings,
+        cloned_from: None,
+        slug: Some(slug),
+        workspace_id,
+        owner,
+        share_links: Vec::new(),
     };
     
-    state.store.write().insert(id, lifecycle.clone());
+    state.repo.insert(&lifecycle).await?;
     tracing::info!("✅ Created lifecycle skeleton with {} stages", lifecycle.stages.len());
-    Json(lifecycle)
+    Ok(Json(lifecycle))
 }
 
 // Generate image for a specific stage
 pub async fn generate_stage_image(
     Path((id, stage_index)): Path<(Uuid, usize)>, 
+    Query(q): Query<StageGenerateQuery>,
+    Query(images): Query<ImagesQuery>,
     State(state): State<AppState>
-) -> Result<Json<StageImage>, StatusCode> {
-    // Get the stage info
-    let (stage_name, product_description, constraints) = {
-        let guard = state.store.read();
-        let lifecycle = guard.get(&id).ok_or(StatusCode::NOT_FOUND)?;
-        if stage_index >= lifecycle.stages.len() { 
-            return Err(StatusCode::BAD_REQUEST); 
+) -> Result<Json<StageImage>, ApiError> {
+    let stage = run_stage_generation(&state, id, stage_index, q.resolution, Lane::Interactive).await?;
+    Ok(Json(images.stage(&state, stage)))
+}
+
+// Generate several stages concurrently (at most STAGE_GENERATION_CONCURRENCY at a time) as server-sent events: `stage`
+// (`{stage_index, stage}`) as each one is stored, `failed` (`{stage_index, error}`) for each that could not be,
+// then `done` (`{succeeded, failed}`)
+pub async fn generate_stages(
+    Path(id): Path<Uuid>,
+    Query(images): Query<ImagesQuery>,
+    State(state): State<AppState>,
+    Json(body): Json<BatchGenerateRequest>
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, ApiError> {
+    let lifecycle = load_lifecycle(&state, id).await?;
+    let indexes = body.stage_indexes.unwrap_or_else(|| (0..lifecycle.stages.len()).collect());
+    if indexes.is_empty() {
+        return Err(ApiError::new(StatusCode::UNPROCESSABLE_ENTITY, "invalid_input", "stage_indexes must not be empty"));
+    }
+    if let Some(&index) = indexes.iter().find(|&&index| index >= lifecycle.stages.len()) {
+        return Err(stage_not_found(index));
+    }
+    let mut seen = HashSet::new();
+    if let Some(&index) = indexes.iter().find(|&&index| !seen.insert(index)) {
+        return Err(ApiError::new(StatusCode::UNPROCESSABLE_ENTITY, "invalid_input", format!("stage {} is listed more than once", index)));
+    }
+    tracing::info!("🎯 Generating {} stages of {}", indexes.len(), id);
+
+    // Detached like single-stage generation: the stages keep generating if the client goes away.
+    let (sender, receiver) = tokio::sync::mpsc::unbounded_channel();
+    tokio::spawn(state.shutdown.clone().track(async move {
+        let (state_ref, resolution) = (&state, body.resolution);
+        let mut generated = futures::stream::iter(indexes)
+            .map(|index| async move { (index, run_stage_generation(state_ref, id, index, resolution, Lane::Batch).await) })
+            .buffer_unordered(state.scheduler.stage_fanout);
+        let (mut succeeded, mut failed) = (Vec::new(), Vec::new());
+        while let Some((index, result)) = generated.next().await {
+            let event = match result {
+                Ok(stage) => {
+                    succeeded.push(index);
+                    Event::default().event("stage").json_data(serde_json::json!({ "stage_index": index, "stage": images.stage(state_ref, stage) }))
+                }
+                Err(e) => {
+                    tracing::error!("❌ Batch generation of stage {} of {} failed: {}", index, id, e.message);
+                    failed.push(index);
+                    let mut data = e.body();
+                    data["stage_index"] = index.into();
+                    Event::default().event("failed").json_data(data)
+                }
+            };
+            let _ = sender.send(event.unwrap_or_default());
         }
-        let stage = &lifecycle.stages[stage_index];
-        (stage.stage_name.clone(), lifecycle.product_description.clone(), lifecycle.constraints.clone())
+        tracing::info!("✅ Batch generation of {} finished: {} succeeded, {} failed", id, succeeded.len(), failed.len());
+        let done = Event::default().event("done").json_data(serde_json::json!({ "succeeded": succeeded, "failed": failed }));
+        let _ = sender.send(done.unwrap_or_default());
+    }));
+    let stream = futures::stream::unfold(receiver, |mut receiver| async move {
+        receiver.recv().await.map(|event| (Ok(event), receiver))
+    });
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
+
+/// Generate a stage's image and description in place, at `resolution` or the lifecycle's tier.
+async fn run_stage_generation(state: &AppState, id: Uuid, stage_index: usize, resolution: Option<Resolution>, lane: Lane) -> Result<StageImage, ApiError> {
+    // Get the stage info
+    let (stage_name, product_description, constraints, actors, locations, resolution) = {
+        let lifecycle = load_lifecycle(state, id).await?;
+        let stage = lifecycle.stages.get(stage_index).ok_or_else(|| stage_not_found(stage_index))?;
+        (stage.stage_name.clone(), lifecycle.prompt_product().to_string(), lifecycle.prompt_constraints().to_vec(), stage.actors.clone(), stage.locations.clone(), resolution.unwrap_or(lifecycle.resolution))
     };
     
-    tracing::info!("🎯 Generating image for stage: {} (index: {})", stage_name, stage_index);
-    
-    // Generate the image
-    let generated_stage = state.gemini.gen_stage_image(&product_description, &stage_name, &constraints).await;
-    
-    // Update the lifecycle with the new image
-    {
-        let mut guard = state.store.write();
-        if let Some(lifecycle) = guard.get_mut(&id) {
-            if stage_index < lifecycle.stages.len() {
-                lifecycle.stages[stage_index] = generated_stage.clone();
-                lifecycle.updated_at = Utc::now();
+    // Identical concurrent requests (same stage and tier) share one generation. It runs detached so a client
+    // disconnect can't leave the stage stuck in `generating`, still as the starting request's workspace and
+    // against the revision it expects.
+    let (task_state, shutdown, expected) = (state.clone(), state.shutdown.clone(), precondition::take_expected());
+    let (generated, joined) = state.stage_generations.run((id, stage_index, resolution), move || shutdown.track(workspaces::carry(precondition::detached(expected, async move {
+        let state = task_state;
+        tracing::info!("🎯 Generating image for stage: {} (index: {}, provider: {})", stage_name, stage_index, state.images.provider());
+        // Stages may be inserted, removed, reordered or renamed meanwhile; both writes only touch the stage if it
+        // is still the one asked for.
+        let previous_status = modify_lifecycle(&state, id, |lifecycle| {
+            let stage = lifecycle.stages.get_mut(stage_index).filter(|s| s.stage_name == stage_name).ok_or(StatusCode::CONFLICT)?;
+            Ok(std::mem::replace(&mut stage.status, StageStatus::Generating))
+        }).await?;
+
+        let ctx = StageContext { product: &product_description, stage: &stage_name, constraints: &constraints, actors: &actors, locations: &locations, resolution };
+        let (generated_stage, usage) = usage::track(state.scheduler.run(lane, state.images.gen_stage_image(ctx))).await;
+
+        // Update the lifecycle with the new image
+        let stored = modify_lifecycle(&state, id, |lifecycle| {
+            let slot = lifecycle.stages.get_mut(stage_index).filter(|s| s.stage_name == stage_name).ok_or(StatusCode::CONFLICT)?;
+            // Attachments are uploaded by people, not generated, and past usage stays billable; both survive
+            // regeneration.
+            let assets = std::mem::take(&mut slot.assets);
+            let mut past_usage = std::mem::take(&mut slot.usage);
+            past_usage.extend(usage);
+            *slot = StageImage { assets, usage: past_usage, ..generated_stage };
+            lifecycle.updated_at = Utc::now();
+            Ok(slot.clone())
+        }).await;
+        let stored = match stored {
+            Ok(stored) => stored,
+            Err(e) => {
+                // The stage moved or was renamed: don't leave it (by name, else whatever took its place) in
+                // `generating` for good.
+                let _ = modify_lifecycle(&state, id, |lifecycle| {
+                    let marked = match lifecycle.stages.iter().position(|s| s.stage_name == stage_name) {
+                        Some(index) => lifecycle.stages.get_mut(index),
+                        None => lifecycle.stages.get_mut(stage_index),
+                    };
+                    let stage = marked.filter(|s| s.status == StageStatus::Generating).ok_or(StatusCode::CONFLICT)?;
+                    stage.status = previous_status;
+                    Ok(())
+                }).await;
+                return Err(e);
             }
+        };
+        state.events.publish(id, stage_index, &stored, &[StagePart::Image, StagePart::Description]);
+        tracing::info!("✅ Generated image for stage: {}", stage_name);
+        Ok(Json(stored))
+    })))).await;
+    if joined {
+        tracing::info!("🔗 Joined in-flight generation for stage {} of {}", stage_index, id);
+    }
+    let (generated, written) = generated.unwrap_or_else(|| {
+        tracing::error!("❌ Stage generation task for {} failed", id);
+        (Err(StatusCode::INTERNAL_SERVER_ERROR.into()), None)
+    });
+    if let Some(revision) = written {
+        precondition::record_written(revision);
+    }
+    let Json(stage) = generated?;
+    Ok(stage)
+}
+
+// Move a lifecycle through the review workflow (draft → in_review → approved → published)
+pub async fn set_review_status(
+    Path(id): Path<Uuid>,
+    State(state): State<AppState>,
+    Json(body): Json<ReviewStatusRequest>
+) -> Result<Json<Lifecycle>, ApiError> {
+    modify_lifecycle(&state, id, |lifecycle| {
+        lifecycle.review_status = body.status;
+        lifecycle.updated_at = Utc::now();
+        tracing::info!("📝 Lifecycle {} review status set to {:?}", id, body.status);
+        Ok(Json(lifecycle.clone()))
+    }).await
+}
+
+/// Static map per stage (keyed by stage index) for every stage that has locations.
+async fn render_stage_maps(state: &AppState, lifecycle: &Lifecycle) -> HashMap<usize, RgbaImage> {
+    let mut maps = HashMap::new();
+    for (i, stage) in lifecycle.stages.iter().enumerate() {
+        if let Some(map) = state.maps.render(&stage.locations, 480, 360).await {
+            maps.insert(i, map);
         }
     }
-    
-    tracing::info!("✅ Generated image for stage: {}", stage_name);
-    Ok(Json(generated_stage))
+    maps
+}
+
+/// The export profile named by `?profile=` with the page format from `?size=` and `?orientation=`, or a 400 listing
+/// the configured ones.
+fn export_profile(export: &ExportConfig, q: &ExportQuery) -> Result<ExportProfile, ApiError> {
+    let mut profile = export.profile(q.profile.as_deref()).ok_or_else(|| {
+        ApiError::new(StatusCode::BAD_REQUEST, "unknown_profile", format!("No export profile named '{}'", q.profile.as_deref().unwrap_or_default()))
+            .with_details(serde_json::json!({ "profiles": export.profiles.keys().collect::<Vec<_>>() }))
+    })?;
+    profile.page_size = q.size.unwrap_or(profile.page_size);
+    profile.orientation = q.orientation.unwrap_or(profile.orientation);
+    Ok(profile)
+}
+
+/// The theme sent inline with a `POST` export, else the one named by `?theme=` or the profile, the stock look without
+/// any, or a 400 listing the configured ones.
+fn export_theme(config: &AppConfig, q: &ExportQuery, profile: &ExportProfile, inline: Option<&ExportTheme>) -> Result<ThemeStyle, ApiError> {
+    if let Some(theme) = inline {
+        return Ok(theme.style(&config.palette));
+    }
+    let Some(name) = q.theme.as_deref().or(profile.theme.as_deref()) else {
+        return Ok(ExportTheme::default().style(&config.palette));
+    };
+    let theme = config.export.themes.get(name).ok_or_else(|| {
+        ApiError::new(StatusCode::BAD_REQUEST, "unknown_theme", format!("No export theme named '{}'", name))
+            .with_details(serde_json::json!({ "themes": config.export.themes.keys().collect::<Vec<_>>() }))
+    })?;
+    Ok(theme.style(&config.palette))
+}
+
+pub async fn export_pdf(Path(id): Path<Uuid>, Query(q): Query<ExportQuery>, headers: HeaderMap, State(state): State<AppState>) -> Result<Response, ApiError> {
+    pdf_export(state, id, q, headers, None).await
+}
+
+// PDF export branded with a one-off theme from the body instead of a configured one
+pub async fn export_pdf_with_theme(Path(id): Path<Uuid>, Query(q): Query<ExportQuery>, headers: HeaderMap, State(state): State<AppState>, Json(req): Json<ThemedExportRequest>) -> Result<Response, ApiError> {
+    pdf_export(state, id, q, headers, Some(req.theme)).await
+}
+
+async fn pdf_export(state: AppState, id: Uuid, q: ExportQuery, headers: HeaderMap, inline: Option<ExportTheme>) -> Result<Response, ApiError> {
+    let profile = export_profile(&state.config.export, &q)?;
+    let style = export_theme(&state.config, &q, &profile, inline.as_ref())?;
+    let lifecycle = load_lifecycle(&state, id).await?.with_narrative(q.narrative.or(profile.narrative));
+    if let Some(since) = q.since_revision.filter(|since| *since > lifecycle.revision) {
+        return Err(ApiError::new(StatusCode::UNPROCESSABLE_ENTITY, "invalid_input", format!("since_revision {} is ahead of the current revision {}", since, lifecycle.revision)));
+    }
+    let mut filename = match &q.profile {
+        Some(name) => format!("lifecycle_{}_{}", id, name.replace(|c: char| !c.is_ascii_alphanumeric() && c != '-', "_")),
+        None => format!("lifecycle_{}", id),
+    };
+    if let Some(since) = q.since_revision {
+        filename = format!("{}_since_r{}", filename, since);
+    }
+    let filename = format!("{}.pdf", filename);
+
+    if q.run_async {
+        let job = state.exports.create(id, filename);
+        let token = job.token.clone();
+        let since = q.since_revision;
+        tracing::info!("📄 Queued PDF export of {} ({} stages)", id, lifecycle.stages.len());
+        tokio::spawn(state.shutdown.clone().track(async move {
+            state.exports.start(&token);
+            let result = render_pdf(&state, lifecycle, profile, style, since).await;
+            state.exports.finish(&token, result.map(bytes::Bytes::from));
+        }));
+        let mut response = (StatusCode::ACCEPTED, Json(job.clone())).into_response();
+        if let Ok(location) = job.status_url.parse() {
+            response.headers_mut().insert(axum::http::header::LOCATION, location);
+        }
+        return Ok(response);
+    }
+
+    // The PDF embeds its creation time, so validate against the content it is rendered from instead. Edits change
+    // that content anyway; the revision and export bookkeeping alone must not defeat `If-None-Match`.
+    let mut versioned = lifecycle.clone();
+    versioned.last_exported_at = None;
+    versioned.revision = 0;
+    let mut content = serde_json::to_vec(&versioned).unwrap_or_default();
+    content.extend(q.profile.as_deref().unwrap_or_default().bytes());
+    match &inline {
+        Some(theme) => content.extend(serde_json::to_vec(theme).unwrap_or_default()),
+        None => content.extend(q.theme.as_deref().unwrap_or_default().bytes()),
+    }
+    content.extend(format!("{}-{}", profile.page_size.as_str(), profile.orientation.as_str()).bytes());
+    content.extend(q.since_revision.map(|since| since.to_string()).unwrap_or_default().bytes());
+    let validators = Validators::new(&content, lifecycle.last_activity());
+    let policy = &state.config.caching.export;
+    if validators.is_fresh(&headers) {
+        return Ok(validators.not_modified(policy));
+    }
+    let pdf_bytes = render_pdf(&state, lifecycle, profile, style, q.since_revision).await.map_err(|e| ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "export_failed", e))?;
+    let mut response_headers = HeaderMap::new();
+    response_headers.insert(axum::http::header::CONTENT_TYPE, "application/pdf".parse().unwrap());
+    response_headers.insert(axum::http::header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", filename).parse().unwrap());
+    Ok(validators.apply(policy, (StatusCode::OK, response_headers, pdf_bytes)))
+}
+
+/// Render the PDF off the async workers and note the export on the lifecycle.
+async fn render_pdf(state: &AppState, lifecycle: Lifecycle, profile: ExportProfile, style: ThemeStyle, since_revision: Option<u64>) -> Result<Vec<u8>, String> {
+    let id = lifecycle.id;
+    let maps = render_stage_maps(state, &lifecycle).await;
+    let provenance = Provenance::new(&lifecycle, state.images.provider(), state.gemini.text_source(), state.gemini.models.table());
+    let config = state.config.clone();
+    let pdf_bytes = tokio::task::spawn_blocking(move || {
+        generate_pdf(&lifecycle, &config.export, &style, &profile, &maps, &provenance, since_revision)
+    }).await.map_err(|e| format!("PDF rendering failed: {}", e))?;
+    note_export(state, id).await;
+    Ok(pdf_bytes)
+}
+
+// Poll a background export: 202 with its status while rendering, the file once done
+pub async fn get_export(Path(token): Path<String>, State(state): State<AppState>) -> Result<Response, ApiError> {
+    let job = state.exports.get(&token)
+        .ok_or_else(|| ApiError::new(StatusCode::NOT_FOUND, "export_not_found", "Unknown or expired export token"))?;
+    match (job.state, job.content.clone()) {
+        (JobState::Succeeded, Some(content)) => Ok((
+            [
+                (axum::http::header::CONTENT_TYPE, "application/pdf".to_string()),
+                (axum::http::header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", job.filename)),
+            ],
+            content,
+        ).into_response()),
+        (JobState::Failed, _) => Err(ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "export_failed", job.error.unwrap_or_default())),
+        _ => Ok((StatusCode::ACCEPTED, [(axum::http::header::RETRY_AFTER, "1")], Json(job)).into_response()),
+    }
+}
+
+// Archival bundle: lifecycle JSON, native-format images, prompts, provenance, the rendered PDF and attachments
+pub async fn export_bundle(Path(id): Path<Uuid>, Query(q): Query<ExportQuery>, State(state): State<AppState>) -> Result<Response, ApiError> {
+    let profile = export_profile(&state.config.export, &q)?;
+    let style = export_theme(&state.config, &q, &profile, None)?;
+    let lifecycle = load_lifecycle(&state, id).await?.with_narrative(q.narrative.or(profile.narrative));
+    let maps = render_stage_maps(&state, &lifecycle).await;
+    let provenance = Provenance::new(&lifecycle, state.images.provider(), state.gemini.text_source(), state.gemini.models.table());
+    let pdf_bytes = generate_pdf(&lifecycle, &state.config.export, &style, &profile, &maps, &provenance, None);
+    let mut assets = Vec::new();
+    for (stage_index, stage) in lifecycle.stages.iter().enumerate() {
+        for asset in &stage.assets {
+            match state.blobs.get(id, asset.id).await {
+                Some(bytes) => assets.push(BundleAsset { id: asset.id, stage_index, filename: asset.filename.clone(), bytes }),
+                None => tracing::warn!("⚠️ Attachment {} of {} is missing from the blob store; left out of the bundle", asset.id, id),
+            }
+        }
+    }
+    let bundle = build_bundle(&lifecycle, &provenance, &pdf_bytes, &assets).map_err(|e| {
+        tracing::error!("❌ Building the bundle for {} failed: {}", id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    note_export(&state, id).await;
+    tracing::info!("📦 Bundled lifecycle {} ({} bytes, {} attachment(s))", id, bundle.len(), assets.len());
+    Ok((
+        [
+            (axum::http::header::CONTENT_TYPE, "application/gzip".to_string()),
+            (axum::http::header::CONTENT_DISPOSITION, format!("attachment; filename=\"lifecycle_{}.tar.gz\"", id)),
+        ],
+        bundle,
+    ).into_response())
+}
+
+// Publishing checklist: missing or placeholder images, descriptions needing review, alt text and impact estimates
+pub async fn quality_report(Path(id): Path<Uuid>, State(state): State<AppState>) -> Result<Json<QualityReport>, ApiError> {
+    let lifecycle = load_lifecycle(&state, id).await?;
+    Ok(Json(QualityReport::for_lifecycle(&lifecycle)))
+}
+
+// ZIP for decks and docs: a Markdown summary, lifecycle JSON and every stage image as a plain file
+pub async fn export_zip(Path(id): Path<Uuid>, Query(q): Query<ExportQuery>, State(state): State<AppState>) -> Result<Response, ApiError> {
+    let profile = export_profile(&state.config.export, &q)?;
+    let lifecycle = load_lifecycle(&state, id).await?.with_narrative(q.narrative.or(profile.narrative));
+    let archive = build_zip(&lifecycle).map_err(|e| {
+        tracing::error!("❌ Building the ZIP export of {} failed: {}", id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    note_export(&state, id).await;
+    tracing::info!("🗜️ Zipped lifecycle {} ({} bytes)", id, archive.len());
+    Ok((
+        [
+            (axum::http::header::CONTENT_TYPE, "application/zip".to_string()),
+            (axum::http::header::CONTENT_DISPOSITION, format!("attachment; filename=\"lifecycle_{}.zip\"", id)),
+        ],
+        archive,
+    ).into_response())
+}
+
+// Render the lifecycle as Markdown for wikis and READMEs
+pub async fn export_markdown(Path(id): Path<Uuid>, Query(q): Query<ExportQuery>, headers: HeaderMap, State(state): State<AppState>) -> Result<Response, ApiError> {
+    let profile = export_profile(&state.config.export, &q)?;
+    let lifecycle = load_lifecycle(&state, id).await?.with_narrative(q.narrative.or(profile.narrative));
+    let origin = request_origin(&headers);
+    let markdown = markdown_report(&lifecycle, |index, stage, mime| match q.images {
+        MarkdownImages::Link => format!("{}/api/lifecycle/{}/stage/{}/image", origin, id, index),
+        MarkdownImages::Embed => format!("data:{};base64,{}", mime, stage.image_base64.as_deref().unwrap_or_default()),
+    });
+    note_export(&state, id).await;
+    tracing::info!("📝 Rendered lifecycle {} as Markdown ({} bytes)", id, markdown.len());
+    Ok(([(axum::http::header::CONTENT_TYPE, "text/markdown; charset=utf-8")], markdown).into_response())
+}
+
+// Import a lifecycle exported from this or another deployment (the `GET /api/lifecycle/{id}` document) under a new id
+pub async fn import_lifecycle(Query(images): Query<ImagesQuery>, State(state): State<AppState>, body: bytes::Bytes) -> Result<(StatusCode, Json<Lifecycle>), ApiError> {
+    let invalid = |message: String| ApiError::new(StatusCode::UNPROCESSABLE_ENTITY, "invalid_input", message);
+    let mut lifecycle: Lifecycle = serde_json::from_slice(&body).map_err(|e| invalid(format!("invalid lifecycle document: {}", e)))?;
+    let source_id = lifecycle.id;
+    lifecycle.product_description = lifecycle.product_description.trim().to_string();
+    if lifecycle.product_description.is_empty() {
+        return Err(invalid("product_description must not be empty".to_string()));
+    }
+    if lifecycle.stages.is_empty() {
+        return Err(invalid("A lifecycle needs at least one stage".to_string()));
+    }
+    if let Some(demo) = state.public_demo.as_ref().filter(|demo| lifecycle.stages.len() > demo.max_stages) {
+        return Err(invalid(format!("at most {} stages can be imported", demo.max_stages)));
+    }
+    for (index, stage) in lifecycle.stages.iter_mut().enumerate() {
+        let field = |name: &str| format!("stages[{}].{}", index, name);
+        stage.stage_name = patched_text(&field("stage_name"), Some(std::mem::take(&mut stage.stage_name)), MAX_STAGE_NAME_CHARS)?.unwrap_or_default();
+        if stage.description.chars().count() > MAX_STAGE_TEXT_CHARS || stage.prompt.chars().count() > MAX_STAGE_TEXT_CHARS {
+            return Err(invalid(format!("{} and {} must be at most {} characters", field("description"), field("prompt"), MAX_STAGE_TEXT_CHARS)));
+        }
+        stage.reattach_imported_images().map_err(|e| invalid(format!("{}: {}", field("image_base64"), e)))?;
+    }
+    for index in 0..lifecycle.stages.len() {
+        ensure_unique_stage_name(&lifecycle, &lifecycle.stages[index].stage_name, Some(index))?;
+    }
+    let constraints = state.config.tenants.constraints(lifecycle.tenant.as_deref(), Some(lifecycle.constraints.clone()));
+    if constraints != lifecycle.constraints {
+        let fitted = state.gemini.fit_prompt_inputs(&lifecycle.product_description, &constraints).await;
+        (lifecycle.constraints, lifecycle.prompt_inputs) = (constraints, fitted.inputs);
+        lifecycle.warnings.extend(fitted.warnings);
+    }
+
+    let id = Uuid::new_v4();
+    let mut notes = Vec::new();
+    for stage in &mut lifecycle.stages {
+        // Usage was billed where it happened; revisions restart with the new lifecycle.
+        stage.usage.clear();
+        stage.revisions = StageRevisions::default();
+        if !stage.has_image() && stage.status != StageStatus::Pending {
+            notes.push(format!("Stage {}: the export carried no image data; generate it again", stage.stage_name));
+            stage.status = StageStatus::Pending;
+        }
+        if !stage.assets.is_empty() {
+            notes.push(format!("Stage {}: {} attachment(s) left out; attachment files are not part of the export", stage.stage_name, stage.assets.len()));
+            stage.assets.clear();
+        }
+    }
+    let (workspace_id, owner) = workspaces::ownership();
+    lifecycle = Lifecycle {
+        id,
+        slug: Some(share_slug(&lifecycle.product_description, id)),
+        workspace_id,
+        owner,
+        share_links: Vec::new(),
+        updated_at: Utc::now(),
+        last_exported_at: None,
+        revision: 0,
+        cloned_from: None,
+        ..lifecycle
+    };
+    lifecycle.warnings.extend(notes);
+
+    state.repo.insert(&lifecycle).await?;
+    tracing::info!("📥 Imported lifecycle {} as {} ({} stages)", source_id, id, lifecycle.stages.len());
+    Ok((StatusCode::CREATED, Json(images.lifecycle(&state, lifecycle))))
+}
+
+// Stage metadata as CSV, one row per stage
+pub async fn export_csv(Path(id): Path<Uuid>, Query(q): Query<ExportQuery>, State(state): State<AppState>) -> Result<Response, ApiError> {
+    let profile = export_profile(&state.config.export, &q)?;
+    let lifecycle = load_lifecycle(&state, id).await?.with_narrative(q.narrative.or(profile.narrative));
+    note_export(&state, id).await;
+    Ok((
+        [
+            (axum::http::header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()),
+            (axum::http::header::CONTENT_DISPOSITION, format!("attachment; filename=\"lifecycle_{}.csv\"", id)),
+        ],
+        lifecycle.to_csv(),
+    ).into_response())
+}
+
+// Render the lifecycle as a single self-contained HTML file for email and archiving
+pub async fn export_html(Path(id): Path<Uuid>, Query(q): Query<ExportQuery>, State(state): State<AppState>) -> Result<Response, ApiError> {
+    html_export(state, id, q, None).await
+}
+
+// HTML export branded with a one-off theme from the body instead of a configured one
+pub async fn export_html_with_theme(Path(id): Path<Uuid>, Query(q): Query<ExportQuery>, State(state): State<AppState>, Json(req): Json<ThemedExportRequest>) -> Result<Response, ApiError> {
+    html_export(state, id, q, Some(req.theme)).await
+}
+
+async fn html_export(state: AppState, id: Uuid, q: ExportQuery, inline: Option<ExportTheme>) -> Result<Response, ApiError> {
+    let profile = export_profile(&state.config.export, &q)?;
+    let style = export_theme(&state.config, &q, &profile, inline.as_ref())?;
+    let lifecycle = load_lifecycle(&state, id).await?.with_narrative(q.narrative.or(profile.narrative));
+    let html = render_html(&lifecycle, &style);
+    note_export(&state, id).await;
+    tracing::info!("🌐 Rendered lifecycle {} as HTML ({} bytes)", id, html.len());
+    Ok((
+        [
+            (axum::http::header::CONTENT_TYPE, "text/html; charset=utf-8".to_string()),
+            (axum::http::header::CONTENT_DISPOSITION, format!("inline; filename=\"lifecycle_{}.html\"", id)),
+        ],
+        html,
+    ).into_response())
+}
+
+/// `scheme://host` the client reached this server at, honouring `X-Forwarded-Proto` / `X-Forwarded-Host` from a proxy.
+fn request_origin(headers: &HeaderMap) -> String {
+    let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok()).and_then(|v| v.split(',').next()).map(str::trim).filter(|v| !v.is_empty());
+    let scheme = header("x-forwarded-proto").unwrap_or("http");
+    let host = header("x-forwarded-host").or_else(|| header("host")).unwrap_or("localhost");
+    format!("{}://{}", scheme, host)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FailuresQuery {
+    #[serde(default = "default_failure_window")]
+    pub hours: i64,
+}
+
+fn default_failure_window() -> i64 { 24 }
+
+// Aggregate recent generation failures into hourly stage/provider buckets
+pub async fn failure_heatmap(Query(q): Query<FailuresQuery>, headers: HeaderMap, State(state): State<AppState>) -> Result<Json<FailureHeatmap>, ApiError> {
+    state.admin.check(&headers)?;
+    let hours = q.hours.clamp(1, 24 * 30);
+    Ok(Json(state.failures.heatmap(hours)))
+}
+
+// Placeholder and failure rates per prompt template version, to decide whether a template change should be rolled back
+pub async fn template_version_stats(headers: HeaderMap, State(state): State<AppState>) -> Result<Json<Vec<TemplateStats>>, ApiError> {
+    state.admin.check(&headers)?;
+    let all = state.repo.list().await?;
+    Ok(Json(template_stats(&all)))
+}
+
+// Preview what the retention policy would delete right now (never deletes)
+pub async fn retention_report(headers: HeaderMap, State(state): State<AppState>) -> Result<Json<RetentionReport>, ApiError> {
+    state.admin.check(&headers)?;
+    state.config.retention.sweep(state.repo.as_ref(), &state.audit, &state.blobs, true).await.map(Json).map_err(ApiError::from)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AuditQuery {
+    pub cursor: Option<String>,
+    #[serde(default = "default_audit_limit")]
+    pub limit: usize,
+}
+
+fn default_audit_limit() -> usize { 100 }
+
+// Most recent audit entries (retention deletions etc.)
+pub async fn audit_log(Query(q): Query<AuditQuery>, headers: HeaderMap, State(state): State<AppState>) -> Result<Json<Page<AuditEntry>>, ApiError> {
+    state.admin.check(&headers)?;
+    let after = parse_cursor(q.cursor.as_deref())?;
+    let cursor_of = |e: &AuditEntry| Cursor { at: e.at, id: e.id };
+    Ok(Json(paginate(state.audit.newest_first(), cursor_of, after, q.limit.clamp(1, 1000))))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BillingQuery {
+    /// `YYYY-MM`; the current month by default.
+    pub month: Option<String>,
+    #[serde(default)]
+    pub format: BillingFormat,
+}
+
+// Provider usage per tenant for one month, by stage and model, as JSON or CSV for chargeback
+pub async fn billing_export(Query(q): Query<BillingQuery>, headers: HeaderMap, State(state): State<AppState>) -> Result<Response, ApiError> {
+    state.admin.check(&headers)?;
+    let first_day = match &q.month {
+        Some(month) => parse_month(month).ok_or_else(|| ApiError::new(StatusCode::UNPROCESSABLE_ENTITY, "invalid_input", "month must be given as YYYY-MM"))?,
+        None => Utc::now().date_naive().with_day(1).unwrap_or_default(),
+    };
+    let all = state.repo.list().await?;
+    let report = BillingReport::for_month(&all, first_day);
+    Ok(match q.format {
+        BillingFormat::Json => Json(report).into_response(),
+        BillingFormat::Csv => (
+            [
+                (axum::http::header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()),
+                (axum::http::header::CONTENT_DISPOSITION, format!("attachment; filename=\"billing_{}.csv\"", report.month)),
+            ],
+            report.to_csv(),
+        ).into_response(),
+    })
+}
+
+// Prometheus scrape endpoint
+pub async fn metrics(State(state): State<AppState>) -> Response {
+    let mut body = String::new();
+    state.gemini.quota.render(&mut body);
+    STORE_LOCK_WAIT.render(&mut body);
+    PROVIDER_REQUEST_DURATION.render(&mut body);
+    ([(axum::http::header::CONTENT_TYPE, "text/plain; version=0.0.4")], body).into_response()
+}
+
+// Export profiles configured for `?profile=`
+pub async fn export_profiles(State(state): State<AppState>) -> Json<BTreeMap<String, ExportProfile>> {
+    Json(state.config.export.profiles.clone())
+}
+
+// Liveness plus the Gemini circuit breaker state
+pub async fn health(State(state): State<AppState>) -> Json<HealthReport> {
+    let gemini = state.gemini.breaker.snapshot();
+    let degraded_mode = state.gemini.degraded.snapshot();
+    let status = if gemini.state == BreakerState::Closed && degraded_mode.is_none() { "ok" } else { "degraded" };
+    Json(HealthReport { status, gemini, degraded_mode })
+}
+
+// Readiness probe; reports whether the server fell back to placeholders because of a key failure, and 503 once it
+// is shutting down so load balancers stop routing to it
+pub async fn readyz(State(state): State<AppState>) -> (StatusCode, Json<ReadinessReport>) {
+    let degraded_mode = state.gemini.degraded.snapshot();
+    if state.shutdown.is_draining() {
+        return (StatusCode::SERVICE_UNAVAILABLE, Json(ReadinessReport { status: "draining", degraded_mode }));
+    }
+    let status = if degraded_mode.is_none() { "ready" } else { "degraded" };
+    (StatusCode::OK, Json(ReadinessReport { status, degraded_mode }))
+}
+
+// Logical model names and the provider model ids they resolve to
+pub async fn model_aliases(headers: HeaderMap, State(state): State<AppState>) -> Result<Json<BTreeMap<String, String>>, ApiError> {
+    state.admin.check(&headers)?;
+    Ok(Json(state.gemini.models.table()))
+}
+
+// Current batch vs interactive generation slot usage
+pub async fn scheduler_stats(headers: HeaderMap, State(state): State<AppState>) -> Result<Json<SchedulerStats>, ApiError> {
+    state.admin.check(&headers)?;
+    Ok(Json(state.scheduler.stats()))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SpriteQuery {
+    #[serde(default = "default_sprite_w")]
+    pub w: u32,
+    #[serde(default = "default_sprite_h")]
+    pub h: u32,
+}
+
+fn default_sprite_w() -> u32 { 160 }
+fn default_sprite_h() -> u32 { 120 }
+
+/// The sprite, or `None` when the client's cached copy is still current (the sprite depends only on the
+/// tile size and the stage images, so those are what the validators cover).
+async fn sprite_for(state: &AppState, id: Uuid, q: &SpriteQuery, headers: &HeaderMap) -> Result<(Validators, Option<(Vec<u8>, SpriteIndex)>), ApiError> {
+    let lifecycle = load_lifecycle(state, id).await?;
+    let (w, h) = (q.w.clamp(16, 512), q.h.clamp(16, 512));
+    let mut inputs = format!("{}x{}", w, h).into_bytes();
+    for stage in &lifecycle.stages {
+        inputs.push(0);
+        inputs.extend_from_slice(stage.image_base64.as_deref().unwrap_or_default().as_bytes());
+    }
+    let validators = Validators::new(&inputs, lifecycle.last_activity());
+    if validators.is_fresh(headers) {
+        return Ok((validators, None));
+    }
+    let sprite = build_sprite(&lifecycle, w, h);
+    Ok((validators, Some(sprite)))
+}
+
+// All stage thumbnails as one PNG strip
+pub async fn thumbnail_sprite(Path(id): Path<Uuid>, Query(q): Query<SpriteQuery>, headers: HeaderMap, State(state): State<AppState>) -> Result<Response, ApiError> {
+    let policy = &state.config.caching.thumbnails;
+    Ok(match sprite_for(&state, id, &q, &headers).await? {
+        (validators, Some((png, _))) => validators.apply(policy, ([(axum::http::header::CONTENT_TYPE, "image/png")], png)),
+        (validators, None) => validators.not_modified(policy),
+    })
+}
+
+// Offsets of each stage within the thumbnail strip
+pub async fn thumbnail_sprite_index(Path(id): Path<Uuid>, Query(q): Query<SpriteQuery>, headers: HeaderMap, State(state): State<AppState>) -> Result<Response, ApiError> {
+    let policy = &state.config.caching.thumbnails;
+    Ok(match sprite_for(&state, id, &q, &headers).await? {
+        (validators, Some((_, index))) => validators.apply(policy, Json(index)),
+        (validators, None) => validators.not_modified(policy),
+    })
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ThumbnailQuery {
+    #[serde(default = "default_thumbnail_w")]
+    pub w: u32,
+}
+
+fn default_thumbnail_w() -> u32 { 200 }
+
+// Small JPEG of one stage image for gallery views (SVG placeholders are already small and are returned as is)
+pub async fn stage_thumbnail(Path((id, stage_index)): Path<(Uuid, usize)>, Query(q): Query<ThumbnailQuery>, headers: HeaderMap, State(state): State<AppState>) -> Result<Response, ApiError> {
+    let lifecycle = load_lifecycle(&state, id).await?;
+    let stage = lifecycle.stages.get(stage_index).ok_or_else(|| stage_not_found(stage_index))?;
+    let (bytes, mime) = stage_image_bytes(stage).ok_or(StatusCode::NOT_FOUND)?;
+    let w = q.w.clamp(16, 1024);
+    let mut inputs = format!("{}w", w).into_bytes();
+    inputs.extend_from_slice(&bytes);
+    let validators = Validators::new(&inputs, stage.last_updated);
+    let policy = &state.config.caching.thumbnails;
+    if validators.is_fresh(&headers) {
+        return Ok(validators.not_modified(policy));
+    }
+    if mime == "image/svg+xml" {
+        return Ok(validators.apply(policy, ([(axum::http::header::CONTENT_TYPE, mime)], bytes)));
+    }
+    let jpeg = jpeg_thumbnail(&bytes, w).ok_or_else(|| {
+        tracing::error!("❌ Stage {} image of {} could not be decoded for a thumbnail", stage_index, id);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    Ok(validators.apply(policy, ([(axum::http::header::CONTENT_TYPE, "image/jpeg")], jpeg)))
+}
+
+// Image stored by content hash; immutable, so cached for as long as clients like. Bucket-stored images
+// redirect to a fresh presigned URL
+pub async fn image_file(Path(image_ref): Path<String>, headers: HeaderMap, State(state): State<AppState>) -> Result<Response, ApiError> {
+    let files = state.image_files.as_ref().ok_or(StatusCode::NOT_FOUND)?;
+    if let Some(url) = files.redirect_url(&image_ref).filter(|_| is_image_ref(&image_ref)) {
+        return Ok(Redirect::temporary(&url).into_response());
+    }
+    let (bytes, modified) = files.read(&image_ref).await.ok_or(StatusCode::NOT_FOUND)?;
+    let validators = Validators::new(image_ref.as_bytes(), modified.into());
+    let policy = &state.config.caching.image_files;
+    if validators.is_fresh(&headers) {
+        return Ok(validators.not_modified(policy));
+    }
+    Ok(validators.apply(policy, ([(axum::http::header::CONTENT_TYPE, sniff_image_bytes(&bytes))], bytes)))
+}
+
+// Stored stage image as a plain image response (PNG/JPEG, or the SVG placeholder)
+pub async fn stage_image(Path((id, stage_index)): Path<(Uuid, usize)>, headers: HeaderMap, State(state): State<AppState>) -> Result<Response, ApiError> {
+    let lifecycle = load_lifecycle(&state, id).await?;
+    let stage = lifecycle.stages.get(stage_index).ok_or_else(|| stage_not_found(stage_index))?;
+    let (bytes, mime) = stage_image_bytes(stage).ok_or(StatusCode::NOT_FOUND)?;
+    let validators = Validators::new(&bytes, stage.last_updated);
+    let policy = &state.config.caching.image;
+    if validators.is_fresh(&headers) {
+        return Ok(validators.not_modified(policy));
+    }
+    Ok(validators.apply(policy, ([(axum::http::header::CONTENT_TYPE, mime)], bytes)))
+}
+
+// Issue short-lived signed links for the PDF export, stage images and attachments
+pub async fn download_links(Path(id): Path<Uuid>, Query(q): Query<ExportQuery>, State(state): State<AppState>) -> Result<Json<DownloadLinks>, ApiError> {
+    let profile = export_profile(&state.config.export, &q)?;
+    export_theme(&state.config, &q, &profile, None)?;
+    let lifecycle = load_lifecycle(&state, id).await?;
+    let encode = |value: &str| -> String {
+        value.bytes().map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        }).collect()
+    };
+    // Signed along with the path, so they can't be changed on the link.
+    let mut options = Vec::new();
+    if let Some(profile) = &q.profile {
+        options.push(format!("profile={}", encode(profile)));
+    }
+    if let Some(theme) = &q.theme {
+        options.push(format!("theme={}", encode(theme)));
+    }
+    if let Some(audience) = q.narrative {
+        options.push(format!("narrative={}", audience.as_str()));
+    }
+    if let Some(size) = q.size {
+        options.push(format!("size={}", size.as_str()));
+    }
+    if let Some(orientation) = q.orientation {
+        options.push(format!("orientation={}", orientation.as_str()));
+    }
+    if let Some(since) = q.since_revision {
+        options.push(format!("since_revision={}", since));
+    }
+    let pdf_path = format!("/dl/lifecycle/{}/pdf", id);
+    let pdf = state.signer.sign(&if options.is_empty() { pdf_path } else { format!("{}?{}", pdf_path, options.join("&")) });
+    let stages = lifecycle.stages.iter().enumerate().map(|(index, stage)| StageDownloadLinks {
+        index,
+        image: stage_image_bytes(stage).map(|_| state.signer.sign(&format!("/dl/lifecycle/{}/stage/{}/image", id, index)).url),
+        assets: stage.assets.iter().map(|asset| AssetDownloadLink {
+            id: asset.id,
+            filename: asset.filename.clone(),
+            url: state.signer.sign(&format!("/dl/lifecycle/{}/stage/{}/assets/{}", id, index, asset.id)).url,
+        }).collect(),
+    }).collect();
+    Ok(Json(DownloadLinks { expires_at: pdf.expires_at, pdf: pdf.url, stages }))
+}
+
+// List the supply-chain actors attached to a stage
+pub async fn list_stage_actors(
+    Path((id, stage_index)): Path<(Uuid, usize)>,
+    State(state): State<AppState>
+) -> Result<Json<Vec<SupplyChainActor>>, ApiError> {
+    let lifecycle = load_lifecycle(&state, id).await?;
+    let stage = lifecycle.stages.get(stage_index).ok_or_else(|| stage_not_found(stage_index))?;
+    Ok(Json(stage.actors.clone()))
+}
+
+// Attach a supply-chain actor to a stage; it is woven into the stage's prompts on the next generation
+pub async fn add_stage_actor(
+    Path((id, stage_index)): Path<(Uuid, usize)>,
+    State(state): State<AppState>,
+    Json(body): Json<NewActorRequest>
+) -> Result<(StatusCode, Json<SupplyChainActor>), ApiError> {
+    if body.name.trim().is_empty() {
+        return Err(ApiError::new(StatusCode::UNPROCESSABLE_ENTITY, "invalid_input", "Actor name must not be empty"));
+    }
+    modify_lifecycle(&state, id, |lifecycle| {
+        let stage = lifecycle.stages.get_mut(stage_index).ok_or_else(|| stage_not_found(stage_index))?;
+        let actor = SupplyChainActor {
+            id: Uuid::new_v4(),
+            role: body.role,
+            name: body.name.trim().to_string(),
+            country: body.country.map(|c| c.trim().to_string()).filter(|c| !c.is_empty()),
+        };
+        stage.actors.push(actor.clone());
+        stage.last_updated = Utc::now();
+        lifecycle.updated_at = Utc::now();
+        Ok((StatusCode::CREATED, Json(actor)))
+    }).await
+}
+
+// Remove a supply-chain actor from a stage
+pub async fn remove_stage_actor(
+    Path((id, stage_index, actor_id)): Path<(Uuid, usize, Uuid)>,
+    State(state): State<AppState>
+) -> Result<StatusCode, ApiError> {
+    modify_lifecycle(&state, id, |lifecycle| {
+        let stage = lifecycle.stages.get_mut(stage_index).ok_or_else(|| stage_not_found(stage_index))?;
+        let before = stage.actors.len();
+        stage.actors.retain(|a| a.id != actor_id);
+        if stage.actors.len() == before {
+            return Err(ApiError::new(StatusCode::NOT_FOUND, "actor_not_found", format!("No actor {} on stage {}", actor_id, stage_index)));
+        }
+        stage.last_updated = Utc::now();
+        lifecycle.updated_at = Utc::now();
+        Ok(())
+    }).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// List the files attached to a stage
+pub async fn list_stage_assets(
+    Path((id, stage_index)): Path<(Uuid, usize)>,
+    State(state): State<AppState>
+) -> Result<Json<Vec<StageAsset>>, ApiError> {
+    let lifecycle = load_lifecycle(&state, id).await?;
+    let stage = lifecycle.stages.get(stage_index).ok_or_else(|| stage_not_found(stage_index))?;
+    Ok(Json(stage.assets.clone()))
+}
+
+// Attach a real-world file (photo, certificate, datasheet) to a stage; the body is the file itself
+pub async fn upload_stage_asset(
+    Path((id, stage_index)): Path<(Uuid, usize)>,
+    Query(q): Query<AssetUploadQuery>,
+    headers: HeaderMap,
+    State(state): State<AppState>,
+    body: bytes::Bytes
+) -> Result<(StatusCode, Json<StageAsset>), ApiError> {
+    let filename = q.filename.trim().rsplit(['/', '\\']).next().unwrap_or_default().to_string();
+    if body.is_empty() || filename.is_empty() {
+        return Err(ApiError::new(StatusCode::UNPROCESSABLE_ENTITY, "invalid_input", "Upload needs a non-empty body and filename"));
+    }
+    let asset = StageAsset {
+        id: Uuid::new_v4(),
+        kind: q.kind,
+        filename,
+        content_type: headers.get(axum::http::header::CONTENT_TYPE).and_then(|v| v.to_str().ok()).unwrap_or("application/octet-stream").to_string(),
+        labels: q.labels.unwrap_or_default().split(',').map(|l| l.trim().to_string()).filter(|l| !l.is_empty()).collect(),
+        size_bytes: body.len(),
+        uploaded_at: Utc::now(),
+    };
+    load_lifecycle(&state, id).await?.stages.get(stage_index).ok_or_else(|| stage_not_found(stage_index))?;
+    state.blobs.put(id, asset.id, &body).await.map_err(|e| {
+        tracing::error!("❌ Could not store attachment for {}: {}", id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    let attached = modify_lifecycle(&state, id, |lifecycle| {
+        let stage = lifecycle.stages.get_mut(stage_index).ok_or_else(|| stage_not_found(stage_index))?;
+        stage.assets.push(asset.clone());
+        stage.last_updated = Utc::now();
+        lifecycle.updated_at = Utc::now();
+        Ok(())
+    }).await;
+    if let Err(status) = attached {
+        state.blobs.remove(id, asset.id).await;
+        return Err(status);
+    }
+    tracing::info!("📎 Attached {} to stage {} of {}", asset.filename, stage_index, id);
+    Ok((StatusCode::CREATED, Json(asset)))
+}
+
+fn asset_not_found(asset_id: Uuid) -> ApiError {
+    ApiError::new(StatusCode::NOT_FOUND, "asset_not_found", format!("No attachment {} on this stage", asset_id))
+}
+
+// Download an attached file
+pub async fn download_stage_asset(
+    Path((id, stage_index, asset_id)): Path<(Uuid, usize, Uuid)>,
+    headers: HeaderMap,
+    State(state): State<AppState>
+) -> Result<Response, ApiError> {
+    let lifecycle = load_lifecycle(&state, id).await?;
+    let stage = lifecycle.stages.get(stage_index).ok_or_else(|| stage_not_found(stage_index))?;
+    let asset = stage.assets.iter().find(|a| a.id == asset_id).ok_or_else(|| asset_not_found(asset_id))?;
+    // Uploads are never modified in place, so the asset id is as good a validator as the content.
+    let validators = Validators::new(asset.id.as_bytes(), asset.uploaded_at);
+    let policy = &state.config.caching.assets;
+    if validators.is_fresh(&headers) {
+        return Ok(validators.not_modified(policy));
+    }
+    let bytes = state.blobs.get(id, asset_id).await.ok_or_else(|| asset_not_found(asset_id))?;
+    let disposition = format!("attachment; filename=\"{}\"", asset.filename.replace('"', ""));
+    Ok(validators.apply(policy, (
+        [(axum::http::header::CONTENT_TYPE, asset.content_type.clone()), (axum::http::header::CONTENT_DISPOSITION, disposition)],
+        bytes,
+    )))
+}
+
+// Remove an attached file from a stage
+pub async fn remove_stage_asset(
+    Path((id, stage_index, asset_id)): Path<(Uuid, usize, Uuid)>,
+    State(state): State<AppState>
+) -> Result<StatusCode, ApiError> {
+    modify_lifecycle(&state, id, |lifecycle| {
+        let stage = lifecycle.stages.get_mut(stage_index).ok_or_else(|| stage_not_found(stage_index))?;
+        let before = stage.assets.len();
+        stage.assets.retain(|a| a.id != asset_id);
+        if stage.assets.len() == before {
+            return Err(asset_not_found(asset_id));
+        }
+        stage.last_updated = Utc::now();
+        lifecycle.updated_at = Utc::now();
+        Ok(())
+    }).await?;
+    state.blobs.remove(id, asset_id).await;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// Replace the list of locations for a stage
+pub async fn set_stage_locations(
+    Path((id, stage_index)): Path<(Uuid, usize)>,
+    State(state): State<AppState>,
+    Json(body): Json<Vec<StageLocation>>
+) -> Result<Json<Vec<StageLocation>>, ApiError> {
+    let valid = body.iter().all(|l| (-90.0..=90.0).contains(&l.lat) && (-180.0..=180.0).contains(&l.lon) && !l.label.trim().is_empty());
+    if !valid {
+        return Err(ApiError::new(StatusCode::UNPROCESSABLE_ENTITY, "invalid_input", "Locations need a label, a latitude within ±90 and a longitude within ±180"));
+    }
+    modify_lifecycle(&state, id, |lifecycle| {
+        let stage = lifecycle.stages.get_mut(stage_index).ok_or_else(|| stage_not_found(stage_index))?;
+        stage.locations = body;
+        stage.last_updated = Utc::now();
+        lifecycle.updated_at = Utc::now();
+        Ok(Json(stage.locations.clone()))
+    }).await
+}
+
+// Ask the model for per-stage cost composition and relative impact, stored on each stage
+pub async fn estimate_economics(Path(id): Path<Uuid>, State(state): State<AppState>) -> Result<Json<Lifecycle>, ApiError> {
+    let lifecycle = load_lifecycle(&state, id).await?;
+    let estimates = state.gemini.estimate_economics(&lifecycle).await.map_err(|e| {
+        tracing::error!("❌ Economics estimation failed for {}: {}", id, e);
+        ApiError::from(e)
+    })?;
+
+    modify_lifecycle(&state, id, |lifecycle| {
+        // Stages may have changed while the model was thinking; only apply when the shape still matches.
+        if lifecycle.stages.len() != estimates.len() {
+            return Err(ApiError::new(StatusCode::CONFLICT, "stages_changed", "Stages changed while estimating; retry"));
+        }
+        for (stage, estimate) in lifecycle.stages.iter_mut().zip(estimates) {
+            stage.economics = Some(estimate);
+        }
+        lifecycle.updated_at = Utc::now();
+        Ok(Json(lifecycle.clone()))
+    }).await
+}
+
+// Ask a vision model what the stored stage image actually shows
+pub async fn explain_stage_image(
+    Path((id, stage_index)): Path<(Uuid, usize)>,
+    State(state): State<AppState>
+) -> Result<Json<ImageExplanation>, ApiError> {
+    let (stage_name, image) = {
+        let lifecycle = load_lifecycle(&state, id).await?;
+        let stage = lifecycle.stages.get(stage_index).ok_or_else(|| stage_not_found(stage_index))?;
+        (stage.stage_name.clone(), stage.image_base64.clone().ok_or_else(no_image)?)
+    };
+    // Our SVG placeholders aren't real generations and vision models can't read them anyway.
+    if sniff_mime_type(&image) == "image/svg+xml" {
+        return Err(placeholder_image());
+    }
+
+    let explanation = state.scheduler.run(Lane::Interactive, state.gemini.explain_image(&image)).await.map_err(|e| {
+        tracing::error!("❌ Image explanation failed for stage {} of {}: {}", stage_index, id, e);
+        ApiError::from(e)
+    })?;
+    Ok(Json(ImageExplanation { stage_index, stage_name, explanation, explained_at: Utc::now() }))
+}
+
+fn no_image() -> ApiError {
+    ApiError::new(StatusCode::CONFLICT, "no_image", "The stage has no image yet")
+}
+
+fn placeholder_image() -> ApiError {
+    ApiError::new(StatusCode::UNPROCESSABLE_ENTITY, "placeholder_image", "The stage image is a placeholder, not a generated image")
+}
+
+/// A stage together with its raster image, rejecting missing images and SVG placeholders.
+async fn stage_with_raster_image(state: &AppState, id: Uuid, stage_index: usize) -> Result<(StageImage, String), ApiError> {
+    let mut lifecycle = load_lifecycle(state, id).await?;
+    if stage_index >= lifecycle.stages.len() {
+        return Err(stage_not_found(stage_index));
+    }
+    let stage = lifecycle.stages.swap_remove(stage_index);
+    let image = stage.image_base64.clone().ok_or_else(no_image)?;
+    if sniff_mime_type(&image) == "image/svg+xml" {
+        return Err(placeholder_image());
+    }
+    Ok((stage, image))
+}
+
+// Compare the stage image with its description and record mismatches as stage warnings
+pub async fn check_stage_consistency(
+    Path((id, stage_index)): Path<(Uuid, usize)>,
+    State(state): State<AppState>
+) -> Result<Json<ConsistencyCheck>, ApiError> {
+    let (stage, image) = stage_with_raster_image(&state, id, stage_index).await?;
+    let (check, usage) = usage::track(state.scheduler.run(Lane::Interactive, state.gemini.check_consistency(&image, &stage.description))).await;
+    let check = check.map_err(|e| {
+        tracing::error!("❌ Consistency check failed for stage {} of {}: {}", stage_index, id, e);
+        ApiError::from(e)
+    })?;
+
+    modify_lifecycle(&state, id, |lifecycle| {
+        let stage = lifecycle.stages.get_mut(stage_index).ok_or_else(|| stage_not_found(stage_index))?;
+        stage.apply_consistency(check.clone());
+        stage.usage.extend(usage);
+        lifecycle.updated_at = Utc::now();
+        Ok(Json(check))
+    }).await
+}
+
+// Regenerate the stage image steered by the recorded mismatches, then re-check it
+pub async fn regenerate_to_match(
+    Path((id, stage_index)): Path<(Uuid, usize)>,
+    Query(images): Query<ImagesQuery>,
+    State(state): State<AppState>
+) -> Result<Json<StageImage>, ApiError> {
+    let (StageImage { stage_name, prompt, description, consistency, resolution, .. }, image) = stage_with_raster_image(&state, id, stage_index).await?;
+
+    let (steered, usage) = usage::track(async {
+        let check = match consistency {
+            Some(check) => check,
+            None => state.gemini.check_consistency(&image, &description).await.map_err(ApiError::from)?,
+        };
+        if check.consistent {
+            return Err(ApiError::new(StatusCode::CONFLICT, "already_consistent", "The image already matches the description"));
+        }
+
+        let corrections: Vec<String> = check.mismatches.iter().map(|m| m.correction()).collect();
+        let steered_prompt = format!("{} The image must match this description: {}", prompt, corrections.join(" "));
+        let checked = state.scheduler.run(Lane::Interactive, state.images.generate_checked_image(&stage_name, &steered_prompt, resolution)).await;
+        let status = image_status(&checked.image);
+        let new_image = checked.image.ok();
+        let recheck = match &new_image {
+            Some(img) if sniff_mime_type(img) != "image/svg+xml" => state.gemini.check_consistency(img, &description).await.ok(),
+            _ => None,
+        };
+        Ok((checked.quality_checks, checked.warnings, status, new_image, recheck))
+    }).await;
+    let (quality_checks, warnings, status, new_image, recheck) = steered?;
+
+    let stage = modify_lifecycle(&state, id, |lifecycle| {
+        let stage = lifecycle.stages.get_mut(stage_index).ok_or_else(|| stage_not_found(stage_index))?;
+        if new_image.is_some() {
+            stage.set_image(new_image);
+            stage.status = status;
+        }
+        stage.quality_checks = quality_checks;
+        stage.warnings = warnings;
+        stage.usage.extend(usage);
+        stage.consistency = None;
+        if let Some(recheck) = recheck {
+            stage.apply_consistency(recheck);
+        }
+        stage.last_updated = Utc::now();
+        lifecycle.updated_at = Utc::now();
+        Ok(stage.clone())
+    }).await?;
+    state.events.publish(id, stage_index, &stage, &[StagePart::Image]);
+    Ok(Json(images.stage(&state, stage)))
+}
+
+// Replace the stage description with one of the stored candidates
+pub async fn select_description_candidate(
+    Path((id, stage_index)): Path<(Uuid, usize)>,
+    State(state): State<AppState>,
+    Json(body): Json<SelectCandidateRequest>
+) -> Result<Json<StageImage>, ApiError> {
+    let stage = modify_lifecycle(&state, id, |lifecycle| {
+        let stage = lifecycle.stages.get_mut(stage_index).ok_or_else(|| stage_not_found(stage_index))?;
+        let text = stage.description_candidates.get(body.index).cloned().ok_or_else(|| ApiError::new(StatusCode::UNPROCESSABLE_ENTITY, "invalid_input", format!("No description candidate at index {}", body.index)))?;
+        // Critique and glossary warnings were about the previous text; re-check the glossary for the new one.
+        let (text, glossary_warnings) = state.config.glossary.enforce(&text);
+        stage.warnings.retain(|w| !w.starts_with(UNSUPPORTED_CLAIM_PREFIX) && !w.starts_with(BANNED_PHRASE_PREFIX));
+        stage.warnings.extend(glossary_warnings);
+        stage.revise_description(text, RevisionSource::CandidateSelection, None);
+        lifecycle.updated_at = Utc::now();
+        tracing::info!("📝 Stage {} of {} now uses description candidate {}", stage_index, id, body.index);
+        Ok(stage.clone())
+    }).await?;
+    state.events.publish(id, stage_index, &stage, &[StagePart::Description]);
+    Ok(Json(stage))
+}
+
+// Edit a stage description by hand, optionally followed by an AI rewrite; the old text goes to the history
+pub async fn edit_stage_description(
+    Path((id, stage_index)): Path<(Uuid, usize)>,
+    State(state): State<AppState>,
+    Json(body): Json<DescriptionEditRequest>
+) -> Result<Json<StageImage>, ApiError> {
+    let manual = body.description.map(|d| d.trim().to_string()).filter(|d| !d.is_empty());
+    let instruction = body.rewrite_instruction.map(|i| i.trim().to_string()).filter(|i| !i.is_empty());
+    if manual.is_none() && instruction.is_none() {
+        return Err(ApiError::new(StatusCode::UNPROCESSABLE_ENTITY, "invalid_input", "Give a description, a rewrite_instruction, or both"));
+    }
+    let (stage_name, product, current) = {
+        let lifecycle = load_lifecycle(&state, id).await?;
+        let stage = lifecycle.stages.get(stage_index).ok_or_else(|| stage_not_found(stage_index))?;
+        (stage.stage_name.clone(), lifecycle.prompt_product().to_string(), stage.description.clone())
+    };
+
+    let mut usage = Vec::new();
+    let rewritten = match &instruction {
+        Some(instruction) => {
+            let base = manual.as_deref().unwrap_or(&current);
+            let (text, rewrite_usage) = usage::track(state.scheduler.run(Lane::Interactive, state.gemini.rewrite_description(&stage_name, &product, base, instruction))).await;
+            usage = rewrite_usage;
+            let text = text.map_err(|e| {
+                tracing::error!("❌ Description rewrite failed for stage {} of {}: {}", stage_index, id, e);
+                ApiError::from(e)
+            })?;
+            Some(text)
+        }
+        None => None,
+    };
+
+    let stage = modify_lifecycle(&state, id, |lifecycle| {
+        let stage = lifecycle.stages.get_mut(stage_index).ok_or_else(|| stage_not_found(stage_index))?;
+        if let Some(text) = manual {
+            stage.revise_description(text, RevisionSource::Manual, None);
+        }
+        if let Some(text) = rewritten {
+            stage.revise_description(text, RevisionSource::AiRewrite, instruction);
+        }
+        stage.usage.extend(usage);
+        recheck_edited_description(&state, stage);
+        lifecycle.updated_at = Utc::now();
+        tracing::info!("✏️ Edited description of stage {} of {}", stage_index, id);
+        Ok(stage.clone())
+    }).await?;
+    state.events.publish(id, stage_index, &stage, &[StagePart::Description]);
+    Ok(Json(stage))
+}
+
+// Warnings were raised against generated text; a human has now taken ownership of it.
+fn recheck_edited_description(state: &AppState, stage: &mut StageImage) {
+    stage.warnings.retain(|w| !w.starts_with(UNSUPPORTED_CLAIM_PREFIX) && !w.starts_with(BANNED_PHRASE_PREFIX));
+    stage.warnings.extend(state.config.glossary.enforce(&stage.description).1);
 }
 
-pub async fn export_pdf(Path(id): Path<Uuid>, State(state): State<AppState>) -> Response {
-    let store = state.store.read();
-    if let Some(lifecycle) = store.get(&id) {
-        let pdf_bytes = generate_pdf(lifecycle);
-        let mut headers = axum::http::HeaderMap::new();
-        headers.insert(axum::http::header::CONTENT_TYPE, "application/pdf".parse().unwrap());
-        headers.insert(axum::http::header::CONTENT_DISPOSITION, format!("attachment; filename=\"lifecycle_{}.pdf\"", id).parse().unwrap());
-        return (StatusCode::OK, headers, pdf_bytes).into_response();
+/// 409 when a stage other than `except` is already called `name` (case-insensitively).
+fn ensure_unique_stage_name(lifecycle: &Lifecycle, name: &str, except: Option<usize>) -> Result<(), ApiError> {
+    if lifecycle.stages.iter().enumerate().any(|(i, s)| Some(i) != except && s.stage_name.eq_ignore_ascii_case(name)) {
+        return Err(ApiError::new(StatusCode::CONFLICT, "duplicate_stage_name", format!("Another stage is already called {}", name)));
     }
-    StatusCode::NOT_FOUND.into_response()
+    Ok(())
+}
+
+pub(crate) const MAX_STAGE_NAME_CHARS: usize = 120;
+const MAX_STAGE_TEXT_CHARS: usize = 10_000;
+const MAX_ALT_TEXT_CHARS: usize = 500;
+const MAX_SHARE_LABEL_CHARS: usize = 200;
+
+/// Trimmed `value` if given, rejecting blank or overlong text.
+fn patched_text(field: &str, value: Option<String>, max_chars: usize) -> Result<Option<String>, ApiError> {
+    let Some(value) = value.map(|v| v.trim().to_string()) else { return Ok(None) };
+    if value.is_empty() || value.chars().count() > max_chars {
+        return Err(ApiError::new(StatusCode::UNPROCESSABLE_ENTITY, "invalid_input", format!("{} must be non-empty and at most {} characters", field, max_chars)));
+    }
+    Ok(Some(value))
+}
+
+// Correct a stage's description, name or prompt by hand, without regenerating anything
+pub async fn patch_stage(
+    Path((id, stage_index)): Path<(Uuid, usize)>,
+    State(state): State<AppState>,
+    Json(body): Json<StagePatchRequest>
+) -> Result<Json<StageImage>, ApiError> {
+    let description = patched_text("description", body.description, MAX_STAGE_TEXT_CHARS)?;
+    let stage_name = patched_text("stage_name", body.stage_name, MAX_STAGE_NAME_CHARS)?;
+    let prompt = patched_text("prompt", body.prompt, MAX_STAGE_TEXT_CHARS)?;
+    let alt_text = patched_text("alt_text", body.alt_text, MAX_ALT_TEXT_CHARS)?;
+    if description.is_none() && stage_name.is_none() && prompt.is_none() && alt_text.is_none() {
+        return Err(ApiError::new(StatusCode::UNPROCESSABLE_ENTITY, "invalid_input", "Give at least one of description, stage_name, prompt or alt_text"));
+    }
+
+    let stage = modify_lifecycle(&state, id, |lifecycle| {
+        if let Some(name) = &stage_name {
+            ensure_unique_stage_name(lifecycle, name, Some(stage_index))?;
+        }
+        let stage = lifecycle.stages.get_mut(stage_index).ok_or_else(|| stage_not_found(stage_index))?;
+        if let Some(text) = description.filter(|d| *d != stage.description) {
+            stage.revise_description(text, RevisionSource::Manual, None);
+            recheck_edited_description(&state, stage);
+        }
+        if let Some(name) = stage_name {
+            stage.stage_name = name;
+        }
+        if let Some(prompt) = prompt {
+            stage.prompt = prompt;
+        }
+        if alt_text.is_some() {
+            stage.alt_text = alt_text;
+        }
+        let now = Utc::now();
+        stage.last_updated = now;
+        lifecycle.updated_at = now;
+        tracing::info!("✏️ Patched stage {} of {}", stage_index, id);
+        Ok(stage.clone())
+    }).await?;
+    state.events.publish(id, stage_index, &stage, &[StagePart::Description]);
+    Ok(Json(stage))
+}
+
+// Change lifecycle constraints and tags; reports (and optionally regenerates) stages generated for other constraints
+pub async fn patch_lifecycle(
+    Path(id): Path<Uuid>,
+    Query(images): Query<ImagesQuery>,
+    State(state): State<AppState>,
+    Json(body): Json<LifecyclePatchRequest>
+) -> Result<Json<LifecyclePatchResult>, ApiError> {
+    let clean = |values: Vec<String>| values.into_iter().map(|v| v.trim().to_string()).filter(|v| !v.is_empty()).collect::<Vec<_>>();
+    let constraints = body.constraints.map(clean);
+    let tags = body.tags.map(clean);
+    if constraints.is_none() && tags.is_none() {
+        return Err(ApiError::new(StatusCode::UNPROCESSABLE_ENTITY, "invalid_input", "Give constraints, tags, or both"));
+    }
+    // The tenant's mandatory constraints stay, whatever the caller sent.
+    let (constraints, fitted) = match constraints {
+        Some(constraints) => {
+            let current = load_lifecycle(&state, id).await?;
+            let constraints = state.config.tenants.constraints(current.tenant.as_deref(), Some(constraints));
+            let fitted = state.gemini.fit_prompt_inputs(&current.product_description, &constraints).await;
+            (Some(constraints), Some(fitted))
+        }
+        None => (None, None),
+    };
+
+    let (lifecycle, invalidated) = modify_lifecycle(&state, id, |lifecycle| {
+        let mut constraints_changed = false;
+        if let (Some(constraints), Some(fitted)) = (constraints, fitted) {
+            constraints_changed = constraints != lifecycle.constraints;
+            lifecycle.constraints = constraints;
+            lifecycle.prompt_inputs = fitted.inputs;
+            lifecycle.warnings = fitted.warnings;
+        }
+        if let Some(tags) = tags {
+            lifecycle.tags = tags;
+        }
+        lifecycle.updated_at = Utc::now();
+        let invalidated: Vec<(usize, String)> = lifecycle.stages.iter().enumerate()
+            .filter(|(_, s)| constraints_changed && s.has_image() && !GeminiClient::prompt_matches_constraints(&s.prompt, lifecycle.prompt_constraints()))
+            .map(|(i, s)| (i, s.stage_name.clone()))
+            .collect();
+        tracing::info!("📝 Updated metadata of {}; {} stage(s) generated for other constraints", id, invalidated.len());
+        Ok((lifecycle.clone(), invalidated))
+    }).await?;
+
+    let invalidated = invalidated.into_iter().map(|(stage_index, stage_name)| {
+        let job_id = body.regenerate_affected.then(|| queue_stage_regeneration(&state, id, stage_index, &stage_name));
+        InvalidatedStage { stage_index, stage_name, job_id }
+    }).collect();
+    Ok(Json(LifecyclePatchResult { lifecycle: images.lifecycle(&state, lifecycle), invalidated }))
+}
+
+/// Regenerate a stage in the background on the batch lane; progress is tracked as a job.
+fn queue_stage_regeneration(state: &AppState, id: Uuid, stage_index: usize, stage_name: &str) -> Uuid {
+    let job_id = state.jobs.create(id, stage_index, stage_name);
+    let state = state.clone();
+    tokio::spawn(state.shutdown.clone().track(async move {
+        state.jobs.start(job_id);
+        let result = run_stage_generation(&state, id, stage_index, None, Lane::Batch).await;
+        if let Err(e) = &result {
+            tracing::error!("❌ Regeneration job {} for stage {} of {} failed: {}", job_id, stage_index, id, e.message);
+        }
+        state.jobs.finish(job_id, result.err().map(|e| e.message));
+    }));
+    job_id
+}
+
+// Status of a background job
+pub async fn get_job(Path(job_id): Path<Uuid>, State(state): State<AppState>) -> Result<Json<Job>, ApiError> {
+    state.jobs.get(job_id).map(Json).ok_or_else(|| ApiError::new(StatusCode::NOT_FOUND, "job_not_found", format!("No job with id {}", job_id)))
+}
+
+// Insert a pending stage; generate it afterwards with POST /stage/{index}
+pub async fn insert_stage(
+    Path(id): Path<Uuid>,
+    Query(images): Query<ImagesQuery>,
+    State(state): State<AppState>,
+    Json(body): Json<NewStageRequest>
+) -> Result<Json<Lifecycle>, ApiError> {
+    let stage_name = patched_text("stage_name", Some(body.stage_name), MAX_STAGE_NAME_CHARS)?.unwrap_or_default();
+    let lifecycle = modify_lifecycle(&state, id, |lifecycle| {
+        let index = body.index.unwrap_or(lifecycle.stages.len());
+        if index > lifecycle.stages.len() {
+            return Err(ApiError::new(StatusCode::UNPROCESSABLE_ENTITY, "invalid_input", format!("index must be at most {}", lifecycle.stages.len())));
+        }
+        ensure_unique_stage_name(lifecycle, &stage_name, None)?;
+        let stage = pending_stage(&stage_name, lifecycle.prompt_product(), lifecycle.resolution);
+        lifecycle.stages.insert(index, stage);
+        lifecycle.updated_at = Utc::now();
+        tracing::info!("➕ Inserted stage {} at {} in {}", stage_name, index, id);
+        Ok(lifecycle.clone())
+    }).await?;
+    Ok(Json(images.lifecycle(&state, lifecycle)))
+}
+
+// Remove a stage together with its attachments
+pub async fn delete_stage(
+    Path((id, stage_index)): Path<(Uuid, usize)>,
+    Query(images): Query<ImagesQuery>,
+    State(state): State<AppState>
+) -> Result<Json<Lifecycle>, ApiError> {
+    let (lifecycle, removed) = modify_lifecycle(&state, id, |lifecycle| {
+        if stage_index >= lifecycle.stages.len() {
+            return Err(stage_not_found(stage_index));
+        }
+        if lifecycle.stages.len() == 1 {
+            return Err(ApiError::new(StatusCode::UNPROCESSABLE_ENTITY, "invalid_input", "A lifecycle needs at least one stage"));
+        }
+        let removed = lifecycle.stages.remove(stage_index);
+        lifecycle.updated_at = Utc::now();
+        tracing::info!("➖ Removed stage {} ({}) from {}", stage_index, removed.stage_name, id);
+        Ok((lifecycle.clone(), removed))
+    }).await?;
+    // Stored images are shared by content hash and left to garbage collection.
+    for asset in &removed.assets {
+        state.blobs.remove(id, asset.id).await;
+    }
+    Ok(Json(images.lifecycle(&state, lifecycle)))
+}
+
+// Reorder stages by a permutation of their current indexes
+pub async fn reorder_stages(
+    Path(id): Path<Uuid>,
+    Query(images): Query<ImagesQuery>,
+    State(state): State<AppState>,
+    Json(body): Json<StageOrderRequest>
+) -> Result<Json<Lifecycle>, ApiError> {
+    let lifecycle = modify_lifecycle(&state, id, |lifecycle| {
+        let mut sorted = body.order.clone();
+        sorted.sort_unstable();
+        if !sorted.iter().copied().eq(0..lifecycle.stages.len()) {
+            return Err(ApiError::new(StatusCode::UNPROCESSABLE_ENTITY, "invalid_input", format!("order must list every stage index from 0 to {} exactly once", lifecycle.stages.len().saturating_sub(1))));
+        }
+        let mut stages: Vec<Option<StageImage>> = std::mem::take(&mut lifecycle.stages).into_iter().map(Some).collect();
+        lifecycle.stages = body.order.iter().filter_map(|&i| stages[i].take()).collect();
+        lifecycle.updated_at = Utc::now();
+        tracing::info!("🔀 Reordered stages of {}: {:?}", id, body.order);
+        Ok(lifecycle.clone())
+    }).await?;
+    Ok(Json(images.lifecycle(&state, lifecycle)))
+}
+
+// Write audience-specific variants (engineering, consumer, investor) of every generated stage description
+pub async fn generate_narratives(
+    Path(id): Path<Uuid>,
+    Query(images): Query<ImagesQuery>,
+    State(state): State<AppState>,
+    Json(body): Json<NarrativeRequest>
+) -> Result<Json<Lifecycle>, ApiError> {
+    let mut audiences = body.audiences;
+    audiences.sort();
+    audiences.dedup();
+    if audiences.is_empty() {
+        return Err(ApiError::new(StatusCode::UNPROCESSABLE_ENTITY, "invalid_input", "audiences must not be empty"));
+    }
+    let lifecycle = load_lifecycle(&state, id).await?;
+    let product = lifecycle.prompt_product();
+    let rewrites: Vec<(usize, String, String, Audience)> = lifecycle.stages.iter().enumerate()
+        .filter(|(_, s)| !matches!(s.status, StageStatus::Pending | StageStatus::Generating))
+        .flat_map(|(i, s)| audiences.iter().map(move |&audience| (i, s.stage_name.clone(), s.description.clone(), audience)))
+        .collect();
+
+    let state_ref = &state;
+    let written: Vec<_> = futures::stream::iter(rewrites)
+        .map(|(i, stage_name, description, audience)| async move {
+            let (text, usage) = usage::track(state_ref.scheduler.run(Lane::Batch, state_ref.gemini.rewrite_description(&stage_name, product, &description, audience.instruction()))).await;
+            (i, stage_name, audience, text, usage)
+        })
+        .buffer_unordered(state.scheduler.stage_fanout)
+        .collect()
+        .await;
+    let mut first_error = None;
+    let mut variants = Vec::new();
+    for (i, stage_name, audience, text, usage) in written {
+        match text {
+            Ok(text) => variants.push((i, stage_name, Some((audience, text)), usage)),
+            Err(e) => {
+                tracing::error!("❌ {} narrative for stage {} of {} failed: {}", audience.as_str(), i, id, e);
+                first_error.get_or_insert(e);
+                variants.push((i, stage_name, None, usage));
+            }
+        }
+    }
+    if let Some(e) = first_error.filter(|_| variants.iter().all(|(_, _, text, _)| text.is_none())) {
+        return Err(e.into());
+    }
+
+    let lifecycle = modify_lifecycle(&state, id, |lifecycle| {
+        let now = Utc::now();
+        for (i, stage_name, variant, usage) in variants {
+            // Stages may have been removed or reordered meanwhile.
+            let Some(stage) = lifecycle.stages.get_mut(i).filter(|s| s.stage_name == stage_name) else { continue };
+            stage.usage.extend(usage);
+            if let Some((audience, text)) = variant {
+                stage.narratives.insert(audience, text);
+                stage.last_updated = now;
+            }
+        }
+        lifecycle.updated_at = now;
+        tracing::info!("📚 Wrote {:?} narratives for {}", audiences, id);
+        Ok(lifecycle.clone())
+    }).await?;
+    Ok(Json(images.lifecycle(&state, lifecycle)))
+}
+
+// (Re)extract tooltip terms from the current stage description
+pub async fn extract_stage_terms(
+    Path((id, stage_index)): Path<(Uuid, usize)>,
+    State(state): State<AppState>
+) -> Result<Json<Vec<StageTerm>>, ApiError> {
+    let (stage_name, description) = {
+        let lifecycle = load_lifecycle(&state, id).await?;
+        let stage = lifecycle.stages.get(stage_index).ok_or_else(|| stage_not_found(stage_index))?;
+        (stage.stage_name.clone(), stage.description.clone())
+    };
+    let (terms, usage) = usage::track(state.scheduler.run(Lane::Interactive, state.gemini.extract_terms(&stage_name, &description))).await;
+    let terms = terms.map_err(|e| {
+        tracing::error!("❌ Term extraction failed for stage {} of {}: {}", stage_index, id, e);
+        ApiError::from(e)
+    })?;
+
+    modify_lifecycle(&state, id, |lifecycle| {
+        let stage = lifecycle.stages.get_mut(stage_index).ok_or_else(|| stage_not_found(stage_index))?;
+        // The description may have been edited meanwhile; don't attach terms extracted from stale text.
+        if stage.description != description {
+            return Err(ApiError::new(StatusCode::CONFLICT, "description_changed", "The description was edited while terms were being extracted; retry"));
+        }
+        stage.terms = terms.clone();
+        stage.usage.extend(usage);
+        lifecycle.updated_at = Utc::now();
+        Ok(Json(terms))
+    }).await
 }
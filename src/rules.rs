@@ -0,0 +1,51 @@
+use serde::Deserialize;
+
+/// Config-defined adjustment of the default stage list for products matching a category.
+///
+/// ```jsonc
+/// { "category": "perishables", "keywords": ["milk", "yogurt", "fresh"],
+///   "add_stages": [{ "name": "Cold Chain", "after": "Distribution" }], "remove_stages": [] }
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+pub struct CategoryRule {
+    pub category: String,
+    /// Case-insensitive substrings of the product description; any match applies the rule.
+    pub keywords: Vec<String>,
+    #[serde(default)]
+    pub add_stages: Vec<StageInsertion>,
+    #[serde(default)]
+    pub remove_stages: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct StageInsertion {
+    pub name: String,
+    /// Insert after this stage; appended at the end when missing or not found.
+    #[serde(default)]
+    pub after: Option<String>,
+}
+
+impl CategoryRule {
+    fn matches(&self, product: &str) -> bool {
+        let product = product.to_lowercase();
+        self.keywords.iter().any(|k| product.contains(&k.to_lowercase()))
+    }
+}
+
+/// Apply every matching rule, in config order, to `stages`. Returns the matched categories.
+pub fn apply_category_rules(rules: &[CategoryRule], product: &str, stages: &mut Vec<String>) -> Vec<String> {
+    let mut categories = Vec::new();
+    for rule in rules.iter().filter(|r| r.matches(product)) {
+        stages.retain(|s| !rule.remove_stages.iter().any(|r| r.eq_ignore_ascii_case(s)));
+        for insertion in &rule.add_stages {
+            if stages.iter().any(|s| s.eq_ignore_ascii_case(&insertion.name)) { continue; }
+            let position = insertion.after.as_ref()
+                .and_then(|after| stages.iter().position(|s| s.eq_ignore_ascii_case(after)))
+                .map(|i| i + 1)
+                .unwrap_or(stages.len());
+            stages.insert(position, insertion.name.clone());
+        }
+        categories.push(rule.category.clone());
+    }
+    categories
+}
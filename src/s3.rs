@@ -0,0 +1,188 @@
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use reqwest::{Client, Url};
+use sha2::{Digest, Sha256};
+use std::{io, time::Duration};
+use tracing::info;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// S3-compatible bucket (AWS, MinIO, R2, ...) addressed through SigV4 presigned URLs, so one signing path
+/// serves uploads, downloads handed to clients, listing and deletes.
+pub struct S3Store {
+    client: Client,
+    /// `S3_ENDPOINT` (default `https://s3.{region}.amazonaws.com`).
+    endpoint: Url,
+    bucket: String,
+    /// `S3_REGION` (default `us-east-1`).
+    region: String,
+    access_key_id: String,
+    secret_access_key: String,
+    /// `S3_PREFIX` (default `images/`): prepended to every object key.
+    prefix: String,
+    /// `S3_PRESIGN_SECS` (default 3600): lifetime of download URLs handed to clients.
+    pub presign_ttl: Duration,
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn hmac(key: &[u8], data: &str) -> Vec<u8> {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(data.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// SigV4 URI encoding: everything but unreserved characters is percent-encoded (`/` too unless `keep_slash`).
+fn uri_encode(s: &str, keep_slash: bool) -> String {
+    s.bytes().map(|b| match b {
+        b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (b as char).to_string(),
+        b'/' if keep_slash => "/".to_string(),
+        _ => format!("%{:02X}", b),
+    }).collect()
+}
+
+/// One object from a bucket listing.
+pub struct S3Object {
+    pub key: String,
+    pub last_modified: Option<DateTime<Utc>>,
+}
+
+impl S3Store {
+    /// `None` unless `S3_BUCKET` is set. Credentials come from `S3_ACCESS_KEY_ID` / `S3_SECRET_ACCESS_KEY`
+    /// (falling back to the standard `AWS_*` variables).
+    pub fn from_env() -> Option<Self> {
+        let bucket = std::env::var("S3_BUCKET").ok().filter(|b| !b.is_empty())?;
+        let var = |names: &[&str]| names.iter().find_map(|n| std::env::var(n).ok()).unwrap_or_default();
+        let region = std::env::var("S3_REGION").unwrap_or_else(|_| "us-east-1".to_string());
+        let endpoint = std::env::var("S3_ENDPOINT").unwrap_or_else(|_| format!("https://s3.{}.amazonaws.com", region));
+        let endpoint = match Url::parse(endpoint.trim_end_matches('/')) {
+            Ok(url) => url,
+            Err(e) => panic!("invalid S3_ENDPOINT {}: {}", endpoint, e),
+        };
+        info!("🪣 Storing stage images in S3 bucket {} at {}", bucket, endpoint);
+        Some(Self {
+            client: crate::outbound::client(),
+            endpoint,
+            bucket,
+            region,
+            access_key_id: var(&["S3_ACCESS_KEY_ID", "AWS_ACCESS_KEY_ID"]),
+            secret_access_key: var(&["S3_SECRET_ACCESS_KEY", "AWS_SECRET_ACCESS_KEY"]),
+            prefix: std::env::var("S3_PREFIX").unwrap_or_else(|_| "images/".to_string()),
+            presign_ttl: Duration::from_secs(std::env::var("S3_PRESIGN_SECS").ok().and_then(|v| v.parse().ok()).unwrap_or(3600).clamp(1, 604_800)),
+        })
+    }
+
+    pub fn key(&self, name: &str) -> String {
+        format!("{}{}", self.prefix, name)
+    }
+
+    /// Object name with the prefix stripped, `None` for keys outside it.
+    pub fn name<'a>(&self, key: &'a str) -> Option<&'a str> {
+        key.strip_prefix(&self.prefix)
+    }
+
+    /// Presigned (query-string SigV4) URL for `method` on `key` (`None` for the bucket itself), valid for `ttl`.
+    /// Path-style addressing, which every S3-compatible service accepts.
+    pub fn presign(&self, method: &str, key: Option<&str>, query: &[(&str, &str)], ttl: Duration, now: DateTime<Utc>) -> String {
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = &amz_date[..8];
+        let scope = format!("{}/{}/s3/aws4_request", date, self.region);
+        let credential = format!("{}/{}", self.access_key_id, scope);
+        let expires = ttl.as_secs().to_string();
+
+        let base_path = self.endpoint.path().trim_end_matches('/');
+        let path = match key {
+            Some(key) => format!("{}/{}/{}", base_path, uri_encode(&self.bucket, false), uri_encode(key, true)),
+            None => format!("{}/{}", base_path, uri_encode(&self.bucket, false)),
+        };
+        let mut params: Vec<(String, String)> = query.iter().map(|(k, v)| (uri_encode(k, false), uri_encode(v, false))).collect();
+        params.extend([
+            ("X-Amz-Algorithm", "AWS4-HMAC-SHA256"),
+            ("X-Amz-Credential", &credential),
+            ("X-Amz-Date", &amz_date),
+            ("X-Amz-Expires", &expires),
+            ("X-Amz-SignedHeaders", "host"),
+        ].map(|(k, v)| (k.to_string(), uri_encode(v, false))));
+        params.sort();
+        let canonical_query = params.iter().map(|(k, v)| format!("{}={}", k, v)).collect::<Vec<_>>().join("&");
+
+        let host = match self.endpoint.port() {
+            Some(port) => format!("{}:{}", self.endpoint.host_str().unwrap_or_default(), port),
+            None => self.endpoint.host_str().unwrap_or_default().to_string(),
+        };
+        let canonical_request = format!("{}\n{}\n{}\nhost:{}\n\nhost\nUNSIGNED-PAYLOAD", method, path, canonical_query, host);
+        let string_to_sign = format!("AWS4-HMAC-SHA256\n{}\n{}\n{}", amz_date, scope, hex(&Sha256::digest(canonical_request.as_bytes())));
+        let signing_key = ["s3", "aws4_request"].iter().fold(
+            hmac(&hmac(format!("AWS4{}", self.secret_access_key).as_bytes(), date), &self.region),
+            |key, part| hmac(&key, part),
+        );
+        let signature = hex(&hmac(&signing_key, &string_to_sign));
+        format!("{}://{}{}?{}&X-Amz-Signature={}", self.endpoint.scheme(), host, path, canonical_query, signature)
+    }
+
+    fn url(&self, method: &str, key: Option<&str>, query: &[(&str, &str)]) -> String {
+        self.presign(method, key, query, Duration::from_secs(300), Utc::now())
+    }
+
+    /// Download URL to hand to clients.
+    pub fn download_url(&self, name: &str) -> String {
+        self.presign("GET", Some(&self.key(name)), &[], self.presign_ttl, Utc::now())
+    }
+
+    async fn send(&self, request: reqwest::RequestBuilder) -> io::Result<reqwest::Response> {
+        let response = request.send().await.map_err(io::Error::other)?;
+        if response.status().is_success() {
+            return Ok(response);
+        }
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        Err(io::Error::other(format!("S3 returned {}: {}", status, body.chars().take(300).collect::<String>())))
+    }
+
+    pub async fn put(&self, name: &str, bytes: Vec<u8>, content_type: &str) -> io::Result<()> {
+        let url = self.url("PUT", Some(&self.key(name)), &[]);
+        self.send(self.client.put(url).header(reqwest::header::CONTENT_TYPE, content_type).body(bytes)).await.map(drop)
+    }
+
+    /// `None` when the object doesn't exist or can't be fetched.
+    pub async fn get(&self, name: &str) -> Option<Vec<u8>> {
+        let url = self.url("GET", Some(&self.key(name)), &[]);
+        self.send(self.client.get(url)).await.ok()?.bytes().await.ok().map(|b| b.to_vec())
+    }
+
+    pub async fn delete(&self, name: &str) -> io::Result<()> {
+        let url = self.url("DELETE", Some(&self.key(name)), &[]);
+        self.send(self.client.delete(url)).await.map(drop)
+    }
+
+    /// Every object under the prefix (ListObjectsV2, following continuation tokens).
+    pub async fn list(&self) -> io::Result<Vec<S3Object>> {
+        let mut objects = Vec::new();
+        let mut token: Option<String> = None;
+        loop {
+            let mut query = vec![("list-type", "2"), ("prefix", self.prefix.as_str())];
+            if let Some(token) = &token {
+                query.push(("continuation-token", token));
+            }
+            let body = self.send(self.client.get(self.url("GET", None, &query))).await?.text().await.map_err(io::Error::other)?;
+            for contents in body.split("<Contents>").skip(1) {
+                let Some(key) = xml_value(contents, "Key") else { continue };
+                let last_modified = xml_value(contents, "LastModified").and_then(|v| DateTime::parse_from_rfc3339(&v).ok()).map(|d| d.with_timezone(&Utc));
+                objects.push(S3Object { key, last_modified });
+            }
+            token = (xml_value(&body, "IsTruncated").as_deref() == Some("true")).then(|| xml_value(&body, "NextContinuationToken")).flatten();
+            if token.is_none() {
+                return Ok(objects);
+            }
+        }
+    }
+}
+
+/// Text of the first `<tag>` element; enough for the flat ListObjectsV2 response.
+fn xml_value(xml: &str, tag: &str) -> Option<String> {
+    let start = xml.find(&format!("<{}>", tag))? + tag.len() + 2;
+    let end = start + xml[start..].find(&format!("</{}>", tag))?;
+    Some(xml[start..end].replace("&amp;", "&").replace("&lt;", "<").replace("&gt;", ">").replace("&quot;", "\"").replace("&apos;", "'"))
+}
@@ -0,0 +1,95 @@
+use std::{future::Future, sync::atomic::{AtomicUsize, Ordering}};
+use serde::Serialize;
+use tokio::sync::Semaphore;
+use tracing::debug;
+
+/// Which budget a generation call is charged against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lane {
+    /// Full-lifecycle jobs (`generate_lifecycle` and friends).
+    Batch,
+    /// Single-stage requests a user is actively waiting on.
+    Interactive,
+}
+
+/// Two-queue scheduler: batch and interactive generations draw from separate semaphores so a large
+/// lifecycle job can never occupy the slots interactive users need.
+pub struct GenerationScheduler {
+    batch: LaneSlots,
+    interactive: LaneSlots,
+    /// How many stages of one lifecycle are generated at once; each still needs a batch slot.
+    pub stage_fanout: usize,
+}
+
+struct LaneSlots {
+    permits: Semaphore,
+    limit: usize,
+    waiting: AtomicUsize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LaneStats {
+    pub limit: usize,
+    pub in_flight: usize,
+    pub waiting: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SchedulerStats {
+    pub batch: LaneStats,
+    pub interactive: LaneStats,
+}
+
+impl LaneSlots {
+    fn new(limit: usize) -> Self {
+        let limit = limit.max(1);
+        Self { permits: Semaphore::new(limit), limit, waiting: AtomicUsize::new(0) }
+    }
+
+    fn stats(&self) -> LaneStats {
+        LaneStats {
+            limit: self.limit,
+            in_flight: self.limit - self.permits.available_permits(),
+            waiting: self.waiting.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl GenerationScheduler {
+    pub fn new(batch_limit: usize, interactive_limit: usize) -> Self {
+        Self { batch: LaneSlots::new(batch_limit), interactive: LaneSlots::new(interactive_limit), stage_fanout: 5 }
+    }
+
+    /// Reads `BATCH_GENERATION_CONCURRENCY` (default 2), `INTERACTIVE_GENERATION_CONCURRENCY` (default 4) and
+    /// `STAGE_GENERATION_CONCURRENCY` (default 5).
+    pub fn from_env() -> Self {
+        let read = |name: &str, default: usize| std::env::var(name).ok().and_then(|v| v.parse().ok()).unwrap_or(default);
+        Self {
+            stage_fanout: read("STAGE_GENERATION_CONCURRENCY", 5).max(1),
+            ..Self::new(read("BATCH_GENERATION_CONCURRENCY", 2), read("INTERACTIVE_GENERATION_CONCURRENCY", 4))
+        }
+    }
+
+    fn lane(&self, lane: Lane) -> &LaneSlots {
+        match lane {
+            Lane::Batch => &self.batch,
+            Lane::Interactive => &self.interactive,
+        }
+    }
+
+    /// Run `fut` once a slot in `lane` is free.
+    pub async fn run<F: Future>(&self, lane: Lane, fut: F) -> F::Output {
+        let l = self.lane(lane);
+        l.waiting.fetch_add(1, Ordering::Relaxed);
+        let permit = l.permits.acquire().await;
+        l.waiting.fetch_sub(1, Ordering::Relaxed);
+        debug!(?lane, in_flight = l.limit - l.permits.available_permits(), "generation slot acquired");
+        let out = fut.await;
+        drop(permit);
+        out
+    }
+
+    pub fn stats(&self) -> SchedulerStats {
+        SchedulerStats { batch: self.batch.stats(), interactive: self.interactive.stats() }
+    }
+}
@@ -0,0 +1,61 @@
+use tracing::{info, warn};
+
+use crate::{
+    gemini::placeholder_image,
+    models::{Lifecycle, StageStatus},
+    palette::Palette,
+    repository::LifecycleRepository,
+};
+
+/// Load the lifecycles in `SEED_FILE` (a JSON array in the `GET /api/lifecycle/{id}` shape) into the store, so
+/// demos and frontend work have content without spending API quota. Stages without an image get the SVG
+/// placeholder for their prompt. Lifecycles whose id is already stored are left alone, so restarting against a
+/// persistent database doesn't overwrite edits. Problems are logged; startup carries on.
+pub async fn seed_from_env(repo: &dyn LifecycleRepository, palette: &Palette) {
+    let Ok(path) = std::env::var("SEED_FILE") else { return };
+    let lifecycles: Vec<Lifecycle> = match std::fs::read_to_string(&path) {
+        Ok(raw) => match serde_json::from_str(&raw) {
+            Ok(lifecycles) => lifecycles,
+            Err(e) => {
+                warn!("⚠️ Invalid seed file {}: {}; nothing seeded", path, e);
+                return;
+            }
+        },
+        Err(e) => {
+            warn!("⚠️ Could not read seed file {}: {}; nothing seeded", path, e);
+            return;
+        }
+    };
+
+    let (mut seeded, mut skipped) = (0, 0);
+    for mut lifecycle in lifecycles {
+        match repo.get(lifecycle.id).await {
+            Ok(None) => {}
+            Ok(Some(_)) => {
+                skipped += 1;
+                continue;
+            }
+            Err(e) => {
+                warn!("⚠️ Could not check seed lifecycle {}: {}", lifecycle.id, e);
+                continue;
+            }
+        }
+        if lifecycle.slug.is_none() {
+            lifecycle.slug = Some(lifecycle.share_slug());
+        }
+        for stage in &mut lifecycle.stages {
+            let image = stage.image_base64.take().unwrap_or_else(|| placeholder_image(&stage.prompt, palette));
+            let alt_text = stage.alt_text.take();
+            stage.set_image(Some(image));
+            stage.alt_text = alt_text;
+            if matches!(stage.status, StageStatus::Pending | StageStatus::Generating) {
+                stage.status = StageStatus::Complete;
+            }
+        }
+        match repo.insert(&lifecycle).await {
+            Ok(()) => seeded += 1,
+            Err(e) => warn!("⚠️ Could not seed lifecycle {}: {}", lifecycle.id, e),
+        }
+    }
+    info!("🌱 Seeded {} lifecycle(s) from {} ({} already present)", seeded, path, skipped);
+}
@@ -0,0 +1,45 @@
+use rand::{distributions::Alphanumeric, Rng};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+/// Words of the product description kept in a share slug.
+const SLUG_WORDS: usize = 6;
+/// Hex digits of the lifecycle id closing a share slug; they alone identify the lifecycle.
+const SHORT_ID_LEN: usize = 8;
+/// Length of share tokens. All alphanumeric, so never mistaken for a slug, which is either `SHORT_ID_LEN` long or
+/// contains `-`.
+const TOKEN_LEN: usize = 32;
+
+/// `raw-materials` from `Raw Materials`, for file names and URLs.
+pub fn slug(name: &str) -> String {
+    let slug: String = name.to_lowercase().chars().map(|c| if c.is_ascii_alphanumeric() { c } else { '-' }).collect();
+    slug.split('-').filter(|part| !part.is_empty()).collect::<Vec<_>>().join("-")
+}
+
+/// Human-readable link name for a lifecycle, e.g. `insulated-stainless-steel-water-bottle-with-5eed0000`: the
+/// first words of the product and the start of the id.
+pub fn share_slug(product_description: &str, id: Uuid) -> String {
+    let words: Vec<String> = slug(product_description).split('-').take(SLUG_WORDS).map(str::to_string).collect();
+    let short_id = &id.simple().to_string()[..SHORT_ID_LEN];
+    if words.is_empty() { short_id.to_string() } else { format!("{}-{}", words.join("-"), short_id) }
+}
+
+/// The id prefix a share slug ends with; the product words may be stale or mistyped.
+pub fn short_id(slug: &str) -> Option<&str> {
+    let short_id = slug.rsplit('-').next()?;
+    (short_id.len() == SHORT_ID_LEN && short_id.bytes().all(|b| b.is_ascii_hexdigit())).then_some(short_id)
+}
+
+/// Random token for a public share link (`GET /share/{token}`).
+pub fn share_token() -> String {
+    rand::thread_rng().sample_iter(&Alphanumeric).take(TOKEN_LEN).map(char::from).collect()
+}
+
+pub fn is_share_token(s: &str) -> bool {
+    s.len() == TOKEN_LEN && s.bytes().all(|b| b.is_ascii_alphanumeric())
+}
+
+/// What is stored of a share token.
+pub fn token_digest(token: &str) -> String {
+    Sha256::digest(token.as_bytes()).iter().map(|b| format!("{:02x}", b)).collect()
+}
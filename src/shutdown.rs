@@ -0,0 +1,141 @@
+use axum::{extract::{Request, State}, http::{header, HeaderValue, StatusCode}, middleware::Next, response::{IntoResponse, Response}};
+use std::{future::{Future, IntoFuture}, sync::{atomic::{AtomicUsize, Ordering}, Arc}, time::Duration};
+use tokio::sync::{watch, Notify};
+use tracing::{error, info, warn};
+
+use crate::{error::ApiError, rate_limit::is_generation, repository::LifecycleRepository, routes::AppState};
+
+/// Graceful shutdown on SIGTERM / SIGINT: stop taking new generations, let open requests and detached generations
+/// finish, then flush the store, so a container restart doesn't drop work halfway through a generation.
+pub struct Shutdown {
+    draining: watch::Sender<bool>,
+    /// Detached work (streamed and batch generations, regeneration jobs, async exports) still running.
+    background: AtomicUsize,
+    idle: Notify,
+    /// `SHUTDOWN_GRACE_SECS` (default 60): how long to wait for that work before exiting anyway. Keep it below the
+    /// orchestrator's kill timeout (`docker stop -t`, `stop_grace_period`, `terminationGracePeriodSeconds`).
+    grace: Duration,
+}
+
+impl Shutdown {
+    pub fn from_env() -> Self {
+        let grace = std::env::var("SHUTDOWN_GRACE_SECS").ok().and_then(|v| v.parse().ok()).unwrap_or(60);
+        Self { draining: watch::channel(false).0, background: AtomicUsize::new(0), idle: Notify::new(), grace: Duration::from_secs(grace) }
+    }
+
+    pub fn is_draining(&self) -> bool {
+        *self.draining.borrow()
+    }
+
+    /// Resolves once a shutdown signal arrived.
+    pub async fn draining(&self) {
+        let _ = self.draining.subscribe().wait_for(|draining| *draining).await;
+    }
+
+    /// `future`, counted as background work the process waits for before exiting.
+    pub fn track<F: Future>(self: &Arc<Self>, future: F) -> impl Future<Output = F::Output> {
+        self.background.fetch_add(1, Ordering::SeqCst);
+        // Counted down on drop, so panicking or cancelled work doesn't hold up the exit.
+        let done = Done(self.clone());
+        async move {
+            let _done = done;
+            future.await
+        }
+    }
+
+    async fn background_finished(&self) {
+        loop {
+            let notified = self.idle.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+            if self.background.load(Ordering::SeqCst) == 0 {
+                return;
+            }
+            notified.await;
+        }
+    }
+
+    /// Resolves on SIGTERM or SIGINT, after marking the server as draining; for `with_graceful_shutdown`.
+    pub async fn signalled(self: Arc<Self>) {
+        signal().await;
+        self.draining.send_replace(true);
+    }
+
+    /// Run `server` until it was signalled, give open requests and background work the grace period, then flush
+    /// `repo`.
+    pub async fn drain(&self, server: impl IntoFuture<Output = std::io::Result<()>>, repo: Arc<dyn LifecycleRepository>) {
+        let server = server.into_future();
+        tokio::pin!(server);
+        tokio::select! {
+            // Draining first: an idle server finishes as soon as it's signalled.
+            biased;
+            _ = self.draining() => {
+                info!("🛑 Shutting down: finishing open requests and {} background generation(s), up to {}s", self.background.load(Ordering::SeqCst), self.grace.as_secs());
+                let drained = tokio::time::timeout(self.grace, async {
+                    let _ = (&mut server).await;
+                    self.background_finished().await;
+                }).await;
+                if drained.is_err() {
+                    warn!("⏱️ Grace period over with {} background generation(s) still running; exiting anyway", self.background.load(Ordering::SeqCst));
+                }
+            }
+            // Only ends unsignalled if the listener fails.
+            result = &mut server => if let Err(e) = result {
+                error!("❌ Server stopped: {}", e);
+            },
+        }
+        match repo.flush().await {
+            Ok(()) => info!("💾 Store flushed; bye"),
+            Err(e) => error!("❌ Flushing the store failed: {}", e),
+        }
+    }
+}
+
+struct Done(Arc<Shutdown>);
+
+impl Drop for Done {
+    fn drop(&mut self) {
+        if self.0.background.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.0.idle.notify_waiters();
+        }
+    }
+}
+
+/// SIGINT (Ctrl-C) or, on Unix, SIGTERM as sent by `docker stop`.
+async fn signal() {
+    let interrupt = async {
+        let _ = tokio::signal::ctrl_c().await;
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut terminate) => { terminate.recv().await; }
+            Err(e) => {
+                warn!("⚠️ Can't listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+    tokio::select! {
+        _ = interrupt => {}
+        _ = terminate => {}
+    }
+}
+
+/// 503 for new generations once shutdown began; everything else is still answered while open requests finish.
+pub async fn refuse_generations(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    if !state.shutdown.is_draining() {
+        return next.run(request).await;
+    }
+    let request = match is_generation(request).await {
+        Ok((request, false)) => return next.run(request).await,
+        Ok((request, true)) => request,
+        Err(error) => return error.into_response(),
+    };
+    warn!("🛑 Refused {} {}: shutting down", request.method(), request.uri().path());
+    let mut response = ApiError::new(StatusCode::SERVICE_UNAVAILABLE, "shutting_down", "The server is shutting down; retry shortly").into_response();
+    response.headers_mut().insert(header::RETRY_AFTER, HeaderValue::from_static("5"));
+    response
+}
@@ -0,0 +1,99 @@
+use axum::{extract::{Query, Request, State}, http::StatusCode, middleware::Next, response::Response};
+use chrono::{Duration, Utc};
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use serde::Deserialize;
+use sha2::Sha256;
+use tracing::{info, warn};
+
+use crate::{error::ApiError, models::SignedUrl, routes::AppState};
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Issues and checks short-lived HMAC-signed download links, so binaries can be fetched (or embedded in
+/// `<img>` tags) without credentials and without passing through the JSON endpoints.
+pub struct UrlSigner {
+    key: Vec<u8>,
+    /// `DOWNLOAD_URL_TTL_SECS` (default 300): how long an issued link stays valid.
+    pub ttl: Duration,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SignatureQuery {
+    pub expires: Option<i64>,
+    pub sig: Option<String>,
+}
+
+impl UrlSigner {
+    /// Reads `URL_SIGNING_KEY`; without it a random key is generated, so links don't survive a restart and
+    /// aren't valid across replicas.
+    pub fn from_env() -> Self {
+        let key = match std::env::var("URL_SIGNING_KEY") {
+            Ok(key) if !key.is_empty() => key.into_bytes(),
+            _ => {
+                info!("🔑 URL_SIGNING_KEY not set; signed download links are only valid until restart");
+                let mut key = vec![0u8; 32];
+                rand::thread_rng().fill_bytes(&mut key);
+                key
+            }
+        };
+        let ttl = std::env::var("DOWNLOAD_URL_TTL_SECS").ok().and_then(|v| v.parse().ok()).unwrap_or(300i64);
+        Self { key, ttl: Duration::seconds(ttl.max(1)) }
+    }
+
+    fn mac(&self, path: &str, expires: i64) -> HmacSha256 {
+        let mut mac = HmacSha256::new_from_slice(&self.key).expect("HMAC accepts keys of any length");
+        mac.update(path.as_bytes());
+        mac.update(b"\n");
+        mac.update(expires.to_string().as_bytes());
+        mac
+    }
+
+    /// Signed link to `target` (an absolute path such as `/dl/lifecycle/{id}/pdf`, optionally with a query string
+    /// of export options, which the signature covers too) valid for `ttl`.
+    pub fn sign(&self, target: &str) -> SignedUrl {
+        let expires_at = Utc::now() + self.ttl;
+        let expires = expires_at.timestamp();
+        let sig: String = self.mac(target, expires).finalize().into_bytes().iter().map(|b| format!("{:02x}", b)).collect();
+        let separator = if target.contains('?') { '&' } else { '?' };
+        SignedUrl { url: format!("{}{}expires={}&sig={}", target, separator, expires, sig), expires_at }
+    }
+
+    /// `path` is what `sign` was given: see `signed_target`.
+    pub fn verify(&self, path: &str, q: &SignatureQuery) -> Result<(), ApiError> {
+        let invalid = || ApiError::new(StatusCode::FORBIDDEN, "invalid_signature", "Download link is missing or has an invalid signature");
+        let (Some(expires), Some(sig)) = (q.expires, q.sig.as_deref()) else { return Err(invalid()) };
+        let sig = decode_hex(sig).ok_or_else(invalid)?;
+        // Constant-time comparison; check the signature first so a tampered expiry can't be probed for.
+        self.mac(path, expires).verify_slice(&sig).map_err(|_| invalid())?;
+        if Utc::now().timestamp() > expires {
+            return Err(ApiError::new(StatusCode::GONE, "link_expired", "Download link has expired; request a new one"));
+        }
+        Ok(())
+    }
+}
+
+fn decode_hex(s: &str) -> Option<Vec<u8>> {
+    if !s.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..s.len()).step_by(2).map(|i| u8::from_str_radix(s.get(i..i + 2)?, 16).ok()).collect()
+}
+
+/// What a link's signature covers: its path and every query parameter but `expires` and `sig`, in link order, so
+/// options can't be added to or changed on a signed link.
+fn signed_target(path: &str, query: Option<&str>) -> String {
+    let options: Vec<&str> = query.into_iter().flat_map(|q| q.split('&'))
+        .filter(|pair| !pair.is_empty() && !pair.starts_with("expires=") && !pair.starts_with("sig="))
+        .collect();
+    if options.is_empty() { path.to_string() } else { format!("{}?{}", path, options.join("&")) }
+}
+
+/// Guards the `/dl/...` routes: the request path and query must carry a valid, unexpired signature.
+pub async fn require_signature(State(state): State<AppState>, Query(q): Query<SignatureQuery>, req: Request, next: Next) -> Result<Response, ApiError> {
+    if let Err(e) = state.signer.verify(&signed_target(req.uri().path(), req.uri().query()), &q) {
+        warn!("🔏 Rejected signed download {}: {}", req.uri().path(), e.code);
+        return Err(e);
+    }
+    Ok(next.run(req).await)
+}
@@ -0,0 +1,61 @@
+use futures::future::{BoxFuture, FutureExt, Shared};
+use parking_lot::Mutex;
+use std::{collections::HashMap, future::Future, hash::Hash, sync::Arc};
+
+/// Coalesces concurrent identical work: while a call for `key` is running, further callers wait for its result
+/// instead of starting their own. The work runs as a detached task, so it completes (and the key is released)
+/// even when every caller disconnects.
+pub struct SingleFlight<K, V: Clone> {
+    in_flight: Mutex<HashMap<K, Shared<BoxFuture<'static, Option<V>>>>>,
+}
+
+impl<K, V: Clone> Default for SingleFlight<K, V> {
+    fn default() -> Self {
+        Self { in_flight: Mutex::new(HashMap::new()) }
+    }
+}
+
+impl<K, V> SingleFlight<K, V>
+where
+    K: Eq + Hash + Clone + Send + Sync + 'static,
+    V: Clone + Send + Sync + 'static,
+{
+    /// Result of the in-flight call for `key`, starting it with `start` if there is none. The flag says whether
+    /// this caller joined an existing call. `None` when the task panicked.
+    pub async fn run<F>(self: &Arc<Self>, key: K, start: impl FnOnce() -> F) -> (Option<V>, bool)
+    where
+        F: Future<Output = V> + Send + 'static,
+    {
+        let (shared, joined) = {
+            let mut in_flight = self.in_flight.lock();
+            match in_flight.get(&key) {
+                Some(shared) => (shared.clone(), true),
+                None => {
+                    let work = start();
+                    // Released when the task ends, panics included. It can't run before the insert below since
+                    // that happens under the same lock.
+                    let release = Release { flights: self.clone(), key: key.clone() };
+                    let task = tokio::spawn(async move {
+                        let _release = release;
+                        work.await
+                    });
+                    let shared = task.map(Result::ok).boxed().shared();
+                    in_flight.insert(key, shared.clone());
+                    (shared, false)
+                }
+            }
+        };
+        (shared.await, joined)
+    }
+}
+
+struct Release<K: Eq + Hash, V: Clone> {
+    flights: Arc<SingleFlight<K, V>>,
+    key: K,
+}
+
+impl<K: Eq + Hash, V: Clone> Drop for Release<K, V> {
+    fn drop(&mut self) {
+        self.flights.in_flight.lock().remove(&self.key);
+    }
+}
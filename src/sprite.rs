@@ -0,0 +1,55 @@
+use image::{imageops::FilterType, GenericImage, Rgba, RgbaImage};
+use serde::Serialize;
+use std::io::Cursor;
+
+use crate::{images::decode_raster, models::Lifecycle};
+
+/// Fill for stages without a decodable raster image (not generated yet, or an SVG placeholder).
+const EMPTY_TILE: Rgba<u8> = Rgba([226, 232, 240, 255]);
+
+#[derive(Debug, Serialize)]
+pub struct SpriteFrame {
+    pub stage_index: usize,
+    pub stage_name: String,
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    pub has_image: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SpriteIndex {
+    pub sprite_url: String,
+    pub width: u32,
+    pub height: u32,
+    pub frames: Vec<SpriteFrame>,
+}
+
+/// Lay every stage thumbnail out left to right in a single PNG strip, each cropped to `tile_w` x `tile_h`.
+pub fn build_sprite(lifecycle: &Lifecycle, tile_w: u32, tile_h: u32) -> (Vec<u8>, SpriteIndex) {
+    let count = lifecycle.stages.len().max(1) as u32;
+    let mut sheet = RgbaImage::from_pixel(tile_w * count, tile_h, EMPTY_TILE);
+    let mut frames = Vec::with_capacity(lifecycle.stages.len());
+
+    for (i, stage) in lifecycle.stages.iter().enumerate() {
+        let x = i as u32 * tile_w;
+        let thumb = decode_raster(stage).map(|img| img.resize_to_fill(tile_w, tile_h, FilterType::Triangle).to_rgba8());
+        let has_image = thumb.is_some();
+        if let Some(thumb) = thumb {
+            sheet.copy_from(&thumb, x, 0).ok();
+        }
+        frames.push(SpriteFrame { stage_index: i, stage_name: stage.stage_name.clone(), x, y: 0, width: tile_w, height: tile_h, has_image });
+    }
+
+    let (width, height) = sheet.dimensions();
+    let mut png = Vec::new();
+    image::DynamicImage::ImageRgba8(sheet).write_to(&mut Cursor::new(&mut png), image::ImageFormat::Png).ok();
+    let index = SpriteIndex {
+        sprite_url: format!("/api/lifecycle/{}/thumbnails.png?w={}&h={}", lifecycle.id, tile_w, tile_h),
+        width,
+        height,
+        frames,
+    };
+    (png, index)
+}
@@ -0,0 +1,168 @@
+use async_trait::async_trait;
+use chrono::Utc;
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::json;
+use std::{sync::Arc, time::Instant};
+use tracing::{error, info, warn};
+
+use crate::{
+    failures::{FailureKind, FailureLog, FailureRecord},
+    gemini::{placeholder_image, GeminiClient, GeminiError, StageContext},
+    images::downscale_base64,
+    metrics::PROVIDER_REQUEST_DURATION,
+    models::{Resolution, StageImage},
+    provider::ImageGenerator,
+    usage::{self, TokenUsage},
+    vision::CheckedImage,
+};
+
+pub const PROVIDER: &str = "stability";
+
+/// Stable Diffusion through the Stability AI REST API (`/v1/generation/{engine}/text-to-image`). Only images come
+/// from Stability; descriptions and the vision passes still run on `text` (Gemini).
+pub struct StabilityClient {
+    client: Client,
+    api_key: String,
+    base_url: String,
+    /// `STABILITY_ENGINE` (default `stable-diffusion-xl-1024-v1-0`).
+    engine: String,
+    /// `STABILITY_WIDTH` / `STABILITY_HEIGHT` (default 1024x1024); must be a size the engine supports.
+    width: u32,
+    height: u32,
+    failures: Arc<FailureLog>,
+    text: Arc<GeminiClient>,
+    /// Generated images are downscaled to fit this box (public demo mode).
+    max_image_px: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TextToImageResponse {
+    #[serde(default)]
+    artifacts: Vec<Artifact>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Artifact {
+    base64: String,
+    #[serde(default)]
+    finish_reason: String,
+}
+
+impl StabilityClient {
+    /// `None` unless `IMAGE_PROVIDER=stability` and `STABILITY_API_KEY` is set.
+    pub fn from_env(failures: Arc<FailureLog>, text: Arc<GeminiClient>) -> Option<Self> {
+        if std::env::var("IMAGE_PROVIDER").map(|v| v.to_lowercase()).as_deref() != Ok(PROVIDER) {
+            return None;
+        }
+        let Some(api_key) = std::env::var("STABILITY_API_KEY").ok().filter(|k| !k.is_empty()) else {
+            warn!("⚠️ IMAGE_PROVIDER=stability but STABILITY_API_KEY is not set; using Gemini for images");
+            return None;
+        };
+        let read = |name: &str, default: u32| std::env::var(name).ok().and_then(|v| v.parse().ok()).unwrap_or(default);
+        let engine = std::env::var("STABILITY_ENGINE").unwrap_or_else(|_| "stable-diffusion-xl-1024-v1-0".to_string());
+        let (width, height) = (read("STABILITY_WIDTH", 1024), read("STABILITY_HEIGHT", 1024));
+        info!("🎨 Generating stage images with Stability AI ({}, {}x{})", engine, width, height);
+        Some(Self {
+            client: crate::outbound::client(),
+            api_key,
+            base_url: std::env::var("STABILITY_API_BASE").unwrap_or_else(|_| "https://api.stability.ai".to_string()),
+            engine,
+            width,
+            height,
+            failures,
+            text,
+            max_image_px: None,
+        })
+    }
+
+    pub fn with_max_image_px(mut self, max_image_px: Option<u32>) -> Self {
+        self.max_image_px = max_image_px;
+        self
+    }
+
+    async fn text_to_image(&self, prompt: &str) -> Result<String, GeminiError> {
+        if let Some(chaos) = &self.text.chaos {
+            if let Some(fault) = chaos.provider_fault(PROVIDER, &self.engine).await {
+                return Err(fault.into_error());
+            }
+        }
+        let url = format!("{}/v1/generation/{}/text-to-image", self.base_url, self.engine);
+        let payload = json!({
+            "text_prompts": [{ "text": prompt, "weight": 1.0 }],
+            "width": self.width,
+            "height": self.height,
+            "samples": 1,
+            "cfg_scale": 7,
+        });
+        let started = Instant::now();
+        let response = self.client
+            .post(&url)
+            .bearer_auth(&self.api_key)
+            .header(reqwest::header::ACCEPT, "application/json")
+            .json(&payload)
+            .send()
+            .await
+            .map_err(|e| GeminiError::Http(e.to_string()))?;
+
+        let status = response.status();
+        info!("📥 Stability response status: {}", status);
+        self.text.quota.observe(PROVIDER, &self.engine, status.as_u16(), response.headers());
+        let body = response.text().await.map_err(|e| GeminiError::Http(e.to_string()))?;
+        PROVIDER_REQUEST_DURATION.observe(&[PROVIDER, &self.engine], started.elapsed());
+        if !status.is_success() {
+            error!("❌ Stability {} call failed with status {}: {}", self.engine, status, body);
+            return Err(GeminiError::Api { status: status.as_u16(), body });
+        }
+        let parsed: TextToImageResponse = serde_json::from_str(&body)
+            .map_err(|e| GeminiError::Other(format!("Failed to parse response: {}", e)))?;
+        // Filtered generations come back as a blurred image with finishReason CONTENT_FILTERED.
+        let artifact = parsed.artifacts.into_iter().find(|a| a.finish_reason != "CONTENT_FILTERED").ok_or(GeminiError::NoImage)?;
+        usage::record(Some(TokenUsage::images(PROVIDER, &self.engine, 1)));
+        Ok(artifact.base64)
+    }
+}
+
+#[async_trait]
+impl ImageGenerator for StabilityClient {
+    fn provider(&self) -> &'static str { PROVIDER }
+
+    /// Like Gemini, failures are recorded and replaced by a placeholder rather than returned.
+    async fn generate_image(&self, stage: &str, prompt: &str, resolution: Resolution) -> Result<String, GeminiError> {
+        match self.text_to_image(prompt).await {
+            Ok(image) => {
+                info!("✅ Stability generated image for stage '{}' ({} chars)", stage, image.len());
+                Ok(match resolution.max_px_within(self.max_image_px) {
+                    Some(px) => downscale_base64(image, px),
+                    None => image,
+                })
+            }
+            Err(e) => {
+                error!("❌ Stability image for stage '{}' failed: {}", stage, e);
+                self.failures.record(FailureRecord {
+                    stage_name: stage.to_string(),
+                    provider: PROVIDER.to_string(),
+                    model: self.engine.clone(),
+                    kind: FailureKind::Image,
+                    reason: e.reason(),
+                    occurred_at: Utc::now(),
+                });
+                info!("🔄 Falling back to placeholder image");
+                Ok(placeholder_image(prompt, &self.text.palette))
+            }
+        }
+    }
+
+    async fn generate_text(&self, prompt: &str) -> Result<String, GeminiError> {
+        ImageGenerator::generate_text(self.text.as_ref(), prompt).await
+    }
+
+    async fn generate_checked_image(&self, stage: &str, prompt: &str, resolution: Resolution) -> CheckedImage {
+        self.text.generate_checked_image_with(self, stage, prompt, resolution).await
+    }
+
+    async fn gen_stage_image(&self, ctx: StageContext<'_>) -> StageImage {
+        self.text.gen_stage_image_with(self, ctx).await
+    }
+}
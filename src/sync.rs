@@ -0,0 +1,96 @@
+use json_patch::Patch;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+use crate::models::Lifecycle;
+
+/// Patches buffered per subscriber; a client that falls further behind gets a snapshot instead.
+const CHANNEL_CAPACITY: usize = 256;
+
+/// RFC 6902 delta taking a lifecycle from `from_revision` to `revision`.
+#[derive(Debug, Clone, Serialize)]
+pub struct LifecyclePatch {
+    pub lifecycle_id: Uuid,
+    pub from_revision: u64,
+    pub revision: u64,
+    pub ops: Patch,
+}
+
+/// Server → client messages on `GET /api/lifecycle/{id}/sync`.
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SyncMessage<'a> {
+    /// Full document; sent on connect (unless the client is already current) and on every resync.
+    Snapshot { revision: u64, lifecycle: &'a Lifecycle },
+    /// The client's `?revision=` is current; only patches follow.
+    InSync { revision: u64 },
+    Patch { from_revision: u64, revision: u64, ops: &'a Patch },
+    Deleted,
+}
+
+/// What subscribers receive: a delta, or notice that the lifecycle is gone.
+#[derive(Debug, Clone)]
+pub enum LifecycleChange {
+    Patched(LifecyclePatch),
+    Deleted(Uuid),
+}
+
+impl LifecycleChange {
+    pub fn lifecycle_id(&self) -> Uuid {
+        match self {
+            LifecycleChange::Patched(patch) => patch.lifecycle_id,
+            LifecycleChange::Deleted(id) => *id,
+        }
+    }
+}
+
+impl LifecyclePatch {
+    /// Delta from the serialized previous state to `after`, whose revision has already been bumped.
+    pub fn between(before: &Value, after: &Lifecycle) -> Option<Self> {
+        let after_value = serde_json::to_value(after).ok()?;
+        Some(Self {
+            lifecycle_id: after.id,
+            from_revision: after.revision.saturating_sub(1),
+            revision: after.revision,
+            ops: json_patch::diff(before, &after_value),
+        })
+    }
+}
+
+/// Client → server messages.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClientMessage {
+    /// The client lost track (failed to apply a patch, saw a revision gap); answer with a snapshot.
+    Resync,
+}
+
+/// Fan-out of lifecycle changes to WebSocket subscribers (this instance only).
+pub struct LifecyclePatches {
+    sender: broadcast::Sender<LifecycleChange>,
+}
+
+impl Default for LifecyclePatches {
+    fn default() -> Self {
+        Self { sender: broadcast::channel(CHANNEL_CAPACITY).0 }
+    }
+}
+
+impl LifecyclePatches {
+    /// Whether anyone is listening; diffing is skipped otherwise since stages carry large base64 images.
+    pub fn has_subscribers(&self) -> bool {
+        self.sender.receiver_count() > 0
+    }
+
+    /// Published after the change has been stored.
+    pub fn publish(&self, change: LifecycleChange) {
+        // No subscribers is the common case and not an error.
+        let _ = self.sender.send(change);
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<LifecycleChange> {
+        self.sender.subscribe()
+    }
+}
@@ -0,0 +1,73 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::models::{Lifecycle, StageStatus};
+
+/// Version of the stage image prompt (`GeminiClient::build_stage_prompt`). Bump on every wording change so
+/// `/api/admin/templates` can compare placeholder and failure rates before and after.
+pub const IMAGE_PROMPT_VERSION: &str = "image-v1";
+/// Version of the stage description prompt (`GeminiClient::generate_stage_description`).
+pub const DESCRIPTION_PROMPT_VERSION: &str = "description-v1";
+
+/// Stages generated before templates were versioned.
+const UNVERSIONED: &str = "unversioned";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TemplateKind {
+    Image,
+    Description,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TemplateStats {
+    pub template: TemplateKind,
+    pub version: String,
+    /// Generated (non-pending) stages produced with this version that still carry its output.
+    pub stages: usize,
+    /// Image: SVG placeholder instead of a generated image. Description: canned fallback text.
+    pub placeholder_stages: usize,
+    /// Image generations that ended in `failed`; description failures always fall back, so they count above.
+    pub failed_stages: usize,
+    pub placeholder_rate: f64,
+    pub failure_rate: f64,
+}
+
+#[derive(Default)]
+struct Tally {
+    stages: usize,
+    placeholders: usize,
+    failed: usize,
+}
+
+/// Placeholder/failure rates per prompt template version across every stored stage, image templates first and
+/// versions in ascending order.
+pub fn template_stats(lifecycles: &[Lifecycle]) -> Vec<TemplateStats> {
+    let mut tallies: BTreeMap<(TemplateKind, String), Tally> = BTreeMap::new();
+    for stage in lifecycles.iter().flat_map(|l| &l.stages) {
+        if matches!(stage.status, StageStatus::Pending | StageStatus::Generating) {
+            continue;
+        }
+        let version = |v: &Option<String>| v.clone().unwrap_or_else(|| UNVERSIONED.to_string());
+
+        let image = tallies.entry((TemplateKind::Image, version(&stage.templates.image))).or_default();
+        image.stages += 1;
+        image.placeholders += usize::from(stage.is_placeholder());
+        image.failed += usize::from(matches!(stage.status, StageStatus::Failed { .. }));
+
+        let description = tallies.entry((TemplateKind::Description, version(&stage.templates.description))).or_default();
+        description.stages += 1;
+        description.placeholders += usize::from(stage.description_fallback);
+    }
+
+    let rate = |n: usize, of: usize| if of == 0 { 0.0 } else { n as f64 / of as f64 };
+    tallies.into_iter().map(|((template, version), t)| TemplateStats {
+        template,
+        version,
+        stages: t.stages,
+        placeholder_stages: t.placeholders,
+        failed_stages: t.failed,
+        placeholder_rate: rate(t.placeholders, t.stages),
+        failure_rate: rate(t.failed, t.stages),
+    }).collect()
+}
@@ -0,0 +1,35 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// Sustainability policy of one tenant, applied to every lifecycle created for it.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct TenantPolicy {
+    /// Constraints for requests that don't give any of their own.
+    pub default_constraints: Vec<String>,
+    /// Always part of the lifecycle's constraints, ahead of the caller's; requests cannot remove them.
+    pub mandatory_constraints: Vec<String>,
+}
+
+/// `tenants` config section, keyed by the `tenant` of generate requests (e.g. corporate policy such as
+/// "EU energy label context" that all of a customer's storyboards must reflect).
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(transparent)]
+pub struct TenantPolicies(HashMap<String, TenantPolicy>);
+
+impl TenantPolicies {
+    /// Constraints of a lifecycle of `tenant` whose caller asked for `requested` (`None`: gave none). Mandatory
+    /// constraints come first, so the prompt budget drops the caller's before them; repeats are left out.
+    pub fn constraints(&self, tenant: Option<&str>, requested: Option<Vec<String>>) -> Vec<String> {
+        let Some(policy) = tenant.and_then(|t| self.0.get(t)) else { return requested.unwrap_or_default() };
+        let requested = requested.unwrap_or_else(|| policy.default_constraints.clone());
+        let mut merged: Vec<String> = Vec::new();
+        for constraint in policy.mandatory_constraints.iter().cloned().chain(requested) {
+            let constraint = constraint.trim().to_string();
+            if !constraint.is_empty() && !merged.iter().any(|c| c.eq_ignore_ascii_case(&constraint)) {
+                merged.push(constraint);
+            }
+        }
+        merged
+    }
+}
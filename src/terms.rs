@@ -0,0 +1,64 @@
+use serde::Deserialize;
+use serde_json::json;
+use tracing::{info, warn};
+
+use crate::{gemini::{GeminiClient, GeminiError, TEXT_MODEL}, models::StageTerm, vision::parse_json_reply};
+
+/// Upper bound on terms kept per stage; tooltips on every other word help nobody.
+const MAX_TERMS: usize = 8;
+
+#[derive(Debug, Deserialize)]
+struct TermsReply {
+    #[serde(default)]
+    terms: Vec<StageTerm>,
+}
+
+impl GeminiClient {
+    /// Technical sustainability terms used in a description, each with a one-sentence plain-language definition.
+    pub async fn extract_terms(&self, stage: &str, description: &str) -> Result<Vec<StageTerm>, GeminiError> {
+        if self.is_demo() {
+            return Ok(Vec::new());
+        }
+        let instruction = format!(
+            "List the technical sustainability or engineering terms a non-expert might not know in this description of the {stage} stage \
+            (e.g. \"embodied carbon\", \"closed-loop recycling\"). Use each term exactly as it appears in the text. \
+            Give each a short plain-language definition (one sentence, max 25 words). At most {MAX_TERMS} terms.\n\
+            Reply with JSON only: {{\"terms\": [{{\"term\": \"...\", \"definition\": \"...\"}}]}}\n\n\
+            Description:\n{description}"
+        );
+        let payload = json!({
+            "contents": [{ "parts": [{"text": instruction}] }],
+            "generationConfig": { "temperature": 0.2, "responseMimeType": "application/json" }
+        });
+        let reply = self.generate_content(TEXT_MODEL, &payload).await?
+            .first_text()
+            .ok_or_else(|| GeminiError::Other("No text content found in response".to_string()))?;
+        let parsed: TermsReply = parse_json_reply(&reply)?;
+        // Only keep terms that really occur in the text, otherwise the UI has nothing to attach the tooltip to.
+        let lower = description.to_lowercase();
+        let mut terms: Vec<StageTerm> = parsed.terms.into_iter()
+            .filter(|t| !t.term.trim().is_empty() && lower.contains(&t.term.trim().to_lowercase()))
+            .map(|t| StageTerm { term: t.term.trim().to_string(), definition: t.definition.trim().to_string() })
+            .collect();
+        terms.dedup_by(|a, b| a.term.eq_ignore_ascii_case(&b.term));
+        terms.truncate(MAX_TERMS);
+        Ok(terms)
+    }
+
+    /// Term extraction run during generation when `EXTRACT_TERMS` is enabled; failures just yield no terms.
+    pub async fn auto_extract_terms(&self, stage: &str, description: &str) -> Vec<StageTerm> {
+        if !self.extract_terms {
+            return Vec::new();
+        }
+        match self.extract_terms(stage, description).await {
+            Ok(terms) => {
+                info!("📖 Stage '{}': extracted {} term(s)", stage, terms.len());
+                terms
+            }
+            Err(e) => {
+                warn!("⚠️ Term extraction failed for stage '{}': {}", stage, e);
+                Vec::new()
+            }
+        }
+    }
+}
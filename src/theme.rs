@@ -0,0 +1,133 @@
+use base64::Engine;
+use image::{imageops::FilterType, DynamicImage, Rgb, RgbImage};
+use serde::{Deserialize, Serialize, Serializer};
+use std::sync::Arc;
+
+use crate::palette::{HexColor, Palette};
+
+/// Typeface family of an export: PDFs use the matching built-in font, HTML a font stack.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FontFamily {
+    #[default]
+    Sans,
+    Serif,
+    Mono,
+}
+
+impl FontFamily {
+    pub fn css_stack(self) -> &'static str {
+        match self {
+            Self::Sans => "-apple-system,'Segoe UI',Roboto,Helvetica,Arial,sans-serif",
+            Self::Serif => "Georgia,'Times New Roman',Times,serif",
+            Self::Mono => "ui-monospace,Menlo,Consolas,'Courier New',monospace",
+        }
+    }
+}
+
+/// How much room the layout leaves between lines and sections.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Density {
+    Compact,
+    #[default]
+    Normal,
+    Comfortable,
+}
+
+impl Density {
+    /// Factor applied to the stock spacing.
+    pub fn scale(self) -> f32 {
+        match self {
+            Self::Compact => 0.85,
+            Self::Normal => 1.0,
+            Self::Comfortable => 1.2,
+        }
+    }
+}
+
+/// A named look for exports (`export.themes`, picked with `?theme=` or a profile's `theme`, or sent inline with a
+/// `POST` export), so each business unit or client gets reports in its visual identity. Unset colors keep the stock
+/// look.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExportTheme {
+    pub font: FontFamily,
+    /// Titles, headings and the PDF cost/impact chart.
+    pub accent: Option<HexColor>,
+    /// Secondary text: prompts, revision and metadata lines, attributions.
+    pub muted: Option<HexColor>,
+    pub density: Density,
+    /// On the PDF cover and running header and atop the HTML report.
+    pub logo: Option<Logo>,
+    /// Line in every PDF page footer and at the end of the HTML report, e.g. "Confidential - prepared for Acme".
+    pub footer: Option<String>,
+}
+
+/// A theme ready for the renderers, text colors adjusted to stay readable on the white page.
+#[derive(Debug, Clone)]
+pub struct ThemeStyle {
+    pub font: FontFamily,
+    pub heading: Option<HexColor>,
+    pub muted: Option<HexColor>,
+    pub chart_accent: HexColor,
+    pub spacing: f32,
+    pub logo: Option<Logo>,
+    pub footer: Option<String>,
+}
+
+/// A PNG or JPEG logo written as a `data:image/png;base64,...` (or `image/jpeg`) URI, in the config and request
+/// bodies alike so a request can't point the renderer at server files. Decoded when read.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Logo {
+    /// As given, for HTML reports.
+    pub data_uri: String,
+    /// At most `MAX_PX` a side with transparency flattened onto white, for PDFs (whose images have no alpha here).
+    pub image: Arc<DynamicImage>,
+}
+
+impl Logo {
+    const MAX_PX: u32 = 600;
+}
+
+impl TryFrom<String> for Logo {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let b64 = ["data:image/png;base64,", "data:image/jpeg;base64,"].iter()
+            .find_map(|prefix| value.strip_prefix(prefix))
+            .ok_or("invalid logo, expected a data:image/png;base64,... or data:image/jpeg;base64,... URI")?;
+        let bytes = base64::engine::general_purpose::STANDARD.decode(b64.trim()).map_err(|e| format!("invalid logo base64: {}", e))?;
+        let img = image::load_from_memory(&bytes).map_err(|e| format!("unreadable logo image: {}", e))?;
+        let img = if img.width() > Self::MAX_PX || img.height() > Self::MAX_PX { img.resize(Self::MAX_PX, Self::MAX_PX, FilterType::Triangle) } else { img };
+        let rgba = img.to_rgba8();
+        let flat = RgbImage::from_fn(rgba.width(), rgba.height(), |x, y| {
+            let [r, g, b, a] = rgba.get_pixel(x, y).0;
+            let over_white = |c: u8| ((c as u16 * a as u16 + 255 * (255 - a as u16)) / 255) as u8;
+            Rgb([over_white(r), over_white(g), over_white(b)])
+        });
+        Ok(Self { data_uri: value, image: Arc::new(DynamicImage::ImageRgb8(flat)) })
+    }
+}
+
+impl Serialize for Logo {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.data_uri)
+    }
+}
+
+impl ExportTheme {
+    pub fn style(&self, palette: &Palette) -> ThemeStyle {
+        let readable = |color: HexColor| color.with_contrast(HexColor::WHITE, palette.min_contrast());
+        ThemeStyle {
+            font: self.font,
+            heading: self.accent.map(readable),
+            muted: self.muted.map(readable),
+            chart_accent: self.accent.map_or_else(|| palette.chart_accent(), readable),
+            spacing: self.density.scale(),
+            logo: self.logo.clone(),
+            footer: self.footer.clone(),
+        }
+    }
+}
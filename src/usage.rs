@@ -0,0 +1,52 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::{cell::RefCell, future::Future};
+
+/// One successful provider call made on behalf of a stage, for cost attribution.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenUsage {
+    pub at: DateTime<Utc>,
+    pub provider: String,
+    pub model: String,
+    pub prompt_tokens: u64,
+    pub output_tokens: u64,
+    /// Images returned by the call; image providers that don't bill by token only report these.
+    pub images: u32,
+}
+
+impl TokenUsage {
+    /// Usage of a Gemini `generateContent` response, from its `usageMetadata` and inline image parts.
+    pub fn from_gemini(provider: &str, model: &str, body: &str) -> Option<Self> {
+        let json: serde_json::Value = serde_json::from_str(body).ok()?;
+        let metadata = json.get("usageMetadata")?;
+        let count = |field: &str| metadata.get(field).and_then(|v| v.as_u64()).unwrap_or(0);
+        let images = json["candidates"].as_array().into_iter().flatten()
+            .flat_map(|c| c["content"]["parts"].as_array().into_iter().flatten())
+            .filter(|p| p.get("inlineData").is_some())
+            .count() as u32;
+        Some(Self { at: Utc::now(), provider: provider.to_string(), model: model.to_string(), prompt_tokens: count("promptTokenCount"), output_tokens: count("candidatesTokenCount"), images })
+    }
+
+    pub fn images(provider: &str, model: &str, images: u32) -> Self {
+        Self { at: Utc::now(), provider: provider.to_string(), model: model.to_string(), prompt_tokens: 0, output_tokens: 0, images }
+    }
+}
+
+tokio::task_local! {
+    static USAGE: RefCell<Vec<TokenUsage>>;
+}
+
+/// Run `fut`, collecting the usage of every provider call made while it runs (on this task).
+pub async fn track<F: Future>(fut: F) -> (F::Output, Vec<TokenUsage>) {
+    USAGE.scope(RefCell::new(Vec::new()), async move {
+        let out = fut.await;
+        (out, USAGE.with(RefCell::take))
+    }).await
+}
+
+/// Note a provider call. Outside [`track`] (calls not made for a particular stage) it is dropped.
+pub fn record(usage: Option<TokenUsage>) {
+    if let Some(usage) = usage {
+        let _ = USAGE.try_with(|calls| calls.borrow_mut().push(usage));
+    }
+}
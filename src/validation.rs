@@ -0,0 +1,101 @@
+use async_trait::async_trait;
+use axum::{extract::{rejection::JsonRejection, FromRequest, Request}, http::StatusCode, response::{IntoResponse, Response}, Json};
+use serde::de::DeserializeOwned;
+
+use crate::{error::ApiError, models::GenerateRequest, routes::MAX_STAGE_NAME_CHARS};
+
+const MIN_PRODUCT_CHARS: usize = 3;
+/// Longer descriptions would only be shortened for the prompt budget anyway.
+const MAX_PRODUCT_CHARS: usize = 2_000;
+const MAX_STAGES: usize = 20;
+const MAX_CONSTRAINTS: usize = 20;
+const MAX_CONSTRAINT_CHARS: usize = 300;
+const MAX_TAGS: usize = 20;
+const MAX_TAG_CHARS: usize = 64;
+const MAX_TENANT_CHARS: usize = 100;
+
+/// Limits a request body is checked against once it has parsed.
+pub trait Validate {
+    fn validate(&self) -> Result<(), ApiError>;
+}
+
+/// `Json<T>` that also passes `T::validate`, so handlers only see bodies within limits. Failures are 422
+/// `invalid_input` with `details: {field, ...}` naming the offending field, e.g. `stages[3]`.
+pub struct Valid<T>(pub T);
+
+#[async_trait]
+impl<S: Send + Sync, T: DeserializeOwned + Validate> FromRequest<S> for Valid<T> {
+    type Rejection = ValidRejection;
+
+    async fn from_request(request: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let Json(value) = Json::<T>::from_request(request, state).await.map_err(ValidRejection::Json)?;
+        value.validate().map_err(ValidRejection::Invalid)?;
+        Ok(Self(value))
+    }
+}
+
+/// Either rejection of `Valid`: the body didn't parse (answered like a plain `Json` body) or broke a limit.
+pub enum ValidRejection {
+    Json(JsonRejection),
+    Invalid(ApiError),
+}
+
+impl IntoResponse for ValidRejection {
+    fn into_response(self) -> Response {
+        match self {
+            Self::Json(rejection) => rejection.into_response(),
+            Self::Invalid(error) => error.into_response(),
+        }
+    }
+}
+
+fn invalid(field: &str, message: String, mut details: serde_json::Value) -> ApiError {
+    details["field"] = field.into();
+    ApiError::new(StatusCode::UNPROCESSABLE_ENTITY, "invalid_input", message).with_details(details)
+}
+
+/// `value` must hold something other than whitespace, within `min..=max` characters once trimmed.
+fn text(field: &str, value: &str, min: usize, max: usize) -> Result<(), ApiError> {
+    let chars = value.trim().chars().count();
+    if chars == 0 {
+        return Err(invalid(field, format!("{} must not be blank", field), serde_json::json!({ "reason": "blank" })));
+    }
+    if chars < min || chars > max {
+        let reason = if chars < min { "too_short" } else { "too_long" };
+        return Err(invalid(field, format!("{} must be {} to {} characters (got {})", field, min, max, chars), serde_json::json!({ "reason": reason, "min": min, "max": max, "actual": chars })));
+    }
+    Ok(())
+}
+
+/// At most `max` entries, each non-blank and at most `max_chars` long.
+fn list(field: &str, values: &[String], max: usize, max_chars: usize) -> Result<(), ApiError> {
+    if values.len() > max {
+        return Err(invalid(field, format!("{} may have at most {} entries (got {})", field, max, values.len()), serde_json::json!({ "reason": "too_many", "max": max, "actual": values.len() })));
+    }
+    values.iter().enumerate().try_for_each(|(i, value)| text(&format!("{}[{}]", field, i), value, 1, max_chars))
+}
+
+impl Validate for GenerateRequest {
+    fn validate(&self) -> Result<(), ApiError> {
+        text("product_description", &self.product_description, MIN_PRODUCT_CHARS, MAX_PRODUCT_CHARS)?;
+        if let Some(constraints) = &self.constraints {
+            list("constraints", constraints, MAX_CONSTRAINTS, MAX_CONSTRAINT_CHARS)?;
+        }
+        if let Some(stages) = &self.stages {
+            if stages.is_empty() {
+                return Err(invalid("stages", "stages must name at least one stage; leave it out for the defaults".into(), serde_json::json!({ "reason": "empty" })));
+            }
+            list("stages", stages, MAX_STAGES, MAX_STAGE_NAME_CHARS)?;
+            for (i, stage) in stages.iter().enumerate() {
+                if stages[..i].iter().any(|other| other.trim().eq_ignore_ascii_case(stage.trim())) {
+                    let field = format!("stages[{}]", i);
+                    return Err(invalid(&field, format!("{} repeats stage {}", field, stage.trim()), serde_json::json!({ "reason": "duplicate" })));
+                }
+            }
+        }
+        if let Some(tenant) = &self.tenant {
+            text("tenant", tenant, 1, MAX_TENANT_CHARS)?;
+        }
+        list("tags", &self.tags, MAX_TAGS, MAX_TAG_CHARS)
+    }
+}
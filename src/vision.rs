@@ -0,0 +1,254 @@
+use crate::{gemini::{sniff_mime_type, GeminiClient, GeminiError, TEXT_MODEL}, provider::ImageGenerator, models::{ConsistencyCheck, Mismatch, QualityCheck, Resolution}};
+use chrono::Utc;
+use serde::Deserialize;
+use serde_json::json;
+use tracing::{info, warn};
+
+/// Settings for the post-generation vision passes, read from the environment.
+#[derive(Debug, Clone, Default)]
+pub struct VisionConfig {
+    /// `QUALITY_GATE_THRESHOLD` (0–10). When set, every generated image is rated and regenerated once if below it.
+    pub quality_threshold: Option<f32>,
+    /// `REJECT_IMAGE_TEXT=true`: check images for rendered text and retry with a strengthened prompt.
+    pub reject_text: bool,
+    /// `TEXT_CHECK_RETRIES` (default 1): how many strengthened retries to attempt before keeping a flagged image.
+    pub text_retries: u32,
+    /// `CONSISTENCY_CHECK=true`: compare each new image with its stage description and flag mismatches.
+    pub consistency_check: bool,
+}
+
+impl VisionConfig {
+    pub fn from_env() -> Self {
+        Self {
+            quality_threshold: std::env::var("QUALITY_GATE_THRESHOLD").ok().and_then(|v| v.parse().ok()),
+            reject_text: std::env::var("REJECT_IMAGE_TEXT").map(|v| v == "true" || v == "1").unwrap_or(false),
+            text_retries: std::env::var("TEXT_CHECK_RETRIES").ok().and_then(|v| v.parse().ok()).unwrap_or(1),
+            consistency_check: std::env::var("CONSISTENCY_CHECK").map(|v| v == "true" || v == "1").unwrap_or(false),
+        }
+    }
+}
+
+/// Result of the image pipeline: generation plus whichever post-checks are enabled.
+#[derive(Debug)]
+pub struct CheckedImage {
+    pub image: Result<String, GeminiError>,
+    pub quality_checks: Vec<QualityCheck>,
+    pub warnings: Vec<String>,
+}
+
+const NO_TEXT_REINFORCEMENT: &str = "IMPORTANT: the image must contain absolutely no text, letters, numbers, labels, captions, logos or watermarks anywhere. Convey everything purely through imagery.";
+
+#[derive(Debug, Deserialize)]
+struct TextDetection {
+    has_text: bool,
+    #[serde(default)]
+    detected_text: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ConsistencyReply {
+    #[serde(default)]
+    mismatches: Vec<Mismatch>,
+}
+
+/// Prefix of stage warnings produced by the consistency check, so a re-check can replace them.
+pub const MISMATCH_WARNING_PREFIX: &str = "Image/description mismatch:";
+
+#[derive(Debug, Deserialize)]
+struct RatingResponse {
+    relevance: f32,
+    artifacts: f32,
+    text_free: f32,
+}
+
+/// Parse a JSON object out of a model reply, tolerating ```json fences around it.
+pub(crate) fn parse_json_reply<T: serde::de::DeserializeOwned>(reply: &str) -> Result<T, GeminiError> {
+    let start = reply.find('{').unwrap_or(0);
+    let end = reply.rfind('}').map(|i| i + 1).unwrap_or(reply.len());
+    serde_json::from_str(&reply[start..end]).map_err(|e| GeminiError::Other(format!("invalid JSON reply: {}", e)))
+}
+
+impl GeminiClient {
+    /// Send an image plus an instruction to the vision-capable text model and return its text reply.
+    pub(crate) async fn ask_about_image(&self, image_b64: &str, instruction: &str, json_reply: bool) -> Result<String, GeminiError> {
+        let mut generation_config = json!({ "temperature": 0.0 });
+        if json_reply {
+            generation_config["responseMimeType"] = json!("application/json");
+        }
+        let payload = json!({
+            "contents": [{
+                "parts": [
+                    {"inlineData": {"mimeType": sniff_mime_type(image_b64), "data": image_b64}},
+                    {"text": instruction}
+                ]
+            }],
+            "generationConfig": generation_config
+        });
+        let parsed = self.generate_content(TEXT_MODEL, &payload).await?;
+        parsed.first_text().ok_or_else(|| GeminiError::Other("No text content found in response".to_string()))
+    }
+
+    pub async fn rate_image(&self, image_b64: &str, prompt: &str, attempt: u32) -> Result<QualityCheck, GeminiError> {
+        let instruction = format!(
+            "You are reviewing an AI-generated illustration. The image was generated from this prompt:\n\"{prompt}\"\n\
+            Rate it on a 0-10 scale (10 is best) and reply with JSON only: \
+            {{\"relevance\": <how well it depicts the prompt>, \"artifacts\": <10 = no visual artifacts or distortions>, \"text_free\": <10 = no rendered text, letters or labels>}}"
+        );
+        let reply = self.ask_about_image(image_b64, &instruction, true).await?;
+        let rating: RatingResponse = parse_json_reply(&reply)?;
+        let clamp = |v: f32| v.clamp(0.0, 10.0);
+        let (relevance, artifacts, text_free) = (clamp(rating.relevance), clamp(rating.artifacts), clamp(rating.text_free));
+        Ok(QualityCheck {
+            attempt,
+            relevance,
+            artifacts,
+            text_free,
+            overall: (relevance + artifacts + text_free) / 3.0,
+            accepted: false,
+            checked_at: Utc::now(),
+        })
+    }
+
+    /// Returns the text the vision model found rendered in the image, if any.
+    pub async fn detect_image_text(&self, image_b64: &str) -> Result<Option<String>, GeminiError> {
+        let instruction = "Does this image contain any rendered text, letters, numbers, labels, captions or watermarks? \
+            Reply with JSON only: {\"has_text\": true|false, \"detected_text\": \"<the text you can read, empty if none>\"}";
+        let reply = self.ask_about_image(image_b64, instruction, true).await?;
+        let detection: TextDetection = parse_json_reply(&reply)?;
+        Ok(detection.has_text.then_some(detection.detected_text))
+    }
+
+    /// Plain-language description of what the image actually depicts, for reviewers comparing it to the stage.
+    pub async fn explain_image(&self, image_b64: &str) -> Result<String, GeminiError> {
+        if self.is_demo() {
+            return Ok("Demo mode: image explanation is unavailable without a Gemini API key.".to_string());
+        }
+        let instruction = "Describe objectively what this image depicts: the setting, objects, processes, materials, vehicles or people shown, \
+            and any visible text. Do not guess the intent behind it. Use 3-5 plain sentences.";
+        self.ask_about_image(image_b64, instruction, false).await
+    }
+
+    /// Compare what the image shows with what the description claims (e.g. rail freight vs. trucks).
+    pub async fn check_consistency(&self, image_b64: &str, description: &str) -> Result<ConsistencyCheck, GeminiError> {
+        let instruction = format!(
+            "Compare this image with the following description of the same product lifecycle stage. \
+            List concrete factual mismatches only (transport modes, materials, processes, energy sources, settings), not style differences.\n\
+            Reply with JSON only: {{\"mismatches\": [{{\"description_says\": \"...\", \"image_shows\": \"...\"}}]}} (empty list if consistent).\n\n\
+            Description:\n{description}"
+        );
+        let reply = self.ask_about_image(image_b64, &instruction, true).await?;
+        let parsed: ConsistencyReply = parse_json_reply(&reply)?;
+        Ok(ConsistencyCheck { consistent: parsed.mismatches.is_empty(), mismatches: parsed.mismatches, checked_at: Utc::now() })
+    }
+
+    /// Consistency check run as part of generation when `CONSISTENCY_CHECK` is enabled. Placeholders, demo mode
+    /// and check failures yield `None`.
+    pub async fn auto_consistency_check(&self, stage: &str, image_b64: Option<&str>, description: &str) -> Option<ConsistencyCheck> {
+        if !self.vision.consistency_check || self.is_demo() {
+            return None;
+        }
+        let image_b64 = image_b64.filter(|img| sniff_mime_type(img) != "image/svg+xml")?;
+        match self.check_consistency(image_b64, description).await {
+            Ok(check) => {
+                info!("🔎 Stage '{}' consistency check: {} mismatch(es)", stage, check.mismatches.len());
+                Some(check)
+            }
+            Err(e) => {
+                warn!("⚠️ Consistency check failed for stage '{}': {}", stage, e);
+                None
+            }
+        }
+    }
+
+    /// Run the full image pipeline for a stage: generate, reject images with rendered text (if enabled), then
+    /// apply the quality gate (if enabled).
+    pub async fn generate_checked_image(&self, stage: &str, prompt: &str, resolution: Resolution) -> CheckedImage {
+        self.generate_checked_image_with(self, stage, prompt, resolution).await
+    }
+
+    /// The same pipeline with images generated by `images`; the checks always run on Gemini's vision model.
+    pub async fn generate_checked_image_with(&self, images: &dyn ImageGenerator, stage: &str, prompt: &str, resolution: Resolution) -> CheckedImage {
+        let (image, warnings) = self.generate_text_free_image(images, stage, prompt, resolution).await;
+        let (image, quality_checks) = self.apply_quality_gate(images, stage, prompt, resolution, image).await;
+        CheckedImage { image, quality_checks, warnings }
+    }
+
+    async fn generate_text_free_image(&self, images: &dyn ImageGenerator, stage: &str, prompt: &str, resolution: Resolution) -> (Result<String, GeminiError>, Vec<String>) {
+        let mut image = images.generate_image(stage, prompt, resolution).await;
+        if !self.vision.reject_text || self.is_demo() {
+            return (image, Vec::new());
+        }
+
+        let mut attempt = 0;
+        loop {
+            let Ok(img) = &image else { return (image, Vec::new()) };
+            if sniff_mime_type(img) == "image/svg+xml" {
+                return (image, Vec::new());
+            }
+            let detected = match self.detect_image_text(img).await {
+                Ok(detected) => detected,
+                Err(e) => {
+                    warn!("⚠️ Text detection failed for stage '{}': {}", stage, e);
+                    return (image, Vec::new());
+                }
+            };
+            let Some(text) = detected else { return (image, Vec::new()) };
+            if attempt >= self.vision.text_retries {
+                warn!("⚠️ Stage '{}' image still contains text after {} retries: {}", stage, attempt, text);
+                return (image, vec![format!("Image contains rendered text: \"{}\"", text)]);
+            }
+            attempt += 1;
+            info!("🔤 Stage '{}' image contains text (\"{}\"), retrying with strengthened prompt ({}/{})", stage, text, attempt, self.vision.text_retries);
+            image = images.generate_image(stage, &format!("{} {}", prompt, NO_TEXT_REINFORCEMENT), resolution).await;
+        }
+    }
+
+    /// When the quality gate is enabled, rate the image and regenerate once if it scores below the threshold.
+    /// The better-scoring attempt is returned; every rating is kept for the stage record.
+    async fn apply_quality_gate(&self, images: &dyn ImageGenerator, stage: &str, prompt: &str, resolution: Resolution, first: Result<String, GeminiError>) -> (Result<String, GeminiError>, Vec<QualityCheck>) {
+        let Some(threshold) = self.vision.quality_threshold else { return (first, Vec::new()) };
+        let Ok(first_img) = first else { return (first, Vec::new()) };
+        // Placeholders are SVGs we drew ourselves; there is nothing to rate.
+        if self.is_demo() || sniff_mime_type(&first_img) == "image/svg+xml" {
+            return (Ok(first_img), Vec::new());
+        }
+
+        let mut first_check = match self.rate_image(&first_img, prompt, 1).await {
+            Ok(check) => check,
+            Err(e) => {
+                warn!("⚠️ Quality rating failed for stage '{}': {}", stage, e);
+                return (Ok(first_img), Vec::new());
+            }
+        };
+        info!("🔍 Stage '{}' attempt 1 quality score {:.1} (threshold {:.1})", stage, first_check.overall, threshold);
+        if first_check.overall >= threshold {
+            first_check.accepted = true;
+            return (Ok(first_img), vec![first_check]);
+        }
+
+        info!("🔄 Stage '{}' below quality threshold, regenerating once", stage);
+        let second_img = match images.generate_image(stage, prompt, resolution).await {
+            Ok(img) if sniff_mime_type(&img) != "image/svg+xml" => img,
+            _ => {
+                first_check.accepted = true;
+                return (Ok(first_img), vec![first_check]);
+            }
+        };
+        let mut second_check = match self.rate_image(&second_img, prompt, 2).await {
+            Ok(check) => check,
+            Err(e) => {
+                warn!("⚠️ Quality rating failed for stage '{}' retry: {}", stage, e);
+                first_check.accepted = true;
+                return (Ok(first_img), vec![first_check]);
+            }
+        };
+        info!("🔍 Stage '{}' attempt 2 quality score {:.1}", stage, second_check.overall);
+        if second_check.overall >= first_check.overall {
+            second_check.accepted = true;
+            (Ok(second_img), vec![first_check, second_check])
+        } else {
+            first_check.accepted = true;
+            (Ok(first_img), vec![first_check, second_check])
+        }
+    }
+}
@@ -0,0 +1,120 @@
+use axum::{extract::{Request, State}, http::StatusCode, middleware::Next, response::{IntoResponse, Response}};
+use sha2::{Digest, Sha256};
+use std::{collections::HashMap, future::Future};
+use tracing::{info, warn};
+
+use crate::{error::ApiError, models::Lifecycle, routes::AppState, share::is_share_token};
+
+/// Header carrying a workspace API key; `?api_key=` works too, for `EventSource` and WebSocket clients that
+/// can't set headers.
+const API_KEY_HEADER: &str = "x-api-key";
+
+/// The workspace (team) and key owner behind a request.
+#[derive(Debug, Clone)]
+pub struct Workspace {
+    pub id: String,
+    pub owner: String,
+}
+
+impl Workspace {
+    pub fn owns(&self, lifecycle: &Lifecycle) -> bool {
+        lifecycle.workspace_id.as_deref() == Some(self.id.as_str())
+    }
+}
+
+tokio::task_local! {
+    static CALLER: Workspace;
+}
+
+/// `WORKSPACE_KEYS`: comma-separated `workspace:owner:key` entries, e.g. `acme:alice:s3cret,globex:ci:t0ken`.
+/// When set, every request needs one of the keys and only sees its workspace's lifecycles; unset, the deployment
+/// is a single workspace and needs no key.
+pub struct WorkspaceKeys {
+    /// By SHA-256 of the key, so lookups don't compare the secrets themselves.
+    keys: HashMap<[u8; 32], Workspace>,
+}
+
+impl WorkspaceKeys {
+    pub fn from_env() -> Self {
+        let mut keys = HashMap::new();
+        for entry in std::env::var("WORKSPACE_KEYS").unwrap_or_default().split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let mut parts = entry.splitn(3, ':').map(str::trim);
+            match (parts.next(), parts.next(), parts.next()) {
+                (Some(id), Some(owner), Some(key)) if !id.is_empty() && !owner.is_empty() && !key.is_empty() => {
+                    keys.insert(Sha256::digest(key.as_bytes()).into(), Workspace { id: id.to_string(), owner: owner.to_string() });
+                }
+                _ => warn!("⚠️ Ignoring malformed WORKSPACE_KEYS entry (expected workspace:owner:key)"),
+            }
+        }
+        if !keys.is_empty() {
+            let mut workspaces: Vec<&str> = keys.values().map(|w| w.id.as_str()).collect();
+            workspaces.sort_unstable();
+            workspaces.dedup();
+            info!("🔐 Workspace keys: {} key(s) for workspace(s) {}", keys.len(), workspaces.join(", "));
+        }
+        Self { keys }
+    }
+
+    pub fn enabled(&self) -> bool {
+        !self.keys.is_empty()
+    }
+
+    pub fn find(&self, key: &str) -> Option<&Workspace> {
+        let digest: [u8; 32] = Sha256::digest(key.trim().as_bytes()).into();
+        self.keys.get(&digest)
+    }
+}
+
+/// Routes authorized otherwise, or holding nothing of a workspace: `/api/admin/` (every handler there checks
+/// `ADMIN_TOKEN` itself), signed `/dl/` links, share tokens, content-addressed image files and probes.
+fn exempt(path: &str) -> bool {
+    ["/api/admin/", "/dl/", "/api/images/"].iter().any(|prefix| path.starts_with(prefix))
+        || path.strip_prefix("/share/").is_some_and(is_share_token)
+        || matches!(path, "/api/health" | "/readyz" | "/metrics")
+}
+
+/// With `WORKSPACE_KEYS` set, 401 for requests without a valid key; the rest run as the key's workspace.
+pub async fn require_key(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    if !state.workspaces.enabled() || exempt(request.uri().path()) {
+        return next.run(request).await;
+    }
+    let Some(workspace) = presented_key(&request).and_then(|key| state.workspaces.find(key)).cloned() else {
+        warn!("🔐 Rejected {} {}: missing or unknown workspace key", request.method(), request.uri().path());
+        return ApiError::new(StatusCode::UNAUTHORIZED, "unauthorized", "Missing or invalid workspace API key (X-API-Key)").into_response();
+    };
+    CALLER.scope(workspace, next.run(request)).await
+}
+
+/// The API key a request carries, valid or not.
+pub fn presented_key(request: &Request) -> Option<&str> {
+    request.headers().get(API_KEY_HEADER).and_then(|v| v.to_str().ok())
+        .or_else(|| request.uri().query().into_iter().flat_map(|q| q.split('&')).find_map(|pair| pair.strip_prefix("api_key=")))
+}
+
+/// Workspace of the current request; `None` without workspace keys and outside requests (background jobs,
+/// signed downloads).
+pub fn current() -> Option<Workspace> {
+    CALLER.try_with(Workspace::clone).ok()
+}
+
+/// Whether the current request may see `lifecycle`. Others' lifecycles are answered like unknown ids, so their
+/// ids can't be probed for.
+pub fn can_access(lifecycle: &Lifecycle) -> bool {
+    CALLER.try_with(|caller| caller.owns(lifecycle)).unwrap_or(true)
+}
+
+/// `workspace_id` and `owner` for a lifecycle the current request creates.
+pub fn ownership() -> (Option<String>, Option<String>) {
+    current().map(|w| (Some(w.id), Some(w.owner))).unwrap_or_default()
+}
+
+/// `future`, spawned off the current request, still acting as its workspace.
+pub fn carry<F: Future>(future: F) -> impl Future<Output = F::Output> {
+    let caller = current();
+    async move {
+        match caller {
+            Some(caller) => CALLER.scope(caller, future).await,
+            None => future.await,
+        }
+    }
+}